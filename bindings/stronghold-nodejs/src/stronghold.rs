@@ -209,6 +209,7 @@ impl NapiStronghold {
         &encryption_algorithm.0,
         &cek_algorithm.0,
         public_key.into(),
+        None,
       )
       .await
       .napi_result()?;
@@ -235,6 +236,7 @@ impl NapiStronghold {
         &encryption_algorithm.0,
         &cek_algorithm.0,
         &private_key.0,
+        None,
       )
       .await
       .napi_result()?;
@@ -51,7 +51,10 @@ expose_to_wasm!(did_purge_test, didPurgeTest);
 expose_to_wasm!(key_generate_test, keyGenerateTest);
 expose_to_wasm!(key_delete_test, keyDeleteTest);
 expose_to_wasm!(key_insert_test, keyInsertTest);
+expose_to_wasm!(key_insert_indexed_test, keyInsertIndexedTest);
+expose_to_wasm!(key_insert_type_mismatch_test, keyInsertTypeMismatchTest);
 expose_to_wasm!(key_sign_ed25519_test, keySignEd25519Test);
+expose_to_wasm!(key_verify_test, keyVerifyTest);
 
 #[wasm_bindgen(js_class = StorageTestSuite)]
 impl WasmStorageTestSuite {
@@ -108,6 +108,7 @@ extern "C" {
     encryption_algorithm: WasmEncryptionAlgorithm,
     cek_algorithm: WasmCekAlgorithm,
     public_key: Vec<u8>,
+    sender_key: Option<WasmKeyLocation>,
   ) -> PromiseEncryptedData;
   #[wasm_bindgen(method, js_name = dataDecrypt)]
   pub fn data_decrypt(
@@ -117,6 +118,7 @@ extern "C" {
     encryption_algorithm: WasmEncryptionAlgorithm,
     cek_algorithm: WasmCekAlgorithm,
     private_key: WasmKeyLocation,
+    sender_public_key: Option<Vec<u8>>,
   ) -> Uint8Array;
   #[wasm_bindgen(method, js_name = blobGet)]
   pub fn blob_get(this: &WasmStorage, did: WasmCoreDID) -> PromiseOptionBytes;
@@ -147,7 +149,8 @@ impl Storage for WasmStorage {
       key_bytes
     });
 
-    let promise: Promise = Promise::resolve(&self.did_create(did_type.into(), network.as_ref(), fragment, private_key));
+    let did_type: WasmDIDType = did_type.try_into()?;
+    let promise: Promise = Promise::resolve(&self.did_create(did_type, network.as_ref(), fragment, private_key));
     let result: JsValueResult = JsFuture::from(promise).await.into();
     let did_location_tuple: js_sys::Array = js_sys::Array::from(&result.to_account_error()?);
     let mut did_location_tuple: js_sys::ArrayIter = did_location_tuple.iter();
@@ -236,7 +239,8 @@ impl Storage for WasmStorage {
     let signature: Signature = js_value
       .into_serde()
       .map_err(|err| AccountStorageError::SerializationError(err.to_string()))?;
-    Ok(signature)
+    // JS storages do not record the algorithm, so it is derived from the key type of the location.
+    Signature::new_checked(location.key_type, signature.into())
   }
 
   async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> AccountStorageResult<bool> {
@@ -253,6 +257,7 @@ impl Storage for WasmStorage {
     encryption_algorithm: &EncryptionAlgorithm,
     cek_algorithm: &CekAlgorithm,
     public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
   ) -> AccountStorageResult<EncryptedData> {
     let promise: Promise = Promise::resolve(&self.data_encrypt(
       did.clone().into(),
@@ -261,6 +266,7 @@ impl Storage for WasmStorage {
       (*encryption_algorithm).into(),
       cek_algorithm.clone().into(),
       public_key.as_ref().to_vec(),
+      sender_key.cloned().map(Into::into),
     ));
     let result: JsValueResult = JsFuture::from(promise).await.into();
     let encrypted_data: EncryptedData = result
@@ -277,6 +283,7 @@ impl Storage for WasmStorage {
     encryption_algorithm: &EncryptionAlgorithm,
     cek_algorithm: &CekAlgorithm,
     private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
   ) -> AccountStorageResult<Vec<u8>> {
     let promise: Promise = Promise::resolve(&self.data_decrypt(
       did.clone().into(),
@@ -284,6 +291,7 @@ impl Storage for WasmStorage {
       (*encryption_algorithm).into(),
       cek_algorithm.clone().into(),
       private_key.clone().into(),
+      sender_public_key.map(|public_key| public_key.as_ref().to_vec()),
     ));
     let result: JsValueResult = JsFuture::from(promise).await.into();
     let data: Vec<u8> = result.to_account_error().map(uint8array_to_bytes)??;
@@ -388,16 +396,22 @@ interface Storage {
   keyExists: (did: CoreDID, keyLocation: KeyLocation) => Promise<boolean>;
 
   /** Encrypts the given `plaintext` with the specified `encryptionAlgorithm` and `cekAlgorithm`.
+   *
+   *  The sender-authenticated ECDH-1PU algorithms additionally require the location of the sender's
+   *  X25519 key in `senderKey`. It is ignored by the other algorithms.
    *
    *  Returns an `EncryptedData` instance.
    */
-  dataEncrypt: (did: CoreDID, plaintext: Uint8Array, associatedData: Uint8Array, encryptionAlgorithm: EncryptionAlgorithm, cekAlgorithm: CekAlgorithm, publicKey: Uint8Array) => Promise<EncryptedData>;
+  dataEncrypt: (did: CoreDID, plaintext: Uint8Array, associatedData: Uint8Array, encryptionAlgorithm: EncryptionAlgorithm, cekAlgorithm: CekAlgorithm, publicKey: Uint8Array, senderKey?: KeyLocation) => Promise<EncryptedData>;
 
   /** Decrypts the given `data` with the specified `encryptionAlgorithm` and `cekAlgorithm`.
+   *
+   *  The sender-authenticated ECDH-1PU algorithms additionally require the sender's X25519 public key
+   *  in `senderPublicKey`. It is ignored by the other algorithms.
    *
    *  Returns the decrypted text.
    */
-  dataDecrypt: (did: CoreDID, data: EncryptedData, encryptionAlgorithm: EncryptionAlgorithm, cekAlgorithm: CekAlgorithm, privateKey: KeyLocation) => Promise<Uint8Array>;
+  dataDecrypt: (did: CoreDID, data: EncryptedData, encryptionAlgorithm: EncryptionAlgorithm, cekAlgorithm: CekAlgorithm, privateKey: KeyLocation, senderPublicKey?: Uint8Array) => Promise<Uint8Array>;
 
   /** Returns the blob stored by the identity specified by `did`. */
   blobGet: (did: CoreDID) => Promise<Uint8Array | undefined>;
@@ -18,11 +18,35 @@ impl WasmCekAlgorithm {
     Self(CekAlgorithm::ECDH_ES(agreement.0.clone()))
   }
 
+  /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF with AES128 key wrapping.
+  #[wasm_bindgen(js_name = EcdhEsA128Kw)]
+  pub fn ecdh_es_a128kw(agreement: &WasmAgreementInfo) -> WasmCekAlgorithm {
+    Self(CekAlgorithm::ECDH_ES_A128KW(agreement.0.clone()))
+  }
+
+  /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF with AES192 key wrapping.
+  #[wasm_bindgen(js_name = EcdhEsA192Kw)]
+  pub fn ecdh_es_a192kw(agreement: &WasmAgreementInfo) -> WasmCekAlgorithm {
+    Self(CekAlgorithm::ECDH_ES_A192KW(agreement.0.clone()))
+  }
+
   /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF.
   #[wasm_bindgen(js_name = EcdhEsA256Kw)]
   pub fn ecdh_es_a256kw(agreement: &WasmAgreementInfo) -> WasmCekAlgorithm {
     Self(CekAlgorithm::ECDH_ES_A256KW(agreement.0.clone()))
   }
+
+  /// Elliptic Curve Diffie-Hellman One-Pass Unified Model key agreement using Concat KDF.
+  #[wasm_bindgen(js_name = Ecdh1Pu)]
+  pub fn ecdh_1pu(agreement: &WasmAgreementInfo) -> WasmCekAlgorithm {
+    Self(CekAlgorithm::ECDH_1PU(agreement.0.clone()))
+  }
+
+  /// Elliptic Curve Diffie-Hellman One-Pass Unified Model key agreement using Concat KDF with AES256 key wrapping.
+  #[wasm_bindgen(js_name = Ecdh1PuA256Kw)]
+  pub fn ecdh_1pu_a256kw(agreement: &WasmAgreementInfo) -> WasmCekAlgorithm {
+    Self(CekAlgorithm::ECDH_1PU_A256KW(agreement.0.clone()))
+  }
 }
 
 impl_wasm_json!(WasmCekAlgorithm, CekAlgorithm);
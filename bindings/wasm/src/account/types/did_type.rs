@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use identity_iota::account_storage::DIDType;
+use identity_iota::account_storage::Error as AccountStorageError;
 use wasm_bindgen::prelude::*;
 
 /// Supported types representing a DID that can be generated by the storage interface.
@@ -19,10 +20,13 @@ impl From<WasmDIDType> for DIDType {
   }
 }
 
-impl From<DIDType> for WasmDIDType {
-  fn from(other: DIDType) -> Self {
+impl TryFrom<DIDType> for WasmDIDType {
+  type Error = AccountStorageError;
+
+  fn try_from(other: DIDType) -> Result<Self, Self::Error> {
     match other {
-      DIDType::IotaDID => WasmDIDType::IotaDID,
+      DIDType::IotaDID => Ok(WasmDIDType::IotaDID),
+      DIDType::WebDID { .. } => Err(AccountStorageError::NotSupported("DIDType::WebDID")),
     }
   }
 }
@@ -10,12 +10,30 @@ pub struct WasmEncryptionAlgorithm(pub(crate) EncryptionAlgorithm);
 
 #[wasm_bindgen(js_class = EncryptionAlgorithm)]
 impl WasmEncryptionAlgorithm {
+  /// AES GCM using 128-bit key.
+  #[wasm_bindgen(js_name = A128GCM)]
+  pub fn aes128gcm() -> WasmEncryptionAlgorithm {
+    Self(EncryptionAlgorithm::AES128GCM)
+  }
+
+  /// AES GCM using 192-bit key.
+  #[wasm_bindgen(js_name = A192GCM)]
+  pub fn aes192gcm() -> WasmEncryptionAlgorithm {
+    Self(EncryptionAlgorithm::AES192GCM)
+  }
+
   /// AES GCM using 256-bit key.
   #[wasm_bindgen(js_name = A256GCM)]
   pub fn aes256gcm() -> WasmEncryptionAlgorithm {
     Self(EncryptionAlgorithm::AES256GCM)
   }
 
+  /// XChaCha20-Poly1305 using 256-bit key and 192-bit nonce.
+  #[wasm_bindgen(js_name = XC20P)]
+  pub fn xchacha20poly1305() -> WasmEncryptionAlgorithm {
+    Self(EncryptionAlgorithm::XCHACHA20POLY1305)
+  }
+
   /// Returns the length of the cipher's key.
   #[wasm_bindgen(js_name = keyLength)]
   pub fn key_length(&self) -> usize {
@@ -31,6 +31,14 @@ impl WasmKeyLocation {
     WasmKeyLocation(KeyLocation::new(keyType.into(), fragment, publicKey.as_ref()))
   }
 
+  /// Create a location from a `KeyType`, the fragment of a verification method
+  /// and the index of a key in a deterministic derivation scheme.
+  #[wasm_bindgen(js_name = withIndex)]
+  #[allow(non_snake_case)]
+  pub fn with_index(keyType: WasmKeyType, fragment: String, index: u32) -> WasmKeyLocation {
+    WasmKeyLocation(KeyLocation::with_index(keyType.into(), fragment, index))
+  }
+
   /// Obtain the location of a verification method's key in storage.
   #[wasm_bindgen(js_name = fromVerificationMethod)]
   pub fn from_verification_method(method: &WasmVerificationMethod) -> Result<WasmKeyLocation> {
@@ -53,6 +61,12 @@ impl WasmKeyLocation {
     self.0.key_type.into()
   }
 
+  /// Returns the derivation index of the key, if the location was created with `withIndex`.
+  #[wasm_bindgen]
+  pub fn index(&self) -> Option<u32> {
+    self.0.index()
+  }
+
   #[wasm_bindgen(js_name = toString)]
   #[allow(clippy::inherent_to_string)]
   pub fn to_string(&self) -> String {
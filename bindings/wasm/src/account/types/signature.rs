@@ -12,7 +12,9 @@ pub struct WasmSignature(pub(crate) Signature);
 impl WasmSignature {
   /// Creates a new `Signature`.
   #[wasm_bindgen(constructor)]
+  #[allow(deprecated)]
   pub fn new(data: Vec<u8>) -> WasmSignature {
+    // JS storages may use any key type, so the length is not validated here.
     WasmSignature(Signature::new(data))
   }
 
@@ -24,6 +24,12 @@ extern "C" {
   #[wasm_bindgen(typescript_type = "number | number[]")]
   pub type UOneOrManyNumber;
 
+  #[wasm_bindgen(typescript_type = "string | string[]")]
+  pub type UOneOrManyString;
+
+  #[wasm_bindgen(typescript_type = "Map<string, string[]>")]
+  pub type MapStringArrayString;
+
   #[wasm_bindgen(typescript_type = "string | string[] | null")]
   pub type OptionOneOrManyString;
 }
@@ -20,6 +20,8 @@ pub use self::wasm_resolved_document::DocumentOrResolvedDocument;
 pub use self::wasm_resolved_document::PromiseArrayResolvedDocument;
 pub use self::wasm_resolved_document::PromiseResolvedDocument;
 pub use self::wasm_resolved_document::WasmResolvedDocument;
+pub(crate) use self::wasm_service::check_service_properties;
+pub(crate) use self::wasm_service::deserialize_service_endpoint;
 pub use self::wasm_service::IService;
 pub use self::wasm_service::UServiceEndpoint;
 pub use self::wasm_service::WasmService;
@@ -1,7 +1,13 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use identity_iota::core::Object;
 use identity_iota::core::OneOrMany;
+use identity_iota::core::OrderedSet;
+use identity_iota::core::Url;
 use identity_iota::did::ServiceEndpoint;
 use identity_iota::iota_core::IotaDIDUrl;
 use identity_iota::iota_core::IotaService;
@@ -11,8 +17,11 @@ use wasm_bindgen::JsCast;
 use crate::common::deserialize_map_or_any;
 use crate::common::ArrayString;
 use crate::common::MapStringAny;
+use crate::common::MapStringArrayString;
+use crate::common::UOneOrManyString;
 use crate::did::WasmDIDUrl;
 use crate::error::Result;
+use crate::error::WasmError;
 use crate::error::WasmResult;
 
 /// A DID Document Service used to enable trusted interactions associated
@@ -24,14 +33,26 @@ pub struct WasmService(pub(crate) IotaService);
 
 #[wasm_bindgen(js_class = Service)]
 impl WasmService {
+  /// Creates a new `Service`.
+  ///
+  /// If `normalize` is `true`, the endpoint URLs are normalized as described in `normalizeEndpoints`.
+  ///
+  /// Throws an `InvalidServiceProperty` error if a custom property collides with a reserved field,
+  /// i.e. `id`, `type` or `serviceEndpoint`.
   #[wasm_bindgen(constructor)]
-  pub fn new(service: IIotaService) -> Result<WasmService> {
+  pub fn new(service: IIotaService, normalize: Option<bool>) -> Result<WasmService> {
     let id: IotaDIDUrl = service.id().into_serde().wasm_result()?;
 
     let base_service: &IService = service.as_ref();
     let types: OneOrMany<String> = service.type_().into_serde().wasm_result()?;
-    let service_endpoint: ServiceEndpoint = deserialize_map_or_any(&base_service.service_endpoint())?;
-    let properties: Option<identity_iota::core::Object> = deserialize_map_or_any(&base_service.properties())?;
+    let mut service_endpoint: ServiceEndpoint = deserialize_service_endpoint(&base_service.service_endpoint())?;
+    if normalize.unwrap_or(false) {
+      service_endpoint = normalize_service_endpoint(&service_endpoint);
+    }
+    let properties: Option<Object> = deserialize_map_or_any(&base_service.properties())?;
+    if let Some(properties) = properties.as_ref() {
+      check_service_properties(properties)?;
+    }
 
     IotaService::builder(properties.unwrap_or_default())
       .id(id)
@@ -42,6 +63,47 @@ impl WasmService {
       .wasm_result()
   }
 
+  /// Creates a new `Service` whose endpoint is a single URL.
+  ///
+  /// Throws an `InvalidServiceEndpoint` error if `url` is not a valid URL.
+  #[wasm_bindgen(js_name = fromUrl)]
+  pub fn from_url(id: &WasmDIDUrl, type_: &UOneOrManyString, url: &str) -> Result<WasmService> {
+    let url: Url = parse_endpoint_url(url, None)?;
+    build_service(id, type_, ServiceEndpoint::One(url))
+  }
+
+  /// Creates a new `Service` whose endpoint is an ordered set of URLs.
+  ///
+  /// Throws an `InvalidServiceEndpoint` error if any entry is not a valid URL or occurs more than once.
+  #[wasm_bindgen(js_name = fromUrls)]
+  pub fn from_urls(id: &WasmDIDUrl, type_: &UOneOrManyString, urls: &ArrayString) -> Result<WasmService> {
+    let urls: Vec<String> = urls.into_serde().wasm_result()?;
+    let set: OrderedSet<Url> = check_endpoint_urls(&urls, None)?;
+    build_service(id, type_, ServiceEndpoint::Set(set))
+  }
+
+  /// Creates a new `Service` whose endpoint is a map of URL sets, preserving the insertion order of
+  /// `map`.
+  ///
+  /// Throws an `InvalidServiceEndpoint` error if any entry is not a valid URL or occurs more than once
+  /// in its set.
+  #[wasm_bindgen(js_name = fromMap)]
+  pub fn from_map(id: &WasmDIDUrl, type_: &UOneOrManyString, map: &MapStringArrayString) -> Result<WasmService> {
+    let map: &js_sys::Map = map.unchecked_ref::<js_sys::Map>();
+    let mut entries: Vec<(String, OrderedSet<Url>)> = Vec::with_capacity(map.size() as usize);
+    for entry in map.entries() {
+      let entry: js_sys::Array = entry?.unchecked_into::<js_sys::Array>();
+      let key: String = entry
+        .get(0)
+        .as_string()
+        .ok_or_else(|| service_endpoint_error("non-string key".to_owned(), None))?;
+      let urls: Vec<String> = entry.get(1).into_serde().wasm_result()?;
+      let set: OrderedSet<Url> = check_endpoint_urls(&urls, Some(&key))?;
+      entries.push((key, set));
+    }
+    build_service(id, type_, ServiceEndpoint::Map(entries.into_iter().collect()))
+  }
+
   /// Returns a copy of the `Service` id.
   #[wasm_bindgen]
   pub fn id(&self) -> WasmDIDUrl {
@@ -67,11 +129,36 @@ impl WasmService {
     UServiceEndpoint::from(self.0.service_endpoint())
   }
 
+  /// Returns a copy of this `Service` with normalized endpoint URLs, so that URLs which only differ
+  /// in their notation compare equal. URLs that become duplicates of an earlier URL in the same set
+  /// are removed.
+  ///
+  /// Every URL is already normalized when it is parsed: the scheme and host are lowercased, the default
+  /// port is removed, `.` and `..` path segments are resolved and an empty path of a hierarchical URL
+  /// becomes `/`, so `HTTPS://Example.com` is stored as `https://example.com/`. In addition, this method:
+  /// - removes a trailing `/` from the path, unless the path is just `/`,
+  /// - decodes percent-encoded unreserved characters (`A-Z`, `a-z`, `0-9`, `-`, `.`, `_` and `~`) in the path, query
+  ///   and fragment,
+  /// - uppercases the hexadecimal digits of the remaining percent-encoded characters.
+  #[wasm_bindgen(js_name = normalizeEndpoints)]
+  pub fn normalize_endpoints(&self) -> WasmService {
+    let mut service: IotaService = self.0.clone();
+    *service.service_endpoint_mut() = normalize_service_endpoint(self.0.service_endpoint());
+    WasmService(service)
+  }
+
   /// Returns a copy of the custom properties on the `Service`.
   #[wasm_bindgen]
   pub fn properties(&self) -> Result<MapStringAny> {
     MapStringAny::try_from(self.0.properties())
   }
+
+  /// Returns whether this `Service` is structurally equal to `other`, regardless of the order
+  /// of their custom properties.
+  #[wasm_bindgen]
+  pub fn equals(&self, other: &WasmService) -> bool {
+    self.0 == other.0
+  }
 }
 
 impl_wasm_json!(WasmService, Service);
@@ -83,6 +170,151 @@ impl From<IotaService> for WasmService {
   }
 }
 
+/// A [`ServiceEndpoint`] whose URLs have not been parsed yet.
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum UncheckedServiceEndpoint {
+  One(String),
+  Set(Vec<String>),
+  Map(BTreeMap<String, Vec<String>>),
+}
+
+/// Deserializes a [`ServiceEndpoint`] from a `string | string[] | Map<string, string[]> | Record<string, string[]>`.
+///
+/// Unlike deserializing the [`ServiceEndpoint`] directly, the returned error names the entry that is
+/// not a valid URL or occurs more than once in its set.
+pub(crate) fn deserialize_service_endpoint(value: &JsValue) -> Result<ServiceEndpoint> {
+  match deserialize_map_or_any(value)? {
+    UncheckedServiceEndpoint::One(url) => {
+      parse_endpoint_url(&url, None)?;
+    }
+    UncheckedServiceEndpoint::Set(urls) => {
+      check_endpoint_urls(&urls, None)?;
+    }
+    UncheckedServiceEndpoint::Map(map) => {
+      for (key, urls) in map.iter() {
+        check_endpoint_urls(urls, Some(key))?;
+      }
+    }
+  }
+  deserialize_map_or_any(value)
+}
+
+fn build_service(id: &WasmDIDUrl, type_: &UOneOrManyString, service_endpoint: ServiceEndpoint) -> Result<WasmService> {
+  let types: OneOrMany<String> = type_.into_serde().wasm_result()?;
+  IotaService::builder(Object::new())
+    .id(id.0.clone())
+    .types(types)
+    .service_endpoint(service_endpoint)
+    .build()
+    .map(WasmService::from)
+    .wasm_result()
+}
+
+/// Field names of a [`Service`](identity_iota::did::Service) which must not be set as custom properties.
+const RESERVED_SERVICE_FIELDS: [&str; 3] = ["id", "type", "serviceEndpoint"];
+
+/// Returns an error naming the first key of `properties` that collides with a reserved service field.
+pub(crate) fn check_service_properties(properties: &Object) -> Result<()> {
+  for key in properties.keys() {
+    if RESERVED_SERVICE_FIELDS.contains(&key.as_str()) {
+      let message: String = format!("custom property `{key}` collides with a reserved service field");
+      return Err(WasmError::new(Cow::Borrowed("InvalidServiceProperty"), Cow::Owned(message)).into());
+    }
+  }
+  Ok(())
+}
+
+fn check_endpoint_urls(urls: &[String], key: Option<&str>) -> Result<OrderedSet<Url>> {
+  let mut parsed: OrderedSet<Url> = OrderedSet::new();
+  for url in urls {
+    let url: Url = parse_endpoint_url(url, key)?;
+    if parsed.contains(&url) {
+      return Err(service_endpoint_error(format!("duplicate URL `{url}`"), key));
+    }
+    parsed.append(url);
+  }
+  Ok(parsed)
+}
+
+fn parse_endpoint_url(url: &str, key: Option<&str>) -> Result<Url> {
+  Url::parse(url).map_err(|error| service_endpoint_error(format!("invalid URL `{url}`: {error}"), key))
+}
+
+fn normalize_service_endpoint(service_endpoint: &ServiceEndpoint) -> ServiceEndpoint {
+  match service_endpoint {
+    ServiceEndpoint::One(url) => ServiceEndpoint::One(normalize_url(url)),
+    ServiceEndpoint::Set(set) => ServiceEndpoint::Set(set.iter().map(normalize_url).collect()),
+    ServiceEndpoint::Map(map) => ServiceEndpoint::Map(
+      map
+        .iter()
+        .map(|(key, set)| (key.clone(), set.iter().map(normalize_url).collect()))
+        .collect(),
+    ),
+  }
+}
+
+/// Applies the rules documented on [`WasmService::normalize_endpoints`] to `url`.
+fn normalize_url(url: &Url) -> Url {
+  let mut normalized: Url = url.clone();
+
+  let path: String = normalize_percent_encoding(url.path());
+  let path: &str = match path.strip_suffix('/') {
+    Some(stripped) if !stripped.is_empty() => stripped,
+    _ => &path,
+  };
+  normalized.set_path(path);
+
+  if let Some(query) = url.query() {
+    normalized.set_query(Some(&normalize_percent_encoding(query)));
+  }
+  if let Some(fragment) = url.fragment() {
+    normalized.set_fragment(Some(&normalize_percent_encoding(fragment)));
+  }
+
+  normalized
+}
+
+/// Decodes percent-encoded unreserved characters and uppercases the remaining percent-encodings,
+/// see RFC 3986, section 6.2.2.
+fn normalize_percent_encoding(input: &str) -> String {
+  let bytes: &[u8] = input.as_bytes();
+  let mut output: String = String::with_capacity(input.len());
+  let mut index: usize = 0;
+  while index < bytes.len() {
+    let decoded: Option<u8> = match bytes.get(index..index + 3) {
+      Some([b'%', high, low]) => std::str::from_utf8(&[*high, *low])
+        .ok()
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+      _ => None,
+    };
+    match decoded {
+      Some(byte) if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') => {
+        output.push(char::from(byte));
+        index += 3;
+      }
+      Some(byte) => {
+        output.push_str(&format!("%{byte:02X}"));
+        index += 3;
+      }
+      None => {
+        // Serialized URLs only contain ASCII characters.
+        output.push(char::from(bytes[index]));
+        index += 1;
+      }
+    }
+  }
+  output
+}
+
+fn service_endpoint_error(message: String, key: Option<&str>) -> JsValue {
+  let message: String = match key {
+    Some(key) => format!("{message} in service endpoint entry `{key}`"),
+    None => format!("{message} in service endpoint"),
+  };
+  WasmError::new(Cow::Borrowed("InvalidServiceEndpoint"), Cow::Owned(message)).into()
+}
+
 impl From<ServiceEndpoint> for UServiceEndpoint {
   fn from(endpoint: ServiceEndpoint) -> Self {
     UServiceEndpoint::from(&endpoint)
@@ -185,7 +417,7 @@ interface IService {
     /**
      * Additional custom properties to embed in the service.
      *
-     * WARNING: entries may overwrite existing fields and result in invalid documents.
+     * NOTE: throws an error if a key collides with a reserved field, i.e. `id`, `type` or `serviceEndpoint`.
      */
     readonly properties?: Map<string, any> | Record<string, any>;
 }"#;
@@ -11,6 +11,8 @@ use wasm_bindgen::JsCast;
 use crate::common::deserialize_map_or_any;
 use crate::common::ArrayString;
 use crate::common::MapStringAny;
+use crate::did::check_service_properties;
+use crate::did::deserialize_service_endpoint;
 use crate::did::IService;
 use crate::did::UServiceEndpoint;
 use crate::error::Result;
@@ -23,14 +25,21 @@ pub struct WasmStardustService(pub(crate) StardustService);
 
 #[wasm_bindgen(js_class = StardustService)]
 impl WasmStardustService {
+  /// Creates a new `StardustService`.
+  ///
+  /// Throws an `InvalidServiceProperty` error if a custom property collides with a reserved field,
+  /// i.e. `id`, `type` or `serviceEndpoint`.
   #[wasm_bindgen(constructor)]
   pub fn new(service: IStardustService) -> Result<WasmStardustService> {
     let id: StardustDIDUrl = service.id().into_serde().wasm_result()?;
 
     let base_service: &IService = service.as_ref();
     let types: OneOrMany<String> = service.type_().into_serde().wasm_result()?;
-    let service_endpoint: ServiceEndpoint = deserialize_map_or_any(&base_service.service_endpoint())?;
+    let service_endpoint: ServiceEndpoint = deserialize_service_endpoint(&base_service.service_endpoint())?;
     let properties: Option<identity_iota::core::Object> = deserialize_map_or_any(&base_service.properties())?;
+    if let Some(properties) = properties.as_ref() {
+      check_service_properties(properties)?;
+    }
 
     StardustService::builder(properties.unwrap_or_default())
       .id(id)
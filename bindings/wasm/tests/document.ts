@@ -46,5 +46,177 @@ describe('Document', function () {
             assert.deepStrictEqual(service.type(), ["LinkedDomains", "ExampleType"]);
             assert.deepStrictEqual(service.serviceEndpoint(), ["https://example.com/", "https://iota.org/"]);
         });
+        it('should compare services structurally', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+            const id = doc.id().toUrl().join('#new-service-1');
+
+            const service1 = new Service({
+                id,
+                type: "LinkedDomains",
+                serviceEndpoint: "https://iota.org/",
+                properties: {
+                    "foo": 1,
+                    "bar": "baz",
+                },
+            });
+            const service2 = new Service({
+                id,
+                type: "LinkedDomains",
+                serviceEndpoint: "https://iota.org/",
+                properties: new Map<string, any>([
+                    ["bar", "baz"],
+                    ["foo", 1],
+                ]),
+            });
+            const service3 = new Service({
+                id,
+                type: "LinkedDomains",
+                serviceEndpoint: "https://example.com/",
+                properties: {
+                    "foo": 1,
+                    "bar": "baz",
+                },
+            });
+            assert.ok(service1.equals(service2));
+            assert.ok(service2.equals(service1));
+            assert.ok(!service1.equals(service3));
+        });
+        it('should reject duplicate endpoint URLs', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            assert.throws(() => new Service({
+                id: doc.id().toUrl().join('#new-service-1'),
+                type: "LinkedDomains",
+                serviceEndpoint: new Map<string, string[]>([
+                    ["origins", ["https://iota.org/", "https://example.com/", "https://iota.org/"]],
+                ]),
+            }), {
+                name: "InvalidServiceEndpoint",
+                message: /duplicate URL `https:\/\/iota.org\/` in service endpoint entry `origins`/,
+            });
+        });
+        it('should reject invalid endpoint URLs', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            assert.throws(() => new Service({
+                id: doc.id().toUrl().join('#new-service-1'),
+                type: "LinkedDomains",
+                serviceEndpoint: {
+                    "origins": ["https://iota.org/", "not a url"],
+                },
+            }), {
+                name: "InvalidServiceEndpoint",
+                message: /invalid URL `not a url`.* in service endpoint entry `origins`/,
+            });
+        });
+        it('should reject custom properties colliding with reserved fields', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            assert.throws(() => new Service({
+                id: doc.id().toUrl().join('#new-service-1'),
+                type: "LinkedDomains",
+                serviceEndpoint: "https://iota.org/",
+                properties: {
+                    "foo": 1,
+                    "serviceEndpoint": "https://example.com/",
+                },
+            }), {
+                name: "InvalidServiceProperty",
+                message: /custom property `serviceEndpoint` collides with a reserved service field/,
+            });
+        });
+        it('should accept benign custom properties', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            const service = new Service({
+                id: doc.id().toUrl().join('#new-service-1'),
+                type: "LinkedDomains",
+                serviceEndpoint: "https://iota.org/",
+                properties: {
+                    "foo": 1,
+                },
+            });
+            assert.deepStrictEqual(service.serviceEndpoint(), "https://iota.org/");
+            assert.deepStrictEqual(service.properties(), new Map<string, any>([["foo", 1]]));
+        });
+    });
+    describe('Service.fromUrl/fromUrls/fromMap', function () {
+        it('should build a single URL endpoint', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            const service = Service.fromUrl(doc.id().toUrl().join('#new-service-1'), "LinkedDomains", "https://iota.org/");
+            assert.deepStrictEqual(service.type(), ["LinkedDomains"]);
+            assert.deepStrictEqual(service.serviceEndpoint(), "https://iota.org/");
+        });
+        it('should build a URL set endpoint', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            const service = Service.fromUrls(doc.id().toUrl().join('#new-service-1'), ["LinkedDomains", "ExampleType"], ["https://example.com/", "https://iota.org/"]);
+            assert.deepStrictEqual(service.type(), ["LinkedDomains", "ExampleType"]);
+            assert.deepStrictEqual(service.serviceEndpoint(), ["https://example.com/", "https://iota.org/"]);
+        });
+        it('should build a URL map endpoint in insertion order', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            const endpoint = new Map<string, string[]>([
+                ["origins", ["https://iota.org/", "https://example.com/"]],
+                ["alternates", ["https://example.org/"]],
+            ]);
+            const service = Service.fromMap(doc.id().toUrl().join('#new-service-1'), "LinkedDomains", endpoint);
+            assert.deepStrictEqual(service.serviceEndpoint(), endpoint);
+            assert.deepStrictEqual(Array.from((service.serviceEndpoint() as Map<string, string[]>).keys()), ["origins", "alternates"]);
+        });
+        it('should reject invalid endpoint URLs', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+            const id = doc.id().toUrl().join('#new-service-1');
+
+            assert.throws(() => Service.fromUrl(id, "LinkedDomains", "not a url"), {
+                name: "InvalidServiceEndpoint",
+                message: /invalid URL `not a url`.* in service endpoint$/,
+            });
+            assert.throws(() => Service.fromUrls(id, "LinkedDomains", ["https://iota.org/", "https://iota.org/"]), {
+                name: "InvalidServiceEndpoint",
+                message: /duplicate URL `https:\/\/iota.org\/` in service endpoint$/,
+            });
+            assert.throws(() => Service.fromMap(id, "LinkedDomains", new Map<string, string[]>([
+                ["origins", ["https://iota.org/", "not a url"]],
+            ])), {
+                name: "InvalidServiceEndpoint",
+                message: /invalid URL `not a url`.* in service endpoint entry `origins`/,
+            });
+        });
+    });
+    describe('Service.normalizeEndpoints', function () {
+        it('should remove trailing slashes', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+            const id = doc.id().toUrl().join('#new-service-1');
+
+            const service = Service.fromUrls(id, "LinkedDomains", ["https://example.com/path/", "https://example.com/path", "https://example.com"]);
+            const normalized = service.normalizeEndpoints();
+            assert.deepStrictEqual(normalized.serviceEndpoint(), ["https://example.com/path", "https://example.com/"]);
+            assert.ok(Service.fromUrl(id, "LinkedDomains", "https://example.com/path/").normalizeEndpoints()
+                .equals(Service.fromUrl(id, "LinkedDomains", "https://example.com/path").normalizeEndpoints()));
+        });
+        it('should lowercase the scheme and normalize percent-encodings', async () => {
+            const keypair = new KeyPair(KeyType.Ed25519);
+            const doc = new Document(keypair);
+
+            const service = new Service({
+                id: doc.id().toUrl().join('#new-service-1'),
+                type: "LinkedDomains",
+                serviceEndpoint: "HTTPS://Example.com/%7euser/a%2fb/",
+            }, true);
+            assert.deepStrictEqual(service.serviceEndpoint(), "https://example.com/~user/a%2Fb");
+        });
     });
 });
@@ -142,6 +142,22 @@ describe('StardustDocument', function () {
             assert.deepStrictEqual(doc.resolveService(fragment1), undefined);
             assert.deepStrictEqual(doc.service().length, 0);
         });
+        it('should reject custom properties colliding with reserved fields', async () => {
+            const doc = new StardustDocument(networkName);
+
+            assert.throws(() => new StardustService({
+                id: doc.id().toUrl().join('#new-service-1'),
+                type: "LinkedDomains",
+                serviceEndpoint: "https://iota.org/",
+                properties: {
+                    "foo": 1,
+                    "serviceEndpoint": "https://example.com/",
+                },
+            }), {
+                name: "InvalidServiceProperty",
+                message: /custom property `serviceEndpoint` collides with a reserved service field/,
+            });
+        });
     });
     describe('#metadata', function () {
         it('should work', () => {
@@ -650,6 +650,7 @@ mod account_encryption {
           encryption_algorithm,
           cek_algorithm,
           public_key,
+          None,
         )
         .await
         .map_err(Into::into)
@@ -679,6 +680,7 @@ mod account_encryption {
           encryption_algorithm,
           cek_algorithm,
           &private_key,
+          None,
         )
         .await
         .map_err(Into::into)
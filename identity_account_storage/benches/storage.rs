@@ -0,0 +1,115 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BatchSize;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use identity_account_storage::storage::MemStore;
+use identity_account_storage::storage::RocksStore;
+use identity_account_storage::storage::Storage;
+use identity_account_storage::types::DIDType;
+use identity_iota_core::tangle::Network;
+use tokio::runtime::Runtime;
+
+const BATCH_SIZES: [usize; 2] = [100, 1000];
+
+/// A `RocksStore` in a temporary directory, which is removed on drop.
+struct TemporaryRocksStore {
+  store: Option<RocksStore>,
+  directory: PathBuf,
+}
+
+impl TemporaryRocksStore {
+  fn new() -> Self {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let directory: PathBuf = std::env::temp_dir().join(format!(
+      "bench_rocksstore_{}_{}",
+      std::process::id(),
+      COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    let store: RocksStore = RocksStore::open(&directory, [0x42; 32]).unwrap();
+
+    Self {
+      store: Some(store),
+      directory,
+    }
+  }
+
+  fn store(&self) -> &RocksStore {
+    self.store.as_ref().unwrap()
+  }
+}
+
+impl Drop for TemporaryRocksStore {
+  fn drop(&mut self) {
+    // The database must be closed before its directory can be removed.
+    drop(self.store.take());
+    let _ = std::fs::remove_dir_all(&self.directory);
+  }
+}
+
+async fn create_identities(storage: &impl Storage, count: usize) {
+  for _ in 0..count {
+    storage
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
+      .await
+      .unwrap();
+  }
+  storage.flush_changes().await.unwrap();
+}
+
+fn bench_did_create(c: &mut Criterion) {
+  let runtime: Runtime = tokio::runtime::Builder::new_multi_thread()
+    .enable_all()
+    .build()
+    .unwrap();
+
+  let mut group = c.benchmark_group("did_create");
+
+  // Every iteration runs on a fresh store, so the measured time does not depend on the number of
+  // identities created by previous iterations. The stores are dropped outside of the measurement.
+  for batch_size in BATCH_SIZES {
+    group.bench_with_input(
+      BenchmarkId::new("MemStore", batch_size),
+      &batch_size,
+      |bencher, &count| {
+        bencher.to_async(&runtime).iter_batched(
+          MemStore::new,
+          |memstore| async move {
+            create_identities(&memstore, count).await;
+            memstore
+          },
+          BatchSize::PerIteration,
+        );
+      },
+    );
+
+    group.bench_with_input(
+      BenchmarkId::new("RocksStore", batch_size),
+      &batch_size,
+      |bencher, &count| {
+        bencher.to_async(&runtime).iter_batched(
+          TemporaryRocksStore::new,
+          |rocksstore| async move {
+            create_identities(rocksstore.store(), count).await;
+            rocksstore
+          },
+          BatchSize::PerIteration,
+        );
+      },
+    );
+  }
+
+  group.finish();
+}
+
+criterion_group!(benches, bench_did_create);
+
+criterion_main!(benches);
@@ -14,7 +14,10 @@ pub enum Error {
   CoreError(#[from] identity_core::Error),
   /// Caused by errors from the [`identity_iota_core`] crate.
   #[error("DID creation failed: {0}")]
-  DIDCreationError(String),
+  DIDCreationError(#[source] identity_iota_core::Error),
+  /// Caused by a domain that does not form a valid `did:web` DID.
+  #[error("did:web creation failed: {0}")]
+  WebDIDCreationError(#[source] identity_did::did::DIDError),
   /// Caused by errors from the [identity_did] crate.
   #[error(transparent)]
   DIDError(#[from] identity_did::Error),
@@ -25,6 +28,22 @@ pub enum Error {
   #[cfg(feature = "stronghold")]
   #[error(transparent)]
   StrongholdError(#[from] crate::stronghold::StrongholdError),
+  /// Caused by errors from the [sled](https://docs.rs/sled) crate.
+  #[cfg(feature = "sled-storage")]
+  #[error(transparent)]
+  SledError(#[from] ::sled::Error),
+  /// Caused by errors from the [rocksdb](https://docs.rs/rocksdb) crate.
+  #[cfg(feature = "rocksdb-storage")]
+  #[error(transparent)]
+  RocksError(#[from] ::rocksdb::Error),
+  /// Caused by errors from the [sqlx](https://docs.rs/sqlx) crate.
+  #[cfg(feature = "postgres-storage")]
+  #[error(transparent)]
+  PgError(#[from] ::sqlx::Error),
+  /// Caused by errors from the [redis](https://docs.rs/redis) crate.
+  #[cfg(feature = "redis-storage")]
+  #[error(transparent)]
+  RedisError(#[from] ::redis::RedisError),
   /// Caused by providing bytes that cannot be used as a private key of the
   /// [`KeyType`][identity_core::crypto::KeyType].
   #[error("invalid private key: {0}")]
@@ -33,6 +52,13 @@ pub enum Error {
   /// [`KeyType`][identity_core::crypto::KeyType].
   #[error("invalid public key: {0}")]
   InvalidPublicKey(String),
+  /// Caused by providing bytes that cannot be a signature created with a key of the
+  /// [`KeyType`][identity_core::crypto::KeyType].
+  #[error("invalid signature: {0}")]
+  InvalidSignature(String),
+  /// Caused by providing agreement information that cannot be used as input for the Concat KDF.
+  #[error("invalid agreement info: {0}")]
+  InvalidAgreementInfo(String),
   /// Caused by failing to decrypt data.
   #[error("failed to decrypt data")]
   DecryptionFailure(#[source] crypto::error::Error),
@@ -42,18 +68,46 @@ pub enum Error {
   /// Caused by attempting to find a key in storage that does not exist.
   #[error("key not found")]
   KeyNotFound,
+  /// Caused by attempting to find a key in storage that does not exist, when several keys are looked up
+  /// at once.
+  #[error("key not found: {0}")]
+  KeyNotFoundAt(crate::types::KeyLocation),
+  /// Caused by attempting to use a key whose expiry time has passed.
+  #[error("key expired")]
+  KeyExpired,
+  /// Caused by attempting to delete the last key of an identity that can create signatures.
+  #[error("cannot remove the last signing key")]
+  CannotRemoveLastSigningKey,
   /// Caused by attempting to find an identity key vault that does not exist.
   #[error("key vault not found")]
   KeyVaultNotFound,
-  /// Caused by attempting to read a poisoned shared resource.
-  #[error("shared resource poisoned: read")]
-  SharedReadPoisoned,
-  /// Caused by attempting to write a poisoned shared resource.
-  #[error("shared resource poisoned: write")]
-  SharedWritePoisoned,
+  /// Caused by attempting to acquire a lock that was poisoned by a thread panicking while holding it.
+  ///
+  /// The panic may have happened in the middle of a write, so the guarded data may be inconsistent.
+  /// [`MemStore::clear_poison`](crate::storage::MemStore::clear_poison) makes the store usable again.
+  /// The kind of access is `read` or `write` for read-write locks and `exclusive` for mutexes.
+  #[error(
+    "lock poisoned on {0} access: a previous operation panicked while holding it and the store may be inconsistent"
+  )]
+  LockPoisoned(&'static str),
   /// Caused by attempting to create a DID that already exists.
   #[error("identity already exists")]
   IdentityAlreadyExists,
+  /// Caused by deriving a key from a seed shorter than [`MIN_SEED_LENGTH`](crate::storage::MIN_SEED_LENGTH).
+  #[error("seed of {0} bytes is shorter than the minimum of 32 bytes")]
+  SeedTooShort(usize),
+  /// Caused by attempting to create a DID on a network whose name is not spec-compliant.
+  #[error("invalid network name: {0}")]
+  InvalidNetwork(String),
+  /// Caused by attempting to load data serialized in an unsupported format version.
+  #[error("unsupported serialization version: {0}")]
+  UnsupportedVersion(u8),
+  /// Caused by calling an operation that the storage implementation does not support.
+  #[error("operation not supported: {0}")]
+  NotSupported(&'static str),
+  /// Caused by using a storage after it was closed with [`Storage::close`](crate::storage::Storage::close).
+  #[error("storage has been closed")]
+  StorageClosed,
   #[cfg(all(target_arch = "wasm32", not(target_os = "wasi")))]
   #[error("JsValue serialization error: {0}")]
   SerializationError(String),
@@ -0,0 +1,840 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::RwLockWriteGuard;
+
+use async_trait::async_trait;
+use crypto::ciphers::aes_gcm::Aes256Gcm;
+use crypto::hashes::sha::Sha256;
+use crypto::hashes::Digest;
+use hashbrown::HashSet;
+use identity_core::common::Timestamp;
+use identity_core::convert::FromJson;
+use identity_core::convert::ToJson;
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyPair;
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PrivateKey;
+use identity_core::crypto::PublicKey;
+use identity_core::crypto::Sign;
+use identity_core::crypto::X25519;
+use identity_did::did::CoreDID;
+use identity_did::did::DID;
+use identity_iota_core::tangle::NetworkName;
+use serde::Deserialize;
+use serde::Serialize;
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::error::Result;
+#[cfg(feature = "encryption")]
+use crate::storage::memstore::memstore_encryption;
+use crate::storage::memstore::prepare_did;
+use crate::storage::Storage;
+use crate::storage::DEFAULT_BLOB_NAME;
+#[cfg(feature = "encryption")]
+use crate::types::CekAlgorithm;
+use crate::types::DIDType;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptedData;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptionAlgorithm;
+use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
+use crate::types::Signature;
+use crate::types::StorageStats;
+use crate::utils::ct_eq_option;
+use crate::utils::open;
+use crate::utils::seal;
+use crate::utils::Shared;
+
+// The extension of the files holding the data of a DID.
+static FILE_EXTENSION: &str = "identity";
+// The extension of the temporary files that are renamed into place after writing.
+static TEMPORARY_FILE_EXTENSION: &str = "tmp";
+
+/// A [`Storage`] implementation persisting the data of every DID in a separate file within a directory.
+///
+/// The keys and named blobs of a DID are serialized and encrypted with AES-256-GCM under the master key
+/// given on construction. Files are named after the SHA-256 hash of the DID, so neither the DIDs nor
+/// their structure leak into the file system.
+///
+/// Changes are written to disk immediately, but only guaranteed to be durable after
+/// [`Storage::flush_changes`] was called.
+///
+/// The file system is accessed with blocking `std::fs` calls on the thread polling the returned futures,
+/// which blocks that executor thread for the duration of the I/O. On an async runtime with few worker threads,
+/// consider moving calls onto a thread meant for blocking work, e.g. with `tokio::task::spawn_blocking`.
+pub struct FsStore {
+  directory: PathBuf,
+  master_key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]>,
+  // Serializes all modifications and tracks the files changed since the last flush.
+  pending: Shared<HashSet<PathBuf>>,
+}
+
+impl FsStore {
+  /// Opens the `FsStore` in `directory`, creating the directory if it does not exist.
+  ///
+  /// All files are encrypted with the given `master_key`, which must be the same every time
+  /// the directory is opened.
+  pub fn open<P>(directory: &P, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self>
+  where
+    P: AsRef<Path> + ?Sized,
+  {
+    std::fs::create_dir_all(directory)?;
+
+    Ok(Self {
+      directory: directory.as_ref().to_owned(),
+      master_key: Zeroizing::new(master_key),
+      pending: Shared::new(HashSet::new()),
+    })
+  }
+
+  /// Returns the directory in which the files are stored.
+  pub fn directory(&self) -> &Path {
+    &self.directory
+  }
+
+  /// Returns the path of the file holding the data of the given `did`.
+  ///
+  /// The DID is hashed rather than used directly, which rules out path traversal.
+  fn path(&self, did: &CoreDID) -> PathBuf {
+    let file_name: String = Sha256::digest(did.as_str().as_bytes())
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect();
+
+    let mut path: PathBuf = self.directory.join(file_name);
+    path.set_extension(FILE_EXTENSION);
+    path
+  }
+
+  /// Reads and decrypts the data of the given `did`, if it exists.
+  fn read_identity(&self, did: &CoreDID) -> Result<Option<FsIdentity>> {
+    self.read_file(&self.path(did))
+  }
+
+  /// Reads and decrypts the file at `path`, if it exists.
+  fn read_file(&self, path: &Path) -> Result<Option<FsIdentity>> {
+    let bytes: Vec<u8> = match std::fs::read(path) {
+      Ok(bytes) => bytes,
+      Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+      Err(error) => return Err(error.into()),
+    };
+
+    let plaintext: Zeroizing<Vec<u8>> = open(&self.master_key, associated_data(path), &bytes)?;
+    FsIdentity::from_json_slice(plaintext.as_slice())
+      .map(Some)
+      .map_err(Into::into)
+  }
+
+  /// Reads the data of all DIDs in the directory.
+  fn read_all(&self) -> Result<Vec<FsIdentity>> {
+    let mut identities: Vec<FsIdentity> = Vec::new();
+
+    for entry in std::fs::read_dir(&self.directory)? {
+      let path: PathBuf = entry?.path();
+      if path.extension().and_then(|extension| extension.to_str()) != Some(FILE_EXTENSION) {
+        continue;
+      }
+
+      // The file may have been removed since the directory was listed.
+      if let Some(identity) = self.read_file(&path)? {
+        identities.push(identity);
+      }
+    }
+
+    Ok(identities)
+  }
+
+  /// Runs `f` on the data of the given `did` and writes the modified data back if `f` succeeds.
+  ///
+  /// If no data exists for the `did` yet, `f` is called on an empty [`FsIdentity`].
+  fn mutate<F, T>(&self, did: &CoreDID, f: F) -> Result<T>
+  where
+    F: FnOnce(&mut FsIdentity) -> Result<T>,
+  {
+    // Obtain exclusive access for the entire read-modify-write cycle.
+    let mut pending: RwLockWriteGuard<'_, _> = self.pending.write()?;
+
+    let path: PathBuf = self.path(did);
+    let mut identity: FsIdentity = self.read_file(&path)?.unwrap_or_else(|| FsIdentity::new(did.clone()));
+
+    let output: T = f(&mut identity)?;
+
+    self.write_file(&path, &identity)?;
+    pending.insert(path);
+
+    Ok(output)
+  }
+
+  /// Runs `f` on the vault of the given `did` and writes the modified vault back if `f` succeeds.
+  ///
+  /// If `create` is `true`, a missing vault is created, otherwise [`Error::KeyVaultNotFound`] is returned.
+  fn mutate_vault<F, T>(&self, did: &CoreDID, create: bool, f: F) -> Result<T>
+  where
+    F: FnOnce(&mut FsVault) -> Result<T>,
+  {
+    self.mutate(did, |identity| match identity.vault.as_mut() {
+      Some(vault) => f(vault),
+      None if create => f(identity.vault.insert(FsVault::default())),
+      None => Err(Error::KeyVaultNotFound),
+    })
+  }
+
+  /// Encrypts and writes `identity` to `path`.
+  ///
+  /// The data is written to a temporary file first, which then replaces the file at `path`,
+  /// so that readers never observe a partially written file.
+  fn write_file(&self, path: &Path, identity: &FsIdentity) -> Result<()> {
+    let plaintext: Zeroizing<Vec<u8>> = Zeroizing::new(identity.to_json_vec()?);
+    let bytes: Vec<u8> = seal(&self.master_key, associated_data(path), &plaintext)?;
+
+    let temporary_path: PathBuf = path.with_extension(TEMPORARY_FILE_EXTENSION);
+    std::fs::write(&temporary_path, bytes)?;
+    std::fs::rename(&temporary_path, path)?;
+
+    Ok(())
+  }
+
+  /// Reconstructs the key pair at `location` of the given `did`.
+  fn keypair(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyPair> {
+    let vault: FsVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    vault.get(location).ok_or(Error::KeyNotFound)?.keypair()
+  }
+
+  /// Reads the vault of the given `did`, if it exists.
+  fn read_vault(&self, did: &CoreDID) -> Result<Option<FsVault>> {
+    Ok(self.read_identity(did)?.and_then(|identity| identity.vault))
+  }
+}
+
+// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
+#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
+#[cfg_attr(feature = "send-sync-storage", async_trait)]
+impl Storage for FsStore {
+  async fn did_create(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    private_key: Option<PrivateKey>,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did(did_type, network, fragment, private_key)?;
+
+    self.mutate(&did, |identity| {
+      // We don't want to overwrite an existing DID.
+      if identity.vault.is_some() {
+        return Err(Error::IdentityAlreadyExists);
+      }
+
+      let mut vault: FsVault = FsVault::default();
+      vault.insert(FsKey::new(location.clone(), &keypair));
+      identity.vault = Some(vault);
+
+      Ok(())
+    })?;
+
+    Ok((did, location))
+  }
+
+  async fn did_copy(&self, from: &CoreDID, to: CoreDID) -> Result<()> {
+    let mut pending: RwLockWriteGuard<'_, _> = self.pending.write()?;
+
+    let identity: FsIdentity = self
+      .read_identity(from)?
+      .filter(|identity| identity.vault.is_some())
+      .ok_or(Error::KeyVaultNotFound)?;
+
+    let path: PathBuf = self.path(&to);
+    if matches!(self.read_file(&path)?, Some(existing) if existing.vault.is_some()) {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    // The blobs of the new identity are replaced entirely, so no stale blobs remain.
+    let identity: FsIdentity = FsIdentity {
+      did: to,
+      vault: identity.vault,
+      blobs: identity.blobs,
+    };
+    self.write_file(&path, &identity)?;
+    pending.insert(path);
+
+    Ok(())
+  }
+
+  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
+    let mut pending: RwLockWriteGuard<'_, _> = self.pending.write()?;
+
+    let path: PathBuf = self.path(did);
+    // This method is supposed to be idempotent, so we only remove the file if the DID still exists.
+    match self.read_file(&path)? {
+      Some(identity) if identity.vault.is_some() => {
+        std::fs::remove_file(&path)?;
+        pending.insert(path);
+        Ok(true)
+      }
+      _ => Ok(false),
+    }
+  }
+
+  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
+    Ok(self.read_vault(did)?.is_some())
+  }
+
+  async fn did_list(&self) -> Result<Vec<CoreDID>> {
+    Ok(
+      self
+        .read_all()?
+        .into_iter()
+        .filter(|identity| identity.vault.is_some())
+        .map(|identity| identity.did)
+        .collect(),
+    )
+  }
+
+  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, true, |vault| {
+      let keypair: KeyPair = KeyPair::new(key_type)?;
+      let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+      vault.insert(FsKey::new(location.clone(), &keypair));
+
+      Ok(location)
+    })
+  }
+
+  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
+    // Validate the private key by reconstructing the key pair for the location's key type.
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(location.key_type, private_key.as_ref())
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+    private_key.zeroize();
+
+    self.mutate_vault(did, true, |vault| {
+      vault.insert(FsKey::new(location.clone(), &keypair));
+      Ok(())
+    })
+  }
+
+  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    Ok(
+      self
+        .read_vault(did)?
+        .map(|vault| vault.get(location).is_some())
+        .unwrap_or(false),
+    )
+  }
+
+  async fn key_list(&self, did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    let vault: FsVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    Ok(vault.0.iter().map(|key| key.location.clone()).collect())
+  }
+
+  async fn key_delete_all(&self, did: &CoreDID) -> Result<usize> {
+    self.mutate_vault(did, false, |vault| {
+      let removed: usize = vault.0.len();
+      vault.0.clear();
+      Ok(removed)
+    })
+  }
+
+  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
+    self.keypair(did, location).map(|keypair| keypair.public().clone())
+  }
+
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    let vault: FsVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    let key: &FsKey = vault.get(location).ok_or(Error::KeyNotFound)?;
+    let keypair: KeyPair = key.keypair()?;
+
+    Ok(KeyMetadata::new(
+      keypair.public().clone(),
+      keypair.type_(),
+      key.created_at,
+    ))
+  }
+
+  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    self.mutate_vault(did, false, |vault| Ok(vault.remove(location)))
+  }
+
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, false, |vault| {
+      if vault.get(old).is_none() {
+        return Err(Error::KeyNotFound);
+      }
+
+      let keypair: KeyPair = KeyPair::new(old.key_type)?;
+      let location: KeyLocation = KeyLocation::new(old.key_type, fragment.to_owned(), keypair.public().as_ref());
+
+      vault.remove(old);
+      vault.insert(FsKey::new(location.clone(), &keypair));
+
+      Ok(location)
+    })
+  }
+
+  async fn key_move(&self, did: &CoreDID, from: &KeyLocation, new_fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, false, |vault| {
+      let key: &FsKey = vault.get(from).ok_or(Error::KeyNotFound)?;
+      let location: KeyLocation = from.with_fragment(new_fragment.to_owned());
+      let moved: FsKey = FsKey {
+        location: location.clone(),
+        private_key: key.private_key.clone(),
+        created_at: key.created_at,
+      };
+
+      vault.remove(from);
+      vault.insert(moved);
+
+      Ok(location)
+    })
+  }
+
+  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
+    let keypair: KeyPair = self.keypair(did, location)?;
+
+    match location.key_type {
+      KeyType::Ed25519 => {
+        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
+        Signature::new_checked(KeyType::Ed25519, signature.to_vec())
+      }
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
+  async fn key_exchange(&self, did: &CoreDID, location: &KeyLocation, public_key: &PublicKey) -> Result<[u8; 32]> {
+    let keypair: KeyPair = self.keypair(did, location)?;
+
+    match keypair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key exchange".to_owned(),
+      )),
+      KeyType::X25519 => {
+        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key.as_ref().try_into().map_err(|_| {
+          Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
+        })?;
+        X25519::key_exchange(keypair.private(), &public_key).map_err(Into::into)
+      }
+    }
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt(
+    &self,
+    did: &CoreDID,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<EncryptedData> {
+    let sender: KeyPair = match sender_key {
+      Some(location) => self.keypair(did, location)?,
+      None => {
+        return self
+          .data_encrypt_anonymous(
+            plaintext,
+            associated_data,
+            encryption_algorithm,
+            cek_algorithm,
+            public_key,
+          )
+          .await
+      }
+    };
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      Some(&sender),
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt_anonymous(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      None,
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let keypair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    let keypair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt_into(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+      out,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt_with_conversion(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    convert_ed25519: bool,
+  ) -> Result<Vec<u8>> {
+    let mut keypair: KeyPair = self.keypair(did, private_key)?;
+    if convert_ed25519 {
+      keypair = memstore_encryption::x25519_key_pair(&keypair)?;
+    }
+    memstore_encryption::decrypt(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  async fn blob_set(&self, did: &CoreDID, blob: Vec<u8>) -> Result<()> {
+    self.blob_set_named(did, DEFAULT_BLOB_NAME, blob).await
+  }
+
+  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
+    self.blob_get_named(did, DEFAULT_BLOB_NAME).await
+  }
+
+  async fn blob_compare_and_swap(&self, did: &CoreDID, expected: Option<&[u8]>, new: Vec<u8>) -> Result<bool> {
+    let mut pending: RwLockWriteGuard<'_, _> = self.pending.write()?;
+
+    let path: PathBuf = self.path(did);
+    let mut identity: FsIdentity = self.read_file(&path)?.unwrap_or_else(|| FsIdentity::new(did.clone()));
+
+    if !ct_eq_option(identity.blobs.get(DEFAULT_BLOB_NAME).map(Vec::as_slice), expected) {
+      return Ok(false);
+    }
+
+    identity.blobs.insert(DEFAULT_BLOB_NAME.to_owned(), new);
+    self.write_file(&path, &identity)?;
+    pending.insert(path);
+
+    Ok(true)
+  }
+
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, blob: Vec<u8>) -> Result<()> {
+    self.mutate(did, |identity| {
+      identity.blobs.insert(name.to_owned(), blob);
+      Ok(())
+    })
+  }
+
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    Ok(
+      self
+        .read_identity(did)?
+        .and_then(|mut identity| identity.blobs.remove(name)),
+    )
+  }
+
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    Ok(
+      self
+        .read_identity(did)?
+        .map(|identity| identity.blobs.into_keys().collect())
+        .unwrap_or_default(),
+    )
+  }
+
+  async fn stats(&self) -> Result<StorageStats> {
+    let mut stats: StorageStats = StorageStats::default();
+
+    for identity in self.read_all()? {
+      if let Some(vault) = identity.vault.as_ref() {
+        stats.did_count += 1;
+        stats.key_count += vault.0.len();
+      }
+      stats.blob_bytes += identity.blobs.values().map(|blob| blob.len()).sum::<usize>();
+    }
+
+    Ok(stats)
+  }
+
+  async fn flush_changes(&self) -> Result<()> {
+    let mut pending: RwLockWriteGuard<'_, _> = self.pending.write()?;
+
+    for path in pending.iter() {
+      // Files removed by `did_purge` only need the directory to be synced.
+      match File::open(path) {
+        Ok(file) => file.sync_all()?,
+        Err(error) if error.kind() == ErrorKind::NotFound => {}
+        Err(error) => return Err(error.into()),
+      }
+    }
+
+    if !pending.is_empty() {
+      sync_directory(&self.directory)?;
+    }
+
+    pending.clear();
+
+    Ok(())
+  }
+}
+
+impl Debug for FsStore {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("FsStore").field("directory", &self.directory).finish()
+  }
+}
+
+/// Persists the creation, renaming and removal of files in `directory`.
+#[cfg(unix)]
+fn sync_directory(directory: &Path) -> Result<()> {
+  File::open(directory)?.sync_all().map_err(Into::into)
+}
+
+/// Directories cannot be opened as files on other platforms, where syncing them is not necessary.
+#[cfg(not(unix))]
+fn sync_directory(_directory: &Path) -> Result<()> {
+  Ok(())
+}
+
+/// Returns the associated data binding the contents of a file to its `path`.
+///
+/// The file name is used, so files cannot be swapped without being noticed.
+fn associated_data(path: &Path) -> &[u8] {
+  path
+    .file_stem()
+    .and_then(|file_stem| file_stem.to_str())
+    .unwrap_or_default()
+    .as_bytes()
+}
+
+/// The serialized representation of the data of a DID, which is stored in a single file.
+#[derive(Deserialize, Serialize)]
+struct FsIdentity {
+  did: CoreDID,
+  // `None` if only blobs were stored for the DID, which therefore does not exist.
+  vault: Option<FsVault>,
+  blobs: BTreeMap<String, Vec<u8>>,
+}
+
+impl FsIdentity {
+  fn new(did: CoreDID) -> Self {
+    Self {
+      did,
+      vault: None,
+      blobs: BTreeMap::new(),
+    }
+  }
+}
+
+/// The serialized representation of the keys of a DID.
+#[derive(Default, Deserialize, Serialize)]
+struct FsVault(Vec<FsKey>);
+
+impl FsVault {
+  fn get(&self, location: &KeyLocation) -> Option<&FsKey> {
+    self.0.iter().find(|key| &key.location == location)
+  }
+
+  /// Inserts the `key`, overwriting any key at the same location.
+  fn insert(&mut self, key: FsKey) {
+    self.remove(&key.location);
+    self.0.push(key);
+  }
+
+  /// Removes the key at `location` and returns whether it existed.
+  fn remove(&mut self, location: &KeyLocation) -> bool {
+    let len: usize = self.0.len();
+    self.0.retain(|key| &key.location != location);
+    self.0.len() != len
+  }
+}
+
+/// A private key together with its location. The private key is zeroized on drop.
+#[derive(Deserialize, Serialize)]
+struct FsKey {
+  location: KeyLocation,
+  private_key: Vec<u8>,
+  created_at: Option<Timestamp>,
+}
+
+impl FsKey {
+  fn new(location: KeyLocation, keypair: &KeyPair) -> Self {
+    Self {
+      location,
+      private_key: keypair.private().as_ref().to_vec(),
+      created_at: Some(Timestamp::now_utc()),
+    }
+  }
+
+  fn keypair(&self) -> Result<KeyPair> {
+    KeyPair::try_from_private_key_bytes(self.location.key_type, &self.private_key).map_err(Into::into)
+  }
+}
+
+impl Drop for FsKey {
+  fn drop(&mut self) {
+    self.private_key.zeroize();
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "storage-test-suite")]
+mod tests {
+  use std::path::PathBuf;
+
+  use identity_did::did::CoreDID;
+  use identity_iota_core::tangle::Network;
+  use rand::distributions::DistString;
+  use rand::rngs::OsRng;
+  use rand::Rng;
+
+  use crate::error::Error;
+  use crate::storage::Storage;
+  use crate::storage::StorageTestSuite;
+  use crate::types::DIDType;
+  use crate::types::KeyLocation;
+
+  use super::FsStore;
+
+  fn random_temporary_directory() -> PathBuf {
+    let mut directory: PathBuf = std::env::temp_dir();
+    directory.push("test_fsstores");
+    directory.push(rand::distributions::Alphanumeric.sample_string(&mut OsRng, 32));
+    directory
+  }
+
+  fn test_fsstore() -> impl Storage {
+    FsStore::open(&random_temporary_directory(), [0x42; 32]).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_fsstore_run_all() {
+    let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { test_fsstore() }).await.unwrap();
+
+    let mut expected: Vec<&'static str> = vec![
+      "key_expiry_test",
+      "did_metadata_test",
+      "did_timestamps_test",
+      "blob_delete_test",
+      "did_export_import_test",
+      "data_decrypt_expired_key_test",
+    ];
+    #[cfg(feature = "encryption")]
+    expected.push("data_encrypt_stream_test");
+    assert_eq!(skipped, expected);
+  }
+
+  #[tokio::test]
+  async fn test_fsstore_close() {
+    let directory: PathBuf = random_temporary_directory();
+    let storage: FsStore = FsStore::open(&directory, [0x42; 32]).unwrap();
+    StorageTestSuite::close_test(storage, move || FsStore::open(&directory, [0x42; 32]).unwrap())
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_fsstore_chain_state_persistence() {
+    let directory: PathBuf = random_temporary_directory();
+    let storage: FsStore = FsStore::open(&directory, [0x42; 32]).unwrap();
+    StorageTestSuite::chain_state_persistence_test(storage, move || FsStore::open(&directory, [0x42; 32]).unwrap())
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_fsstore_reopen() {
+    let directory: PathBuf = random_temporary_directory();
+    let master_key: [u8; 32] = OsRng.gen();
+
+    let fsstore: FsStore = FsStore::open(&directory, master_key).unwrap();
+    let (did, location): (CoreDID, KeyLocation) = fsstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+    fsstore.blob_set(&did, b"blob".to_vec()).await.unwrap();
+    fsstore.flush_changes().await.unwrap();
+    drop(fsstore);
+
+    let fsstore: FsStore = FsStore::open(&directory, master_key).unwrap();
+    assert_eq!(fsstore.did_list().await.unwrap(), vec![did.clone()]);
+    assert!(fsstore.key_exists(&did, &location).await.unwrap());
+    assert_eq!(fsstore.blob_get(&did).await.unwrap(), Some(b"blob".to_vec()));
+
+    // The files cannot be decrypted with a different master key.
+    let fsstore: FsStore = FsStore::open(&directory, [0; 32]).unwrap();
+    assert!(matches!(
+      fsstore.key_exists(&did, &location).await.unwrap_err(),
+      Error::DecryptionFailure(_)
+    ));
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_fsstore_concurrency() {
+    StorageTestSuite::concurrency_test(test_fsstore()).await.unwrap()
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_fsstore_blob_append() {
+    StorageTestSuite::blob_append_test(test_fsstore()).await.unwrap()
+  }
+}
@@ -5,53 +5,223 @@ use core::fmt::Debug;
 use core::fmt::Formatter;
 
 use async_trait::async_trait;
-#[cfg(feature = "encryption")]
 use crypto::ciphers::aes_gcm::Aes256Gcm;
-#[cfg(feature = "encryption")]
-use crypto::ciphers::aes_kw::Aes256Kw;
-#[cfg(feature = "encryption")]
 use crypto::ciphers::traits::Aead;
+use crypto::hashes::sha::Sha256;
+use crypto::hashes::sha::SHA256_LEN;
+use crypto::hashes::Digest;
+use crypto::keys::pbkdf::PBKDF2_HMAC_SHA512;
 use hashbrown::HashMap;
+use hashbrown::HashSet;
+use identity_core::common::Timestamp;
+use identity_core::convert::FromJson;
+use identity_core::convert::ToJson;
 use identity_core::crypto::Ed25519;
 use identity_core::crypto::KeyPair;
 use identity_core::crypto::KeyType;
 use identity_core::crypto::PrivateKey;
 use identity_core::crypto::PublicKey;
 use identity_core::crypto::Sign;
-#[cfg(feature = "encryption")]
 use identity_core::crypto::X25519;
+use identity_core::utils::Base;
+use identity_core::utils::BaseEncoding;
 use identity_did::did::CoreDID;
-use identity_iota_core::did::IotaDID;
+use identity_did::did::DID;
 use identity_iota_core::tangle::NetworkName;
+use serde::Deserialize;
+use serde::Serialize;
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::hash::Hash;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+#[cfg(feature = "encryption")]
+use std::sync::Mutex;
+#[cfg(feature = "encryption")]
+use std::sync::MutexGuard;
 use std::sync::RwLockReadGuard;
 use std::sync::RwLockWriteGuard;
 use zeroize::Zeroize;
+use zeroize::Zeroizing;
 
 use crate::error::Error;
 use crate::error::Result;
+#[cfg(feature = "encryption")]
+use crate::storage::DataStreamReader;
+#[cfg(feature = "encryption")]
+use crate::storage::DataStreamWriter;
 use crate::storage::Storage;
+use crate::storage::TransactionFuture;
+use crate::storage::DEFAULT_BLOB_NAME;
 #[cfg(feature = "encryption")]
 use crate::types::CekAlgorithm;
+use crate::types::ConflictPolicy;
 use crate::types::DIDType;
 #[cfg(feature = "encryption")]
 use crate::types::EncryptedData;
 #[cfg(feature = "encryption")]
 use crate::types::EncryptionAlgorithm;
 use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
+use crate::types::MergeReport;
 use crate::types::Signature;
+use crate::types::StorageStats;
+use crate::utils::ct_eq_option;
+#[cfg(feature = "tracing")]
+use crate::utils::did_hash;
 use crate::utils::Shared;
 
+// The map type of the vaults and blobs. With the `deterministic-order` feature it iterates in insertion order,
+// so that serializing the same store twice yields the same bytes.
+#[cfg(not(feature = "deterministic-order"))]
+type MemMap<K, V> = HashMap<K, V>;
+#[cfg(feature = "deterministic-order")]
+type MemMap<K, V> = indexmap::IndexMap<K, V>;
 // The map from DIDs to vaults.
-type Vaults = HashMap<CoreDID, MemVault>;
+type Vaults = MemMap<CoreDID, MemVault>;
+// The map from names to blobs, that lives within a DID partition.
+type NamedBlobs = MemMap<String, Vec<u8>>;
+// The map from DIDs to their named blobs.
+type Blobs = MemMap<CoreDID, NamedBlobs>;
+// The map from DIDs to their metadata.
+type Metadata = HashMap<CoreDID, BTreeMap<String, String>>;
+// The map from DIDs to the times they were created and last updated.
+type Timestamps = HashMap<CoreDID, (Timestamp, Timestamp)>;
 // The map from key locations to key pairs, that lives within a DID partition.
-type MemVault = HashMap<KeyLocation, KeyPair>;
+type MemVault = MemMap<KeyLocation, MemKey>;
+
+/// Removes the entry for `key` from the `map`.
+#[cfg(not(feature = "deterministic-order"))]
+fn map_remove<K, V, Q>(map: &mut MemMap<K, V>, key: &Q) -> Option<V>
+where
+  K: Hash + Eq + Borrow<Q>,
+  Q: Hash + Eq + ?Sized,
+{
+  map.remove(key)
+}
+
+/// Removes the entry for `key` from the `map`, without changing the order of the remaining entries.
+#[cfg(feature = "deterministic-order")]
+fn map_remove<K, V, Q>(map: &mut MemMap<K, V>, key: &Q) -> Option<V>
+where
+  K: Hash + Eq + Borrow<Q>,
+  Q: Hash + Eq + ?Sized,
+{
+  map.shift_remove(key)
+}
+// A replacement for the random number generator used in encryption.
+#[cfg(feature = "encryption")]
+type RandomFill = Box<dyn FnMut(&mut [u8]) + Send>;
+
+/// A key pair stored in a [`MemVault`] along with its metadata.
+#[derive(Clone)]
+struct MemKey {
+  keypair: KeyPair,
+  created_at: Option<Timestamp>,
+  expires_at: Option<Timestamp>,
+}
+
+impl MemKey {
+  fn new(keypair: KeyPair) -> Self {
+    Self {
+      keypair,
+      created_at: Some(Timestamp::now_utc()),
+      expires_at: None,
+    }
+  }
+
+  /// Returns `true` if the expiry time of the key has passed.
+  fn is_expired(&self, now: Timestamp) -> bool {
+    match self.expires_at {
+      Some(expires_at) => expires_at <= now,
+      None => false,
+    }
+  }
+}
+
+/// Looks up the key at `location` in the `vault`, failing if it does not exist or has expired.
+fn usable_key<'vault>(vault: &'vault MemVault, location: &KeyLocation) -> Result<&'vault MemKey> {
+  let key: &MemKey = vault.get(location).ok_or(Error::KeyNotFound)?;
+  if key.is_expired(Timestamp::now_utc()) {
+    return Err(Error::KeyExpired);
+  }
+  Ok(key)
+}
+
+// Never print the private key, since the expanded `MemStore` debug output tends to end up in logs.
+impl Debug for MemKey {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    let public_key: String = self
+      .keypair
+      .public()
+      .as_ref()
+      .iter()
+      .map(|byte| format!("{byte:02x}"))
+      .collect();
+
+    f.debug_struct("MemKey")
+      .field("key_type", &self.keypair.type_())
+      .field("public_key", &format_args!("{public_key}"))
+      .field("private_key", &format_args!("<redacted>"))
+      .field("created_at", &self.created_at)
+      .field("expires_at", &self.expires_at)
+      .finish()
+  }
+}
+
+/// Tracks the order in which DIDs were used, so that the least-recently-used DID can be evicted.
+#[derive(Clone, Default)]
+struct MemUsage {
+  // A logical clock that advances on every use.
+  clock: u64,
+  last_used: HashMap<CoreDID, u64>,
+  // The DIDs evicted since the last call to `MemStore::take_evicted`, bounded by the capacity.
+  evicted: Vec<CoreDID>,
+}
+
+impl MemUsage {
+  /// Marks the `did` as most recently used, if it is tracked.
+  fn touch(&mut self, did: &CoreDID) {
+    self.clock += 1;
+    if let Some(last_used) = self.last_used.get_mut(did) {
+      *last_used = self.clock;
+    }
+  }
+
+  /// Starts tracking the `did` as most recently used.
+  fn insert(&mut self, did: CoreDID) {
+    self.clock += 1;
+    self.last_used.insert(did, self.clock);
+  }
+
+  fn least_recently_used(&self) -> Option<CoreDID> {
+    self
+      .last_used
+      .iter()
+      .min_by_key(|(_, last_used)| **last_used)
+      .map(|(did, _)| did.clone())
+  }
+}
 
 /// An insecure, in-memory [`Storage`] implementation that serves as an example and is used in tests.
+///
+/// Cloning a `MemStore` returns another handle to the same underlying state rather than a deep copy,
+/// so mutations made through one clone are visible through all others.
 pub struct MemStore {
   // Controls whether to print the storages content when debugging.
   expand: bool,
-  blobs: Shared<HashMap<CoreDID, Vec<u8>>>,
-  vaults: Shared<Vaults>,
+  blobs: Arc<Shared<Blobs>>,
+  vaults: Arc<Shared<Vaults>>,
+  metadata: Arc<Shared<Metadata>>,
+  timestamps: Arc<Shared<Timestamps>>,
+  // The maximum number of DIDs, if any.
+  capacity: Option<usize>,
+  usage: Arc<Shared<MemUsage>>,
+  // Controls whether the next call to `flush_changes` fails.
+  fail_next_flush: Arc<AtomicBool>,
+  #[cfg(feature = "encryption")]
+  random: Arc<Mutex<Option<RandomFill>>>,
 }
 
 impl MemStore {
@@ -59,11 +229,43 @@ impl MemStore {
   pub fn new() -> Self {
     Self {
       expand: false,
-      blobs: Shared::new(HashMap::new()),
-      vaults: Shared::new(HashMap::new()),
+      blobs: Arc::new(Shared::new(MemMap::new())),
+      vaults: Arc::new(Shared::new(MemMap::new())),
+      metadata: Arc::new(Shared::new(HashMap::new())),
+      timestamps: Arc::new(Shared::new(HashMap::new())),
+      capacity: None,
+      usage: Arc::new(Shared::new(MemUsage::default())),
+      fail_next_flush: Arc::new(AtomicBool::new(false)),
+      #[cfg(feature = "encryption")]
+      random: Arc::new(Mutex::new(None)),
     }
   }
 
+  /// Creates a new, empty `MemStore` instance that holds at most `max_dids` DIDs.
+  ///
+  /// When a new DID would exceed the capacity, the least-recently-used DID is evicted together with
+  /// its keys and blobs. Every operation on a DID marks it as used. The evicted DIDs can be retrieved
+  /// with [`MemStore::take_evicted`], which returns at most the `max_dids` most recently evicted ones.
+  pub fn with_capacity(max_dids: usize) -> Self {
+    Self {
+      capacity: Some(max_dids),
+      ..Self::new()
+    }
+  }
+
+  /// Returns the maximum number of DIDs, if this `MemStore` was created with a capacity.
+  pub fn capacity(&self) -> Option<usize> {
+    self.capacity
+  }
+
+  /// Returns the DIDs evicted since the last call to this method, from least to most recently evicted.
+  ///
+  /// Only the [`capacity`](MemStore::capacity) most recently evicted DIDs are kept, so the evictions are
+  /// bounded in memory even if this method is never called.
+  pub fn take_evicted(&self) -> Result<Vec<CoreDID>> {
+    Ok(core::mem::take(&mut self.usage.write()?.evicted))
+  }
+
   /// Returns whether to expand the debug representation.
   pub fn expand(&self) -> bool {
     self.expand
@@ -73,341 +275,2428 @@ impl MemStore {
   pub fn set_expand(&mut self, value: bool) {
     self.expand = value;
   }
-}
 
-// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
-#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
-#[cfg_attr(feature = "send-sync-storage", async_trait)]
-impl Storage for MemStore {
-  async fn did_create(
-    &self,
-    did_type: DIDType,
-    network: NetworkName,
-    fragment: &str,
-    private_key: Option<PrivateKey>,
-  ) -> Result<(CoreDID, KeyLocation)> {
-    // Extract a `KeyPair` from the passed private key or generate a new one.
-    // For `did_create` we can assume the `KeyType` to be `Ed25519` because
-    // that is the only currently available signature type.
-    let keypair: KeyPair = match private_key {
-      Some(private_key) => KeyPair::try_from_private_key_bytes(KeyType::Ed25519, private_key.as_ref())?,
-      None => KeyPair::new(KeyType::Ed25519)?,
-    };
+  /// Makes the next call to [`Storage::flush_changes`] fail with an [`Error::IoError`], as a persistent
+  /// backend might. This is only meant for testing the handling of flush failures.
+  pub fn fail_next_flush(&self) {
+    self.fail_next_flush.store(true, Ordering::SeqCst);
+  }
 
-    // We create the location at which the key pair will be stored.
-    // Most notably, this uses the public key as an input.
-    let location: KeyLocation = KeyLocation::new(KeyType::Ed25519, fragment.to_owned(), keypair.public().as_ref());
+  /// Clears the poisoned state of all locks in this `MemStore`.
+  ///
+  /// A lock is poisoned when a thread panics while holding it, after which every operation fails with
+  /// [`Error::LockPoisoned`]. Since the panic may have happened in the middle of a write, the contents
+  /// of the store may be inconsistent after recovering, e.g. a DID whose key vault was only partially
+  /// updated.
+  pub fn clear_poison(&self) {
+    self.blobs.clear_poison();
+    self.vaults.clear_poison();
+    self.metadata.clear_poison();
+    self.timestamps.clear_poison();
+    self.usage.clear_poison();
+    #[cfg(feature = "encryption")]
+    self.random.clear_poison();
+  }
 
-    // Next we use the public key to derive the initial DID.
-    let did: CoreDID = {
-      match did_type {
-        DIDType::IotaDID => IotaDID::new_with_network(keypair.public().as_ref(), network)
-          .map_err(|err| crate::Error::DIDCreationError(err.to_string()))?
-          .into(),
-      }
-    };
+  /// Replaces the random number generator used for ephemeral keys, content encryption keys and nonces
+  /// in [`Storage::data_encrypt`] with `fill`, which must fill the given buffer with bytes.
+  ///
+  /// WARNING: This is only meant for producing reproducible test vectors. Encrypting with predictable
+  /// randomness breaks the confidentiality of the encrypted data, so this is only available in tests and
+  /// with the `storage-test-suite` feature.
+  #[cfg(all(feature = "encryption", any(test, feature = "storage-test-suite")))]
+  pub fn set_rng_for_testing<F>(&self, fill: F) -> Result<()>
+  where
+    F: FnMut(&mut [u8]) + Send + 'static,
+  {
+    *self.random.lock().map_err(|_| Error::LockPoisoned("exclusive"))? = Some(Box::new(fill));
+    Ok(())
+  }
 
-    // Obtain exclusive access to the vaults.
-    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+  /// Encrypts the `plaintext` with [`memstore_encryption::encrypt`], using the random number generator set
+  /// for testing, if any.
+  #[cfg(feature = "encryption")]
+  fn encrypt(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender: Option<&KeyPair>,
+  ) -> Result<EncryptedData> {
+    let mut random: MutexGuard<'_, Option<RandomFill>> =
+      self.random.lock().map_err(|_| Error::LockPoisoned("exclusive"))?;
+    match random.as_mut() {
+      Some(fill) => memstore_encryption::encrypt(
+        plaintext,
+        associated_data,
+        encryption_algorithm,
+        cek_algorithm,
+        public_key,
+        sender,
+        &mut |bytes| {
+          fill(bytes);
+          Ok(())
+        },
+      ),
+      None => memstore_encryption::encrypt(
+        plaintext,
+        associated_data,
+        encryption_algorithm,
+        cek_algorithm,
+        public_key,
+        sender,
+        &mut memstore_encryption::os_random,
+      ),
+    }
+  }
 
-    // We use the vaults as the index of DIDs stored in this storage instance.
-    // If the DID already exists, we need to return an error. We don't want to overwrite an existing DID.
-    if vaults.contains_key(&did) {
-      return Err(Error::IdentityAlreadyExists);
+  /// Marks the given `did` as most recently used, if this `MemStore` has a capacity.
+  fn touch(&self, did: &CoreDID) -> Result<()> {
+    if self.capacity.is_some() {
+      self.usage.write()?.touch(did);
     }
+    Ok(())
+  }
 
-    // Obtain the exiting mem vault or create a new one.
-    let vault: &mut MemVault = vaults.entry(did.clone()).or_default();
+  /// Marks the given `did` as updated now, if it exists.
+  fn record_update(&self, did: &CoreDID) -> Result<()> {
+    if let Some((_, updated)) = self.timestamps.write()?.get_mut(did) {
+      *updated = Timestamp::now_utc();
+    }
+    Ok(())
+  }
 
-    // Insert the key pair at the previously created location.
-    vault.insert(location.clone(), keypair);
+  /// Returns the vault of the given `did`, inserting an empty vault if it does not exist.
+  ///
+  /// The `did` is marked as created now if its vault is inserted and as updated otherwise, since the vault
+  /// is only requested in order to modify it.
+  ///
+  /// If this `MemStore` has a capacity, the `did` is marked as most recently used and the least-recently-used
+  /// DIDs are evicted as long as the capacity is exceeded. Must not be called while holding the blobs, metadata
+  /// or timestamps lock.
+  fn vault_entry<'vaults>(&self, vaults: &'vaults mut Vaults, did: &CoreDID) -> Result<&'vaults mut MemVault> {
+    if let Some(capacity) = self.capacity {
+      let mut usage: RwLockWriteGuard<'_, MemUsage> = self.usage.write()?;
+      usage.insert(did.clone());
+      vaults.entry(did.clone()).or_default();
+
+      let mut evicted: Vec<CoreDID> = Vec::new();
+      while vaults.len() > capacity {
+        // The given DID is the most recently used one, so it is only returned if nothing else can be evicted.
+        match usage.least_recently_used() {
+          Some(lru) if &lru != did => {
+            map_remove(vaults, &lru);
+            usage.last_used.remove(&lru);
+            evicted.push(lru);
+          }
+          _ => break,
+        }
+      }
 
-    // Return did and location.
-    Ok((did, location))
+      if !evicted.is_empty() {
+        let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+        let mut metadata: RwLockWriteGuard<'_, _> = self.metadata.write()?;
+        let mut timestamps: RwLockWriteGuard<'_, _> = self.timestamps.write()?;
+        for did in evicted.iter() {
+          map_remove(&mut blobs, did);
+          metadata.remove(did);
+          timestamps.remove(did);
+        }
+        usage.evicted.extend(evicted);
+        let excess: usize = usage.evicted.len().saturating_sub(capacity);
+        usage.evicted.drain(..excess);
+      }
+    }
+
+    let now: Timestamp = Timestamp::now_utc();
+    self
+      .timestamps
+      .write()?
+      .entry(did.clone())
+      .and_modify(|(_, updated)| *updated = now)
+      .or_insert((now, now));
+
+    Ok(vaults.entry(did.clone()).or_default())
   }
 
-  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
-    // This method is supposed to be idempotent,
-    // so we only need to do work if the DID still exists.
-    // The return value signals whether the DID was actually removed during this operation.
-    if self.vaults.write()?.remove(did).is_some() {
-      let _ = self.blobs.write()?.remove(did);
-      Ok(true)
-    } else {
-      Ok(false)
-    }
+  /// Returns a copy of the vaults, blobs, metadata, timestamps and usage of this `MemStore`.
+  ///
+  /// Each lock is released before the next one is acquired, so this cannot deadlock with other operations.
+  fn state(&self) -> Result<MemState> {
+    Ok(MemState {
+      vaults: self.vaults.read()?.clone(),
+      blobs: self.blobs.read()?.clone(),
+      metadata: self.metadata.read()?.clone(),
+      timestamps: self.timestamps.read()?.clone(),
+      usage: self.usage.read()?.clone(),
+    })
   }
 
-  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
-    // Note that any failure to get access to the storage and do the actual existence check
-    // should result in an error rather than returning `false`.
-    Ok(self.vaults.read()?.contains_key(did))
+  /// Replaces the state of this `MemStore` with the given copy.
+  fn restore(&self, state: MemState) -> Result<()> {
+    *self.vaults.write()? = state.vaults;
+    *self.blobs.write()? = state.blobs;
+    *self.metadata.write()? = state.metadata;
+    *self.timestamps.write()? = state.timestamps;
+    *self.usage.write()? = state.usage;
+    Ok(())
   }
 
-  async fn did_list(&self) -> Result<Vec<CoreDID>> {
-    Ok(self.vaults.read()?.keys().cloned().collect())
+  /// Returns a copy of the key pair at `location` of the given `did`.
+  #[cfg(feature = "encryption")]
+  fn keypair(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyPair> {
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    Ok(usable_key(vault, location)?.keypair.clone())
   }
 
-  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
-    // Obtain exclusive access to the vaults.
-    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
-    // Get or insert the MemVault.
-    let vault: &mut MemVault = vaults.entry(did.clone()).or_default();
+  /// Invokes `f` for every stored DID, without collecting them into a `Vec` like [`Storage::did_list`].
+  ///
+  /// The closure runs while the read lock on the vaults is held, so it must not call back into this
+  /// `MemStore`, which could deadlock.
+  pub fn with_dids<F: FnMut(&CoreDID)>(&self, mut f: F) -> Result<()> {
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    for did in vaults.keys() {
+      f(did);
+    }
+    Ok(())
+  }
 
-    // Generate a new key pair for the given `key_type`.
-    let keypair: KeyPair = KeyPair::new(key_type)?;
+  /// Returns a SHA-256 digest of the vaults and blobs of this `MemStore`, which is equal for stores
+  /// that compare equal regardless of the order in which their contents were inserted.
+  ///
+  /// Keys are represented by their location and public key, and their creation times are ignored.
+  pub fn fingerprint(&self) -> Result<[u8; SHA256_LEN]> {
+    fn update(hasher: &mut Sha256, bytes: &[u8]) {
+      // Length-prefix every field, so that the boundaries between fields are unambiguous.
+      hasher.update((bytes.len() as u64).to_le_bytes());
+      hasher.update(bytes);
+    }
 
-    // Derive the key location from the fragment and public key and set the `KeyType` of the location.
-    let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let blobs: RwLockReadGuard<'_, _> = self.blobs.read()?;
+    let mut hasher: Sha256 = Sha256::new();
+
+    let mut sorted_vaults: Vec<(&CoreDID, &MemVault)> = vaults.iter().collect();
+    sorted_vaults.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+    update(&mut hasher, b"vaults");
+    for (did, vault) in sorted_vaults {
+      update(&mut hasher, did.as_str().as_bytes());
+      let mut keys: Vec<(String, &MemKey)> = vault
+        .iter()
+        .map(|(location, key)| (location.canonical(), key))
+        .collect();
+      keys.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+      for (location, key) in keys {
+        update(&mut hasher, location.as_bytes());
+        update(&mut hasher, key.keypair.type_().as_str().as_bytes());
+        update(&mut hasher, key.keypair.public().as_ref());
+      }
+    }
 
-    vault.insert(location.clone(), keypair);
+    let mut sorted_blobs: Vec<(&CoreDID, &NamedBlobs)> = blobs.iter().collect();
+    sorted_blobs.sort_unstable_by(|(a, _), (b, _)| a.as_str().cmp(b.as_str()));
+    update(&mut hasher, b"blobs");
+    for (did, named_blobs) in sorted_blobs {
+      update(&mut hasher, did.as_str().as_bytes());
+      let mut named_blobs: Vec<(&String, &Vec<u8>)> = named_blobs.iter().collect();
+      named_blobs.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+      for (name, blob) in named_blobs {
+        update(&mut hasher, name.as_bytes());
+        update(&mut hasher, blob);
+      }
+    }
 
-    // Return the location at which the key was generated.
-    Ok(location)
+    Ok(hasher.finalize().into())
   }
 
-  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
-    // Obtain exclusive access to the vaults.
-    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
-    // Get or insert the MemVault.
-    let vault: &mut MemVault = vaults.entry(did.clone()).or_default();
+  /// Returns whether the vaults and blobs of this `MemStore` and `other` hold the same contents.
+  fn content_eq(&self, other: &MemStore) -> Result<bool> {
+    // Clones share their state, and acquiring the same read lock twice could deadlock.
+    if Arc::ptr_eq(&self.vaults, &other.vaults) && Arc::ptr_eq(&self.blobs, &other.blobs) {
+      return Ok(true);
+    }
 
-    // Reconstruct the key pair from the given private key by inspecting the location for its key type.
-    // Then insert the key at the given location.
-    match location.key_type {
-      KeyType::Ed25519 => {
-        let keypair: KeyPair = KeyPair::try_from_private_key_bytes(KeyType::Ed25519, private_key.as_ref())
-          .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
-        private_key.zeroize();
+    let vaults_eq: bool = {
+      let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+      let other_vaults: RwLockReadGuard<'_, _> = other.vaults.read()?;
+      vaults.len() == other_vaults.len()
+        && vaults.iter().all(|(did, vault)| match other_vaults.get(did) {
+          Some(other_vault) => {
+            vault.len() == other_vault.len()
+              && vault.iter().all(|(location, key)| match other_vault.get(location) {
+                Some(other_key) => {
+                  key.keypair.type_() == other_key.keypair.type_()
+                    && key.keypair.public().as_ref() == other_key.keypair.public().as_ref()
+                }
+                None => false,
+              })
+          }
+          None => false,
+        })
+    };
 
-        vault.insert(location.to_owned(), keypair);
+    Ok(vaults_eq && *self.blobs.read()? == *other.blobs.read()?)
+  }
 
-        Ok(())
-      }
-      KeyType::X25519 => {
-        let keypair: KeyPair = KeyPair::try_from_private_key_bytes(KeyType::X25519, private_key.as_ref())
-          .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
-        private_key.zeroize();
+  /// Serializes the vaults and blobs of this `MemStore` into a versioned byte representation,
+  /// which can be loaded again with [`MemStore::from_bytes`].
+  ///
+  /// The vaults and blobs are serialized in an unspecified order, unless the `deterministic-order` feature
+  /// is enabled, which serializes them in insertion order. Then, a store serializes to the same bytes as
+  /// the store loaded from them.
+  ///
+  /// WARNING: The returned bytes contain all private keys in plaintext. Never persist them anywhere
+  /// other than in tests or local tooling.
+  pub fn to_bytes(&self) -> Result<Vec<u8>> {
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let blobs: RwLockReadGuard<'_, _> = self.blobs.read()?;
+
+    let snapshot: MemStoreSnapshot = MemStoreSnapshot {
+      version: MemStoreSnapshot::VERSION,
+      vaults: vaults
+        .iter()
+        .map(|(did, vault)| MemVaultSnapshot::new(did, vault))
+        .collect(),
+      blobs: blobs
+        .iter()
+        .flat_map(|(did, named_blobs)| {
+          named_blobs.iter().map(|(name, blob)| MemBlobSnapshot {
+            did: did.clone(),
+            name: name.clone(),
+            blob: blob.clone(),
+          })
+        })
+        .collect(),
+    };
 
-        vault.insert(location.to_owned(), keypair);
+    snapshot.to_json_vec().map_err(Into::into)
+  }
 
-        Ok(())
+  /// Loads a `MemStore` from the byte representation created by [`MemStore::to_bytes`].
+  pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+    // Check the version first, since the layout of other versions might not deserialize.
+    let version: u8 = MemStoreSnapshotVersion::from_json_slice(bytes)?.version;
+
+    let mut blobs: Blobs = MemMap::new();
+    let vault_snapshots: Vec<MemVaultSnapshot> = match version {
+      // Version 1 only supported a single blob per DID.
+      1 => {
+        let snapshot: MemStoreSnapshotV1 = MemStoreSnapshotV1::from_json_slice(bytes)?;
+        for (did, blob) in snapshot.blobs {
+          blobs.entry(did).or_default().insert(DEFAULT_BLOB_NAME.to_owned(), blob);
+        }
+        snapshot.vaults
+      }
+      MemStoreSnapshot::VERSION => {
+        let snapshot: MemStoreSnapshot = MemStoreSnapshot::from_json_slice(bytes)?;
+        for blob in snapshot.blobs {
+          blobs.entry(blob.did).or_default().insert(blob.name, blob.blob);
+        }
+        snapshot.vaults
       }
+      _ => return Err(Error::UnsupportedVersion(version)),
+    };
+
+    let mut vaults: Vaults = MemMap::with_capacity(vault_snapshots.len());
+    for vault_snapshot in vault_snapshots.iter() {
+      vaults.insert(vault_snapshot.did.clone(), vault_snapshot.to_vault()?);
     }
+
+    Ok(Self {
+      blobs: Arc::new(Shared::new(blobs)),
+      timestamps: Arc::new(Shared::new(loaded_timestamps(&vaults))),
+      vaults: Arc::new(Shared::new(vaults)),
+      ..Self::new()
+    })
   }
 
-  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
-    // Obtain read access to the vaults.
+  /// Exports all DIDs of this `MemStore` with their keys and blobs as JSON, for tooling that migrates
+  /// them into another [`Storage`] implementation. The schema is stable and can be read with
+  /// [`MemStore::import_json`].
+  ///
+  /// **The JSON contains every private key in plaintext.** It is meant to be consumed by a migration
+  /// script only and must not be persisted or transmitted. Use [`MemStore::to_bytes`] to persist a
+  /// `MemStore` and [`Storage::did_export`] for encrypted backups.
+  ///
+  /// The schema in version 1 is:
+  ///
+  /// ```json
+  /// {
+  ///   "version": 1,
+  ///   "dids": [
+  ///     {
+  ///       "did": "did:iota:...",
+  ///       "keys": [
+  ///         {
+  ///           "type": "Ed25519",
+  ///           "fragment": "sign-0",
+  ///           "index": null,
+  ///           "public_key": "<base58btc>",
+  ///           "private_key": "<base58btc>",
+  ///           "created_at": "2022-01-01T00:00:00Z",
+  ///           "expires_at": null
+  ///         }
+  ///       ],
+  ///       "blobs": {
+  ///         "__default__": "<base64>"
+  ///       }
+  ///     }
+  ///   ]
+  /// }
+  /// ```
+  ///
+  /// - `type` is either `Ed25519` or `X25519`.
+  /// - `index` is the derivation index of keys stored at a [`KeyLocation::with_index`] location and `null` for all
+  ///   other keys, whose location is derived from `type`, `fragment` and `public_key`.
+  /// - `created_at` and `expires_at` are RFC 3339 timestamps or `null`.
+  /// - `keys` is `null` for a DID that only has blobs, but was never created in this store.
+  /// - `blobs` maps the name of every blob to its standard, padded base64 encoding. The blob set with
+  ///   [`Storage::blob_set`] is named [`DEFAULT_BLOB_NAME`].
+  pub fn export_json(&self) -> Result<String> {
     let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let blobs: RwLockReadGuard<'_, _> = self.blobs.read()?;
+
+    let mut dids: Vec<MemDIDExport> = vaults
+      .iter()
+      .map(|(did, vault)| MemDIDExport::new(did, Some(vault), blobs.get(did)))
+      .collect();
+    dids.extend(
+      blobs
+        .iter()
+        .filter(|(did, _)| !vaults.contains_key(*did))
+        .map(|(did, named_blobs)| MemDIDExport::new(did, None, Some(named_blobs))),
+    );
+
+    let export: MemStoreExport = MemStoreExport {
+      version: MemStoreExport::VERSION,
+      dids,
+    };
 
-    // Within the DID vault, check for existence of the given location.
-    if let Some(vault) = vaults.get(did) {
-      return Ok(vault.contains_key(location));
+    export.to_json_pretty().map_err(Into::into)
+  }
+
+  /// Loads a `MemStore` from the JSON created by [`MemStore::export_json`].
+  ///
+  /// Returns an error without loading anything if the JSON does not match the schema, a key is invalid,
+  /// a public key does not belong to its private key or a DID occurs more than once.
+  pub fn import_json(json: &str) -> Result<Self> {
+    // Check the version first, since the schema of other versions might not deserialize.
+    let version: u8 = MemStoreSnapshotVersion::from_json(json)?.version;
+    if version != MemStoreExport::VERSION {
+      return Err(Error::UnsupportedVersion(version));
     }
 
-    Ok(false)
-  }
+    let export: MemStoreExport = MemStoreExport::from_json(json)?;
 
-  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
-    // Obtain read access to the vaults.
-    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
-    // Lookup the vault for the given DID.
-    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
-    // Lookup the key pair within the vault.
-    let keypair: &KeyPair = vault.get(location).ok_or(Error::KeyNotFound)?;
+    let mut vaults: Vaults = MemMap::new();
+    let mut blobs: Blobs = MemMap::new();
+    for entry in export.dids.iter() {
+      if vaults.contains_key(&entry.did) || blobs.contains_key(&entry.did) {
+        return Err(Error::IdentityAlreadyExists);
+      }
 
-    // Return the public key.
-    Ok(keypair.public().clone())
-  }
+      if let Some(keys) = entry.keys.as_ref() {
+        let mut vault: MemVault = MemMap::with_capacity(keys.len());
+        for key in keys.iter() {
+          let (location, key): (KeyLocation, MemKey) = key.to_key()?;
+          vault.insert(location, key);
+        }
+        vaults.insert(entry.did.clone(), vault);
+      }
 
-  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
-    // Obtain read access to the vaults.
-    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
-    // Lookup the vault for the given DID.
-    let vault: &mut MemVault = vaults.get_mut(did).ok_or(Error::KeyVaultNotFound)?;
+      if !entry.blobs.is_empty() {
+        let mut named_blobs: NamedBlobs = MemMap::with_capacity(entry.blobs.len());
+        for (name, blob) in entry.blobs.iter() {
+          named_blobs.insert(name.clone(), BaseEncoding::decode(blob, Base::Base64Pad)?);
+        }
+        blobs.insert(entry.did.clone(), named_blobs);
+      }
+    }
 
-    // This method is supposed to be idempotent, so we delete the key
-    // if it exists and return whether it was actually deleted during this operation.
-    Ok(vault.remove(location).is_some())
+    Ok(Self {
+      blobs: Arc::new(Shared::new(blobs)),
+      timestamps: Arc::new(Shared::new(loaded_timestamps(&vaults))),
+      vaults: Arc::new(Shared::new(vaults)),
+      ..Self::new()
+    })
   }
 
-  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
-    // Obtain read access to the vaults.
-    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
-    // Lookup the vault for the given DID.
-    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
-    // Lookup the key pair within the vault.
-    let keypair: &KeyPair = vault.get(location).ok_or(Error::KeyNotFound)?;
+  /// Merges the DIDs of `other` into this `MemStore`, e.g. after an offline device synced its store.
+  ///
+  /// Every DID of `other` is merged together with its keys, blobs and metadata. A DID that exists in both
+  /// stores is resolved as a whole according to the `conflict` policy, so its keys and blobs are never
+  /// mixed. With [`ConflictPolicy::Error`], [`Error::IdentityAlreadyExists`] is returned without merging
+  /// anything if any DID exists in both stores. Blobs of DIDs that do not exist in `other` are not merged.
+  pub fn merge(&self, other: &MemStore, conflict: ConflictPolicy) -> Result<MergeReport> {
+    // Copying the state of `other` releases its locks, so merging a store with a clone of itself cannot deadlock.
+    let mut other_state: MemState = other.state()?;
 
-    match location.key_type {
-      KeyType::Ed25519 => {
-        assert_eq!(keypair.type_(), KeyType::Ed25519);
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
 
-        // Use the `Ed25519` API to sign the given data with the private key.
-        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
-        // Construct a new `Signature` wrapper with the returned signature bytes.
-        let signature: Signature = Signature::new(signature.to_vec());
-        Ok(signature)
+    // Check all DIDs for conflicts before merging anything, so that a failing merge leaves no partial state.
+    let mut report: MergeReport = MergeReport::default();
+    let mut merged: Vec<(CoreDID, MemVault)> = Vec::with_capacity(other_state.vaults.len());
+    for (did, vault) in other_state.vaults {
+      if !vaults.contains_key(&did) {
+        report.added += 1;
+      } else {
+        match conflict {
+          ConflictPolicy::KeepSelf => {
+            report.skipped += 1;
+            continue;
+          }
+          ConflictPolicy::KeepOther => report.overwritten += 1,
+          ConflictPolicy::Error => return Err(Error::IdentityAlreadyExists),
+        }
       }
-      KeyType::X25519 => {
-        // Calling key_sign on key types that cannot be signed with should return an error.
-        return Err(identity_did::Error::InvalidMethodType.into());
+      merged.push((did, vault));
+    }
+
+    // The blobs and metadata are replaced first, since inserting the vaults may evict DIDs, which removes
+    // their blobs and metadata as well.
+    {
+      let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+      let mut metadata: RwLockWriteGuard<'_, _> = self.metadata.write()?;
+      for (did, _) in merged.iter() {
+        match map_remove(&mut other_state.blobs, did) {
+          Some(named_blobs) => blobs.insert(did.clone(), named_blobs),
+          None => map_remove(&mut blobs, did),
+        };
+        match other_state.metadata.remove(did) {
+          Some(did_metadata) => metadata.insert(did.clone(), did_metadata),
+          None => metadata.remove(did),
+        };
       }
     }
+
+    for (did, vault) in merged {
+      *self.vault_entry(&mut vaults, &did)? = vault;
+    }
+
+    Ok(report)
   }
+}
 
-  #[cfg(feature = "encryption")]
-  async fn data_encrypt(
-    &self,
-    _did: &CoreDID,
-    plaintext: Vec<u8>,
-    associated_data: Vec<u8>,
-    encryption_algorithm: &EncryptionAlgorithm,
-    cek_algorithm: &CekAlgorithm,
-    public_key: PublicKey,
-  ) -> Result<EncryptedData> {
-    let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key
-      .as_ref()
-      .try_into()
-      .map_err(|_| Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH)))?;
-    match cek_algorithm {
-      CekAlgorithm::ECDH_ES(agreement) => {
-        // Generate ephemeral key
-        let keypair: KeyPair = KeyPair::new(KeyType::X25519)?;
-        // Obtain the shared secret by combining the ephemeral key and the static public key
-        let shared_secret: [u8; 32] = X25519::key_exchange(keypair.private(), &public_key)?;
-        let derived_secret: Vec<u8> =
-          memstore_encryption::concat_kdf(cek_algorithm.name(), Aes256Gcm::KEY_LENGTH, &shared_secret, agreement)
-            .map_err(Error::EncryptionFailure)?;
-        let encrypted_data = memstore_encryption::try_encrypt(
-          &derived_secret,
-          encryption_algorithm,
-          &plaintext,
-          associated_data,
-          Vec::new(),
-          keypair.public().as_ref().to_vec(),
-        )?;
-        Ok(encrypted_data)
-      }
-      CekAlgorithm::ECDH_ES_A256KW(agreement) => {
-        let keypair: KeyPair = KeyPair::new(KeyType::X25519)?;
-        let shared_secret: [u8; 32] = X25519::key_exchange(keypair.private(), &public_key)?;
-        let derived_secret: Vec<u8> =
-          memstore_encryption::concat_kdf(cek_algorithm.name(), Aes256Kw::KEY_LENGTH, &shared_secret, agreement)
-            .map_err(Error::EncryptionFailure)?;
+/// Returns the timestamps of the DIDs of loaded `vaults`. Since the times at which the DIDs were created
+/// and updated are not serialized, they count as created when they are loaded.
+fn loaded_timestamps(vaults: &Vaults) -> Timestamps {
+  let now: Timestamp = Timestamp::now_utc();
+  vaults.keys().map(|did| (did.clone(), (now, now))).collect()
+}
 
-        let cek: Vec<u8> = memstore_encryption::generate_content_encryption_key(*encryption_algorithm)?;
+/// Only used to read the version of a serialized [`MemStoreSnapshot`].
+#[derive(Deserialize)]
+struct MemStoreSnapshotVersion {
+  version: u8,
+}
 
-        let mut encrypted_cek: Vec<u8> = vec![0; cek.len() + Aes256Kw::BLOCK];
-        let aes_kw: Aes256Kw<'_> = Aes256Kw::new(derived_secret.as_ref());
-        aes_kw
-          .wrap_key(cek.as_ref(), &mut encrypted_cek)
-          .map_err(Error::EncryptionFailure)?;
+/// The serialized representation of a [`MemStore`].
+#[derive(Deserialize, Serialize)]
+struct MemStoreSnapshot {
+  version: u8,
+  vaults: Vec<MemVaultSnapshot>,
+  blobs: Vec<MemBlobSnapshot>,
+}
 
-        let encrypted_data = memstore_encryption::try_encrypt(
-          &cek,
-          encryption_algorithm,
-          &plaintext,
-          associated_data,
-          encrypted_cek,
-          keypair.public().as_ref().to_vec(),
-        )?;
-        Ok(encrypted_data)
-      }
+impl MemStoreSnapshot {
+  /// The current version of the serialization format.
+  const VERSION: u8 = 2;
+}
+
+/// The serialized representation of a [`MemStore`] in version 1, which only held a single blob per DID.
+#[derive(Deserialize)]
+struct MemStoreSnapshotV1 {
+  vaults: Vec<MemVaultSnapshot>,
+  blobs: Vec<(CoreDID, Vec<u8>)>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct MemBlobSnapshot {
+  did: CoreDID,
+  name: String,
+  blob: Vec<u8>,
+}
+
+#[derive(Deserialize, Serialize)]
+struct MemVaultSnapshot {
+  did: CoreDID,
+  keys: Vec<MemKeySnapshot>,
+}
+
+impl MemVaultSnapshot {
+  fn new(did: &CoreDID, vault: &MemVault) -> Self {
+    Self {
+      did: did.clone(),
+      keys: vault
+        .iter()
+        .map(|(location, key)| MemKeySnapshot {
+          location: location.clone(),
+          key_type: key.keypair.type_(),
+          private_key: key.keypair.private().as_ref().to_vec(),
+          created_at: key.created_at,
+          expires_at: key.expires_at,
+        })
+        .collect(),
     }
   }
 
-  #[cfg(feature = "encryption")]
-  async fn data_decrypt(
-    &self,
-    did: &CoreDID,
-    data: EncryptedData,
-    encryption_algorithm: &EncryptionAlgorithm,
-    cek_algorithm: &CekAlgorithm,
-    private_key: &KeyLocation,
-  ) -> Result<Vec<u8>> {
-    // Retrieves the PrivateKey from the vault
-    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
-    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
-    let key_pair: &KeyPair = vault.get(private_key).ok_or(Error::KeyNotFound)?;
+  /// Reconstructs the vault, validating every private key.
+  fn to_vault(&self) -> Result<MemVault> {
+    let mut vault: MemVault = MemMap::with_capacity(self.keys.len());
+    for key in self.keys.iter() {
+      let keypair: KeyPair = KeyPair::try_from_private_key_bytes(key.key_type, &key.private_key)
+        .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+      vault.insert(
+        key.location.clone(),
+        MemKey {
+          keypair,
+          created_at: key.created_at,
+          expires_at: key.expires_at,
+        },
+      );
+    }
+    Ok(vault)
+  }
+}
+
+/// A serialized key pair. The private key is zeroized on drop.
+#[derive(Deserialize, Serialize)]
+struct MemKeySnapshot {
+  location: KeyLocation,
+  key_type: KeyType,
+  private_key: Vec<u8>,
+  // Absent in snapshots written before creation times were tracked.
+  #[serde(default)]
+  created_at: Option<Timestamp>,
+  // Omitted for keys without an expiry, so that snapshots of such keys are unchanged.
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  expires_at: Option<Timestamp>,
+}
+
+impl Drop for MemKeySnapshot {
+  fn drop(&mut self) {
+    self.private_key.zeroize();
+  }
+}
+
+/// The JSON representation of a [`MemStore`] written by [`MemStore::export_json`].
+///
+/// The field names and encodings are part of the documented schema and must not change within a version.
+#[derive(Deserialize, Serialize)]
+struct MemStoreExport {
+  version: u8,
+  dids: Vec<MemDIDExport>,
+}
+
+impl MemStoreExport {
+  /// The current version of the export schema.
+  const VERSION: u8 = 1;
+}
+
+#[derive(Deserialize, Serialize)]
+struct MemDIDExport {
+  did: CoreDID,
+  keys: Option<Vec<MemKeyExport>>,
+  #[serde(default)]
+  blobs: BTreeMap<String, String>,
+}
+
+impl MemDIDExport {
+  fn new(did: &CoreDID, vault: Option<&MemVault>, named_blobs: Option<&NamedBlobs>) -> Self {
+    Self {
+      did: did.clone(),
+      keys: vault.map(|vault| {
+        vault
+          .iter()
+          .map(|(location, key)| MemKeyExport {
+            key_type: location.key_type,
+            fragment: location.fragment().to_owned(),
+            index: location.index(),
+            public_key: BaseEncoding::encode_base58(key.keypair.public()),
+            private_key: BaseEncoding::encode_base58(key.keypair.private()),
+            created_at: key.created_at,
+            expires_at: key.expires_at,
+          })
+          .collect()
+      }),
+      blobs: named_blobs
+        .into_iter()
+        .flatten()
+        .map(|(name, blob)| (name.clone(), BaseEncoding::encode(blob, Base::Base64Pad)))
+        .collect(),
+    }
+  }
+}
+
+/// An exported key pair. The private key is zeroized on drop.
+#[derive(Deserialize, Serialize)]
+struct MemKeyExport {
+  #[serde(rename = "type")]
+  key_type: KeyType,
+  fragment: String,
+  #[serde(default)]
+  index: Option<u32>,
+  public_key: String,
+  private_key: String,
+  #[serde(default)]
+  created_at: Option<Timestamp>,
+  #[serde(default)]
+  expires_at: Option<Timestamp>,
+}
+
+impl MemKeyExport {
+  /// Reconstructs the location and key, validating that the public key belongs to the private key.
+  fn to_key(&self) -> Result<(KeyLocation, MemKey)> {
+    let private_key: Zeroizing<Vec<u8>> = Zeroizing::new(
+      BaseEncoding::decode_base58(&self.private_key).map_err(|err| Error::InvalidPrivateKey(err.to_string()))?,
+    );
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(self.key_type, &private_key)
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+
+    let public_key: Vec<u8> =
+      BaseEncoding::decode_base58(&self.public_key).map_err(|err| Error::InvalidPublicKey(err.to_string()))?;
+    if public_key != keypair.public().as_ref() {
+      return Err(Error::InvalidPublicKey(format!(
+        "public key of fragment `{}` does not match its private key",
+        self.fragment
+      )));
+    }
+
+    let location: KeyLocation = match self.index {
+      Some(index) => KeyLocation::with_index(self.key_type, self.fragment.clone(), index),
+      None => KeyLocation::new(self.key_type, self.fragment.clone(), &public_key),
+    };
+    let key: MemKey = MemKey {
+      keypair,
+      created_at: self.created_at,
+      expires_at: self.expires_at,
+    };
+
+    Ok((location, key))
+  }
+}
+
+impl Drop for MemKeyExport {
+  fn drop(&mut self) {
+    self.private_key.zeroize();
+  }
+}
+
+/// The version of the backup format written by [`Storage::did_export`].
+const BACKUP_VERSION: u8 = 1;
+/// The number of PBKDF2 iterations used to derive the backup key from the password.
+const BACKUP_PBKDF2_ITERATIONS: usize = 100_000;
+const BACKUP_SALT_LENGTH: usize = 16;
+const BACKUP_HEADER_LENGTH: usize = 1 + BACKUP_SALT_LENGTH;
+
+/// Encrypts `plaintext` into `version || salt || nonce || tag || ciphertext`, using a key derived
+/// from `password` with a random salt.
+///
+/// The version and salt are used as associated data, so tampering with them is detected on decryption.
+fn seal_backup(plaintext: &[u8], password: &str) -> Result<Vec<u8>> {
+  let mut header: [u8; BACKUP_HEADER_LENGTH] = [0; BACKUP_HEADER_LENGTH];
+  header[0] = BACKUP_VERSION;
+  crypto::utils::rand::fill(&mut header[1..]).map_err(Error::EncryptionFailure)?;
+
+  let key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]> =
+    derive_backup_key(password, &header[1..]).map_err(Error::EncryptionFailure)?;
+
+  let mut nonce: [u8; Aes256Gcm::NONCE_LENGTH] = [0; Aes256Gcm::NONCE_LENGTH];
+  crypto::utils::rand::fill(&mut nonce).map_err(Error::EncryptionFailure)?;
+
+  let mut tag: [u8; Aes256Gcm::TAG_LENGTH] = [0; Aes256Gcm::TAG_LENGTH];
+  let mut ciphertext: Vec<u8> = vec![0; plaintext.len()];
+  Aes256Gcm::try_encrypt(key.as_ref(), &nonce, &header, plaintext, &mut ciphertext, &mut tag)
+    .map_err(Error::EncryptionFailure)?;
+
+  let mut bytes: Vec<u8> = Vec::with_capacity(header.len() + nonce.len() + tag.len() + ciphertext.len());
+  bytes.extend_from_slice(&header);
+  bytes.extend_from_slice(&nonce);
+  bytes.extend_from_slice(&tag);
+  bytes.extend_from_slice(&ciphertext);
+  Ok(bytes)
+}
+
+/// Decrypts the `bytes` created by [`seal_backup`].
+fn open_backup(bytes: &[u8], password: &str) -> Result<Zeroizing<Vec<u8>>> {
+  let minimum_length: usize = BACKUP_HEADER_LENGTH + Aes256Gcm::NONCE_LENGTH + Aes256Gcm::TAG_LENGTH;
+  if bytes.len() < minimum_length {
+    return Err(Error::DecryptionFailure(crypto::error::Error::BufferSize {
+      name: "backup",
+      needs: minimum_length,
+      has: bytes.len(),
+    }));
+  }
+
+  let (header, bytes): (&[u8], &[u8]) = bytes.split_at(BACKUP_HEADER_LENGTH);
+  if header[0] != BACKUP_VERSION {
+    return Err(Error::UnsupportedVersion(header[0]));
+  }
+
+  let (nonce, bytes): (&[u8], &[u8]) = bytes.split_at(Aes256Gcm::NONCE_LENGTH);
+  let (tag, ciphertext): (&[u8], &[u8]) = bytes.split_at(Aes256Gcm::TAG_LENGTH);
+
+  let key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]> =
+    derive_backup_key(password, &header[1..]).map_err(Error::DecryptionFailure)?;
+
+  let mut plaintext: Zeroizing<Vec<u8>> = Zeroizing::new(vec![0; ciphertext.len()]);
+  let len: usize = Aes256Gcm::try_decrypt(key.as_ref(), nonce, header, &mut plaintext, ciphertext, tag)
+    .map_err(Error::DecryptionFailure)?;
+  plaintext.truncate(len);
+
+  Ok(plaintext)
+}
+
+fn derive_backup_key(password: &str, salt: &[u8]) -> crypto::error::Result<Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]>> {
+  let mut key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]> = Zeroizing::new([0; Aes256Gcm::KEY_LENGTH]);
+  PBKDF2_HMAC_SHA512(password.as_bytes(), salt, BACKUP_PBKDF2_ITERATIONS, key.as_mut())?;
+  Ok(key)
+}
+
+// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
+#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
+#[cfg_attr(feature = "send-sync-storage", async_trait)]
+impl Storage for MemStore {
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_create(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    private_key: Option<PrivateKey>,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did(did_type, network, fragment, private_key)?;
+
+    // Obtain exclusive access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+
+    // We use the vaults as the index of DIDs stored in this storage instance.
+    // If the DID already exists, we need to return an error. We don't want to overwrite an existing DID.
+    if vaults.contains_key(&did) {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    // Obtain the exiting mem vault or create a new one.
+    let vault: &mut MemVault = self.vault_entry(&mut vaults, &did)?;
+
+    // Insert the key pair at the previously created location.
+    vault.insert(location.clone(), MemKey::new(keypair));
+
+    // Return did and location.
+    Ok((did, location))
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_create_from_keypair(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    keypair: KeyPair,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did_from_keypair(did_type, network, fragment, keypair)?;
+
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+
+    if vaults.contains_key(&did) {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    let vault: &mut MemVault = self.vault_entry(&mut vaults, &did)?;
+    vault.insert(location.clone(), MemKey::new(keypair));
+
+    Ok((did, location))
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_create_many(
+    &self,
+    requests: Vec<(DIDType, NetworkName, String, Option<PrivateKey>)>,
+  ) -> Result<Vec<(CoreDID, KeyLocation)>> {
+    // Derive all DIDs before acquiring the lock to keep the critical section short.
+    let mut prepared: Vec<(CoreDID, KeyLocation, KeyPair)> = Vec::with_capacity(requests.len());
+    for (did_type, network, fragment, private_key) in requests {
+      prepared.push(prepare_did(did_type, network, &fragment, private_key)?);
+    }
+
+    // Obtain exclusive access to the vaults for the entire batch.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+
+    // Check all DIDs for conflicts before inserting anything, so that a failing batch leaves no partial state.
+    let mut batch: HashSet<&CoreDID> = HashSet::with_capacity(prepared.len());
+    for (did, _, _) in prepared.iter() {
+      if vaults.contains_key(did) || !batch.insert(did) {
+        return Err(Error::IdentityAlreadyExists);
+      }
+    }
+
+    let mut output: Vec<(CoreDID, KeyLocation)> = Vec::with_capacity(prepared.len());
+    for (did, location, keypair) in prepared {
+      let vault: &mut MemVault = self.vault_entry(&mut vaults, &did)?;
+      vault.insert(location.clone(), MemKey::new(keypair));
+      output.push((did, location));
+    }
+
+    Ok(output)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(from = %did_hash(from), to = %did_hash(&to))))]
+  async fn did_copy(&self, from: &CoreDID, to: CoreDID) -> Result<()> {
+    self.touch(from)?;
+
+    // Obtain exclusive access to the vaults and blobs, in the same order as `did_purge`.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+
+    let vault: MemVault = vaults.get(from).ok_or(Error::KeyVaultNotFound)?.clone();
+    if vaults.contains_key(&to) {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    // The blobs of the new identity are replaced entirely, so no stale blobs remain.
+    match blobs.get(from).cloned() {
+      Some(named_blobs) => {
+        blobs.insert(to.clone(), named_blobs);
+      }
+      None => {
+        map_remove(&mut blobs, &to);
+      }
+    }
+    // Inserting the vault may evict other DIDs, which requires the blobs lock.
+    drop(blobs);
+    *self.vault_entry(&mut vaults, &to)? = vault;
+
+    Ok(())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn did_export(&self, did: &CoreDID, password: &str) -> Result<Vec<u8>> {
+    self.touch(did)?;
+
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let blobs: RwLockReadGuard<'_, _> = self.blobs.read()?;
+
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    let snapshot: MemStoreSnapshot = MemStoreSnapshot {
+      version: MemStoreSnapshot::VERSION,
+      vaults: vec![MemVaultSnapshot::new(did, vault)],
+      blobs: blobs
+        .get(did)
+        .into_iter()
+        .flatten()
+        .map(|(name, blob)| MemBlobSnapshot {
+          did: did.clone(),
+          name: name.clone(),
+          blob: blob.clone(),
+        })
+        .collect(),
+    };
+    drop(blobs);
+    drop(vaults);
+
+    let plaintext: Zeroizing<Vec<u8>> = Zeroizing::new(snapshot.to_json_vec()?);
+    seal_backup(&plaintext, password)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_import(&self, data: &[u8], password: &str) -> Result<CoreDID> {
+    let plaintext: Zeroizing<Vec<u8>> = open_backup(data, password)?;
+    let snapshot: MemStoreSnapshot = MemStoreSnapshot::from_json_slice(&plaintext)?;
+    if snapshot.version != MemStoreSnapshot::VERSION {
+      return Err(Error::UnsupportedVersion(snapshot.version));
+    }
+
+    // A backup holds exactly one identity.
+    let vault_snapshot: &MemVaultSnapshot = snapshot.vaults.first().ok_or(Error::KeyVaultNotFound)?;
+    let did: CoreDID = vault_snapshot.did.clone();
+    let vault: MemVault = vault_snapshot.to_vault()?;
+    let named_blobs: NamedBlobs = snapshot
+      .blobs
+      .into_iter()
+      .filter(|blob| blob.did == did)
+      .map(|blob| (blob.name, blob.blob))
+      .collect();
+
+    // Obtain exclusive access to the vaults and blobs, in the same order as `did_purge`.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+
+    if vaults.contains_key(&did) {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    if named_blobs.is_empty() {
+      map_remove(&mut blobs, &did);
+    } else {
+      blobs.insert(did.clone(), named_blobs);
+    }
+    // Inserting the vault may evict other DIDs, which requires the blobs lock.
+    drop(blobs);
+    *self.vault_entry(&mut vaults, &did)? = vault;
+
+    Ok(did)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
+    // This method is supposed to be idempotent,
+    // so we only need to do work if the DID still exists.
+    // The return value signals whether the DID was actually removed during this operation.
+    if map_remove(&mut self.vaults.write()?, did).is_some() {
+      let _ = map_remove(&mut self.blobs.write()?, did);
+      let _ = self.metadata.write()?.remove(did);
+      let _ = self.timestamps.write()?.remove(did);
+      let _ = self.usage.write()?.last_used.remove(did);
+      Ok(true)
+    } else {
+      Ok(false)
+    }
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
+    self.touch(did)?;
+
+    // Note that any failure to get access to the storage and do the actual existence check
+    // should result in an error rather than returning `false`.
+    Ok(self.vaults.read()?.contains_key(did))
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_exists_many(&self, dids: &[CoreDID]) -> Result<Vec<bool>> {
+    if self.capacity.is_some() {
+      let mut usage: RwLockWriteGuard<'_, MemUsage> = self.usage.write()?;
+      for did in dids {
+        usage.touch(did);
+      }
+    }
+
+    // Check all DIDs under a single read lock.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    Ok(dids.iter().map(|did| vaults.contains_key(did)).collect())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_list(&self) -> Result<Vec<CoreDID>> {
+    Ok(self.vaults.read()?.keys().cloned().collect())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_list_paged(&self, offset: usize, limit: usize) -> Result<Vec<CoreDID>> {
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+
+    // Sort references, so only the DIDs of the requested page are cloned.
+    let mut dids: Vec<&CoreDID> = vaults.keys().collect();
+    dids.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    Ok(dids.into_iter().skip(offset).take(limit).cloned().collect())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn did_set_metadata(&self, did: &CoreDID, metadata: BTreeMap<String, String>) -> Result<()> {
+    self.touch(did)?;
+
+    self.metadata.write()?.insert(did.clone(), metadata);
+    self.record_update(did)?;
+
+    Ok(())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn did_get_metadata(&self, did: &CoreDID) -> Result<BTreeMap<String, String>> {
+    self.touch(did)?;
+
+    Ok(self.metadata.read()?.get(did).cloned().unwrap_or_default())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_find_by_metadata(&self, key: &str, value: &str) -> Result<Vec<CoreDID>> {
+    Ok(
+      self
+        .metadata
+        .read()?
+        .iter()
+        .filter(|(_, metadata)| metadata.get(key).map(String::as_str) == Some(value))
+        .map(|(did, _)| did.clone())
+        .collect(),
+    )
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn did_timestamps(&self, did: &CoreDID) -> Result<(Timestamp, Timestamp)> {
+    self.touch(did)?;
+
+    // Every DID with a vault has timestamps, which are removed together with the vault.
+    self.timestamps.read()?.get(did).copied().ok_or(Error::KeyVaultNotFound)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
+    // Obtain exclusive access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Get or insert the MemVault.
+    let vault: &mut MemVault = self.vault_entry(&mut vaults, did)?;
+
+    // Generate a new key pair for the given `key_type`.
+    let keypair: KeyPair = KeyPair::new(key_type)?;
+
+    // Derive the key location from the fragment and public key and set the `KeyType` of the location.
+    let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+    vault.insert(location.clone(), MemKey::new(keypair));
+
+    // Return the location at which the key was generated.
+    Ok(location)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_generate_with_expiry(
+    &self,
+    did: &CoreDID,
+    key_type: KeyType,
+    fragment: &str,
+    expires_at: Timestamp,
+  ) -> Result<KeyLocation> {
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    let vault: &mut MemVault = self.vault_entry(&mut vaults, did)?;
+
+    let keypair: KeyPair = KeyPair::new(key_type)?;
+    let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+    // The expiry is checked lazily whenever the key is used.
+    vault.insert(
+      location.clone(),
+      MemKey {
+        expires_at: Some(expires_at),
+        ..MemKey::new(keypair)
+      },
+    );
+
+    Ok(location)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_prune_expired(&self, did: &CoreDID) -> Result<usize> {
+    self.touch(did)?;
+
+    // Obtain exclusive access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Lookup the vault for the given DID.
+    let vault: &mut MemVault = vaults.get_mut(did).ok_or(Error::KeyVaultNotFound)?;
+
+    let now: Timestamp = Timestamp::now_utc();
+    let len: usize = vault.len();
+    vault.retain(|_, key| !key.is_expired(now));
+    let removed: usize = len - vault.len();
+
+    if removed > 0 {
+      self.record_update(did)?;
+    }
+
+    Ok(removed)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
+    // Reconstruct the key pair from the given private key by inspecting the location for its key type.
+    // This happens before acquiring the vault, so an invalid key leaves the storage untouched.
+    let keypair: KeyPair = match location.key_type {
+      KeyType::Ed25519 => KeyPair::try_from_private_key_bytes(KeyType::Ed25519, private_key.as_ref())
+        .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?,
+      KeyType::X25519 => KeyPair::try_from_private_key_bytes(KeyType::X25519, private_key.as_ref())
+        .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?,
+    };
+    private_key.zeroize();
+
+    // Obtain exclusive access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Get or insert the MemVault.
+    let vault: &mut MemVault = self.vault_entry(&mut vaults, did)?;
+
+    // Insert the key at the given location.
+    vault.insert(location.to_owned(), MemKey::new(keypair));
+
+    Ok(())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+
+    // Within the DID vault, check for existence of the given location.
+    if let Some(vault) = vaults.get(did) {
+      return Ok(vault.contains_key(location));
+    }
+
+    Ok(false)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_list(&self, did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    // Lookup the vault for the given DID.
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+
+    // The vault is keyed by location, so every location is returned exactly once.
+    Ok(vault.keys().cloned().collect())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_list_by_type(&self, did: &CoreDID, key_type: KeyType) -> Result<Vec<KeyLocation>> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    // Lookup the vault for the given DID.
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+
+    Ok(
+      vault
+        .keys()
+        .filter(|location| location.key_type == key_type)
+        .cloned()
+        .collect(),
+    )
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_delete_all(&self, did: &CoreDID) -> Result<usize> {
+    self.touch(did)?;
+
+    // Obtain exclusive access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Lookup the vault for the given DID.
+    let vault: &mut MemVault = vaults.get_mut(did).ok_or(Error::KeyVaultNotFound)?;
+
+    // The (now empty) vault is kept, so that the DID continues to exist.
+    let removed: usize = vault.len();
+    vault.clear();
+    self.record_update(did)?;
+
+    Ok(removed)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    // Lookup the vault for the given DID.
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    // Lookup the key pair within the vault.
+    let keypair: &KeyPair = &usable_key(vault, location)?.keypair;
+
+    // Return the public key.
+    Ok(keypair.public().clone())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_public_many(&self, did: &CoreDID, locations: &[KeyLocation]) -> Result<Vec<PublicKey>> {
+    self.touch(did)?;
+
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+
+    locations
+      .iter()
+      .map(|location| match usable_key(vault, location) {
+        Ok(key) => Ok(key.keypair.public().clone()),
+        Err(Error::KeyNotFound) => Err(Error::KeyNotFoundAt(location.clone())),
+        Err(error) => Err(error),
+      })
+      .collect()
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    self.touch(did)?;
+
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    let key: &MemKey = vault.get(location).ok_or(Error::KeyNotFound)?;
+
+    Ok(
+      KeyMetadata::new(key.keypair.public().clone(), key.keypair.type_(), key.created_at)
+        .with_expires_at(key.expires_at),
+    )
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Lookup the vault for the given DID.
+    let vault: &mut MemVault = vaults.get_mut(did).ok_or(Error::KeyVaultNotFound)?;
+
+    // This method is supposed to be idempotent, so we delete the key
+    // if it exists and return whether it was actually deleted during this operation.
+    let deleted: bool = map_remove(vault, location).is_some();
+
+    if deleted {
+      self.record_update(did)?;
+    }
+
+    Ok(deleted)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    self.touch(did)?;
+
+    // Obtain exclusive access to the vaults for the entire rotation,
+    // so that no other operation can observe an intermediate state.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Lookup the vault for the given DID.
+    let vault: &mut MemVault = vaults.get_mut(did).ok_or(Error::KeyVaultNotFound)?;
+
+    // Return an error before modifying the vault if the old key does not exist.
+    if !vault.contains_key(old) {
+      return Err(Error::KeyNotFound);
+    }
+
+    // Generate a new key pair with the same key type as the old one.
+    let keypair: KeyPair = KeyPair::new(old.key_type)?;
+    let location: KeyLocation = KeyLocation::new(old.key_type, fragment.to_owned(), keypair.public().as_ref());
+
+    map_remove(vault, old);
+    vault.insert(location.clone(), MemKey::new(keypair));
+    self.record_update(did)?;
+
+    Ok(location)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_move(&self, did: &CoreDID, from: &KeyLocation, new_fragment: &str) -> Result<KeyLocation> {
+    self.touch(did)?;
+
+    // Obtain exclusive access to the vaults.
+    let mut vaults: RwLockWriteGuard<'_, _> = self.vaults.write()?;
+    // Lookup the vault for the given DID.
+    let vault: &mut MemVault = vaults.get_mut(did).ok_or(Error::KeyVaultNotFound)?;
+
+    // The key, including its creation time, is moved as is.
+    let key: MemKey = map_remove(vault, from).ok_or(Error::KeyNotFound)?;
+    let location: KeyLocation = from.with_fragment(new_fragment.to_owned());
+    vault.insert(location.clone(), key);
+    self.record_update(did)?;
+
+    Ok(location)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    // Lookup the vault for the given DID.
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    // Lookup the key pair within the vault.
+    let keypair: &KeyPair = &usable_key(vault, location)?.keypair;
+
+    match location.key_type {
+      KeyType::Ed25519 => {
+        assert_eq!(keypair.type_(), KeyType::Ed25519);
+
+        // Use the `Ed25519` API to sign the given data with the private key.
+        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
+        // Construct a new `Signature` wrapper with the returned signature bytes.
+        let signature: Signature = Signature::new_checked(KeyType::Ed25519, signature.to_vec())?;
+        Ok(signature)
+      }
+      KeyType::X25519 => {
+        // Calling key_sign on key types that cannot be signed with should return an error.
+        return Err(identity_did::Error::InvalidMethodType.into());
+      }
+    }
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_exchange(&self, did: &CoreDID, location: &KeyLocation, public_key: &PublicKey) -> Result<[u8; 32]> {
+    self.touch(did)?;
+
+    // Obtain read access to the vaults.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    // Lookup the vault for the given DID.
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    // Lookup the key pair within the vault.
+    let keypair: &KeyPair = &usable_key(vault, location)?.keypair;
+
+    match keypair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key exchange".to_owned(),
+      )),
+      KeyType::X25519 => {
+        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key.as_ref().try_into().map_err(|_| {
+          Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
+        })?;
+        // Only the shared secret is returned, the private key never leaves the vault.
+        let shared_secret: [u8; 32] = X25519::key_exchange(keypair.private(), &public_key)?;
+        Ok(shared_secret)
+      }
+    }
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_encrypt(
+    &self,
+    did: &CoreDID,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<EncryptedData> {
+    self.touch(did)?;
+
+    let location: &KeyLocation = match sender_key {
+      Some(location) => location,
+      None => {
+        return self
+          .data_encrypt_anonymous(
+            plaintext,
+            associated_data,
+            encryption_algorithm,
+            cek_algorithm,
+            public_key,
+          )
+          .await
+      }
+    };
+
+    // Retrieves the sender's KeyPair from the vault
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    let sender: &KeyPair = &usable_key(vault, location)?.keypair;
+    self.encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      Some(sender),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn data_encrypt_anonymous(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    self.encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      None,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_decrypt(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let mut plaintext: Vec<u8> = Vec::new();
+    self
+      .data_decrypt_into(
+        did,
+        data,
+        encryption_algorithm,
+        cek_algorithm,
+        private_key,
+        sender_public_key,
+        &mut plaintext,
+      )
+      .await?;
+    Ok(plaintext)
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    self.touch(did)?;
+
+    // Retrieves the PrivateKey from the vault
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let vault: &MemVault = vaults.get(did).ok_or(Error::KeyVaultNotFound)?;
+    let key_pair: &KeyPair = &usable_key(vault, private_key)?.keypair;
     // Decrypts the data
-    match key_pair.type_() {
-      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
-        "Ed25519 keys are not supported for decryption".to_owned(),
-      )),
-      KeyType::X25519 => {
-        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] =
-          data.ephemeral_public_key.clone().try_into().map_err(|_| {
-            Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
-          })?;
-        match cek_algorithm {
-          CekAlgorithm::ECDH_ES(agreement) => {
-            let shared_secret: [u8; 32] = X25519::key_exchange(key_pair.private(), &public_key)?;
-            let derived_secret: Vec<u8> =
-              memstore_encryption::concat_kdf(cek_algorithm.name(), Aes256Gcm::KEY_LENGTH, &shared_secret, agreement)
-                .map_err(Error::DecryptionFailure)?;
-            memstore_encryption::try_decrypt(&derived_secret, encryption_algorithm, &data)
-          }
-          CekAlgorithm::ECDH_ES_A256KW(agreement) => {
-            let shared_secret: [u8; 32] = X25519::key_exchange(key_pair.private(), &public_key)?;
-            let derived_secret: Vec<u8> =
-              memstore_encryption::concat_kdf(cek_algorithm.name(), Aes256Kw::KEY_LENGTH, &shared_secret, agreement)
-                .map_err(Error::DecryptionFailure)?;
-
-            let cek_len: usize =
-              data
-                .encrypted_cek
-                .len()
-                .checked_sub(Aes256Kw::BLOCK)
-                .ok_or(Error::DecryptionFailure(crypto::Error::BufferSize {
-                  name: "plaintext cek",
-                  needs: Aes256Kw::BLOCK,
-                  has: data.encrypted_cek.len(),
-                }))?;
-
-            let mut cek: Vec<u8> = vec![0; cek_len];
-            let aes_kw: Aes256Kw<'_> = Aes256Kw::new(derived_secret.as_ref());
-            aes_kw
-              .unwrap_key(data.encrypted_cek.as_ref(), &mut cek)
-              .map_err(Error::DecryptionFailure)?;
-
-            memstore_encryption::try_decrypt(&cek, encryption_algorithm, &data)
+    memstore_encryption::decrypt_into(
+      key_pair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+      out,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_decrypt_with_conversion(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    convert_ed25519: bool,
+  ) -> Result<Vec<u8>> {
+    self.touch(did)?;
+
+    let mut key_pair: KeyPair = self.keypair(did, private_key)?;
+    if convert_ed25519 {
+      key_pair = memstore_encryption::x25519_key_pair(&key_pair)?;
+    }
+    memstore_encryption::decrypt(
+      &key_pair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_encrypt_stream(
+    &self,
+    did: &CoreDID,
+    reader: &mut dyn DataStreamReader,
+    writer: &mut dyn DataStreamWriter,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<()> {
+    self.touch(did)?;
+
+    // Clones the sender's KeyPair from the vault, if required, since the lock cannot be held while streaming
+    let sender: Option<KeyPair> = match sender_key {
+      Some(location) => Some(self.keypair(did, location)?),
+      None => None,
+    };
+    memstore_encryption::encrypt_stream(
+      reader,
+      writer,
+      &associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      sender.as_ref(),
+    )
+    .await
+  }
+
+  #[cfg(feature = "encryption")]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_decrypt_stream(
+    &self,
+    did: &CoreDID,
+    reader: &mut dyn DataStreamReader,
+    writer: &mut dyn DataStreamWriter,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<()> {
+    self.touch(did)?;
+
+    // Clones the KeyPair from the vault, since the lock cannot be held while streaming
+    let key_pair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt_stream(
+      &key_pair,
+      reader,
+      writer,
+      &associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+    .await
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_set(&self, did: &CoreDID, value: Vec<u8>) -> Result<()> {
+    self.blob_set_named(did, DEFAULT_BLOB_NAME, value).await
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
+    self.blob_get_named(did, DEFAULT_BLOB_NAME).await
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_delete(&self, did: &CoreDID) -> Result<bool> {
+    self.touch(did)?;
+
+    let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+    let named_blobs: &mut NamedBlobs = match blobs.get_mut(did) {
+      Some(named_blobs) => named_blobs,
+      None => return Ok(false),
+    };
+
+    let removed: bool = map_remove(named_blobs, DEFAULT_BLOB_NAME).is_some();
+    // Drop the entry once no named blobs remain, as `did_purge` would.
+    if named_blobs.is_empty() {
+      map_remove(&mut blobs, did);
+    }
+
+    if removed {
+      self.record_update(did)?;
+    }
+
+    Ok(removed)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_compare_and_swap(&self, did: &CoreDID, expected: Option<&[u8]>, new: Vec<u8>) -> Result<bool> {
+    self.touch(did)?;
+
+    // Hold the write lock for the comparison, so no other write can happen in between.
+    let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+
+    let current: Option<&[u8]> = blobs
+      .get(did)
+      .and_then(|named_blobs| named_blobs.get(DEFAULT_BLOB_NAME))
+      .map(Vec::as_slice);
+
+    if !ct_eq_option(current, expected) {
+      return Ok(false);
+    }
+
+    blobs
+      .entry(did.clone())
+      .or_default()
+      .insert(DEFAULT_BLOB_NAME.to_owned(), new);
+    self.record_update(did)?;
+
+    Ok(true)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_append(&self, did: &CoreDID, data: &[u8]) -> Result<usize> {
+    self.touch(did)?;
+
+    let mut blobs: RwLockWriteGuard<'_, _> = self.blobs.write()?;
+    let blob: &mut Vec<u8> = blobs
+      .entry(did.clone())
+      .or_default()
+      .entry(DEFAULT_BLOB_NAME.to_owned())
+      .or_default();
+    blob.extend_from_slice(data);
+    let len: usize = blob.len();
+    self.record_update(did)?;
+
+    Ok(len)
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, value: Vec<u8>) -> Result<()> {
+    self.touch(did)?;
+
+    // Set the arbitrary value under the given name for the given DID.
+    self
+      .blobs
+      .write()?
+      .entry(did.clone())
+      .or_default()
+      .insert(name.to_owned(), value);
+    self.record_update(did)?;
+
+    Ok(())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    self.touch(did)?;
+
+    // Lookup the value stored under the given name of the given DID.
+    self
+      .blobs
+      .read()
+      .map(|data| data.get(did).and_then(|named_blobs| named_blobs.get(name)).cloned())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    self.touch(did)?;
+
+    self.blobs.read().map(|data| {
+      data
+        .get(did)
+        .map(|named_blobs| named_blobs.keys().cloned().collect())
+        .unwrap_or_default()
+    })
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn stats(&self) -> Result<StorageStats> {
+    // Obtain read access to both maps, so the counts are consistent with each other.
+    let vaults: RwLockReadGuard<'_, _> = self.vaults.read()?;
+    let blobs: RwLockReadGuard<'_, _> = self.blobs.read()?;
+
+    let key_count: usize = vaults.values().map(|vault| vault.len()).sum();
+    let blob_bytes: usize = blobs
+      .values()
+      .flat_map(|named_blobs| named_blobs.values())
+      .map(|blob| blob.len())
+      .sum();
+
+    Ok(StorageStats::new(vaults.len(), key_count, blob_bytes))
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn flush_changes(&self) -> Result<()> {
+    if self.fail_next_flush.swap(false, Ordering::SeqCst) {
+      return Err(Error::IoError(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "injected flush failure",
+      )));
+    }
+
+    // The MemStore doesn't need to flush changes to disk or any other persistent store,
+    // which is why this function does nothing otherwise.
+    Ok(())
+  }
+
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn transaction<'s>(&'s self, transaction: TransactionFuture<'s>) -> Result<()> {
+    // Rolling back restores a copy of the entire state taken before running the `transaction`.
+    let state: MemState = self.state()?;
+
+    match transaction.await {
+      Ok(()) => self.flush_changes().await,
+      Err(error) => {
+        self.restore(state)?;
+        Err(error)
+      }
+    }
+  }
+}
+
+/// A copy of the state of a [`MemStore`], used to roll back a [`Storage::transaction`].
+struct MemState {
+  vaults: Vaults,
+  blobs: Blobs,
+  metadata: Metadata,
+  timestamps: Timestamps,
+  usage: MemUsage,
+}
+
+/// Derives the DID and key location of a new identity from the given `private_key`,
+/// or a newly generated key if it's `None`.
+pub(super) fn prepare_did(
+  did_type: DIDType,
+  network: NetworkName,
+  fragment: &str,
+  private_key: Option<PrivateKey>,
+) -> Result<(CoreDID, KeyLocation, KeyPair)> {
+  // Extract a `KeyPair` from the passed private key or generate a new one.
+  // For `did_create` we can assume the `KeyType` to be `Ed25519` because
+  // that is the only currently available signature type.
+  let keypair: KeyPair = match private_key {
+    Some(private_key) => KeyPair::try_from_private_key_bytes(KeyType::Ed25519, private_key.as_ref())?,
+    None => KeyPair::new(KeyType::Ed25519)?,
+  };
+
+  prepare_did_from_keypair(did_type, network, fragment, keypair)
+}
+
+/// Derives the DID and key location of a new identity from an existing `keypair`.
+///
+/// Returns [`Error::InvalidNetwork`] if the `network` cannot be used by the `did_type`, and
+/// [`identity_did::Error::InvalidMethodType`] if the key type of `keypair` cannot be used for signing.
+pub(super) fn prepare_did_from_keypair(
+  did_type: DIDType,
+  network: NetworkName,
+  fragment: &str,
+  keypair: KeyPair,
+) -> Result<(CoreDID, KeyLocation, KeyPair)> {
+  did_type.validate_network(&network)?;
+
+  if keypair.type_() != KeyType::Ed25519 {
+    return Err(identity_did::Error::InvalidMethodType.into());
+  }
+
+  // We create the location at which the key pair will be stored.
+  // Most notably, this uses the public key as an input.
+  let location: KeyLocation = KeyLocation::new(keypair.type_(), fragment.to_owned(), keypair.public().as_ref());
+
+  // Next we use the public key to derive the initial DID.
+  let did: CoreDID = did_type.create_did(keypair.public().as_ref(), network)?;
+
+  Ok((did, location, keypair))
+}
+
+#[cfg(feature = "encryption")]
+pub(super) mod memstore_encryption {
+  use crate::storage::DataStreamReader;
+  use crate::storage::DataStreamWriter;
+  use crate::types::AgreementInfo;
+  use crate::types::CekAlgorithm;
+  use crate::types::EncryptedData;
+  use crate::types::EncryptionAlgorithm;
+  use crate::types::KeyDerivation;
+  use crate::Error;
+  use crate::Result;
+  use crypto::ciphers::aes_gcm::Aes128Gcm;
+  use crypto::ciphers::aes_gcm::Aes192Gcm;
+  use crypto::ciphers::aes_gcm::Aes256Gcm;
+  use crypto::ciphers::aes_kw::Aes128Kw;
+  use crypto::ciphers::aes_kw::Aes192Kw;
+  use crypto::ciphers::aes_kw::Aes256Kw;
+  use crypto::ciphers::chacha::XChaCha20Poly1305;
+  use crypto::ciphers::traits::Aead;
+  use crypto::hashes::sha::Sha256;
+  use crypto::hashes::sha::SHA256_LEN;
+  use crypto::hashes::Digest;
+  use crypto::macs::hmac::HMAC_SHA256;
+  use futures::io::AsyncReadExt;
+  use futures::io::AsyncWriteExt;
+  use identity_core::crypto::KeyPair;
+  use identity_core::crypto::KeyType;
+  use identity_core::crypto::PrivateKey;
+  use identity_core::crypto::PublicKey;
+  use identity_core::crypto::X25519;
+  use zeroize::Zeroize;
+  use zeroize::Zeroizing;
+
+  /// Encrypts `plaintext` for the owner of the given X25519 `public_key`.
+  ///
+  /// The `sender` key pair is required by the sender-authenticated ECDH-1PU algorithms and ignored otherwise.
+  /// The ephemeral key, content encryption key and nonce are drawn from `random`, see [`os_random`].
+  pub(crate) fn encrypt(
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender: Option<&KeyPair>,
+    random: &mut dyn FnMut(&mut [u8]) -> Result<()>,
+  ) -> Result<EncryptedData> {
+    let mut key: ContentEncryptionKey =
+      agree_content_encryption_key(encryption_algorithm, cek_algorithm, public_key, sender, random)?;
+
+    let mut data: EncryptedData = try_encrypt(
+      &key.cek,
+      encryption_algorithm,
+      &plaintext,
+      associated_data,
+      std::mem::take(&mut key.encrypted_cek),
+      std::mem::take(&mut key.ephemeral_public_key),
+      random,
+    )?;
+
+    if let CekAlgorithm::ECDH_1PU_A256KW(agreement) = cek_algorithm {
+      data.encrypted_cek = wrap_tagged_content_encryption_key(cek_algorithm, agreement, &key, &data.tag)
+        .map_err(Error::EncryptionFailure)?;
+    }
+
+    Ok(data.with_algorithm(encryption_algorithm))
+  }
+
+  /// Decrypts `data` with the private key of the given X25519 `key_pair`.
+  ///
+  /// The `sender_public_key` is required by the sender-authenticated ECDH-1PU algorithms and ignored otherwise.
+  pub(crate) fn decrypt(
+    key_pair: &KeyPair,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    sender_public_key: Option<&PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let mut plaintext: Vec<u8> = Vec::new();
+    decrypt_into(
+      key_pair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key,
+      &mut plaintext,
+    )?;
+    Ok(plaintext)
+  }
+
+  /// Decrypts the given `data` into `out`, replacing its content, and returns the length of the plaintext.
+  pub(crate) fn decrypt_into(
+    key_pair: &KeyPair,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    sender_public_key: Option<&PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    data.validate(encryption_algorithm)?;
+
+    let cek: Zeroizing<Vec<u8>> = recover_content_encryption_key(
+      key_pair,
+      &data.ephemeral_public_key,
+      &data.encrypted_cek,
+      &data.tag,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key,
+    )?;
+
+    try_decrypt_into(&cek, encryption_algorithm, &data, out)
+  }
+
+  /// The maximum length of the plaintext of a chunk of an encrypted stream.
+  pub(crate) const STREAM_CHUNK_LENGTH: usize = 64 * 1024;
+  // Marks every chunk of an encrypted stream except for the last.
+  const STREAM_CHUNK: u8 = 0x00;
+  // Marks the last chunk of an encrypted stream.
+  const STREAM_LAST_CHUNK: u8 = 0x01;
+
+  /// Encrypts the data read from `reader` in chunks for the owner of the given X25519 `public_key` and writes
+  /// the encrypted stream to `writer`.
+  ///
+  /// See [`Storage::data_encrypt_stream`](crate::storage::Storage::data_encrypt_stream) for the stream format.
+  pub(crate) async fn encrypt_stream(
+    reader: &mut dyn DataStreamReader,
+    writer: &mut dyn DataStreamWriter,
+    associated_data: &[u8],
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender: Option<&KeyPair>,
+  ) -> Result<()> {
+    check_stream_cek_algorithm(cek_algorithm)?;
+    let key: ContentEncryptionKey =
+      agree_content_encryption_key(encryption_algorithm, cek_algorithm, public_key, sender, &mut os_random)?;
+    let mut base_nonce: Vec<u8> = vec![0; encryption_algorithm.nonce_length()];
+    os_random(&mut base_nonce)?;
+
+    // The wrapped key is only 8 bytes longer than the content encryption key, so its length always fits.
+    writer.write_all(&key.ephemeral_public_key).await?;
+    writer
+      .write_all(&(key.encrypted_cek.len() as u16).to_be_bytes())
+      .await?;
+    writer.write_all(&key.encrypted_cek).await?;
+    writer.write_all(&base_nonce).await?;
+
+    let mut index: u64 = 0;
+    let mut chunk: Vec<u8> = read_chunk(reader).await?;
+    loop {
+      // A chunk shorter than the maximum length is always followed by the end of the stream.
+      let next: Vec<u8> = if chunk.len() == STREAM_CHUNK_LENGTH {
+        read_chunk(reader).await?
+      } else {
+        Vec::new()
+      };
+      let flag: u8 = if next.is_empty() {
+        STREAM_LAST_CHUNK
+      } else {
+        STREAM_CHUNK
+      };
+
+      let sealed: Vec<u8> = seal_chunk(
+        &key.cek,
+        encryption_algorithm,
+        &chunk_nonce(&base_nonce, index),
+        &chunk_associated_data(associated_data, flag),
+        &chunk,
+      )?;
+
+      writer.write_all(&[flag]).await?;
+      writer.write_all(&(chunk.len() as u32).to_be_bytes()).await?;
+      writer.write_all(&sealed).await?;
+
+      if flag == STREAM_LAST_CHUNK {
+        break;
+      }
+
+      chunk = next;
+      index += 1;
+    }
+
+    writer.flush().await?;
+
+    Ok(())
+  }
+
+  /// Decrypts the stream created by [`encrypt_stream`] read from `reader` with the private key of the given
+  /// X25519 `key_pair` and writes the plaintext to `writer`.
+  ///
+  /// Every chunk is authenticated before it is written, but chunks preceding an invalid chunk may
+  /// already have been written when an error is returned.
+  pub(crate) async fn decrypt_stream(
+    key_pair: &KeyPair,
+    reader: &mut dyn DataStreamReader,
+    writer: &mut dyn DataStreamWriter,
+    associated_data: &[u8],
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    sender_public_key: Option<&PublicKey>,
+  ) -> Result<()> {
+    check_stream_cek_algorithm(cek_algorithm)?;
+    let mut ephemeral_public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = [0; X25519::PUBLIC_KEY_LENGTH];
+    reader.read_exact(&mut ephemeral_public_key).await?;
+    let mut encrypted_cek_length: [u8; 2] = [0; 2];
+    reader.read_exact(&mut encrypted_cek_length).await?;
+    let mut encrypted_cek: Vec<u8> = vec![0; u16::from_be_bytes(encrypted_cek_length) as usize];
+    reader.read_exact(&mut encrypted_cek).await?;
+    let mut base_nonce: Vec<u8> = vec![0; encryption_algorithm.nonce_length()];
+    reader.read_exact(&mut base_nonce).await?;
+
+    let cek: Zeroizing<Vec<u8>> = recover_content_encryption_key(
+      key_pair,
+      &ephemeral_public_key,
+      &encrypted_cek,
+      &[],
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key,
+    )?;
+
+    let mut index: u64 = 0;
+    loop {
+      let mut flag: [u8; 1] = [0; 1];
+      reader.read_exact(&mut flag).await?;
+      let mut length: [u8; 4] = [0; 4];
+      reader.read_exact(&mut length).await?;
+
+      let length: usize = u32::from_be_bytes(length) as usize;
+      if length > STREAM_CHUNK_LENGTH {
+        return Err(Error::DecryptionFailure(crypto::Error::InvalidArgumentError {
+          alg: encryption_algorithm.name(),
+          expected: "chunks of at most 64 KiB",
+        }));
+      }
+
+      let mut sealed: Vec<u8> = vec![0; length + encryption_algorithm.tag_length()];
+      reader.read_exact(&mut sealed).await?;
+
+      let chunk: Vec<u8> = open_chunk(
+        &cek,
+        encryption_algorithm,
+        &chunk_nonce(&base_nonce, index),
+        &chunk_associated_data(associated_data, flag[0]),
+        &sealed,
+      )?;
+      writer.write_all(&chunk).await?;
+
+      if flag[0] == STREAM_LAST_CHUNK {
+        break;
+      }
+
+      index += 1;
+    }
+
+    writer.flush().await?;
+
+    Ok(())
+  }
+
+  /// Reads up to [`STREAM_CHUNK_LENGTH`] bytes from `reader`, returning fewer bytes only at the end of the stream.
+  async fn read_chunk(reader: &mut dyn DataStreamReader) -> Result<Vec<u8>> {
+    let mut chunk: Vec<u8> = Vec::with_capacity(STREAM_CHUNK_LENGTH);
+    (&mut *reader)
+      .take(STREAM_CHUNK_LENGTH as u64)
+      .read_to_end(&mut chunk)
+      .await?;
+    Ok(chunk)
+  }
+
+  /// Derives the nonce of the chunk at `index` by XOR-ing the big-endian `index` into the last bytes of the
+  /// `base_nonce`.
+  fn chunk_nonce(base_nonce: &[u8], index: u64) -> Vec<u8> {
+    let mut nonce: Vec<u8> = base_nonce.to_vec();
+    let offset: usize = nonce.len() - core::mem::size_of::<u64>();
+    for (byte, index_byte) in nonce[offset..].iter_mut().zip(index.to_be_bytes()) {
+      *byte ^= index_byte;
+    }
+    nonce
+  }
+
+  /// Appends the chunk `flag` to the `associated_data`, so that the last chunk cannot be truncated unnoticed.
+  fn chunk_associated_data(associated_data: &[u8], flag: u8) -> Vec<u8> {
+    let mut chunk_associated_data: Vec<u8> = Vec::with_capacity(associated_data.len() + 1);
+    chunk_associated_data.extend_from_slice(associated_data);
+    chunk_associated_data.push(flag);
+    chunk_associated_data
+  }
+
+  fn seal_chunk(
+    key: &[u8],
+    algorithm: &EncryptionAlgorithm,
+    nonce: &[u8],
+    associated_data: &[u8],
+    plaintext: &[u8],
+  ) -> Result<Vec<u8>> {
+    match algorithm {
+      EncryptionAlgorithm::AES128GCM => aead_seal::<Aes128Gcm>(key, nonce, associated_data, plaintext),
+      EncryptionAlgorithm::AES192GCM => aead_seal::<Aes192Gcm>(key, nonce, associated_data, plaintext),
+      EncryptionAlgorithm::AES256GCM => aead_seal::<Aes256Gcm>(key, nonce, associated_data, plaintext),
+      EncryptionAlgorithm::XCHACHA20POLY1305 => aead_seal::<XChaCha20Poly1305>(key, nonce, associated_data, plaintext),
+    }
+  }
+
+  fn open_chunk(
+    key: &[u8],
+    algorithm: &EncryptionAlgorithm,
+    nonce: &[u8],
+    associated_data: &[u8],
+    sealed: &[u8],
+  ) -> Result<Vec<u8>> {
+    match algorithm {
+      EncryptionAlgorithm::AES128GCM => aead_open::<Aes128Gcm>(key, nonce, associated_data, sealed),
+      EncryptionAlgorithm::AES192GCM => aead_open::<Aes192Gcm>(key, nonce, associated_data, sealed),
+      EncryptionAlgorithm::AES256GCM => aead_open::<Aes256Gcm>(key, nonce, associated_data, sealed),
+      EncryptionAlgorithm::XCHACHA20POLY1305 => aead_open::<XChaCha20Poly1305>(key, nonce, associated_data, sealed),
+    }
+  }
+
+  /// Encrypts `plaintext` into `ciphertext || tag`.
+  fn aead_seal<A: Aead>(key: &[u8], nonce: &[u8], associated_data: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let mut sealed: Vec<u8> = vec![0; plaintext.len() + A::TAG_LENGTH];
+    let (ciphertext, tag): (&mut [u8], &mut [u8]) = sealed.split_at_mut(plaintext.len());
+    A::try_encrypt(key, nonce, associated_data, plaintext, ciphertext, tag).map_err(Error::EncryptionFailure)?;
+    Ok(sealed)
+  }
+
+  /// Decrypts the `ciphertext || tag` created by [`aead_seal`].
+  fn aead_open<A: Aead>(key: &[u8], nonce: &[u8], associated_data: &[u8], sealed: &[u8]) -> Result<Vec<u8>> {
+    let (ciphertext, tag): (&[u8], &[u8]) = sealed.split_at(sealed.len() - A::TAG_LENGTH);
+    let mut plaintext: Vec<u8> = vec![0; ciphertext.len()];
+    let len: usize =
+      A::try_decrypt(key, nonce, associated_data, &mut plaintext, ciphertext, tag).map_err(Error::DecryptionFailure)?;
+    plaintext.truncate(len);
+    Ok(plaintext)
+  }
+
+  /// A content encryption key together with the values the recipient requires to recover it.
+  ///
+  /// The content encryption key is zeroized on drop.
+  struct ContentEncryptionKey {
+    cek: Vec<u8>,
+    encrypted_cek: Vec<u8>,
+    ephemeral_public_key: Vec<u8>,
+    /// The shared secret `Z` of [`CekAlgorithm::ECDH_1PU_A256KW`], whose key-wrapping key can only be derived
+    /// once the content was encrypted, see [`wrap_tagged_content_encryption_key`]. Empty otherwise.
+    shared_secret: Zeroizing<Vec<u8>>,
+  }
+
+  impl Zeroize for ContentEncryptionKey {
+    fn zeroize(&mut self) {
+      self.cek.zeroize();
+      self.shared_secret.zeroize();
+    }
+  }
+
+  impl Drop for ContentEncryptionKey {
+    fn drop(&mut self) {
+      self.zeroize();
+    }
+  }
+
+  /// Establishes a content encryption key with the owner of the given X25519 `public_key`.
+  fn agree_content_encryption_key(
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender: Option<&KeyPair>,
+    random: &mut dyn FnMut(&mut [u8]) -> Result<()>,
+  ) -> Result<ContentEncryptionKey> {
+    let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = x25519_public_key(public_key.as_ref())?;
+
+    // Generate ephemeral key
+    let mut ephemeral_secret: [u8; X25519::PRIVATE_KEY_LENGTH] = [0; X25519::PRIVATE_KEY_LENGTH];
+    random(&mut ephemeral_secret)?;
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(KeyType::X25519, &ephemeral_secret)?;
+    ephemeral_secret.zeroize();
+    // Obtain the shared secret by combining the ephemeral key and the static public key
+    let mut shared_secret: Zeroizing<Vec<u8>> =
+      Zeroizing::new(X25519::key_exchange(keypair.private(), &public_key)?.to_vec());
+    if is_sender_authenticated(cek_algorithm) {
+      // ECDH-1PU additionally combines the sender's static key and the static public key: Z = Ze || Zs.
+      let sender: &KeyPair = sender.ok_or_else(|| {
+        Error::InvalidPrivateKey(format!("{} requires the sender's private key", cek_algorithm.name()))
+      })?;
+      let sender_secret: Zeroizing<[u8; 32]> =
+        Zeroizing::new(X25519::key_exchange(x25519_private_key(sender)?, &public_key)?);
+      shared_secret.extend_from_slice(&sender_secret[..]);
+    }
+
+    match cek_algorithm {
+      CekAlgorithm::ECDH_ES(agreement) | CekAlgorithm::ECDH_1PU(agreement) => {
+        // The derived secret is the content encryption key, which is zeroized when the key is dropped.
+        let derived_secret: Vec<u8> = derive_key(
+          cek_algorithm.name(),
+          encryption_algorithm.key_length(),
+          &shared_secret,
+          agreement,
+        )
+        .map_err(Error::EncryptionFailure)?;
+
+        Ok(ContentEncryptionKey {
+          cek: derived_secret,
+          encrypted_cek: Vec::new(),
+          ephemeral_public_key: keypair.public().as_ref().to_vec(),
+          shared_secret: Zeroizing::new(Vec::new()),
+        })
+      }
+      CekAlgorithm::ECDH_1PU_A256KW(_) => {
+        // The key-wrapping key is derived from the tag of the content encryption, so the content encryption
+        // key is wrapped only after the content was encrypted.
+        Ok(ContentEncryptionKey {
+          cek: generate_content_encryption_key(*encryption_algorithm, random)?,
+          encrypted_cek: Vec::new(),
+          ephemeral_public_key: keypair.public().as_ref().to_vec(),
+          shared_secret,
+        })
+      }
+      CekAlgorithm::ECDH_ES_A128KW(agreement)
+      | CekAlgorithm::ECDH_ES_A192KW(agreement)
+      | CekAlgorithm::ECDH_ES_A256KW(agreement) => {
+        let derived_secret: Zeroizing<Vec<u8>> = Zeroizing::new(
+          derive_key(
+            cek_algorithm.name(),
+            key_wrap_length(cek_algorithm),
+            &shared_secret,
+            agreement,
+          )
+          .map_err(Error::EncryptionFailure)?,
+        );
+
+        let mut key: ContentEncryptionKey = ContentEncryptionKey {
+          cek: generate_content_encryption_key(*encryption_algorithm, random)?,
+          encrypted_cek: Vec::new(),
+          ephemeral_public_key: keypair.public().as_ref().to_vec(),
+          shared_secret: Zeroizing::new(Vec::new()),
+        };
+
+        key.encrypted_cek = vec![0; key.cek.len() + Aes256Kw::BLOCK];
+        wrap_key(&derived_secret, &key.cek, &mut key.encrypted_cek).map_err(Error::EncryptionFailure)?;
+
+        Ok(key)
+      }
+    }
+  }
+
+  /// Wraps the content encryption key of [`CekAlgorithm::ECDH_1PU_A256KW`], whose key-wrapping key is derived
+  /// with the tag `cctag` of the content encryption, see [`derive_tagged_key`].
+  fn wrap_tagged_content_encryption_key(
+    cek_algorithm: &CekAlgorithm,
+    agreement: &AgreementInfo,
+    key: &ContentEncryptionKey,
+    cctag: &[u8],
+  ) -> crypto::error::Result<Vec<u8>> {
+    let derived_secret: Zeroizing<Vec<u8>> = Zeroizing::new(derive_tagged_key(
+      cek_algorithm.name(),
+      key_wrap_length(cek_algorithm),
+      &key.shared_secret,
+      agreement,
+      cctag,
+    )?);
+
+    let mut encrypted_cek: Vec<u8> = vec![0; key.cek.len() + Aes256Kw::BLOCK];
+    wrap_key(&derived_secret, &key.cek, &mut encrypted_cek)?;
+
+    Ok(encrypted_cek)
+  }
+
+  /// Recovers the content encryption key established by [`agree_content_encryption_key`] with the private key
+  /// of the given X25519 `key_pair`.
+  ///
+  /// The `cctag` is the tag of the content encryption, which is only used by [`CekAlgorithm::ECDH_1PU_A256KW`].
+  #[allow(clippy::too_many_arguments)]
+  fn recover_content_encryption_key(
+    key_pair: &KeyPair,
+    ephemeral_public_key: &[u8],
+    encrypted_cek: &[u8],
+    cctag: &[u8],
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    sender_public_key: Option<&PublicKey>,
+  ) -> Result<Zeroizing<Vec<u8>>> {
+    let private_key: &PrivateKey = match key_pair.type_() {
+      KeyType::Ed25519 => {
+        return Err(Error::InvalidPrivateKey(
+          "Ed25519 keys are not supported for decryption".to_owned(),
+        ))
+      }
+      KeyType::X25519 => key_pair.private(),
+    };
+    let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = x25519_public_key(ephemeral_public_key)?;
+
+    let mut shared_secret: Zeroizing<Vec<u8>> =
+      Zeroizing::new(X25519::key_exchange(private_key, &public_key)?.to_vec());
+    if is_sender_authenticated(cek_algorithm) {
+      let sender_public_key: &PublicKey = sender_public_key
+        .ok_or_else(|| Error::InvalidPublicKey(format!("{} requires the sender's public key", cek_algorithm.name())))?;
+      let sender_public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = x25519_public_key(sender_public_key.as_ref())?;
+      let sender_secret: Zeroizing<[u8; 32]> = Zeroizing::new(X25519::key_exchange(private_key, &sender_public_key)?);
+      shared_secret.extend_from_slice(&sender_secret[..]);
+    }
+
+    match cek_algorithm {
+      CekAlgorithm::ECDH_ES(agreement) | CekAlgorithm::ECDH_1PU(agreement) => derive_key(
+        cek_algorithm.name(),
+        encryption_algorithm.key_length(),
+        &shared_secret,
+        agreement,
+      )
+      .map(Zeroizing::new)
+      .map_err(Error::DecryptionFailure),
+      CekAlgorithm::ECDH_ES_A128KW(agreement)
+      | CekAlgorithm::ECDH_ES_A192KW(agreement)
+      | CekAlgorithm::ECDH_ES_A256KW(agreement)
+      | CekAlgorithm::ECDH_1PU_A256KW(agreement) => {
+        let derived_secret: Zeroizing<Vec<u8>> = Zeroizing::new(
+          match cek_algorithm {
+            CekAlgorithm::ECDH_1PU_A256KW(_) => derive_tagged_key(
+              cek_algorithm.name(),
+              key_wrap_length(cek_algorithm),
+              &shared_secret,
+              agreement,
+              cctag,
+            ),
+            _ => derive_key(
+              cek_algorithm.name(),
+              key_wrap_length(cek_algorithm),
+              &shared_secret,
+              agreement,
+            ),
           }
-        }
+          .map_err(Error::DecryptionFailure)?,
+        );
+
+        let cek_len: usize = encrypted_cek
+          .len()
+          .checked_sub(Aes256Kw::BLOCK)
+          .ok_or(Error::DecryptionFailure(crypto::Error::BufferSize {
+            name: "plaintext cek",
+            needs: Aes256Kw::BLOCK,
+            has: encrypted_cek.len(),
+          }))?;
+
+        let mut cek: Zeroizing<Vec<u8>> = Zeroizing::new(vec![0; cek_len]);
+        unwrap_key(&derived_secret, encrypted_cek, &mut cek).map_err(Error::DecryptionFailure)?;
+
+        Ok(cek)
       }
     }
   }
 
-  async fn blob_set(&self, did: &CoreDID, value: Vec<u8>) -> Result<()> {
-    // Set the arbitrary value for the given DID.
-    self.blobs.write()?.insert(did.clone(), value);
+  /// Returns the length of the key-wrapping key derived for the `cek_algorithm`.
+  ///
+  /// Only meaningful for the key wrapping variants; the direct key agreement variants derive the
+  /// content encryption key itself.
+  fn key_wrap_length(cek_algorithm: &CekAlgorithm) -> usize {
+    match cek_algorithm {
+      CekAlgorithm::ECDH_ES_A128KW(_) => Aes128Kw::KEY_LENGTH,
+      CekAlgorithm::ECDH_ES_A192KW(_) => Aes192Kw::KEY_LENGTH,
+      CekAlgorithm::ECDH_ES(_)
+      | CekAlgorithm::ECDH_ES_A256KW(_)
+      | CekAlgorithm::ECDH_1PU(_)
+      | CekAlgorithm::ECDH_1PU_A256KW(_) => Aes256Kw::KEY_LENGTH,
+    }
+  }
 
-    Ok(())
+  /// Wraps the `cek` into `output` with the AES key wrap variant matching the length of the `key`,
+  /// as returned by [`key_wrap_length`].
+  fn wrap_key(key: &[u8], cek: &[u8], output: &mut [u8]) -> crypto::error::Result<()> {
+    match key.len() {
+      length if length == Aes128Kw::KEY_LENGTH => Aes128Kw::new(key).wrap_key(cek, output),
+      length if length == Aes192Kw::KEY_LENGTH => Aes192Kw::new(key).wrap_key(cek, output),
+      _ => Aes256Kw::new(key).wrap_key(cek, output),
+    }
   }
 
-  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
-    // Lookup the value stored of the given DID.
-    self.blobs.read().map(|data| data.get(did).cloned())
+  /// Unwraps the `encrypted_cek` into `output`, see [`wrap_key`].
+  fn unwrap_key(key: &[u8], encrypted_cek: &[u8], output: &mut [u8]) -> crypto::error::Result<()> {
+    match key.len() {
+      length if length == Aes128Kw::KEY_LENGTH => Aes128Kw::new(key).unwrap_key(encrypted_cek, output),
+      length if length == Aes192Kw::KEY_LENGTH => Aes192Kw::new(key).unwrap_key(encrypted_cek, output),
+      _ => Aes256Kw::new(key).unwrap_key(encrypted_cek, output),
+    }
   }
 
-  async fn flush_changes(&self) -> Result<()> {
-    // The MemStore doesn't need to flush changes to disk or any other persistent store,
-    // which is why this function does nothing.
-    Ok(())
+  /// Rejects [`CekAlgorithm::ECDH_1PU_A256KW`] for streams, whose chunks have no single tag to derive the
+  /// key-wrapping key from.
+  fn check_stream_cek_algorithm(cek_algorithm: &CekAlgorithm) -> Result<()> {
+    match cek_algorithm {
+      CekAlgorithm::ECDH_1PU_A256KW(_) => Err(Error::NotSupported(cek_algorithm.name())),
+      _ => Ok(()),
+    }
   }
-}
 
-#[cfg(feature = "encryption")]
-mod memstore_encryption {
-  use crate::types::AgreementInfo;
-  use crate::types::EncryptedData;
-  use crate::types::EncryptionAlgorithm;
-  use crate::Error;
-  use crate::Result;
-  use crypto::ciphers::aes_gcm::Aes256Gcm;
-  use crypto::ciphers::traits::Aead;
-  use crypto::hashes::sha::Sha256;
-  use crypto::hashes::Digest;
+  /// Returns whether the `cek_algorithm` authenticates the sender by including its static key in the key agreement.
+  fn is_sender_authenticated(cek_algorithm: &CekAlgorithm) -> bool {
+    matches!(
+      cek_algorithm,
+      CekAlgorithm::ECDH_1PU(_) | CekAlgorithm::ECDH_1PU_A256KW(_)
+    )
+  }
+
+  fn x25519_public_key(public_key: &[u8]) -> Result<[u8; X25519::PUBLIC_KEY_LENGTH]> {
+    public_key
+      .try_into()
+      .map_err(|_| Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH)))
+  }
+
+  fn x25519_private_key(key_pair: &KeyPair) -> Result<&PrivateKey> {
+    match key_pair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key agreement".to_owned(),
+      )),
+      KeyType::X25519 => Ok(key_pair.private()),
+    }
+  }
+
+  /// Returns the X25519 key pair converted from the given Ed25519 `key_pair`, or a copy of an X25519 `key_pair`.
+  pub(crate) fn x25519_key_pair(key_pair: &KeyPair) -> Result<KeyPair> {
+    match key_pair.type_() {
+      KeyType::Ed25519 => {
+        let private_key: PrivateKey = X25519::ed25519_to_x25519_private(key_pair.private())?;
+        KeyPair::try_from_private_key_bytes(KeyType::X25519, private_key.as_ref()).map_err(Into::into)
+      }
+      KeyType::X25519 => Ok(key_pair.clone()),
+    }
+  }
 
   pub(crate) fn try_encrypt(
     key: &[u8],
@@ -416,46 +2705,202 @@ mod memstore_encryption {
     associated_data: Vec<u8>,
     encrypted_cek: Vec<u8>,
     ephemeral_public_key: Vec<u8>,
+    random: &mut dyn FnMut(&mut [u8]) -> Result<()>,
   ) -> Result<EncryptedData> {
     match algorithm {
+      EncryptionAlgorithm::AES128GCM => {
+        aead_encrypt::<Aes128Gcm>(key, data, associated_data, encrypted_cek, ephemeral_public_key, random)
+      }
+      EncryptionAlgorithm::AES192GCM => {
+        aead_encrypt::<Aes192Gcm>(key, data, associated_data, encrypted_cek, ephemeral_public_key, random)
+      }
       EncryptionAlgorithm::AES256GCM => {
-        let nonce: &[u8] = &Aes256Gcm::random_nonce().map_err(Error::EncryptionFailure)?;
-        let padding: usize = Aes256Gcm::padsize(data).map(|size| size.get()).unwrap_or_default();
-        let mut ciphertext: Vec<u8> = vec![0; data.len() + padding];
-        let mut tag: Vec<u8> = [0; Aes256Gcm::TAG_LENGTH].to_vec();
-        Aes256Gcm::try_encrypt(key, nonce, associated_data.as_ref(), data, &mut ciphertext, &mut tag)
-          .map_err(Error::EncryptionFailure)?;
-        Ok(EncryptedData::new(
-          nonce.to_vec(),
-          associated_data,
-          tag,
-          ciphertext,
-          encrypted_cek,
-          ephemeral_public_key,
-        ))
+        aead_encrypt::<Aes256Gcm>(key, data, associated_data, encrypted_cek, ephemeral_public_key, random)
+      }
+      EncryptionAlgorithm::XCHACHA20POLY1305 => {
+        aead_encrypt::<XChaCha20Poly1305>(key, data, associated_data, encrypted_cek, ephemeral_public_key, random)
       }
     }
   }
 
-  pub(crate) fn try_decrypt(key: &[u8], algorithm: &EncryptionAlgorithm, data: &EncryptedData) -> Result<Vec<u8>> {
+  pub(crate) fn try_decrypt_into(
+    key: &[u8],
+    algorithm: &EncryptionAlgorithm,
+    data: &EncryptedData,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
     match algorithm {
-      EncryptionAlgorithm::AES256GCM => {
-        let mut plaintext = vec![0; data.ciphertext.len()];
-        let len: usize = Aes256Gcm::try_decrypt(
-          key,
-          &data.nonce,
-          &data.associated_data,
-          &mut plaintext,
-          &data.ciphertext,
-          &data.tag,
-        )
-        .map_err(Error::DecryptionFailure)?;
-        plaintext.truncate(len);
-        Ok(plaintext)
+      EncryptionAlgorithm::AES128GCM => aead_decrypt_into::<Aes128Gcm>(key, data, out),
+      EncryptionAlgorithm::AES192GCM => aead_decrypt_into::<Aes192Gcm>(key, data, out),
+      EncryptionAlgorithm::AES256GCM => aead_decrypt_into::<Aes256Gcm>(key, data, out),
+      EncryptionAlgorithm::XCHACHA20POLY1305 => aead_decrypt_into::<XChaCha20Poly1305>(key, data, out),
+    }
+  }
+
+  fn aead_encrypt<A: Aead>(
+    key: &[u8],
+    data: &[u8],
+    associated_data: Vec<u8>,
+    encrypted_cek: Vec<u8>,
+    ephemeral_public_key: Vec<u8>,
+    random: &mut dyn FnMut(&mut [u8]) -> Result<()>,
+  ) -> Result<EncryptedData> {
+    let mut nonce: Vec<u8> = vec![0; A::NONCE_LENGTH];
+    random(&mut nonce)?;
+    let padding: usize = A::padsize(data).map(|size| size.get()).unwrap_or_default();
+    let mut ciphertext: Vec<u8> = vec![0; data.len() + padding];
+    let mut tag: Vec<u8> = vec![0; A::TAG_LENGTH];
+    A::try_encrypt(key, &nonce, associated_data.as_ref(), data, &mut ciphertext, &mut tag)
+      .map_err(Error::EncryptionFailure)?;
+    Ok(EncryptedData::new(
+      nonce,
+      associated_data,
+      tag,
+      ciphertext,
+      encrypted_cek,
+      ephemeral_public_key,
+    ))
+  }
+
+  fn aead_decrypt_into<A: Aead>(key: &[u8], data: &EncryptedData, out: &mut Vec<u8>) -> Result<usize> {
+    // Resizing only allocates if the capacity of `out` is too small for the ciphertext.
+    out.clear();
+    out.resize(data.ciphertext.len(), 0);
+    match A::try_decrypt(
+      key,
+      &data.nonce,
+      &data.associated_data,
+      out,
+      &data.ciphertext,
+      &data.tag,
+    ) {
+      Ok(len) => {
+        out.truncate(len);
+        Ok(len)
       }
+      Err(error) => {
+        // Don't leave unauthenticated plaintext behind.
+        out.clear();
+        Err(Error::DecryptionFailure(describe_decryption_error::<A>(error)))
+      }
+    }
+  }
+
+  /// Describes a failed tag verification, which happens if the ciphertext, tag or associated data were
+  /// modified since encryption. Note that a wrong key cannot be told apart from modified data.
+  fn describe_decryption_error<A: Aead>(error: crypto::Error) -> crypto::Error {
+    match error {
+      crypto::Error::CipherError { .. } => crypto::Error::InvalidArgumentError {
+        alg: A::NAME,
+        expected: "ciphertext, tag and associated data unmodified since encryption",
+      },
+      error => error,
+    }
+  }
+
+  /// Derives `len` bytes from the `shared_secret` with the [`KeyDerivation`] selected in the `agreement`.
+  fn derive_key(
+    alg: &'static str,
+    len: usize,
+    shared_secret: &[u8],
+    agreement: &AgreementInfo,
+  ) -> crypto::error::Result<Vec<u8>> {
+    match agreement.key_derivation {
+      KeyDerivation::ConcatKdf => concat_kdf(alg, len, shared_secret, agreement),
+      KeyDerivation::HkdfSha256 => hkdf_sha256(&[], shared_secret, &hkdf_info(alg, agreement)?, len),
+    }
+  }
+
+  /// Derives `len` bytes like [`derive_key`], but with the tag `cctag` of the content encryption appended to
+  /// the `SuppPubInfo` as `len(cctag) || cctag`, where the length is a 32-bit big-endian integer.
+  ///
+  /// ECDH-1PU with key wrapping requires this to protect against a compromised recipient, which could otherwise
+  /// re-encrypt different content under the same content encryption key, see section 2.3 of
+  /// draft-madden-jose-ecdh-1pu-04.
+  fn derive_tagged_key(
+    alg: &'static str,
+    len: usize,
+    shared_secret: &[u8],
+    agreement: &AgreementInfo,
+    cctag: &[u8],
+  ) -> crypto::error::Result<Vec<u8>> {
+    let mut agreement: AgreementInfo = agreement.clone();
+    agreement
+      .pub_info
+      .extend_from_slice(&(cctag.len() as u32).to_be_bytes());
+    agreement.pub_info.extend_from_slice(cctag);
+    derive_key(alg, len, shared_secret, &agreement)
+  }
+
+  /// The HKDF `info` parameter for an `agreement`, laid out like the `OtherInfo` of the [`concat_kdf`]:
+  /// `len(alg) || alg || len(apu) || apu || len(apv) || apv || pub_info || priv_info`,
+  /// where each length is a 32-bit big-endian integer.
+  fn hkdf_info(alg: &'static str, agreement: &AgreementInfo) -> crypto::error::Result<Vec<u8>> {
+    let apu_len: u32 = u32::try_from(agreement.apu.len()).map_err(|_| crypto::error::Error::InvalidArgumentError {
+      alg,
+      expected: "apu can't exceed 2^32 - 1 bytes",
+    })?;
+    let apv_len: u32 = u32::try_from(agreement.apv.len()).map_err(|_| crypto::error::Error::InvalidArgumentError {
+      alg,
+      expected: "apv can't exceed 2^32 - 1 bytes",
+    })?;
+
+    let mut info: Vec<u8> = Vec::new();
+    info.extend_from_slice(&(alg.len() as u32).to_be_bytes());
+    info.extend_from_slice(alg.as_bytes());
+    info.extend_from_slice(&apu_len.to_be_bytes());
+    info.extend_from_slice(&agreement.apu);
+    info.extend_from_slice(&apv_len.to_be_bytes());
+    info.extend_from_slice(&agreement.apv);
+    info.extend_from_slice(&agreement.pub_info);
+    info.extend_from_slice(&agreement.priv_info);
+
+    Ok(info)
+  }
+
+  /// HKDF (using HMAC-SHA256) as defined in RFC 5869. An empty `salt` is replaced by zeros.
+  ///
+  /// iota-crypto 0.12 provides HMAC-SHA256 but no HKDF, so the extract and expand steps are built on its
+  /// [`HMAC_SHA256`] and checked against the test vectors of RFC 5869.
+  pub(crate) fn hkdf_sha256(salt: &[u8], ikm: &[u8], info: &[u8], len: usize) -> crypto::error::Result<Vec<u8>> {
+    if len > 255 * SHA256_LEN {
+      return Err(crypto::error::Error::InvalidArgumentError {
+        alg: "HKDF-SHA256",
+        expected: "output can't exceed 255 * 32 bytes",
+      });
+    }
+
+    // Extract
+    let mut prk: [u8; SHA256_LEN] = [0; SHA256_LEN];
+    HMAC_SHA256(ikm, if salt.is_empty() { &[0; SHA256_LEN] } else { salt }, &mut prk);
+
+    // Expand
+    let mut output: Vec<u8> = Vec::with_capacity(len + SHA256_LEN);
+    let mut block: [u8; SHA256_LEN] = [0; SHA256_LEN];
+    let mut input: Vec<u8> = Vec::with_capacity(SHA256_LEN + info.len() + 1);
+    for counter in 1..=((len + SHA256_LEN - 1) / SHA256_LEN) as u8 {
+      input.clear();
+      if counter > 1 {
+        input.extend_from_slice(&block);
+      }
+      input.extend_from_slice(info);
+      input.push(counter);
+      HMAC_SHA256(&input, &prk, &mut block);
+      output.extend_from_slice(&block);
     }
+
+    prk.zeroize();
+    block.zeroize();
+    input.zeroize();
+    output[len..].zeroize();
+    output.truncate(len);
+
+    Ok(output)
   }
 
+  /// The maximum number of bytes derived by the [`concat_kdf`], far beyond the length of any key.
+  pub(crate) const CONCAT_KDF_MAX_LENGTH: usize = 1024 * 1024;
+
   /// The Concat KDF (using SHA-256) as defined in Section 5.8.1 of NIST.800-56A
   pub(crate) fn concat_kdf(
     alg: &'static str,
@@ -466,10 +2911,22 @@ mod memstore_encryption {
     let mut digest: Sha256 = Sha256::new();
     let mut output: Vec<u8> = Vec::new();
 
-    let target: usize = (len + (Sha256::output_size() - 1)) / Sha256::output_size();
-    let rounds: u32 = u32::try_from(target).map_err(|_| crypto::error::Error::InvalidArgumentError {
+    if len > CONCAT_KDF_MAX_LENGTH {
+      return Err(crypto::error::Error::InvalidArgumentError {
+        alg,
+        expected: "output can't exceed 1 MiB",
+      });
+    }
+    // Neither the addition can overflow nor the number of rounds exceed `u32::MAX` for lengths below the cap.
+    let rounds: u32 = ((len + (SHA256_LEN - 1)) / SHA256_LEN) as u32;
+
+    let apu_len: u32 = u32::try_from(agreement.apu.len()).map_err(|_| crypto::error::Error::InvalidArgumentError {
+      alg,
+      expected: "apu can't exceed 2^32 - 1 bytes",
+    })?;
+    let apv_len: u32 = u32::try_from(agreement.apv.len()).map_err(|_| crypto::error::Error::InvalidArgumentError {
       alg,
-      expected: "iterations can't exceed 2^32 - 1",
+      expected: "apv can't exceed 2^32 - 1 bytes",
     })?;
 
     for count in 0..rounds {
@@ -484,11 +2941,11 @@ mod memstore_encryption {
       digest.update(alg.as_bytes());
 
       // PartyUInfo
-      digest.update(&(agreement.apu.len() as u32).to_be_bytes());
+      digest.update(&apu_len.to_be_bytes());
       digest.update(&agreement.apu);
 
       // PartyVInfo
-      digest.update(&(agreement.apv.len() as u32).to_be_bytes());
+      digest.update(&apv_len.to_be_bytes());
       digest.update(&agreement.apv);
 
       // SuppPubInfo
@@ -500,17 +2957,207 @@ mod memstore_encryption {
       output.extend_from_slice(&digest.finalize_reset());
     }
 
+    output[len..].zeroize();
     output.truncate(len);
 
     Ok(output)
   }
 
   /// Generate a random content encryption key of suitable length for `encryption_algorithm`.
-  pub(crate) fn generate_content_encryption_key(encryption_algorithm: EncryptionAlgorithm) -> Result<Vec<u8>> {
+  pub(crate) fn generate_content_encryption_key(
+    encryption_algorithm: EncryptionAlgorithm,
+    random: &mut dyn FnMut(&mut [u8]) -> Result<()>,
+  ) -> Result<Vec<u8>> {
     let mut bytes: Vec<u8> = vec![0; encryption_algorithm.key_length()];
-    crypto::utils::rand::fill(bytes.as_mut()).map_err(Error::EncryptionFailure)?;
+    random(bytes.as_mut())?;
     Ok(bytes)
   }
+
+  /// Fills `bytes` using the operating system's random number generator.
+  pub(crate) fn os_random(bytes: &mut [u8]) -> Result<()> {
+    crypto::utils::rand::fill(bytes).map_err(Error::EncryptionFailure)
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use proptest::collection::vec;
+    use proptest::prelude::any;
+    use proptest::proptest;
+    use proptest::sample::Index;
+
+    use crate::types::AgreementInfo;
+
+    use identity_core::crypto::X25519;
+    use identity_core::utils::Base;
+    use identity_core::utils::BaseEncoding;
+
+    use super::concat_kdf;
+    use super::derive_tagged_key;
+    use super::hkdf_sha256;
+    use super::unwrap_key;
+    use super::wrap_key;
+    use super::ContentEncryptionKey;
+    use super::CONCAT_KDF_MAX_LENGTH;
+    use zeroize::Zeroize;
+    use zeroize::Zeroizing;
+
+    const ALG: &str = "ECDH-ES";
+
+    fn flip(bytes: &mut [u8], index: &Index) {
+      let index: usize = index.index(bytes.len());
+      bytes[index] ^= 0x01;
+    }
+
+    proptest! {
+      #[test]
+      fn test_concat_kdf_output_length(
+        shared_secret in vec(any::<u8>(), 0..64),
+        apu in vec(any::<u8>(), 0..64),
+        apv in vec(any::<u8>(), 0..64),
+        pub_info in vec(any::<u8>(), 0..64),
+        priv_info in vec(any::<u8>(), 0..64),
+        len in 0usize..256,
+      ) {
+        let agreement: AgreementInfo = AgreementInfo::new(apu, apv, pub_info, priv_info);
+        let output: Vec<u8> = concat_kdf(ALG, len, &shared_secret, &agreement).unwrap();
+        assert_eq!(output.len(), len);
+        assert_eq!(output, concat_kdf(ALG, len, &shared_secret, &agreement).unwrap());
+      }
+
+      #[test]
+      fn test_concat_kdf_input_sensitivity(
+        shared_secret in vec(any::<u8>(), 1..64),
+        apu in vec(any::<u8>(), 1..64),
+        apv in vec(any::<u8>(), 1..64),
+        pub_info in vec(any::<u8>(), 1..64),
+        priv_info in vec(any::<u8>(), 1..64),
+        // Short outputs could collide by chance, so at least 16 bytes are derived.
+        len in 16usize..256,
+        index in any::<Index>(),
+      ) {
+        let agreement: AgreementInfo = AgreementInfo::new(apu, apv, pub_info, priv_info);
+        let output: Vec<u8> = concat_kdf(ALG, len, &shared_secret, &agreement).unwrap();
+
+        let mut modified_secret: Vec<u8> = shared_secret.clone();
+        flip(&mut modified_secret, &index);
+        assert_ne!(output, concat_kdf(ALG, len, &modified_secret, &agreement).unwrap());
+
+        let mut modified: AgreementInfo = agreement.clone();
+        flip(&mut modified.apu, &index);
+        assert_ne!(output, concat_kdf(ALG, len, &shared_secret, &modified).unwrap());
+
+        let mut modified: AgreementInfo = agreement.clone();
+        flip(&mut modified.apv, &index);
+        assert_ne!(output, concat_kdf(ALG, len, &shared_secret, &modified).unwrap());
+
+        let mut modified: AgreementInfo = agreement.clone();
+        flip(&mut modified.pub_info, &index);
+        assert_ne!(output, concat_kdf(ALG, len, &shared_secret, &modified).unwrap());
+
+        let mut modified: AgreementInfo = agreement;
+        flip(&mut modified.priv_info, &index);
+        assert_ne!(output, concat_kdf(ALG, len, &shared_secret, &modified).unwrap());
+      }
+    }
+
+    #[test]
+    fn test_concat_kdf_length_limit() {
+      let agreement: AgreementInfo = AgreementInfo::new(vec![1], vec![2], vec![3], vec![4]);
+
+      let output: Vec<u8> = concat_kdf(ALG, CONCAT_KDF_MAX_LENGTH, &[0xAB; 32], &agreement).unwrap();
+      assert_eq!(output.len(), CONCAT_KDF_MAX_LENGTH);
+
+      assert!(concat_kdf(ALG, CONCAT_KDF_MAX_LENGTH + 1, &[0xAB; 32], &agreement).is_err());
+      // Rounding up to a whole number of rounds must not wrap around to a short output.
+      assert!(concat_kdf(ALG, usize::MAX, &[0xAB; 32], &agreement).is_err());
+    }
+
+    // Test cases 1 and 3 of RFC 5869, Appendix A.
+    #[test]
+    fn test_hkdf_sha256_vectors() {
+      let ikm: [u8; 22] = [0x0b; 22];
+
+      let salt: Vec<u8> = (0x00..=0x0c).collect();
+      let info: Vec<u8> = (0xf0..=0xf9).collect();
+      let expected: [u8; 42] = [
+        60, 178, 95, 37, 250, 172, 213, 122, 144, 67, 79, 100, 208, 54, 47, 42, 45, 45, 10, 144, 207, 26, 90, 76, 93,
+        176, 45, 86, 236, 196, 197, 191, 52, 0, 114, 8, 213, 184, 135, 24, 88, 101,
+      ];
+      assert_eq!(hkdf_sha256(&salt, &ikm, &info, 42).unwrap(), expected);
+
+      let expected: [u8; 42] = [
+        141, 164, 231, 117, 165, 99, 193, 143, 113, 95, 128, 42, 6, 60, 90, 49, 184, 161, 31, 92, 94, 225, 135, 158,
+        195, 69, 78, 95, 60, 115, 141, 45, 157, 32, 19, 149, 250, 164, 182, 26, 150, 200,
+      ];
+      assert_eq!(hkdf_sha256(&[], &ikm, &[], 42).unwrap(), expected);
+
+      assert!(hkdf_sha256(&[], &ikm, &[], 255 * 32 + 1).is_err());
+    }
+
+    // The key wrapped for Bob in Appendix B of draft-madden-jose-ecdh-1pu-04, which uses ECDH-1PU+A128KW
+    // with the tag of the content encryption in the key derivation.
+    #[test]
+    fn test_ecdh_1pu_key_wrap_vector() {
+      let decode = |data: &str| BaseEncoding::decode(data, Base::Base64Url).unwrap();
+
+      let alice_private_key: Vec<u8> = decode("i9KuFhSzEBsiv3PKVL5115OCdsqQai5nj_Flzfkw5jU");
+      let bob_public_key: Vec<u8> = decode("BT7aR0ItXfeDAldeeOlXL_wXqp-j5FltT0vRSG16kRw");
+      let ephemeral_private_key: Vec<u8> = decode("x8EVZH4Fwk673_mUujnliJoSrLz0zYzzCWp5GUX2fc8");
+      let cctag: Vec<u8> = decode("HLb4fTlm8spGmij3RyOs2gJ4DpHM4hhVRwdF_hGb3WQ");
+      let expected: Vec<u8> =
+        decode("pOMVA9_PtoRe7xXW1139NzzN1UhiFoio8lGto9cf0t8PyU-sjNXH8-LIRLycq8CHJQbDwvQeU1cSl55cQ0hGezJu2N9IY0QN");
+      let cek: Vec<u8> = (0xc0..=0xff).rev().collect();
+
+      // Z = Ze || Zs
+      let mut shared_secret: Vec<u8> = X25519::key_exchange(&ephemeral_private_key, &bob_public_key)
+        .unwrap()
+        .to_vec();
+      shared_secret.extend_from_slice(&X25519::key_exchange(&alice_private_key, &bob_public_key).unwrap());
+
+      // The SuppPubInfo starts with the length of the key-wrapping key in bits.
+      let agreement: AgreementInfo = AgreementInfo::new(
+        b"Alice".to_vec(),
+        b"Bob and Charlie".to_vec(),
+        128u32.to_be_bytes().to_vec(),
+        Vec::new(),
+      );
+      let key: Zeroizing<Vec<u8>> =
+        Zeroizing::new(derive_tagged_key("ECDH-1PU+A128KW", 16, &shared_secret, &agreement, &cctag).unwrap());
+
+      let mut encrypted_cek: Vec<u8> = vec![0; cek.len() + 8];
+      wrap_key(&key, &cek, &mut encrypted_cek).unwrap();
+      assert_eq!(encrypted_cek, expected);
+
+      let mut unwrapped: Vec<u8> = vec![0; cek.len()];
+      unwrap_key(&key, &expected, &mut unwrapped).unwrap();
+      assert_eq!(unwrapped, cek);
+
+      // The key-wrapping key depends on the tag.
+      let mut modified: Vec<u8> = cctag;
+      modified[0] ^= 0x01;
+      let key: Vec<u8> = derive_tagged_key("ECDH-1PU+A128KW", 16, &shared_secret, &agreement, &modified).unwrap();
+      assert!(unwrap_key(&key, &expected, &mut unwrapped).is_err());
+    }
+
+    #[test]
+    fn test_content_encryption_key_zeroize() {
+      let mut key: ContentEncryptionKey = ContentEncryptionKey {
+        cek: vec![0xAB; 32],
+        encrypted_cek: vec![0xCD; 40],
+        ephemeral_public_key: vec![0xEF; 32],
+        shared_secret: Zeroizing::new(vec![0x12; 64]),
+      };
+
+      // Reading the buffer after the key was dropped is undefined behaviour, so this checks the
+      // zeroization that `Drop` relies on instead.
+      key.zeroize();
+
+      assert!(key.cek.is_empty());
+      assert!(key.shared_secret.is_empty());
+      assert_eq!(key.encrypted_cek, vec![0xCD; 40]);
+      assert_eq!(key.ephemeral_public_key, vec![0xEF; 32]);
+    }
+  }
 }
 
 impl Debug for MemStore {
@@ -519,6 +3166,8 @@ impl Debug for MemStore {
       f.debug_struct("MemStore")
         .field("blobs", &self.blobs)
         .field("vaults", &self.vaults)
+        .field("metadata", &self.metadata)
+        .field("timestamps", &self.timestamps)
         .finish()
     } else {
       f.write_str("MemStore")
@@ -526,6 +3175,34 @@ impl Debug for MemStore {
   }
 }
 
+impl Clone for MemStore {
+  fn clone(&self) -> Self {
+    Self {
+      expand: self.expand,
+      blobs: Arc::clone(&self.blobs),
+      vaults: Arc::clone(&self.vaults),
+      metadata: Arc::clone(&self.metadata),
+      timestamps: Arc::clone(&self.timestamps),
+      capacity: self.capacity,
+      usage: Arc::clone(&self.usage),
+      fail_next_flush: Arc::clone(&self.fail_next_flush),
+      #[cfg(feature = "encryption")]
+      random: Arc::clone(&self.random),
+    }
+  }
+}
+
+/// Compares the vaults and blobs of two stores as sets, regardless of the order in which their contents
+/// were inserted. Keys are compared by location, type and public key, and their creation times are
+/// ignored, as are the key-value metadata, the DID timestamps and the usage tracking.
+///
+/// Stores whose locks are poisoned compare unequal.
+impl PartialEq for MemStore {
+  fn eq(&self, other: &Self) -> bool {
+    self.content_eq(other).unwrap_or(false)
+  }
+}
+
 impl Default for MemStore {
   fn default() -> Self {
     Self::new()
@@ -535,8 +3212,22 @@ impl Default for MemStore {
 #[cfg(test)]
 #[cfg(feature = "storage-test-suite")]
 mod tests {
+  use identity_core::crypto::KeyPair;
+  use identity_core::crypto::KeyType;
+  use identity_core::utils::BaseEncoding;
+  use identity_did::did::CoreDID;
+  use identity_did::did::DID;
+  use identity_iota_core::tangle::Network;
+
+  use crate::error::Error;
+  use crate::storage::DynStorage;
   use crate::storage::Storage;
   use crate::storage::StorageTestSuite;
+  use crate::types::ConflictPolicy;
+  use crate::types::DIDType;
+  use crate::types::KeyLocation;
+  use crate::types::MergeReport;
+  use crate::types::Signature;
 
   use super::MemStore;
 
@@ -545,58 +3236,635 @@ mod tests {
   }
 
   #[tokio::test]
-  async fn test_memstore_did_create_with_private_key() {
-    StorageTestSuite::did_create_private_key_test(test_memstore())
+  async fn test_memstore_run_all() {
+    let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { test_memstore() }).await.unwrap();
+    assert!(
+      skipped.is_empty(),
+      "expected MemStore to support every test, skipped {skipped:?}"
+    );
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_memstore_concurrency() {
+    StorageTestSuite::concurrency_test(test_memstore()).await.unwrap()
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_memstore_blob_append() {
+    StorageTestSuite::blob_append_test(test_memstore()).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_memstore_debug_redacts_private_keys() {
+    let mut memstore: MemStore = MemStore::new();
+    memstore.set_expand(true);
+
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let (did, _): (CoreDID, KeyLocation) = memstore
+      .did_create(
+        DIDType::IotaDID,
+        Network::Mainnet.name(),
+        "key-1",
+        Some(keypair.private().clone()),
+      )
+      .await
+      .unwrap();
+    memstore.blob_set(&did, b"blob".to_vec()).await.unwrap();
+
+    let debug: String = format!("{memstore:?}");
+    let encode_hex = |bytes: &[u8]| -> String { bytes.iter().map(|byte| format!("{byte:02x}")).collect() };
+
+    assert!(debug.contains("key-1"));
+    assert!(debug.contains(&encode_hex(keypair.public().as_ref())));
+    assert!(debug.contains("<redacted>"));
+    assert!(!debug.contains(&encode_hex(keypair.private().as_ref())));
+    assert!(!debug.contains(&format!("{:?}", keypair.private().as_ref())));
+  }
+
+  #[tokio::test]
+  async fn test_memstore_capacity_evicts_least_recently_used() {
+    const CAPACITY: usize = 10;
+    let memstore: MemStore = MemStore::with_capacity(CAPACITY);
+
+    let mut dids: Vec<CoreDID> = Vec::new();
+    for _ in 0..CAPACITY + 5 {
+      let (did, _): (CoreDID, KeyLocation) = memstore
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+        .await
+        .unwrap();
+      memstore.blob_set(&did, b"blob".to_vec()).await.unwrap();
+      dids.push(did);
+    }
+
+    // The five oldest DIDs were evicted together with their blobs.
+    assert_eq!(memstore.take_evicted().unwrap(), dids[..5].to_vec());
+    assert!(memstore.take_evicted().unwrap().is_empty());
+    assert_eq!(memstore.did_list().await.unwrap().len(), CAPACITY);
+    for did in dids[..5].iter() {
+      assert!(!memstore.did_exists(did).await.unwrap());
+      assert!(memstore.blob_get(did).await.unwrap().is_none());
+    }
+    for did in dids[5..].iter() {
+      assert!(memstore.did_exists(did).await.unwrap());
+    }
+
+    // Reading the oldest remaining DID marks it as recently used, so the next oldest is evicted instead.
+    assert!(memstore.blob_get(&dids[5]).await.unwrap().is_some());
+    memstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+
+    assert_eq!(memstore.take_evicted().unwrap(), vec![dids[6].clone()]);
+    assert!(memstore.did_exists(&dids[5]).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn test_memstore_capacity_bounds_evicted() {
+    const CAPACITY: usize = 2;
+    let memstore: MemStore = MemStore::with_capacity(CAPACITY);
+
+    let mut dids: Vec<CoreDID> = Vec::new();
+    for _ in 0..CAPACITY * 3 {
+      let (did, _): (CoreDID, KeyLocation) = memstore
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+        .await
+        .unwrap();
+      dids.push(did);
+    }
+
+    // Four DIDs were evicted, but only the most recently evicted ones are kept.
+    assert_eq!(memstore.take_evicted().unwrap(), dids[CAPACITY..CAPACITY * 2].to_vec());
+  }
+
+  #[tokio::test]
+  async fn test_memstore_dyn_storage() {
+    let memstore: Box<dyn Storage> = Box::new(MemStore::new());
+    let storages: Vec<DynStorage> = vec![memstore.into()];
+
+    for storage in storages.iter() {
+      let (did, location): (CoreDID, KeyLocation) = storage
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+        .await
+        .unwrap();
+
+      let signature: Signature = storage.key_sign(&did, &location, b"data".to_vec()).await.unwrap();
+      assert!(storage.key_verify(&did, &location, b"data", &signature).await.unwrap());
+
+      storage
+        .transaction(Box::pin(async { storage.blob_set(&did, b"blob".to_vec()).await }))
+        .await
+        .unwrap();
+      assert_eq!(storage.blob_get(&did).await.unwrap(), Some(b"blob".to_vec()));
+      assert_eq!(storage.did_list().await.unwrap(), vec![did]);
+
+      storage.close().await.unwrap();
+    }
+  }
+
+  #[tokio::test]
+  async fn test_memstore_clone_shares_state() {
+    let memstore: MemStore = MemStore::new();
+    let clone: MemStore = memstore.clone();
+
+    let (did, _): (CoreDID, KeyLocation) = memstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+    assert!(clone.did_exists(&did).await.unwrap());
+
+    assert!(clone.did_purge(&did).await.unwrap());
+    assert!(!memstore.did_exists(&did).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn test_memstore_encryption_shared() {
+    let memstore: MemStore = MemStore::new();
+    StorageTestSuite::encryption_test(memstore.clone(), memstore)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_memstore_with_dids() {
+    let memstore: MemStore = MemStore::new();
+    for _ in 0..3 {
+      memstore
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+        .await
+        .unwrap();
+    }
+
+    let mut count: usize = 0;
+    memstore.with_dids(|_| count += 1).unwrap();
+
+    assert_eq!(count, 3);
+    assert_eq!(count, memstore.did_list().await.unwrap().len());
+  }
+
+  #[tokio::test]
+  async fn test_memstore_close() {
+    let memstore: MemStore = MemStore::new();
+    StorageTestSuite::close_test(memstore.clone(), move || memstore)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_memstore_chain_state_persistence() {
+    let memstore: MemStore = MemStore::new();
+    StorageTestSuite::chain_state_persistence_test(memstore.clone(), move || memstore)
       .await
       .unwrap()
   }
 
   #[tokio::test]
-  async fn test_memstore_did_create_generate_key() {
-    StorageTestSuite::did_create_generate_key_test(test_memstore())
+  async fn test_memstore_flush_failure() {
+    let memstore: MemStore = MemStore::new();
+    memstore.fail_next_flush();
+    StorageTestSuite::flush_failure_test(memstore).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_memstore_transaction_rollback() {
+    StorageTestSuite::transaction_rollback_test(test_memstore())
       .await
       .unwrap()
   }
 
+  #[cfg(feature = "tracing")]
   #[tokio::test]
-  async fn test_memstore_key_generate() {
-    StorageTestSuite::key_generate_test(test_memstore()).await.unwrap()
+  async fn test_memstore_tracing_spans() {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use tracing::field::Field;
+    use tracing::field::Visit;
+    use tracing::span::Attributes;
+    use tracing::span::Id;
+    use tracing::span::Record;
+    use tracing::Event;
+    use tracing::Metadata;
+    use tracing::Subscriber;
+
+    use crate::utils::did_hash;
+
+    type RecordedSpan = (&'static str, Vec<(String, String)>);
+
+    // Records the name and fields of every span that is created.
+    #[derive(Clone, Default)]
+    struct SpanRecorder(Arc<Mutex<Vec<RecordedSpan>>>);
+
+    struct FieldRecorder<'a>(&'a mut Vec<(String, String)>);
+
+    impl Visit for FieldRecorder<'_> {
+      fn record_debug(&mut self, field: &Field, value: &dyn core::fmt::Debug) {
+        self.0.push((field.name().to_owned(), format!("{value:?}")));
+      }
+    }
+
+    impl Subscriber for SpanRecorder {
+      fn enabled(&self, _: &Metadata<'_>) -> bool {
+        true
+      }
+
+      fn new_span(&self, span: &Attributes<'_>) -> Id {
+        let mut fields: Vec<(String, String)> = Vec::new();
+        span.record(&mut FieldRecorder(&mut fields));
+
+        let mut spans = self.0.lock().unwrap();
+        spans.push((span.metadata().name(), fields));
+        Id::from_u64(spans.len() as u64)
+      }
+
+      fn record(&self, _: &Id, _: &Record<'_>) {}
+
+      fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+      fn event(&self, _: &Event<'_>) {}
+
+      fn enter(&self, _: &Id) {}
+
+      fn exit(&self, _: &Id) {}
+    }
+
+    let recorder: SpanRecorder = SpanRecorder::default();
+    let _guard = tracing::subscriber::set_default(recorder.clone());
+
+    let memstore: MemStore = MemStore::new();
+    let (did, location): (CoreDID, KeyLocation) = memstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+    memstore.key_public(&did, &location).await.unwrap();
+
+    let spans = recorder.0.lock().unwrap();
+
+    assert_eq!(spans.iter().filter(|(name, _)| *name == "did_create").count(), 1);
+    assert!(spans.iter().any(|(name, _)| *name == "lock"));
+
+    let (_, fields): &RecordedSpan = spans.iter().find(|(name, _)| *name == "key_public").unwrap();
+    assert_eq!(fields, &vec![("did".to_owned(), did_hash(&did))]);
+
+    // Neither the DID nor key material end up in any span.
+    for (_, fields) in spans.iter() {
+      for (_, value) in fields {
+        assert!(!value.contains(did.method_id()));
+      }
+    }
   }
 
   #[tokio::test]
-  async fn test_memstore_key_delete() {
-    StorageTestSuite::key_delete_test(test_memstore()).await.unwrap()
+  async fn test_memstore_encryption_vector() {
+    let memstore: MemStore = MemStore::new();
+    memstore
+      .set_rng_for_testing(StorageTestSuite::deterministic_fill())
+      .unwrap();
+    StorageTestSuite::encryption_vector_test(memstore).await.unwrap()
   }
 
   #[tokio::test]
-  async fn test_memstore_did_list() {
-    StorageTestSuite::did_list_test(test_memstore()).await.unwrap()
+  async fn test_memstore_to_bytes_from_bytes_roundtrip() {
+    let memstore: MemStore = MemStore::new();
+    let mut keys: Vec<(CoreDID, KeyLocation)> = Vec::new();
+
+    for _ in 0..3 {
+      let (did, location): (CoreDID, KeyLocation) = memstore
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
+        .await
+        .unwrap();
+      let x25519_location: KeyLocation = memstore.key_generate(&did, KeyType::X25519, "kex-0").await.unwrap();
+      memstore.blob_set(&did, did.as_str().as_bytes().to_vec()).await.unwrap();
+
+      keys.push((did.clone(), location));
+      keys.push((did, x25519_location));
+    }
+
+    let bytes: Vec<u8> = memstore.to_bytes().unwrap();
+    let reloaded: MemStore = MemStore::from_bytes(&bytes).unwrap();
+
+    let mut expected_dids: Vec<CoreDID> = memstore.did_list().await.unwrap();
+    let mut dids: Vec<CoreDID> = reloaded.did_list().await.unwrap();
+    expected_dids.sort();
+    dids.sort();
+    assert_eq!(dids, expected_dids);
+
+    for (did, location) in keys.iter() {
+      assert_eq!(
+        reloaded.key_public(did, location).await.unwrap().as_ref(),
+        memstore.key_public(did, location).await.unwrap().as_ref()
+      );
+      assert_eq!(
+        reloaded.key_metadata(did, location).await.unwrap().created_at,
+        memstore.key_metadata(did, location).await.unwrap().created_at
+      );
+      assert_eq!(
+        reloaded.blob_get(did).await.unwrap(),
+        Some(did.as_str().as_bytes().to_vec())
+      );
+
+      if location.key_type == KeyType::Ed25519 {
+        let data: Vec<u8> = b"message".to_vec();
+        let signature: Signature = reloaded.key_sign(did, location, data.clone()).await.unwrap();
+        let expected: Signature = memstore.key_sign(did, location, data).await.unwrap();
+        assert_eq!(signature.as_bytes(), expected.as_bytes());
+      }
+    }
   }
 
   #[tokio::test]
-  async fn test_memstore_key_insert() {
-    StorageTestSuite::key_insert_test(test_memstore()).await.unwrap()
+  async fn test_memstore_lock_poisoned() {
+    let memstore: MemStore = MemStore::new();
+    let (did, _): (CoreDID, KeyLocation) = memstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
+      .await
+      .unwrap();
+
+    let vaults = memstore.vaults.clone();
+    let result = std::thread::spawn(move || {
+      let _guard = vaults.write().unwrap();
+      panic!("panic while holding the vaults lock");
+    })
+    .join();
+    assert!(result.is_err());
+
+    assert!(matches!(
+      memstore.did_exists(&did).await,
+      Err(Error::LockPoisoned("read"))
+    ));
+    assert!(matches!(
+      memstore.did_purge(&did).await,
+      Err(Error::LockPoisoned("write"))
+    ));
+
+    memstore.clear_poison();
+    assert!(memstore.did_exists(&did).await.unwrap());
   }
 
   #[tokio::test]
-  async fn test_memstore_key_sign_ed25519() {
-    StorageTestSuite::key_sign_ed25519_test(test_memstore()).await.unwrap()
+  async fn test_memstore_eq_and_fingerprint_independent_of_order() {
+    let keypairs: Vec<KeyPair> = (0..3).map(|_| KeyPair::new(KeyType::Ed25519).unwrap()).collect();
+    let x25519_keypair: KeyPair = KeyPair::new(KeyType::X25519).unwrap();
+
+    async fn populate(memstore: &MemStore, keypairs: &[&KeyPair], x25519_keypair: &KeyPair) -> Vec<CoreDID> {
+      let mut dids: Vec<CoreDID> = Vec::new();
+      for keypair in keypairs {
+        let (did, _): (CoreDID, KeyLocation) = memstore
+          .did_create(
+            DIDType::IotaDID,
+            Network::Mainnet.name(),
+            "sign-0",
+            Some(keypair.private().clone()),
+          )
+          .await
+          .unwrap();
+        let location: KeyLocation =
+          KeyLocation::new(KeyType::X25519, "kex-0".to_owned(), x25519_keypair.public().as_ref());
+        memstore
+          .key_insert(&did, &location, x25519_keypair.private().clone())
+          .await
+          .unwrap();
+        memstore.blob_set(&did, did.as_str().as_bytes().to_vec()).await.unwrap();
+        dids.push(did);
+      }
+      dids
+    }
+
+    let memstore: MemStore = MemStore::new();
+    let reordered: MemStore = MemStore::new();
+    let dids: Vec<CoreDID> = populate(&memstore, &keypairs.iter().collect::<Vec<_>>(), &x25519_keypair).await;
+    populate(&reordered, &keypairs.iter().rev().collect::<Vec<_>>(), &x25519_keypair).await;
+
+    assert!(memstore == reordered);
+    assert!(memstore == memstore.clone());
+    assert_eq!(memstore.fingerprint().unwrap(), reordered.fingerprint().unwrap());
+
+    reordered.blob_set(&dids[0], b"changed".to_vec()).await.unwrap();
+    assert!(memstore != reordered);
+    assert_ne!(memstore.fingerprint().unwrap(), reordered.fingerprint().unwrap());
+
+    reordered
+      .blob_set(&dids[0], dids[0].as_str().as_bytes().to_vec())
+      .await
+      .unwrap();
+    reordered.did_purge(&dids[1]).await.unwrap();
+    assert!(memstore != reordered);
+    assert_ne!(memstore.fingerprint().unwrap(), reordered.fingerprint().unwrap());
   }
 
+  #[cfg(feature = "deterministic-order")]
   #[tokio::test]
-  async fn test_memstore_key_value_store() {
-    StorageTestSuite::key_value_store_test(test_memstore()).await.unwrap()
+  async fn test_memstore_to_bytes_deterministic() {
+    let memstore: MemStore = MemStore::new();
+    let mut dids: Vec<CoreDID> = Vec::new();
+
+    for _ in 0..5 {
+      let (did, _): (CoreDID, KeyLocation) = memstore
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
+        .await
+        .unwrap();
+      for fragment in ["kex-0", "kex-1", "kex-2"] {
+        memstore.key_generate(&did, KeyType::X25519, fragment).await.unwrap();
+      }
+      memstore.blob_set(&did, did.as_str().as_bytes().to_vec()).await.unwrap();
+      dids.push(did);
+    }
+    // Removing entries must not reorder the remaining ones.
+    memstore.did_purge(&dids[1]).await.unwrap();
+
+    let bytes: Vec<u8> = memstore.to_bytes().unwrap();
+    assert_eq!(memstore.to_bytes().unwrap(), bytes);
+
+    // A reloaded store is rebuilt in the serialized order, so it serializes to the same bytes.
+    let reloaded: MemStore = MemStore::from_bytes(&bytes).unwrap();
+    assert_eq!(reloaded.to_bytes().unwrap(), bytes);
+  }
+
+  #[test]
+  fn test_memstore_from_bytes_unsupported_version() {
+    let bytes: Vec<u8> = br#"{"version":255}"#.to_vec();
+    assert!(matches!(
+      MemStore::from_bytes(&bytes),
+      Err(Error::UnsupportedVersion(255))
+    ));
   }
 
   #[tokio::test]
-  async fn test_memstore_did_purge() {
-    StorageTestSuite::did_purge_test(test_memstore()).await.unwrap()
+  async fn test_memstore_export_json_import_json_roundtrip() {
+    let memstore: MemStore = MemStore::new();
+
+    let (did, location): (CoreDID, KeyLocation) = memstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
+      .await
+      .unwrap();
+    let x25519_location: KeyLocation = memstore.key_generate(&did, KeyType::X25519, "kex-0").await.unwrap();
+    let indexed_location: KeyLocation = KeyLocation::with_index(KeyType::Ed25519, "derived".to_owned(), 7);
+    let indexed_keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    memstore
+      .key_insert(&did, &indexed_location, indexed_keypair.private().clone())
+      .await
+      .unwrap();
+    memstore.blob_set(&did, b"default blob".to_vec()).await.unwrap();
+    memstore
+      .blob_set_named(&did, "named", b"named blob".to_vec())
+      .await
+      .unwrap();
+
+    // A DID which only has a blob must not be created by the import.
+    let blob_only_did: CoreDID = CoreDID::parse("did:example:blob-only").unwrap();
+    memstore.blob_set(&blob_only_did, b"orphan".to_vec()).await.unwrap();
+
+    let json: String = memstore.export_json().unwrap();
+    let imported: MemStore = MemStore::import_json(&json).unwrap();
+
+    assert!(imported == memstore);
+    assert!(imported.did_exists(&did).await.unwrap());
+    assert!(!imported.did_exists(&blob_only_did).await.unwrap());
+    assert_eq!(
+      imported.blob_get(&blob_only_did).await.unwrap(),
+      Some(b"orphan".to_vec())
+    );
+    for location in [&location, &x25519_location, &indexed_location] {
+      assert_eq!(
+        imported.key_metadata(&did, location).await.unwrap().created_at,
+        memstore.key_metadata(&did, location).await.unwrap().created_at
+      );
+    }
+    assert_eq!(
+      imported.key_public(&did, &indexed_location).await.unwrap().as_ref(),
+      indexed_keypair.public().as_ref()
+    );
+  }
+
+  #[test]
+  fn test_memstore_import_json_malformed() {
+    // Not JSON at all.
+    assert!(matches!(
+      MemStore::import_json("{\"version\": 1, \"dids\": ["),
+      Err(Error::CoreError(identity_core::Error::DecodeJSON(_)))
+    ));
+
+    // A key without its public and private key.
+    let error: Error = MemStore::import_json(
+      r#"{"version":1,"dids":[{"did":"did:example:123","keys":[{"type":"Ed25519","fragment":"sign-0"}]}]}"#,
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("missing field `public_key`"), "{error}");
+
+    // A public key which does not belong to the private key.
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let other: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let json: String = format!(
+      r#"{{"version":1,"dids":[{{"did":"did:example:123","keys":[{{"type":"Ed25519","fragment":"sign-0","public_key":"{}","private_key":"{}"}}]}}]}}"#,
+      BaseEncoding::encode_base58(other.public()),
+      BaseEncoding::encode_base58(keypair.private()),
+    );
+    assert!(matches!(MemStore::import_json(&json), Err(Error::InvalidPublicKey(_))));
+
+    assert!(matches!(
+      MemStore::import_json(r#"{"version":255}"#),
+      Err(Error::UnsupportedVersion(255))
+    ));
+  }
+
+  /// Returns two stores which share the DID of `shared`, while each has a DID of its own.
+  async fn merge_stores(shared: &KeyPair) -> (MemStore, MemStore, CoreDID, CoreDID, KeyLocation) {
+    let (primary, offline): (MemStore, MemStore) = (MemStore::new(), MemStore::new());
+
+    let mut shared_did: Option<CoreDID> = None;
+    for (store, blob) in [(&primary, b"primary"), (&offline, b"offline")] {
+      let (did, _): (CoreDID, KeyLocation) = store
+        .did_create(
+          DIDType::IotaDID,
+          Network::Mainnet.name(),
+          "sign-0",
+          Some(shared.private().clone()),
+        )
+        .await
+        .unwrap();
+      store.blob_set(&did, blob.to_vec()).await.unwrap();
+      store
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
+        .await
+        .unwrap();
+      shared_did = Some(did);
+    }
+    let shared_did: CoreDID = shared_did.unwrap();
+
+    // A key which was only added to the shared DID on the offline device.
+    let offline_location: KeyLocation = offline
+      .key_generate(&shared_did, KeyType::X25519, "kex-0")
+      .await
+      .unwrap();
+    let (offline_did, _): (CoreDID, KeyLocation) = offline
+      .did_create(DIDType::IotaDID, Network::Devnet.name(), "sign-0", None)
+      .await
+      .unwrap();
+
+    (primary, offline, shared_did, offline_did, offline_location)
   }
 
   #[tokio::test]
-  async fn test_memstore_encryption() {
-    StorageTestSuite::encryption_test(test_memstore(), test_memstore())
+  async fn test_memstore_merge() {
+    let shared: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+
+    let (primary, offline, shared_did, offline_did, offline_location) = merge_stores(&shared).await;
+    let report: MergeReport = primary.merge(&offline, ConflictPolicy::KeepSelf).unwrap();
+    assert_eq!(
+      report,
+      MergeReport {
+        added: 2,
+        skipped: 1,
+        overwritten: 0
+      }
+    );
+    assert_eq!(primary.did_list().await.unwrap().len(), 4);
+    assert!(primary.did_exists(&offline_did).await.unwrap());
+    assert_eq!(primary.blob_get(&shared_did).await.unwrap().unwrap(), b"primary");
+    assert!(!primary.key_exists(&shared_did, &offline_location).await.unwrap());
+
+    let (primary, offline, shared_did, offline_did, offline_location) = merge_stores(&shared).await;
+    let report: MergeReport = primary.merge(&offline, ConflictPolicy::KeepOther).unwrap();
+    assert_eq!(
+      report,
+      MergeReport {
+        added: 2,
+        skipped: 0,
+        overwritten: 1
+      }
+    );
+    assert_eq!(primary.did_list().await.unwrap().len(), 4);
+    assert!(primary.did_exists(&offline_did).await.unwrap());
+    assert_eq!(primary.blob_get(&shared_did).await.unwrap().unwrap(), b"offline");
+    assert!(primary.key_exists(&shared_did, &offline_location).await.unwrap());
+
+    let (primary, offline, shared_did, offline_did, _) = merge_stores(&shared).await;
+    assert!(matches!(
+      primary.merge(&offline, ConflictPolicy::Error),
+      Err(Error::IdentityAlreadyExists)
+    ));
+    // A failed merge leaves the store untouched.
+    assert_eq!(primary.did_list().await.unwrap().len(), 2);
+    assert!(!primary.did_exists(&offline_did).await.unwrap());
+    assert_eq!(primary.blob_get(&shared_did).await.unwrap().unwrap(), b"primary");
+
+    // Without overlapping DIDs, every policy adds all DIDs.
+    let disjoint: MemStore = MemStore::new();
+    disjoint
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "sign-0", None)
       .await
-      .unwrap()
+      .unwrap();
+    let report: MergeReport = primary.merge(&disjoint, ConflictPolicy::Error).unwrap();
+    assert_eq!(
+      report,
+      MergeReport {
+        added: 1,
+        skipped: 0,
+        overwritten: 0
+      }
+    );
+    assert_eq!(primary.did_list().await.unwrap().len(), 3);
   }
 }
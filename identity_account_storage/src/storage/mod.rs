@@ -1,14 +1,34 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(feature = "fs-storage")]
+mod fs;
 mod memstore;
+#[cfg(feature = "postgres-storage")]
+mod pg;
+#[cfg(feature = "redis-storage")]
+mod redis;
+#[cfg(feature = "rocksdb-storage")]
+mod rocks;
+#[cfg(feature = "sled-storage")]
+pub mod sled;
 #[cfg(feature = "stronghold")]
 pub(crate) mod stronghold;
 #[cfg(feature = "storage-test-suite")]
 mod test_suite;
 mod traits;
 
+#[cfg(feature = "fs-storage")]
+pub use self::fs::FsStore;
 pub use self::memstore::*;
+#[cfg(feature = "postgres-storage")]
+pub use self::pg::PgStore;
+#[cfg(feature = "redis-storage")]
+pub use self::redis::RedisStore;
+#[cfg(feature = "rocksdb-storage")]
+pub use self::rocks::RocksStore;
+#[cfg(feature = "sled-storage")]
+pub use self::sled::SledStore;
 pub use self::traits::*;
 #[cfg(feature = "stronghold")]
 pub use crate::stronghold::Stronghold;
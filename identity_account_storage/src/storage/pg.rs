@@ -0,0 +1,940 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use core::fmt::Debug;
+use core::fmt::Formatter;
+
+use ::sqlx::postgres::PgConnection;
+use ::sqlx::postgres::PgPool;
+use ::sqlx::postgres::PgPoolOptions;
+use ::sqlx::Connection;
+use ::sqlx::Postgres;
+use ::sqlx::Transaction;
+use async_trait::async_trait;
+use crypto::ciphers::aes_gcm::Aes256Gcm;
+use identity_core::common::Timestamp;
+use identity_core::convert::FromJson;
+use identity_core::convert::ToJson;
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyPair;
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PrivateKey;
+use identity_core::crypto::PublicKey;
+use identity_core::crypto::Sign;
+use identity_core::crypto::X25519;
+use identity_did::did::CoreDID;
+use identity_did::did::DID;
+use identity_iota_core::tangle::NetworkName;
+use tokio::sync::MappedMutexGuard;
+use tokio::sync::Mutex;
+use tokio::sync::MutexGuard;
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::error::Result;
+#[cfg(feature = "encryption")]
+use crate::storage::memstore::memstore_encryption;
+use crate::storage::memstore::prepare_did;
+use crate::storage::Storage;
+use crate::storage::TransactionFuture;
+use crate::storage::DEFAULT_BLOB_NAME;
+#[cfg(feature = "encryption")]
+use crate::types::CekAlgorithm;
+use crate::types::DIDType;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptedData;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptionAlgorithm;
+use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
+use crate::types::Signature;
+use crate::types::StorageStats;
+use crate::utils::ct_eq_option;
+use crate::utils::open;
+use crate::utils::seal;
+
+// The statements creating the tables of the store, if they do not exist yet.
+static SCHEMA: [&str; 3] = [
+  "CREATE TABLE IF NOT EXISTS identity_dids (
+    did TEXT PRIMARY KEY
+  )",
+  "CREATE TABLE IF NOT EXISTS identity_keys (
+    did TEXT NOT NULL REFERENCES identity_dids (did) ON DELETE CASCADE,
+    location TEXT NOT NULL,
+    key_location TEXT NOT NULL,
+    private_key BYTEA NOT NULL,
+    created_at BIGINT,
+    PRIMARY KEY (did, location)
+  )",
+  "CREATE TABLE IF NOT EXISTS identity_blobs (
+    did TEXT NOT NULL,
+    name TEXT NOT NULL,
+    blob BYTEA NOT NULL,
+    PRIMARY KEY (did, name)
+  )",
+];
+
+type PgTransaction = Transaction<'static, Postgres>;
+
+/// A [`Storage`] implementation persisting data in a [PostgreSQL](https://www.postgresql.org) database.
+///
+/// DIDs, keys and named blobs are stored in the `identity_dids`, `identity_keys` and `identity_blobs`
+/// tables, which are created on construction if they do not exist. Keys are identified by the DID and
+/// the canonical representation of their location.
+///
+/// Private keys are encrypted with AES-256-GCM under the master key given on construction, so they never
+/// reach the database in plaintext. Blobs are stored unencrypted.
+///
+/// All operations are executed in a pending transaction, which is committed by [`Storage::flush_changes`].
+/// Changes that have not been flushed when the store is dropped are rolled back. After [`Storage::close`],
+/// all operations fail with [`Error::StorageClosed`].
+///
+/// The pending transaction holds a single connection, so all operations run on that one connection and
+/// concurrent operations are serialized, regardless of the size of the connection pool. The pool is only used
+/// to create the tables and to begin the next transaction on every flush, before the previous one is committed.
+/// It therefore needs to allow at least two connections. Use several stores for parallel access.
+pub struct PgStore {
+  pool: PgPool,
+  master_key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]>,
+  // `None` once the store has been closed.
+  transaction: Mutex<Option<PgTransaction>>,
+}
+
+impl PgStore {
+  /// Connects to the PostgreSQL database at `url` with a default connection pool.
+  ///
+  /// All private keys are encrypted with the given `master_key`, which must be the same every time
+  /// the database is accessed.
+  pub async fn connect(url: &str, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self> {
+    Self::connect_with(PgPoolOptions::new(), url, master_key).await
+  }
+
+  /// Connects to the PostgreSQL database at `url` with a connection pool configured by `options`.
+  ///
+  /// The `options` control how connections are established, e.g. their timeouts, lifetimes and how long to
+  /// wait for a free connection. `max_connections` must be at least two, but larger values do not make the store
+  /// process operations in parallel.
+  pub async fn connect_with(
+    options: PgPoolOptions,
+    url: &str,
+    master_key: [u8; Aes256Gcm::KEY_LENGTH],
+  ) -> Result<Self> {
+    let pool: PgPool = options.connect(url).await?;
+    Self::from_pool(pool, master_key).await
+  }
+
+  /// Creates a store using the given connection `pool` and creates the tables if they do not exist.
+  ///
+  /// The store keeps one connection of the `pool` checked out for its pending transaction.
+  pub async fn from_pool(pool: PgPool, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self> {
+    for statement in SCHEMA {
+      ::sqlx::query(statement).execute(&pool).await?;
+    }
+
+    let transaction: PgTransaction = pool.begin().await?;
+
+    Ok(Self {
+      pool,
+      master_key: Zeroizing::new(master_key),
+      transaction: Mutex::new(Some(transaction)),
+    })
+  }
+
+  /// Locks the pending transaction, failing if the store has been closed.
+  async fn pending(&self) -> Result<MappedMutexGuard<'_, PgTransaction>> {
+    MutexGuard::try_map(self.transaction.lock().await, Option::as_mut).map_err(|_| Error::StorageClosed)
+  }
+
+  /// Returns whether the vault of the given `did` exists.
+  async fn vault_exists(connection: &mut PgConnection, did: &CoreDID) -> Result<bool> {
+    let exists: Option<i32> = ::sqlx::query_scalar("SELECT 1 FROM identity_dids WHERE did = $1")
+      .bind(did.as_str())
+      .fetch_optional(connection)
+      .await?;
+
+    Ok(exists.is_some())
+  }
+
+  /// Reads and decrypts the key at `location` of the given `did`, together with its creation time.
+  async fn read_key(
+    &self,
+    connection: &mut PgConnection,
+    did: &CoreDID,
+    location: &KeyLocation,
+  ) -> Result<(KeyPair, Option<Timestamp>)> {
+    let row: Option<(String, Vec<u8>, Option<i64>)> = ::sqlx::query_as(
+      "SELECT key_location, private_key, created_at FROM identity_keys WHERE did = $1 AND location = $2",
+    )
+    .bind(did.as_str())
+    .bind(location.canonical())
+    .fetch_optional(&mut *connection)
+    .await?;
+
+    let (key_location, private_key, created_at): (String, Vec<u8>, Option<i64>) = match row {
+      Some(row) => row,
+      None if Self::vault_exists(connection, did).await? => return Err(Error::KeyNotFound),
+      None => return Err(Error::KeyVaultNotFound),
+    };
+
+    // The stored location is used, since locations are compared without their key type.
+    let location: KeyLocation = KeyLocation::from_json(&key_location)?;
+    let private_key: Zeroizing<Vec<u8>> = open(&self.master_key, &associated_data(did, &location), &private_key)?;
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(location.key_type, &private_key)?;
+
+    Ok((keypair, created_at.map(Timestamp::from_unix).transpose()?))
+  }
+
+  /// Encrypts and writes the `private_key` to `location`, overwriting any key at the same location.
+  ///
+  /// The vault of the given `did` is created if it does not exist.
+  async fn write_key(
+    &self,
+    connection: &mut PgConnection,
+    did: &CoreDID,
+    location: &KeyLocation,
+    private_key: &[u8],
+    created_at: Option<Timestamp>,
+  ) -> Result<()> {
+    ::sqlx::query("INSERT INTO identity_dids (did) VALUES ($1) ON CONFLICT DO NOTHING")
+      .bind(did.as_str())
+      .execute(&mut *connection)
+      .await?;
+
+    ::sqlx::query(
+      "INSERT INTO identity_keys (did, location, key_location, private_key, created_at) VALUES ($1, $2, $3, $4, $5)
+      ON CONFLICT (did, location) DO UPDATE
+      SET key_location = EXCLUDED.key_location, private_key = EXCLUDED.private_key, created_at = EXCLUDED.created_at",
+    )
+    .bind(did.as_str())
+    .bind(location.canonical())
+    .bind(location.to_json()?)
+    .bind(seal(&self.master_key, &associated_data(did, location), private_key)?)
+    .bind(created_at.map(|timestamp| timestamp.to_unix()))
+    .execute(connection)
+    .await?;
+
+    Ok(())
+  }
+
+  /// Reconstructs the key pair at `location` of the given `did`.
+  async fn keypair(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyPair> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let (keypair, _): (KeyPair, _) = self.read_key(&mut transaction, did, location).await?;
+    Ok(keypair)
+  }
+}
+
+// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
+//
+// Every modifying operation runs in a savepoint of the pending transaction, so a failing operation
+// is rolled back on its own instead of aborting the entire transaction.
+#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
+#[cfg_attr(feature = "send-sync-storage", async_trait)]
+impl Storage for PgStore {
+  async fn did_create(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    private_key: Option<PrivateKey>,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did(did_type, network, fragment, private_key)?;
+
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    // We don't want to overwrite an existing DID.
+    if Self::vault_exists(&mut savepoint, &did).await? {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    self
+      .write_key(
+        &mut savepoint,
+        &did,
+        &location,
+        keypair.private().as_ref(),
+        Some(Timestamp::now_utc()),
+      )
+      .await?;
+    savepoint.commit().await?;
+
+    Ok((did, location))
+  }
+
+  async fn did_copy(&self, from: &CoreDID, to: CoreDID) -> Result<()> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    if !Self::vault_exists(&mut savepoint, from).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+    if Self::vault_exists(&mut savepoint, &to).await? {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    ::sqlx::query("INSERT INTO identity_dids (did) VALUES ($1)")
+      .bind(to.as_str())
+      .execute(&mut savepoint)
+      .await?;
+
+    // The keys are re-encrypted, since the DID is bound to them as associated data.
+    let keys: Vec<(String, Vec<u8>, Option<i64>)> =
+      ::sqlx::query_as("SELECT key_location, private_key, created_at FROM identity_keys WHERE did = $1")
+        .bind(from.as_str())
+        .fetch_all(&mut savepoint)
+        .await?;
+
+    for (key_location, private_key, created_at) in keys {
+      let location: KeyLocation = KeyLocation::from_json(&key_location)?;
+      let private_key: Zeroizing<Vec<u8>> = open(&self.master_key, &associated_data(from, &location), &private_key)?;
+      let created_at: Option<Timestamp> = created_at.map(Timestamp::from_unix).transpose()?;
+
+      self
+        .write_key(&mut savepoint, &to, &location, &private_key, created_at)
+        .await?;
+    }
+
+    // The blobs of the new identity are replaced entirely, so no stale blobs remain.
+    ::sqlx::query("DELETE FROM identity_blobs WHERE did = $1")
+      .bind(to.as_str())
+      .execute(&mut savepoint)
+      .await?;
+    ::sqlx::query(
+      "INSERT INTO identity_blobs (did, name, blob) SELECT $2, name, blob FROM identity_blobs WHERE did = $1",
+    )
+    .bind(from.as_str())
+    .bind(to.as_str())
+    .execute(&mut savepoint)
+    .await?;
+
+    savepoint.commit().await?;
+
+    Ok(())
+  }
+
+  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    // The keys are deleted along with the DID.
+    let purged: u64 = ::sqlx::query("DELETE FROM identity_dids WHERE did = $1")
+      .bind(did.as_str())
+      .execute(&mut savepoint)
+      .await?
+      .rows_affected();
+
+    // This method is supposed to be idempotent, so we only report removals of existing DIDs.
+    if purged == 0 {
+      return Ok(false);
+    }
+
+    ::sqlx::query("DELETE FROM identity_blobs WHERE did = $1")
+      .bind(did.as_str())
+      .execute(&mut savepoint)
+      .await?;
+    savepoint.commit().await?;
+
+    Ok(true)
+  }
+
+  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    Self::vault_exists(&mut transaction, did).await
+  }
+
+  async fn did_list(&self) -> Result<Vec<CoreDID>> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    let dids: Vec<String> = ::sqlx::query_scalar("SELECT did FROM identity_dids ORDER BY did")
+      .fetch_all(&mut *transaction)
+      .await?;
+
+    dids
+      .into_iter()
+      .map(|did| CoreDID::parse(did).map_err(Into::into))
+      .collect()
+  }
+
+  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
+    let keypair: KeyPair = KeyPair::new(key_type)?;
+    let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    self
+      .write_key(
+        &mut savepoint,
+        did,
+        &location,
+        keypair.private().as_ref(),
+        Some(Timestamp::now_utc()),
+      )
+      .await?;
+    savepoint.commit().await?;
+
+    Ok(location)
+  }
+
+  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
+    // Validate the private key by reconstructing the key pair for the location's key type.
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(location.key_type, private_key.as_ref())
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+    private_key.zeroize();
+
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    self
+      .write_key(
+        &mut savepoint,
+        did,
+        location,
+        keypair.private().as_ref(),
+        Some(Timestamp::now_utc()),
+      )
+      .await?;
+    savepoint.commit().await?;
+
+    Ok(())
+  }
+
+  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    let exists: Option<i32> = ::sqlx::query_scalar("SELECT 1 FROM identity_keys WHERE did = $1 AND location = $2")
+      .bind(did.as_str())
+      .bind(location.canonical())
+      .fetch_optional(&mut *transaction)
+      .await?;
+
+    Ok(exists.is_some())
+  }
+
+  async fn key_list(&self, did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    if !Self::vault_exists(&mut transaction, did).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+
+    let locations: Vec<String> =
+      ::sqlx::query_scalar("SELECT key_location FROM identity_keys WHERE did = $1 ORDER BY location")
+        .bind(did.as_str())
+        .fetch_all(&mut *transaction)
+        .await?;
+
+    locations
+      .iter()
+      .map(|location| KeyLocation::from_json(location).map_err(Into::into))
+      .collect()
+  }
+
+  async fn key_delete_all(&self, did: &CoreDID) -> Result<usize> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    if !Self::vault_exists(&mut savepoint, did).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+
+    let removed: u64 = ::sqlx::query("DELETE FROM identity_keys WHERE did = $1")
+      .bind(did.as_str())
+      .execute(&mut savepoint)
+      .await?
+      .rows_affected();
+    savepoint.commit().await?;
+
+    Ok(removed as usize)
+  }
+
+  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
+    self
+      .keypair(did, location)
+      .await
+      .map(|keypair| keypair.public().clone())
+  }
+
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let (keypair, created_at): (KeyPair, Option<Timestamp>) = self.read_key(&mut transaction, did, location).await?;
+
+    Ok(KeyMetadata::new(keypair.public().clone(), keypair.type_(), created_at))
+  }
+
+  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    if !Self::vault_exists(&mut savepoint, did).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+
+    let removed: u64 = ::sqlx::query("DELETE FROM identity_keys WHERE did = $1 AND location = $2")
+      .bind(did.as_str())
+      .bind(location.canonical())
+      .execute(&mut savepoint)
+      .await?
+      .rows_affected();
+    savepoint.commit().await?;
+
+    Ok(removed > 0)
+  }
+
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    // Fails if either the vault or the old key does not exist.
+    self.read_key(&mut savepoint, did, old).await?;
+
+    let keypair: KeyPair = KeyPair::new(old.key_type)?;
+    let location: KeyLocation = KeyLocation::new(old.key_type, fragment.to_owned(), keypair.public().as_ref());
+
+    ::sqlx::query("DELETE FROM identity_keys WHERE did = $1 AND location = $2")
+      .bind(did.as_str())
+      .bind(old.canonical())
+      .execute(&mut savepoint)
+      .await?;
+    self
+      .write_key(
+        &mut savepoint,
+        did,
+        &location,
+        keypair.private().as_ref(),
+        Some(Timestamp::now_utc()),
+      )
+      .await?;
+    savepoint.commit().await?;
+
+    Ok(location)
+  }
+
+  async fn key_move(&self, did: &CoreDID, from: &KeyLocation, new_fragment: &str) -> Result<KeyLocation> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    // Fails if either the vault or the key does not exist.
+    let (keypair, created_at): (KeyPair, Option<Timestamp>) = self.read_key(&mut savepoint, did, from).await?;
+    let location: KeyLocation = from.with_fragment(new_fragment.to_owned());
+
+    ::sqlx::query("DELETE FROM identity_keys WHERE did = $1 AND location = $2")
+      .bind(did.as_str())
+      .bind(from.canonical())
+      .execute(&mut savepoint)
+      .await?;
+    self
+      .write_key(&mut savepoint, did, &location, keypair.private().as_ref(), created_at)
+      .await?;
+    savepoint.commit().await?;
+
+    Ok(location)
+  }
+
+  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
+    let keypair: KeyPair = self.keypair(did, location).await?;
+
+    match location.key_type {
+      KeyType::Ed25519 => {
+        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
+        Signature::new_checked(KeyType::Ed25519, signature.to_vec())
+      }
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
+  async fn key_exchange(&self, did: &CoreDID, location: &KeyLocation, public_key: &PublicKey) -> Result<[u8; 32]> {
+    let keypair: KeyPair = self.keypair(did, location).await?;
+
+    match keypair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key exchange".to_owned(),
+      )),
+      KeyType::X25519 => {
+        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key.as_ref().try_into().map_err(|_| {
+          Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
+        })?;
+        X25519::key_exchange(keypair.private(), &public_key).map_err(Into::into)
+      }
+    }
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt(
+    &self,
+    did: &CoreDID,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<EncryptedData> {
+    let sender: KeyPair = match sender_key {
+      Some(location) => self.keypair(did, location).await?,
+      None => {
+        return self
+          .data_encrypt_anonymous(
+            plaintext,
+            associated_data,
+            encryption_algorithm,
+            cek_algorithm,
+            public_key,
+          )
+          .await
+      }
+    };
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      Some(&sender),
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt_anonymous(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      None,
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let keypair: KeyPair = self.keypair(did, private_key).await?;
+    memstore_encryption::decrypt(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    let keypair: KeyPair = self.keypair(did, private_key).await?;
+    memstore_encryption::decrypt_into(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+      out,
+    )
+  }
+
+  async fn blob_set(&self, did: &CoreDID, blob: Vec<u8>) -> Result<()> {
+    self.blob_set_named(did, DEFAULT_BLOB_NAME, blob).await
+  }
+
+  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
+    self.blob_get_named(did, DEFAULT_BLOB_NAME).await
+  }
+
+  async fn blob_compare_and_swap(&self, did: &CoreDID, expected: Option<&[u8]>, new: Vec<u8>) -> Result<bool> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    // Lock the row, so the swap is also atomic with respect to other processes using the database.
+    let current: Option<Vec<u8>> =
+      ::sqlx::query_scalar("SELECT blob FROM identity_blobs WHERE did = $1 AND name = $2 FOR UPDATE")
+        .bind(did.as_str())
+        .bind(DEFAULT_BLOB_NAME)
+        .fetch_optional(&mut savepoint)
+        .await?;
+
+    if !ct_eq_option(current.as_deref(), expected) {
+      return Ok(false);
+    }
+
+    ::sqlx::query(
+      "INSERT INTO identity_blobs (did, name, blob) VALUES ($1, $2, $3)
+      ON CONFLICT (did, name) DO UPDATE SET blob = EXCLUDED.blob",
+    )
+    .bind(did.as_str())
+    .bind(DEFAULT_BLOB_NAME)
+    .bind(new)
+    .execute(&mut savepoint)
+    .await?;
+    savepoint.commit().await?;
+
+    Ok(true)
+  }
+
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, blob: Vec<u8>) -> Result<()> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+    let mut savepoint: Transaction<'_, Postgres> = transaction.begin().await?;
+
+    ::sqlx::query(
+      "INSERT INTO identity_blobs (did, name, blob) VALUES ($1, $2, $3)
+      ON CONFLICT (did, name) DO UPDATE SET blob = EXCLUDED.blob",
+    )
+    .bind(did.as_str())
+    .bind(name)
+    .bind(blob)
+    .execute(&mut savepoint)
+    .await?;
+    savepoint.commit().await?;
+
+    Ok(())
+  }
+
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    ::sqlx::query_scalar("SELECT blob FROM identity_blobs WHERE did = $1 AND name = $2")
+      .bind(did.as_str())
+      .bind(name)
+      .fetch_optional(&mut *transaction)
+      .await
+      .map_err(Into::into)
+  }
+
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    ::sqlx::query_scalar("SELECT name FROM identity_blobs WHERE did = $1 ORDER BY name")
+      .bind(did.as_str())
+      .fetch_all(&mut *transaction)
+      .await
+      .map_err(Into::into)
+  }
+
+  async fn stats(&self) -> Result<StorageStats> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    let (did_count, key_count, blob_bytes): (i64, i64, i64) = ::sqlx::query_as(
+      "SELECT
+        (SELECT COUNT(*) FROM identity_dids),
+        (SELECT COUNT(*) FROM identity_keys),
+        (SELECT COALESCE(SUM(OCTET_LENGTH(blob)), 0) FROM identity_blobs)",
+    )
+    .fetch_one(&mut *transaction)
+    .await?;
+
+    Ok(StorageStats {
+      did_count: did_count as usize,
+      key_count: key_count as usize,
+      blob_bytes: blob_bytes as usize,
+    })
+  }
+
+  async fn flush_changes(&self) -> Result<()> {
+    let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+
+    // Subsequent operations run in a new transaction. If the commit fails, the changes of the
+    // committed transaction are lost, since PostgreSQL rolls it back.
+    let next: PgTransaction = self.pool.begin().await?;
+    let pending: PgTransaction = std::mem::replace(&mut *transaction, next);
+    pending.commit().await?;
+
+    Ok(())
+  }
+
+  async fn transaction<'s>(&'s self, transaction: TransactionFuture<'s>) -> Result<()> {
+    // Commit the changes made so far, so that a rollback only discards the changes made by the `transaction`.
+    self.flush_changes().await?;
+
+    match transaction.await {
+      Ok(()) => self.flush_changes().await,
+      Err(error) => {
+        let mut transaction: MappedMutexGuard<'_, PgTransaction> = self.pending().await?;
+        let next: PgTransaction = self.pool.begin().await?;
+        let pending: PgTransaction = std::mem::replace(&mut *transaction, next);
+        pending.rollback().await?;
+        Err(error)
+      }
+    }
+  }
+
+  async fn close(&self) -> Result<()> {
+    // The pending transaction holds a connection, which must be returned before the pool can close.
+    if let Some(pending) = self.transaction.lock().await.take() {
+      pending.commit().await?;
+    }
+    self.pool.close().await;
+    Ok(())
+  }
+}
+
+impl Debug for PgStore {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("PgStore").field("pool", &self.pool).finish()
+  }
+}
+
+/// The associated data binding an encrypted private key to its DID and location.
+///
+/// Keys can therefore not be swapped between DIDs or locations without being noticed.
+fn associated_data(did: &CoreDID, location: &KeyLocation) -> Vec<u8> {
+  format!("{did}/{location}").into_bytes()
+}
+
+#[cfg(test)]
+#[cfg(feature = "storage-test-suite")]
+mod tests {
+  use ::sqlx::postgres::PgPoolOptions;
+  use ::sqlx::Executor;
+  use identity_did::did::CoreDID;
+  use identity_iota_core::tangle::Network;
+  use rand::distributions::DistString;
+  use rand::rngs::OsRng;
+
+  use crate::error::Error;
+  use crate::storage::Storage;
+  use crate::storage::StorageTestSuite;
+  use crate::types::DIDType;
+  use crate::types::KeyLocation;
+
+  use super::PgStore;
+
+  // The tests only run if this variable holds the URL of a database they can create schemas in.
+  const DATABASE_URL_VARIABLE: &str = "IDENTITY_TEST_POSTGRES_URL";
+
+  fn random_schema() -> String {
+    format!(
+      "test_{}",
+      rand::distributions::Alphanumeric
+        .sample_string(&mut OsRng, 16)
+        .to_lowercase()
+    )
+  }
+
+  /// Returns pool options that create and use the given `schema` on every connection.
+  fn test_pool_options(schema: String) -> PgPoolOptions {
+    PgPoolOptions::new()
+      .max_connections(2)
+      .after_connect(move |connection, _| {
+        let statement: String = format!("CREATE SCHEMA IF NOT EXISTS {schema}; SET search_path TO {schema}");
+        Box::pin(async move { connection.execute(statement.as_str()).await.map(|_| ()) })
+      })
+  }
+
+  /// Connects to the test database, if configured.
+  ///
+  /// Every store uses a new schema, since some tests expect an empty storage.
+  async fn test_pgstore() -> Option<PgStore> {
+    let url: String = std::env::var(DATABASE_URL_VARIABLE).ok()?;
+    Some(
+      PgStore::connect_with(test_pool_options(random_schema()), &url, [0x42; 32])
+        .await
+        .unwrap(),
+    )
+  }
+
+  #[tokio::test]
+  async fn test_pgstore_run_all() {
+    if std::env::var(DATABASE_URL_VARIABLE).is_err() {
+      return;
+    }
+
+    let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { test_pgstore().await.unwrap() })
+      .await
+      .unwrap();
+
+    let mut expected: Vec<&'static str> = vec![
+      "key_expiry_test",
+      "did_metadata_test",
+      "did_timestamps_test",
+      "blob_delete_test",
+      "did_export_import_test",
+      "data_decrypt_expired_key_test",
+      "data_decrypt_with_conversion_test",
+    ];
+    #[cfg(feature = "encryption")]
+    expected.push("data_encrypt_stream_test");
+    assert_eq!(skipped, expected);
+  }
+
+  #[tokio::test]
+  async fn test_pgstore_flush_changes() {
+    let url: String = match std::env::var(DATABASE_URL_VARIABLE) {
+      Ok(url) => url,
+      Err(_) => return,
+    };
+    let schema: String = random_schema();
+
+    let pgstore: PgStore = PgStore::connect_with(test_pool_options(schema.clone()), &url, [0x42; 32])
+      .await
+      .unwrap();
+    let (did, location): (CoreDID, KeyLocation) = pgstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+
+    // Changes are only visible to other stores once they are flushed.
+    let observer: PgStore = PgStore::connect_with(test_pool_options(schema), &url, [0; 32])
+      .await
+      .unwrap();
+    assert!(!observer.did_exists(&did).await.unwrap());
+
+    pgstore.flush_changes().await.unwrap();
+    assert!(observer.did_exists(&did).await.unwrap());
+
+    // The private keys cannot be decrypted with a different master key.
+    assert!(matches!(
+      observer.key_public(&did, &location).await.unwrap_err(),
+      Error::DecryptionFailure(_)
+    ));
+  }
+
+  #[tokio::test]
+  async fn test_pgstore_transaction_rollback() {
+    if let Some(storage) = test_pgstore().await {
+      StorageTestSuite::transaction_rollback_test(storage).await.unwrap()
+    }
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_pgstore_concurrency() {
+    if let Some(storage) = test_pgstore().await {
+      StorageTestSuite::concurrency_test(storage).await.unwrap()
+    }
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_pgstore_blob_append() {
+    if let Some(storage) = test_pgstore().await {
+      StorageTestSuite::blob_append_test(storage).await.unwrap()
+    }
+  }
+}
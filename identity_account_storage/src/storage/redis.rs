@@ -0,0 +1,812 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::time::Duration;
+
+use ::redis::aio::ConnectionManager;
+use ::redis::AsyncCommands;
+use ::redis::Client;
+use ::redis::Script;
+use async_trait::async_trait;
+use crypto::ciphers::aes_gcm::Aes256Gcm;
+use identity_core::common::Timestamp;
+use identity_core::convert::FromJson;
+use identity_core::convert::ToJson;
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyPair;
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PrivateKey;
+use identity_core::crypto::PublicKey;
+use identity_core::crypto::Sign;
+use identity_core::crypto::X25519;
+use identity_did::did::CoreDID;
+use identity_did::did::DID;
+use identity_iota_core::tangle::NetworkName;
+use serde::Deserialize;
+use serde::Serialize;
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::error::Result;
+#[cfg(feature = "encryption")]
+use crate::storage::memstore::memstore_encryption;
+use crate::storage::memstore::prepare_did;
+use crate::storage::Storage;
+use crate::storage::DEFAULT_BLOB_NAME;
+#[cfg(feature = "encryption")]
+use crate::types::CekAlgorithm;
+use crate::types::DIDType;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptedData;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptionAlgorithm;
+use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
+use crate::types::Signature;
+use crate::types::StorageStats;
+use crate::utils::open;
+use crate::utils::seal;
+
+// The prefix of all keys written by a store, unless another one is given.
+const DEFAULT_PREFIX: &str = "identity";
+
+// Sets the blob in the hash `KEYS[1]` under the name `ARGV[1]` to `ARGV[4]`, if its current value
+// is absent and `ARGV[2]` is "0", or equal to `ARGV[3]` and `ARGV[2]` is "1".
+const COMPARE_AND_SWAP: &str = r#"
+local current = redis.call("HGET", KEYS[1], ARGV[1])
+if (ARGV[2] == "0" and current == false) or (ARGV[2] == "1" and current == ARGV[3]) then
+  redis.call("HSET", KEYS[1], ARGV[1], ARGV[4])
+  return 1
+end
+return 0
+"#;
+
+/// A key as it is stored in the vault hash of a DID.
+#[derive(Deserialize, Serialize)]
+struct RedisKey {
+  location: KeyLocation,
+  // The private key encrypted with the master key, see `RedisStore::encrypt`.
+  private_key: Vec<u8>,
+  created_at: Option<Timestamp>,
+}
+
+/// A [`Storage`] implementation persisting data in a [Redis](https://redis.io) server, so that multiple
+/// processes can share the same vaults.
+///
+/// All keys written by the store start with a prefix, `identity` by default:
+/// - `{prefix}:dids` is a set of all DIDs with a vault.
+/// - `{prefix}:did:{did}` is a hash of the keys of a DID, indexed by the canonical representation of their location.
+/// - `{prefix}:blob:{did}` is a hash of the named blobs of a DID.
+///
+/// Private keys are encrypted with AES-256-GCM under the master key given on construction, so they never
+/// reach the server in plaintext. Blobs are stored unencrypted.
+///
+/// Every write is immediately visible to other clients. [`Storage::flush_changes`] optionally waits until
+/// the writes have been acknowledged by a number of replicas, see [`RedisStore::with_wait`].
+pub struct RedisStore {
+  connection: ConnectionManager,
+  prefix: String,
+  master_key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]>,
+  // The number of replicas and the timeout to `WAIT` for on flush, if any.
+  wait: Option<(usize, Duration)>,
+}
+
+impl RedisStore {
+  /// Connects to the Redis server at `url`.
+  ///
+  /// All private keys are encrypted with the given `master_key`, which must be the same every time
+  /// the server is accessed.
+  pub async fn connect(url: &str, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self> {
+    Self::connect_with_prefix(url, DEFAULT_PREFIX, master_key).await
+  }
+
+  /// Connects to the Redis server at `url`, prefixing all keys with `prefix`.
+  ///
+  /// Stores with different prefixes do not see each other's data, even if they share a server.
+  pub async fn connect_with_prefix(url: &str, prefix: &str, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self> {
+    let client: Client = Client::open(url)?;
+    let connection: ConnectionManager = ConnectionManager::new(client).await?;
+
+    Ok(Self {
+      connection,
+      prefix: prefix.to_owned(),
+      master_key: Zeroizing::new(master_key),
+      wait: None,
+    })
+  }
+
+  /// Makes [`Storage::flush_changes`] block until all previous writes have been acknowledged by at least
+  /// `replicas` replicas, failing with [`Error::IoError`] if that does not happen within `timeout`.
+  pub fn with_wait(mut self, replicas: usize, timeout: Duration) -> Self {
+    self.wait = Some((replicas, timeout));
+    self
+  }
+
+  /// Returns the key of the set of all DIDs.
+  fn dids_key(&self) -> String {
+    format!("{}:dids", self.prefix)
+  }
+
+  /// Returns the key of the vault hash of the given `did`.
+  fn vault_key(&self, did: &CoreDID) -> String {
+    format!("{}:did:{did}", self.prefix)
+  }
+
+  /// Returns the key of the blob hash of the given `did`.
+  fn blob_key(&self, did: &CoreDID) -> String {
+    format!("{}:blob:{did}", self.prefix)
+  }
+
+  /// Returns a handle to the multiplexed connection.
+  fn connection(&self) -> ConnectionManager {
+    self.connection.clone()
+  }
+
+  /// Returns whether the vault of the given `did` exists.
+  async fn vault_exists(&self, did: &CoreDID) -> Result<bool> {
+    self
+      .connection()
+      .sismember(self.dids_key(), did.as_str())
+      .await
+      .map_err(Into::into)
+  }
+
+  /// Reads and decrypts the key at `location` of the given `did`, together with its creation time.
+  async fn read_key(&self, did: &CoreDID, location: &KeyLocation) -> Result<(KeyPair, Option<Timestamp>)> {
+    let value: Option<Vec<u8>> = self
+      .connection()
+      .hget(self.vault_key(did), location.canonical())
+      .await?;
+
+    let key: RedisKey = match value {
+      Some(value) => RedisKey::from_json_slice(&value)?,
+      None if self.vault_exists(did).await? => return Err(Error::KeyNotFound),
+      None => return Err(Error::KeyVaultNotFound),
+    };
+
+    // The stored location is used, since locations are compared without their key type.
+    let private_key: Zeroizing<Vec<u8>> =
+      open(&self.master_key, &associated_data(did, &key.location), &key.private_key)?;
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(key.location.key_type, &private_key)?;
+
+    Ok((keypair, key.created_at))
+  }
+
+  /// Encrypts the `private_key` into the value stored at `location` in the vault of the given `did`.
+  fn encode_key(
+    &self,
+    did: &CoreDID,
+    location: &KeyLocation,
+    private_key: &[u8],
+    created_at: Option<Timestamp>,
+  ) -> Result<Vec<u8>> {
+    let key: RedisKey = RedisKey {
+      location: location.clone(),
+      private_key: seal(&self.master_key, &associated_data(did, location), private_key)?,
+      created_at,
+    };
+    key.to_json_vec().map_err(Into::into)
+  }
+
+  /// Encrypts and writes the `private_key` to `location`, overwriting any key at the same location.
+  ///
+  /// The vault of the given `did` is created if it does not exist.
+  async fn write_key(
+    &self,
+    did: &CoreDID,
+    location: &KeyLocation,
+    private_key: &[u8],
+    created_at: Option<Timestamp>,
+  ) -> Result<()> {
+    let value: Vec<u8> = self.encode_key(did, location, private_key, created_at)?;
+
+    ::redis::pipe()
+      .atomic()
+      .sadd(self.dids_key(), did.as_str())
+      .ignore()
+      .hset(self.vault_key(did), location.canonical(), value)
+      .ignore()
+      .query_async(&mut self.connection())
+      .await
+      .map_err(Into::into)
+  }
+
+  /// Reconstructs the key pair at `location` of the given `did`.
+  async fn keypair(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyPair> {
+    let (keypair, _): (KeyPair, _) = self.read_key(did, location).await?;
+    Ok(keypair)
+  }
+}
+
+// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
+//
+// Operations writing more than one key run in a `MULTI` transaction, so other clients never
+// observe them partially applied.
+#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
+#[cfg_attr(feature = "send-sync-storage", async_trait)]
+impl Storage for RedisStore {
+  async fn did_create(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    private_key: Option<PrivateKey>,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did(did_type, network, fragment, private_key)?;
+    let value: Vec<u8> = self.encode_key(&did, &location, keypair.private().as_ref(), Some(Timestamp::now_utc()))?;
+
+    // Adding the DID to the set fails if it already exists, so concurrent creations cannot both succeed.
+    let added: usize = self.connection().sadd(self.dids_key(), did.as_str()).await?;
+    if added == 0 {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    self
+      .connection()
+      .hset::<_, _, _, ()>(self.vault_key(&did), location.canonical(), value)
+      .await?;
+
+    Ok((did, location))
+  }
+
+  async fn did_copy(&self, from: &CoreDID, to: CoreDID) -> Result<()> {
+    if !self.vault_exists(from).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+    if self.vault_exists(&to).await? {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    let keys: Vec<Vec<u8>> = self.connection().hvals(self.vault_key(from)).await?;
+    let blobs: Vec<(String, Vec<u8>)> = self.connection().hgetall(self.blob_key(from)).await?;
+
+    // The keys are re-encrypted, since the DID is bound to them as associated data.
+    let mut values: Vec<(String, Vec<u8>)> = Vec::with_capacity(keys.len());
+    for value in keys {
+      let key: RedisKey = RedisKey::from_json_slice(&value)?;
+      let private_key: Zeroizing<Vec<u8>> = open(
+        &self.master_key,
+        &associated_data(from, &key.location),
+        &key.private_key,
+      )?;
+      values.push((
+        key.location.canonical(),
+        self.encode_key(&to, &key.location, &private_key, key.created_at)?,
+      ));
+    }
+
+    // The keys and blobs of the new identity are replaced entirely, so no stale entries remain.
+    let mut pipeline: ::redis::Pipeline = ::redis::pipe();
+    pipeline
+      .atomic()
+      .sadd(self.dids_key(), to.as_str())
+      .ignore()
+      .del(self.vault_key(&to))
+      .ignore()
+      .del(self.blob_key(&to))
+      .ignore();
+    if !values.is_empty() {
+      pipeline.hset_multiple(self.vault_key(&to), &values).ignore();
+    }
+    if !blobs.is_empty() {
+      pipeline.hset_multiple(self.blob_key(&to), &blobs).ignore();
+    }
+    pipeline.query_async::<_, ()>(&mut self.connection()).await?;
+
+    Ok(())
+  }
+
+  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
+    let (purged, _, _): (usize, usize, usize) = ::redis::pipe()
+      .atomic()
+      .srem(self.dids_key(), did.as_str())
+      .del(self.vault_key(did))
+      .del(self.blob_key(did))
+      .query_async(&mut self.connection())
+      .await?;
+
+    // This method is supposed to be idempotent, so we only report removals of existing DIDs.
+    Ok(purged > 0)
+  }
+
+  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
+    self.vault_exists(did).await
+  }
+
+  async fn did_list(&self) -> Result<Vec<CoreDID>> {
+    let mut dids: Vec<String> = self.connection().smembers(self.dids_key()).await?;
+    dids.sort();
+
+    dids
+      .into_iter()
+      .map(|did| CoreDID::parse(did).map_err(Into::into))
+      .collect()
+  }
+
+  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
+    let keypair: KeyPair = KeyPair::new(key_type)?;
+    let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+    self
+      .write_key(did, &location, keypair.private().as_ref(), Some(Timestamp::now_utc()))
+      .await?;
+
+    Ok(location)
+  }
+
+  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
+    // Validate the private key by reconstructing the key pair for the location's key type.
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(location.key_type, private_key.as_ref())
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+    private_key.zeroize();
+
+    self
+      .write_key(did, location, keypair.private().as_ref(), Some(Timestamp::now_utc()))
+      .await
+  }
+
+  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    self
+      .connection()
+      .hexists(self.vault_key(did), location.canonical())
+      .await
+      .map_err(Into::into)
+  }
+
+  async fn key_list(&self, did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    if !self.vault_exists(did).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+
+    let values: Vec<Vec<u8>> = self.connection().hvals(self.vault_key(did)).await?;
+
+    let mut locations: Vec<KeyLocation> = values
+      .iter()
+      .map(|value| RedisKey::from_json_slice(value).map(|key| key.location))
+      .collect::<Result<_, _>>()?;
+    locations.sort_by_key(KeyLocation::canonical);
+
+    Ok(locations)
+  }
+
+  async fn key_delete_all(&self, did: &CoreDID) -> Result<usize> {
+    if !self.vault_exists(did).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+
+    let (removed, _): (usize, usize) = ::redis::pipe()
+      .atomic()
+      .hlen(self.vault_key(did))
+      .del(self.vault_key(did))
+      .query_async(&mut self.connection())
+      .await?;
+
+    Ok(removed)
+  }
+
+  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
+    self
+      .keypair(did, location)
+      .await
+      .map(|keypair| keypair.public().clone())
+  }
+
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    let (keypair, created_at): (KeyPair, Option<Timestamp>) = self.read_key(did, location).await?;
+
+    Ok(KeyMetadata::new(keypair.public().clone(), keypair.type_(), created_at))
+  }
+
+  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    if !self.vault_exists(did).await? {
+      return Err(Error::KeyVaultNotFound);
+    }
+
+    let removed: usize = self
+      .connection()
+      .hdel(self.vault_key(did), location.canonical())
+      .await?;
+
+    Ok(removed > 0)
+  }
+
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    // Fails if either the vault or the old key does not exist.
+    self.read_key(did, old).await?;
+
+    let keypair: KeyPair = KeyPair::new(old.key_type)?;
+    let location: KeyLocation = KeyLocation::new(old.key_type, fragment.to_owned(), keypair.public().as_ref());
+    let value: Vec<u8> = self.encode_key(did, &location, keypair.private().as_ref(), Some(Timestamp::now_utc()))?;
+
+    ::redis::pipe()
+      .atomic()
+      .hdel(self.vault_key(did), old.canonical())
+      .ignore()
+      .hset(self.vault_key(did), location.canonical(), value)
+      .ignore()
+      .query_async::<_, ()>(&mut self.connection())
+      .await?;
+
+    Ok(location)
+  }
+
+  async fn key_move(&self, did: &CoreDID, from: &KeyLocation, new_fragment: &str) -> Result<KeyLocation> {
+    // Fails if either the vault or the key does not exist.
+    let (keypair, created_at): (KeyPair, Option<Timestamp>) = self.read_key(did, from).await?;
+
+    let location: KeyLocation = from.with_fragment(new_fragment.to_owned());
+    // The private key is encrypted anew, since the location is part of its associated data.
+    let value: Vec<u8> = self.encode_key(did, &location, keypair.private().as_ref(), created_at)?;
+
+    ::redis::pipe()
+      .atomic()
+      .hdel(self.vault_key(did), from.canonical())
+      .ignore()
+      .hset(self.vault_key(did), location.canonical(), value)
+      .ignore()
+      .query_async::<_, ()>(&mut self.connection())
+      .await?;
+
+    Ok(location)
+  }
+
+  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
+    let keypair: KeyPair = self.keypair(did, location).await?;
+
+    match location.key_type {
+      KeyType::Ed25519 => {
+        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
+        Signature::new_checked(KeyType::Ed25519, signature.to_vec())
+      }
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
+  async fn key_exchange(&self, did: &CoreDID, location: &KeyLocation, public_key: &PublicKey) -> Result<[u8; 32]> {
+    let keypair: KeyPair = self.keypair(did, location).await?;
+
+    match keypair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key exchange".to_owned(),
+      )),
+      KeyType::X25519 => {
+        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key.as_ref().try_into().map_err(|_| {
+          Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
+        })?;
+        X25519::key_exchange(keypair.private(), &public_key).map_err(Into::into)
+      }
+    }
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt(
+    &self,
+    did: &CoreDID,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<EncryptedData> {
+    let sender: KeyPair = match sender_key {
+      Some(location) => self.keypair(did, location).await?,
+      None => {
+        return self
+          .data_encrypt_anonymous(
+            plaintext,
+            associated_data,
+            encryption_algorithm,
+            cek_algorithm,
+            public_key,
+          )
+          .await
+      }
+    };
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      Some(&sender),
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt_anonymous(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      None,
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let keypair: KeyPair = self.keypair(did, private_key).await?;
+    memstore_encryption::decrypt(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    let keypair: KeyPair = self.keypair(did, private_key).await?;
+    memstore_encryption::decrypt_into(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+      out,
+    )
+  }
+
+  async fn blob_set(&self, did: &CoreDID, blob: Vec<u8>) -> Result<()> {
+    self.blob_set_named(did, DEFAULT_BLOB_NAME, blob).await
+  }
+
+  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
+    self.blob_get_named(did, DEFAULT_BLOB_NAME).await
+  }
+
+  async fn blob_compare_and_swap(&self, did: &CoreDID, expected: Option<&[u8]>, new: Vec<u8>) -> Result<bool> {
+    // The comparison runs in a script, so the swap is atomic with respect to other clients.
+    let swapped: usize = Script::new(COMPARE_AND_SWAP)
+      .key(self.blob_key(did))
+      .arg(DEFAULT_BLOB_NAME)
+      .arg(if expected.is_some() { "1" } else { "0" })
+      .arg(expected.unwrap_or_default())
+      .arg(new)
+      .invoke_async(&mut self.connection())
+      .await?;
+
+    Ok(swapped == 1)
+  }
+
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, blob: Vec<u8>) -> Result<()> {
+    self
+      .connection()
+      .hset(self.blob_key(did), name, blob)
+      .await
+      .map_err(Into::into)
+  }
+
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    self
+      .connection()
+      .hget(self.blob_key(did), name)
+      .await
+      .map_err(Into::into)
+  }
+
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    let mut names: Vec<String> = self.connection().hkeys(self.blob_key(did)).await?;
+    names.sort();
+    Ok(names)
+  }
+
+  async fn stats(&self) -> Result<StorageStats> {
+    let dids: Vec<String> = self.connection().smembers(self.dids_key()).await?;
+
+    let mut stats: StorageStats = StorageStats {
+      did_count: dids.len(),
+      ..StorageStats::default()
+    };
+
+    for did in dids {
+      let did: CoreDID = CoreDID::parse(did)?;
+      let (key_count, blobs): (usize, Vec<Vec<u8>>) = ::redis::pipe()
+        .hlen(self.vault_key(&did))
+        .hvals(self.blob_key(&did))
+        .query_async(&mut self.connection())
+        .await?;
+
+      stats.key_count += key_count;
+      stats.blob_bytes += blobs.iter().map(Vec::len).sum::<usize>();
+    }
+
+    Ok(stats)
+  }
+
+  async fn flush_changes(&self) -> Result<()> {
+    // Writes are applied immediately, so there is only something to do if replication should be awaited.
+    if let Some((replicas, timeout)) = self.wait {
+      let acknowledged: usize = ::redis::cmd("WAIT")
+        .arg(replicas)
+        .arg(timeout.as_millis() as u64)
+        .query_async(&mut self.connection())
+        .await?;
+
+      if acknowledged < replicas {
+        return Err(Error::IoError(std::io::Error::new(
+          std::io::ErrorKind::TimedOut,
+          format!("only {acknowledged} of {replicas} replicas acknowledged the writes"),
+        )));
+      }
+    }
+
+    Ok(())
+  }
+}
+
+impl Debug for RedisStore {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("RedisStore")
+      .field("prefix", &self.prefix)
+      .field("wait", &self.wait)
+      .finish()
+  }
+}
+
+/// The associated data binding an encrypted private key to its DID and location.
+///
+/// Keys can therefore not be swapped between DIDs or locations without being noticed.
+fn associated_data(did: &CoreDID, location: &KeyLocation) -> Vec<u8> {
+  format!("{did}/{location}").into_bytes()
+}
+
+#[cfg(test)]
+#[cfg(feature = "storage-test-suite")]
+mod tests {
+  use std::time::Duration;
+
+  use identity_did::did::CoreDID;
+  use identity_iota_core::tangle::Network;
+  use rand::distributions::DistString;
+  use rand::rngs::OsRng;
+
+  use crate::error::Error;
+  use crate::storage::Storage;
+  use crate::storage::StorageTestSuite;
+  use crate::types::DIDType;
+  use crate::types::KeyLocation;
+
+  use super::RedisStore;
+
+  // The tests only run if this variable holds the URL of a Redis server they can write to.
+  const REDIS_URL_VARIABLE: &str = "IDENTITY_TEST_REDIS_URL";
+
+  fn random_prefix() -> String {
+    format!(
+      "test-{}",
+      rand::distributions::Alphanumeric.sample_string(&mut OsRng, 16)
+    )
+  }
+
+  /// Connects to the test server, if configured.
+  ///
+  /// Every store uses a new prefix, since some tests expect an empty storage.
+  async fn test_redisstore() -> Option<RedisStore> {
+    let url: String = std::env::var(REDIS_URL_VARIABLE).ok()?;
+    Some(
+      RedisStore::connect_with_prefix(&url, &random_prefix(), [0x42; 32])
+        .await
+        .unwrap(),
+    )
+  }
+
+  #[tokio::test]
+  async fn test_redisstore_run_all() {
+    if std::env::var(REDIS_URL_VARIABLE).is_err() {
+      return;
+    }
+
+    let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { test_redisstore().await.unwrap() })
+      .await
+      .unwrap();
+
+    let mut expected: Vec<&'static str> = vec![
+      "key_expiry_test",
+      "did_metadata_test",
+      "did_timestamps_test",
+      "blob_delete_test",
+      "did_export_import_test",
+      "data_decrypt_expired_key_test",
+      "data_decrypt_with_conversion_test",
+    ];
+    #[cfg(feature = "encryption")]
+    expected.push("data_encrypt_stream_test");
+    assert_eq!(skipped, expected);
+  }
+
+  #[tokio::test]
+  async fn test_redisstore_shared() {
+    let url: String = match std::env::var(REDIS_URL_VARIABLE) {
+      Ok(url) => url,
+      Err(_) => return,
+    };
+    let prefix: String = random_prefix();
+
+    let redisstore: RedisStore = RedisStore::connect_with_prefix(&url, &prefix, [0x42; 32])
+      .await
+      .unwrap();
+    let (did, location): (CoreDID, KeyLocation) = redisstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+
+    // Changes are visible to other stores with the same prefix without flushing.
+    let observer: RedisStore = RedisStore::connect_with_prefix(&url, &prefix, [0; 32]).await.unwrap();
+    assert!(observer.did_exists(&did).await.unwrap());
+
+    // The private keys cannot be decrypted with a different master key.
+    assert!(matches!(
+      observer.key_public(&did, &location).await.unwrap_err(),
+      Error::DecryptionFailure(_)
+    ));
+
+    // Stores with another prefix do not see the DID.
+    let other: RedisStore = RedisStore::connect_with_prefix(&url, &random_prefix(), [0x42; 32])
+      .await
+      .unwrap();
+    assert!(!other.did_exists(&did).await.unwrap());
+  }
+
+  #[tokio::test]
+  async fn test_redisstore_flush_changes_wait() {
+    if let Some(storage) = test_redisstore().await {
+      // Waiting for zero replicas succeeds immediately, even without replication.
+      let storage: RedisStore = storage.with_wait(0, Duration::from_millis(100));
+      storage
+        .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+        .await
+        .unwrap();
+      storage.flush_changes().await.unwrap();
+    }
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_redisstore_concurrency() {
+    if let Some(storage) = test_redisstore().await {
+      StorageTestSuite::concurrency_test(storage).await.unwrap()
+    }
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_redisstore_blob_append() {
+    if let Some(storage) = test_redisstore().await {
+      StorageTestSuite::blob_append_test(storage).await.unwrap()
+    }
+  }
+}
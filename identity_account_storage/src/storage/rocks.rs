@@ -0,0 +1,730 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::RwLockWriteGuard;
+
+use ::rocksdb::ColumnFamily;
+use ::rocksdb::IteratorMode;
+use ::rocksdb::Options;
+use ::rocksdb::WriteBatch;
+use ::rocksdb::DB;
+use async_trait::async_trait;
+use crypto::ciphers::aes_gcm::Aes256Gcm;
+use identity_core::common::Timestamp;
+use identity_core::convert::FromJson;
+use identity_core::convert::ToJson;
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyPair;
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PrivateKey;
+use identity_core::crypto::PublicKey;
+use identity_core::crypto::Sign;
+use identity_core::crypto::X25519;
+use identity_did::did::CoreDID;
+use identity_did::did::DID;
+use identity_iota_core::tangle::NetworkName;
+use serde::Deserialize;
+use serde::Serialize;
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::error::Result;
+#[cfg(feature = "encryption")]
+use crate::storage::memstore::memstore_encryption;
+use crate::storage::memstore::prepare_did;
+use crate::storage::Storage;
+use crate::storage::DEFAULT_BLOB_NAME;
+#[cfg(feature = "encryption")]
+use crate::types::CekAlgorithm;
+use crate::types::DIDType;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptedData;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptionAlgorithm;
+use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
+use crate::types::Signature;
+use crate::types::StorageStats;
+use crate::utils::ct_eq_option;
+use crate::utils::open;
+use crate::utils::seal;
+use crate::utils::Shared;
+
+// The name of the column family holding the encrypted vaults, keyed by DID.
+static VAULTS_CF: &str = "vaults";
+// The name of the column family holding the serialized named blobs, keyed by DID.
+static BLOBS_CF: &str = "blobs";
+
+/// A [`Storage`] implementation persisting data in a [RocksDB](https://rocksdb.org) database.
+///
+/// The keys of every DID are serialized into a single entry of a vault column family, while the named
+/// blobs of every DID are serialized into a single entry of a separate column family. Both are keyed by
+/// the string representation of the DID.
+///
+/// Vaults are encrypted with AES-256-GCM under the master key given on construction, so private keys
+/// never reach the disk in plaintext. Blobs are stored unencrypted.
+pub struct RocksStore {
+  db: DB,
+  master_key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]>,
+  // Serializes all read-modify-write cycles, which RocksDB does not make atomic by itself.
+  lock: Shared<()>,
+}
+
+impl RocksStore {
+  /// Opens the RocksDB database at `path`, creating it if it does not exist.
+  ///
+  /// All vaults are encrypted with the given `master_key`, which must be the same every time
+  /// the database is opened.
+  pub fn open<P>(path: &P, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self>
+  where
+    P: AsRef<Path> + ?Sized,
+  {
+    let mut options: Options = Options::default();
+    options.create_if_missing(true);
+    options.create_missing_column_families(true);
+
+    Self::open_with_options(path, options, master_key)
+  }
+
+  /// Opens the RocksDB database at `path` with the given [`Options`].
+  ///
+  /// The `options` must allow the creation of missing column families if the database is
+  /// opened for the first time.
+  pub fn open_with_options<P>(path: &P, options: Options, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self>
+  where
+    P: AsRef<Path> + ?Sized,
+  {
+    let db: DB = DB::open_cf(&options, path, [VAULTS_CF, BLOBS_CF])?;
+
+    Ok(Self {
+      db,
+      master_key: Zeroizing::new(master_key),
+      lock: Shared::new(()),
+    })
+  }
+
+  fn vaults(&self) -> &ColumnFamily {
+    // Both column families are created when the database is opened.
+    self.db.cf_handle(VAULTS_CF).expect("vaults column family exists")
+  }
+
+  fn blobs(&self) -> &ColumnFamily {
+    self.db.cf_handle(BLOBS_CF).expect("blobs column family exists")
+  }
+
+  /// Reads and decrypts the vault of the given `did`, if it exists.
+  fn read_vault(&self, did: &CoreDID) -> Result<Option<RocksVault>> {
+    self
+      .db
+      .get_cf(self.vaults(), did.as_str())?
+      .map(|bytes| self.decrypt_vault(did, &bytes))
+      .transpose()
+  }
+
+  /// Reads and deserializes the named blobs of the given `did`, if any exist.
+  fn read_blobs(&self, did: &CoreDID) -> Result<Option<RocksBlobs>> {
+    self
+      .db
+      .get_cf(self.blobs(), did.as_str())?
+      .map(|bytes| RocksBlobs::from_json_slice(&bytes).map_err(Into::into))
+      .transpose()
+  }
+
+  /// Runs `f` on the vault of the given `did` and writes the modified vault back if `f` succeeds.
+  ///
+  /// If `create` is `true`, a missing vault is created, otherwise [`Error::KeyVaultNotFound`] is returned.
+  fn mutate_vault<F, T>(&self, did: &CoreDID, create: bool, f: F) -> Result<T>
+  where
+    F: FnOnce(&mut RocksVault) -> Result<T>,
+  {
+    let _lock: RwLockWriteGuard<'_, ()> = self.lock.write()?;
+
+    let mut vault: RocksVault = match self.read_vault(did)? {
+      Some(vault) => vault,
+      None if create => RocksVault::default(),
+      None => return Err(Error::KeyVaultNotFound),
+    };
+
+    let output: T = f(&mut vault)?;
+
+    self
+      .db
+      .put_cf(self.vaults(), did.as_str(), self.encrypt_vault(did, &vault)?)?;
+
+    Ok(output)
+  }
+
+  /// Serializes and seals `vault` with the master key.
+  ///
+  /// The DID is used as associated data, so vaults cannot be swapped between DIDs without being noticed.
+  fn encrypt_vault(&self, did: &CoreDID, vault: &RocksVault) -> Result<Vec<u8>> {
+    let plaintext: Zeroizing<Vec<u8>> = Zeroizing::new(vault.to_json_vec()?);
+    seal(&self.master_key, did.as_str().as_bytes(), &plaintext)
+  }
+
+  /// Opens and deserializes the `bytes` created by [`RocksStore::encrypt_vault`].
+  fn decrypt_vault(&self, did: &CoreDID, bytes: &[u8]) -> Result<RocksVault> {
+    let plaintext: Zeroizing<Vec<u8>> = open(&self.master_key, did.as_str().as_bytes(), bytes)?;
+    RocksVault::from_json_slice(plaintext.as_slice()).map_err(Into::into)
+  }
+
+  /// Reconstructs the key pair at `location` of the given `did`.
+  fn keypair(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyPair> {
+    let vault: RocksVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    vault.get(location).ok_or(Error::KeyNotFound)?.keypair()
+  }
+}
+
+// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
+#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
+#[cfg_attr(feature = "send-sync-storage", async_trait)]
+impl Storage for RocksStore {
+  async fn did_create(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    private_key: Option<PrivateKey>,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did(did_type, network, fragment, private_key)?;
+
+    let mut vault: RocksVault = RocksVault::default();
+    vault.insert(RocksKey::new(location.clone(), &keypair));
+    let bytes: Vec<u8> = self.encrypt_vault(&did, &vault)?;
+
+    let _lock: RwLockWriteGuard<'_, ()> = self.lock.write()?;
+
+    // We don't want to overwrite an existing DID.
+    if self.db.get_pinned_cf(self.vaults(), did.as_str())?.is_some() {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    self.db.put_cf(self.vaults(), did.as_str(), bytes)?;
+
+    Ok((did, location))
+  }
+
+  async fn did_copy(&self, from: &CoreDID, to: CoreDID) -> Result<()> {
+    let _lock: RwLockWriteGuard<'_, ()> = self.lock.write()?;
+
+    let vault: RocksVault = self.read_vault(from)?.ok_or(Error::KeyVaultNotFound)?;
+    if self.db.get_pinned_cf(self.vaults(), to.as_str())?.is_some() {
+      return Err(Error::IdentityAlreadyExists);
+    }
+
+    // The vault is re-encrypted, since the DID is bound to it as associated data.
+    let mut batch: WriteBatch = WriteBatch::default();
+    batch.put_cf(self.vaults(), to.as_str(), self.encrypt_vault(&to, &vault)?);
+    // The blobs of the new identity are replaced entirely, so no stale blobs remain.
+    match self.db.get_cf(self.blobs(), from.as_str())? {
+      Some(named_blobs) => batch.put_cf(self.blobs(), to.as_str(), named_blobs),
+      None => batch.delete_cf(self.blobs(), to.as_str()),
+    }
+    self.db.write(batch)?;
+
+    Ok(())
+  }
+
+  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
+    let _lock: RwLockWriteGuard<'_, ()> = self.lock.write()?;
+
+    // This method is supposed to be idempotent, so we only report removals of existing DIDs.
+    if self.db.get_pinned_cf(self.vaults(), did.as_str())?.is_none() {
+      return Ok(false);
+    }
+
+    let mut batch: WriteBatch = WriteBatch::default();
+    batch.delete_cf(self.vaults(), did.as_str());
+    batch.delete_cf(self.blobs(), did.as_str());
+    self.db.write(batch)?;
+
+    Ok(true)
+  }
+
+  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
+    Ok(self.db.get_pinned_cf(self.vaults(), did.as_str())?.is_some())
+  }
+
+  async fn did_list(&self) -> Result<Vec<CoreDID>> {
+    self
+      .db
+      .iterator_cf(self.vaults(), IteratorMode::Start)
+      .map(|entry| {
+        let (key, _): (Box<[u8]>, Box<[u8]>) = entry?;
+        CoreDID::parse(String::from_utf8_lossy(&key)).map_err(Into::into)
+      })
+      .collect()
+  }
+
+  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, true, |vault| {
+      let keypair: KeyPair = KeyPair::new(key_type)?;
+      let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+      vault.insert(RocksKey::new(location.clone(), &keypair));
+
+      Ok(location)
+    })
+  }
+
+  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
+    // Validate the private key by reconstructing the key pair for the location's key type.
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(location.key_type, private_key.as_ref())
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+    private_key.zeroize();
+
+    self.mutate_vault(did, true, |vault| {
+      vault.insert(RocksKey::new(location.clone(), &keypair));
+      Ok(())
+    })
+  }
+
+  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    Ok(
+      self
+        .read_vault(did)?
+        .map(|vault| vault.get(location).is_some())
+        .unwrap_or(false),
+    )
+  }
+
+  async fn key_list(&self, did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    let vault: RocksVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    Ok(vault.0.iter().map(|key| key.location.clone()).collect())
+  }
+
+  async fn key_delete_all(&self, did: &CoreDID) -> Result<usize> {
+    self.mutate_vault(did, false, |vault| {
+      let removed: usize = vault.0.len();
+      vault.0.clear();
+      Ok(removed)
+    })
+  }
+
+  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
+    self.keypair(did, location).map(|keypair| keypair.public().clone())
+  }
+
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    let vault: RocksVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    let key: &RocksKey = vault.get(location).ok_or(Error::KeyNotFound)?;
+    let keypair: KeyPair = key.keypair()?;
+
+    Ok(KeyMetadata::new(
+      keypair.public().clone(),
+      keypair.type_(),
+      key.created_at,
+    ))
+  }
+
+  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    self.mutate_vault(did, false, |vault| Ok(vault.remove(location)))
+  }
+
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, false, |vault| {
+      if vault.get(old).is_none() {
+        return Err(Error::KeyNotFound);
+      }
+
+      let keypair: KeyPair = KeyPair::new(old.key_type)?;
+      let location: KeyLocation = KeyLocation::new(old.key_type, fragment.to_owned(), keypair.public().as_ref());
+
+      vault.remove(old);
+      vault.insert(RocksKey::new(location.clone(), &keypair));
+
+      Ok(location)
+    })
+  }
+
+  async fn key_move(&self, did: &CoreDID, from: &KeyLocation, new_fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, false, |vault| {
+      let key: &RocksKey = vault.get(from).ok_or(Error::KeyNotFound)?;
+      let location: KeyLocation = from.with_fragment(new_fragment.to_owned());
+      let moved: RocksKey = RocksKey {
+        location: location.clone(),
+        private_key: key.private_key.clone(),
+        created_at: key.created_at,
+      };
+
+      vault.remove(from);
+      vault.insert(moved);
+
+      Ok(location)
+    })
+  }
+
+  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
+    let keypair: KeyPair = self.keypair(did, location)?;
+
+    match location.key_type {
+      KeyType::Ed25519 => {
+        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
+        Signature::new_checked(KeyType::Ed25519, signature.to_vec())
+      }
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
+  async fn key_exchange(&self, did: &CoreDID, location: &KeyLocation, public_key: &PublicKey) -> Result<[u8; 32]> {
+    let keypair: KeyPair = self.keypair(did, location)?;
+
+    match keypair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key exchange".to_owned(),
+      )),
+      KeyType::X25519 => {
+        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key.as_ref().try_into().map_err(|_| {
+          Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
+        })?;
+        X25519::key_exchange(keypair.private(), &public_key).map_err(Into::into)
+      }
+    }
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt(
+    &self,
+    did: &CoreDID,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<EncryptedData> {
+    let sender: KeyPair = match sender_key {
+      Some(location) => self.keypair(did, location)?,
+      None => {
+        return self
+          .data_encrypt_anonymous(
+            plaintext,
+            associated_data,
+            encryption_algorithm,
+            cek_algorithm,
+            public_key,
+          )
+          .await
+      }
+    };
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      Some(&sender),
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt_anonymous(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      None,
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let keypair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    let keypair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt_into(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+      out,
+    )
+  }
+
+  async fn blob_set(&self, did: &CoreDID, blob: Vec<u8>) -> Result<()> {
+    self.blob_set_named(did, DEFAULT_BLOB_NAME, blob).await
+  }
+
+  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
+    self.blob_get_named(did, DEFAULT_BLOB_NAME).await
+  }
+
+  async fn blob_compare_and_swap(&self, did: &CoreDID, expected: Option<&[u8]>, new: Vec<u8>) -> Result<bool> {
+    let _lock: RwLockWriteGuard<'_, ()> = self.lock.write()?;
+
+    let mut blobs: RocksBlobs = self.read_blobs(did)?.unwrap_or_default();
+    if !ct_eq_option(blobs.0.get(DEFAULT_BLOB_NAME).map(Vec::as_slice), expected) {
+      return Ok(false);
+    }
+
+    blobs.0.insert(DEFAULT_BLOB_NAME.to_owned(), new);
+    self.db.put_cf(self.blobs(), did.as_str(), blobs.to_json_vec()?)?;
+
+    Ok(true)
+  }
+
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, blob: Vec<u8>) -> Result<()> {
+    let _lock: RwLockWriteGuard<'_, ()> = self.lock.write()?;
+
+    let mut blobs: RocksBlobs = self.read_blobs(did)?.unwrap_or_default();
+    blobs.0.insert(name.to_owned(), blob);
+    self.db.put_cf(self.blobs(), did.as_str(), blobs.to_json_vec()?)?;
+
+    Ok(())
+  }
+
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    Ok(self.read_blobs(did)?.and_then(|mut blobs| blobs.0.remove(name)))
+  }
+
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    Ok(
+      self
+        .read_blobs(did)?
+        .map(|blobs| blobs.0.into_keys().collect())
+        .unwrap_or_default(),
+    )
+  }
+
+  async fn stats(&self) -> Result<StorageStats> {
+    // The column families are read one after another, so concurrent writes may be partially reflected.
+    let mut stats: StorageStats = StorageStats::default();
+
+    for entry in self.db.iterator_cf(self.vaults(), IteratorMode::Start) {
+      let (key, bytes): (Box<[u8]>, Box<[u8]>) = entry?;
+      let did: CoreDID = CoreDID::parse(String::from_utf8_lossy(&key))?;
+      stats.did_count += 1;
+      stats.key_count += self.decrypt_vault(&did, &bytes)?.0.len();
+    }
+
+    for entry in self.db.iterator_cf(self.blobs(), IteratorMode::Start) {
+      let (_, bytes): (Box<[u8]>, Box<[u8]>) = entry?;
+      stats.blob_bytes += RocksBlobs::from_json_slice(&bytes)?
+        .0
+        .values()
+        .map(|blob| blob.len())
+        .sum::<usize>();
+    }
+
+    Ok(stats)
+  }
+
+  async fn flush_changes(&self) -> Result<()> {
+    self.db.flush_cf(self.vaults())?;
+    self.db.flush_cf(self.blobs())?;
+    Ok(())
+  }
+}
+
+impl Debug for RocksStore {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("RocksStore").field("path", &self.db.path()).finish()
+  }
+}
+
+/// The serialized representation of the keys of a DID.
+#[derive(Default, Deserialize, Serialize)]
+struct RocksVault(Vec<RocksKey>);
+
+impl RocksVault {
+  fn get(&self, location: &KeyLocation) -> Option<&RocksKey> {
+    self.0.iter().find(|key| &key.location == location)
+  }
+
+  /// Inserts the `key`, overwriting any key at the same location.
+  fn insert(&mut self, key: RocksKey) {
+    self.remove(&key.location);
+    self.0.push(key);
+  }
+
+  /// Removes the key at `location` and returns whether it existed.
+  fn remove(&mut self, location: &KeyLocation) -> bool {
+    let len: usize = self.0.len();
+    self.0.retain(|key| &key.location != location);
+    self.0.len() != len
+  }
+}
+
+/// The serialized representation of the named blobs of a DID.
+#[derive(Default, Deserialize, Serialize)]
+struct RocksBlobs(BTreeMap<String, Vec<u8>>);
+
+/// A private key together with its location. The private key is zeroized on drop.
+#[derive(Deserialize, Serialize)]
+struct RocksKey {
+  location: KeyLocation,
+  private_key: Vec<u8>,
+  created_at: Option<Timestamp>,
+}
+
+impl RocksKey {
+  fn new(location: KeyLocation, keypair: &KeyPair) -> Self {
+    Self {
+      location,
+      private_key: keypair.private().as_ref().to_vec(),
+      created_at: Some(Timestamp::now_utc()),
+    }
+  }
+
+  fn keypair(&self) -> Result<KeyPair> {
+    KeyPair::try_from_private_key_bytes(self.location.key_type, &self.private_key).map_err(Into::into)
+  }
+}
+
+impl Drop for RocksKey {
+  fn drop(&mut self) {
+    self.private_key.zeroize();
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "storage-test-suite")]
+mod tests {
+  use std::path::PathBuf;
+
+  use identity_did::did::CoreDID;
+  use identity_iota_core::tangle::Network;
+  use rand::distributions::DistString;
+  use rand::rngs::OsRng;
+  use rand::Rng;
+
+  use crate::error::Error;
+  use crate::storage::Storage;
+  use crate::storage::StorageTestSuite;
+  use crate::types::DIDType;
+  use crate::types::KeyLocation;
+
+  use super::RocksStore;
+
+  fn random_temporary_directory() -> PathBuf {
+    let mut directory: PathBuf = std::env::temp_dir();
+    directory.push("test_rocksstores");
+    directory.push(rand::distributions::Alphanumeric.sample_string(&mut OsRng, 32));
+    directory
+  }
+
+  fn test_rocksstore() -> impl Storage {
+    RocksStore::open(&random_temporary_directory(), [0x42; 32]).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_rocksstore_run_all() {
+    let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { test_rocksstore() }).await.unwrap();
+
+    let mut expected: Vec<&'static str> = vec![
+      "key_expiry_test",
+      "did_metadata_test",
+      "did_timestamps_test",
+      "blob_delete_test",
+      "did_export_import_test",
+      "data_decrypt_expired_key_test",
+      "data_decrypt_with_conversion_test",
+    ];
+    #[cfg(feature = "encryption")]
+    expected.push("data_encrypt_stream_test");
+    assert_eq!(skipped, expected);
+  }
+
+  #[tokio::test]
+  async fn test_rocksstore_close() {
+    let directory: PathBuf = random_temporary_directory();
+    let storage: RocksStore = RocksStore::open(&directory, [0x42; 32]).unwrap();
+    StorageTestSuite::close_test(storage, move || RocksStore::open(&directory, [0x42; 32]).unwrap())
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_rocksstore_chain_state_persistence() {
+    let directory: PathBuf = random_temporary_directory();
+    let storage: RocksStore = RocksStore::open(&directory, [0x42; 32]).unwrap();
+    StorageTestSuite::chain_state_persistence_test(storage, move || RocksStore::open(&directory, [0x42; 32]).unwrap())
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_rocksstore_reopen() {
+    let directory: PathBuf = random_temporary_directory();
+    let master_key: [u8; 32] = OsRng.gen();
+
+    let rocksstore: RocksStore = RocksStore::open(&directory, master_key).unwrap();
+    let (did, location): (CoreDID, KeyLocation) = rocksstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+    rocksstore.blob_set(&did, b"blob".to_vec()).await.unwrap();
+    rocksstore.flush_changes().await.unwrap();
+    drop(rocksstore);
+
+    let rocksstore: RocksStore = RocksStore::open(&directory, master_key).unwrap();
+    assert_eq!(rocksstore.did_list().await.unwrap(), vec![did.clone()]);
+    assert!(rocksstore.key_exists(&did, &location).await.unwrap());
+    assert_eq!(rocksstore.blob_get(&did).await.unwrap(), Some(b"blob".to_vec()));
+    drop(rocksstore);
+
+    // The vaults cannot be decrypted with a different master key.
+    let rocksstore: RocksStore = RocksStore::open(&directory, [0; 32]).unwrap();
+    assert!(matches!(
+      rocksstore.key_exists(&did, &location).await.unwrap_err(),
+      Error::DecryptionFailure(_)
+    ));
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_rocksstore_concurrency() {
+    StorageTestSuite::concurrency_test(test_rocksstore()).await.unwrap()
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_rocksstore_blob_append() {
+    StorageTestSuite::blob_append_test(test_rocksstore()).await.unwrap()
+  }
+}
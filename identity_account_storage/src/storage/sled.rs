@@ -0,0 +1,736 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use ::sled::transaction::ConflictableTransactionError;
+use ::sled::transaction::TransactionError;
+use ::sled::transaction::TransactionalTree;
+use ::sled::Db;
+use ::sled::IVec;
+use ::sled::Tree;
+use async_trait::async_trait;
+use crypto::ciphers::aes_gcm::Aes256Gcm;
+use identity_core::common::Timestamp;
+use identity_core::convert::FromJson;
+use identity_core::convert::ToJson;
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyPair;
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PrivateKey;
+use identity_core::crypto::PublicKey;
+use identity_core::crypto::Sign;
+use identity_core::crypto::X25519;
+use identity_did::did::CoreDID;
+use identity_did::did::DID;
+use identity_iota_core::tangle::NetworkName;
+use serde::Deserialize;
+use serde::Serialize;
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::error::Result;
+#[cfg(feature = "encryption")]
+use crate::storage::memstore::memstore_encryption;
+use crate::storage::memstore::prepare_did;
+use crate::storage::Storage;
+use crate::storage::DEFAULT_BLOB_NAME;
+#[cfg(feature = "encryption")]
+use crate::types::CekAlgorithm;
+use crate::types::DIDType;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptedData;
+#[cfg(feature = "encryption")]
+use crate::types::EncryptionAlgorithm;
+use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
+use crate::types::Signature;
+use crate::types::StorageStats;
+use crate::utils::ct_eq_option;
+use crate::utils::open;
+use crate::utils::seal;
+
+// The name of the tree holding the serialized vaults, keyed by DID.
+static VAULTS_TREE: &str = "$vaults";
+// The name of the tree holding the serialized named blobs, keyed by DID.
+static BLOBS_TREE: &str = "$blobs";
+
+/// A [`Storage`] implementation persisting data in an embedded [sled](https://docs.rs/sled) database.
+///
+/// The keys of every DID are serialized into a single entry of a vault tree, while the named blobs
+/// of every DID are serialized into a single entry of a separate tree. Both are keyed by the string representation of
+/// the DID.
+///
+/// The vaults are encrypted with AES-256-GCM under the master key given on construction, so private keys
+/// never reach the disk in plaintext. Blobs are stored unencrypted.
+pub struct SledStore {
+  db: Db,
+  vaults: Tree,
+  blobs: Tree,
+  master_key: Zeroizing<[u8; Aes256Gcm::KEY_LENGTH]>,
+}
+
+impl SledStore {
+  /// Opens the sled database at `path`, creating it if it does not exist.
+  ///
+  /// All vaults are encrypted with the given `master_key`, which must be the same every time
+  /// the database is opened.
+  pub fn open<P>(path: &P, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self>
+  where
+    P: AsRef<Path> + ?Sized,
+  {
+    let db: Db = ::sled::open(path)?;
+    Self::from_db(db, master_key)
+  }
+
+  /// Creates a new `SledStore` from an already opened sled [`Db`].
+  pub fn from_db(db: Db, master_key: [u8; Aes256Gcm::KEY_LENGTH]) -> Result<Self> {
+    let vaults: Tree = db.open_tree(VAULTS_TREE)?;
+    let blobs: Tree = db.open_tree(BLOBS_TREE)?;
+
+    Ok(Self {
+      db,
+      vaults,
+      blobs,
+      master_key: Zeroizing::new(master_key),
+    })
+  }
+
+  /// Serializes and seals `vault` with the master key.
+  ///
+  /// The DID is used as associated data, so vaults cannot be swapped between DIDs without being noticed.
+  fn encrypt_vault(&self, did: &CoreDID, vault: &SledVault) -> Result<Vec<u8>> {
+    let plaintext: Zeroizing<Vec<u8>> = Zeroizing::new(vault.to_json_vec()?);
+    seal(&self.master_key, did.as_str().as_bytes(), &plaintext)
+  }
+
+  /// Opens and deserializes the `bytes` created by [`SledStore::encrypt_vault`].
+  fn decrypt_vault(&self, did: &CoreDID, bytes: &[u8]) -> Result<SledVault> {
+    let plaintext: Zeroizing<Vec<u8>> = open(&self.master_key, did.as_str().as_bytes(), bytes)?;
+    SledVault::from_json_slice(plaintext.as_slice()).map_err(Into::into)
+  }
+
+  /// Reads and decrypts the vault of the given `did`, if it exists.
+  fn read_vault(&self, did: &CoreDID) -> Result<Option<SledVault>> {
+    self
+      .vaults
+      .get(did.as_str())?
+      .map(|bytes| self.decrypt_vault(did, &bytes))
+      .transpose()
+  }
+
+  /// Reads and deserializes the named blobs of the given `did`, if any exist.
+  fn read_blobs(&self, did: &CoreDID) -> Result<Option<SledBlobs>> {
+    self
+      .blobs
+      .get(did.as_str())?
+      .map(|bytes| SledBlobs::from_json_slice(&bytes).map_err(Into::into))
+      .transpose()
+  }
+
+  /// Runs `f` on the vault of the given `did` within a transaction and writes the modified vault back.
+  ///
+  /// If `create` is `true`, a missing vault is created, otherwise [`Error::KeyVaultNotFound`] is returned.
+  fn mutate_vault<F, T>(&self, did: &CoreDID, create: bool, f: F) -> Result<T>
+  where
+    F: Fn(&mut SledVault) -> Result<T>,
+  {
+    self
+      .vaults
+      .transaction(|tree: &TransactionalTree| {
+        let mut vault: SledVault = match tree.get(did.as_str())? {
+          Some(bytes) => self
+            .decrypt_vault(did, &bytes)
+            .map_err(ConflictableTransactionError::Abort)?,
+          None if create => SledVault::default(),
+          None => return Err(ConflictableTransactionError::Abort(Error::KeyVaultNotFound)),
+        };
+
+        let output: T = f(&mut vault).map_err(ConflictableTransactionError::Abort)?;
+
+        let bytes: Vec<u8> = self
+          .encrypt_vault(did, &vault)
+          .map_err(ConflictableTransactionError::Abort)?;
+        tree.insert(did.as_str(), bytes)?;
+
+        Ok(output)
+      })
+      .map_err(transaction_error)
+  }
+
+  /// Reconstructs the key pair at `location` of the given `did`.
+  fn keypair(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyPair> {
+    let vault: SledVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    vault.get(location).ok_or(Error::KeyNotFound)?.keypair()
+  }
+}
+
+// Refer to the `Storage` interface docs for high-level documentation of the individual methods.
+#[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
+#[cfg_attr(feature = "send-sync-storage", async_trait)]
+impl Storage for SledStore {
+  async fn did_create(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    private_key: Option<PrivateKey>,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    let (did, location, keypair): (CoreDID, KeyLocation, KeyPair) =
+      prepare_did(did_type, network, fragment, private_key)?;
+
+    let mut vault: SledVault = SledVault::default();
+    vault.insert(SledKey::new(location.clone(), &keypair));
+
+    let bytes: Vec<u8> = self.encrypt_vault(&did, &vault)?;
+
+    // Only insert the vault if no vault exists for the DID yet, which makes the existence check
+    // and the insertion atomic.
+    self
+      .vaults
+      .compare_and_swap(did.as_str(), None as Option<&[u8]>, Some(bytes.as_slice()))?
+      .map_err(|_| Error::IdentityAlreadyExists)?;
+
+    Ok((did, location))
+  }
+
+  async fn did_copy(&self, from: &CoreDID, to: CoreDID) -> Result<()> {
+    (&self.vaults, &self.blobs)
+      .transaction(|(vaults, blobs)| {
+        let vault: IVec = vaults
+          .get(from.as_str())?
+          .ok_or(ConflictableTransactionError::Abort(Error::KeyVaultNotFound))?;
+        if vaults.get(to.as_str())?.is_some() {
+          return Err(ConflictableTransactionError::Abort(Error::IdentityAlreadyExists));
+        }
+
+        // The vault is re-encrypted, since the DID is bound to it as associated data.
+        let vault: SledVault = self
+          .decrypt_vault(from, &vault)
+          .map_err(ConflictableTransactionError::Abort)?;
+        let vault: Vec<u8> = self
+          .encrypt_vault(&to, &vault)
+          .map_err(ConflictableTransactionError::Abort)?;
+        vaults.insert(to.as_str(), vault)?;
+        // The blobs of the new identity are replaced entirely, so no stale blobs remain.
+        match blobs.get(from.as_str())? {
+          Some(named_blobs) => {
+            blobs.insert(to.as_str(), named_blobs)?;
+          }
+          None => {
+            blobs.remove(to.as_str())?;
+          }
+        }
+
+        Ok(())
+      })
+      .map_err(transaction_error)
+  }
+
+  async fn did_purge(&self, did: &CoreDID) -> Result<bool> {
+    (&self.vaults, &self.blobs)
+      .transaction(|(vaults, blobs)| {
+        if vaults.remove(did.as_str())?.is_some() {
+          let _ = blobs.remove(did.as_str())?;
+          Ok(true)
+        } else {
+          Ok(false)
+        }
+      })
+      .map_err(transaction_error)
+  }
+
+  async fn did_exists(&self, did: &CoreDID) -> Result<bool> {
+    self.vaults.contains_key(did.as_str()).map_err(Into::into)
+  }
+
+  async fn did_list(&self) -> Result<Vec<CoreDID>> {
+    self
+      .vaults
+      .iter()
+      .keys()
+      .map(|key| {
+        let key: IVec = key?;
+        CoreDID::parse(String::from_utf8_lossy(&key)).map_err(Into::into)
+      })
+      .collect()
+  }
+
+  async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, true, |vault| {
+      let keypair: KeyPair = KeyPair::new(key_type)?;
+      let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+
+      vault.insert(SledKey::new(location.clone(), &keypair));
+
+      Ok(location)
+    })
+  }
+
+  async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, mut private_key: PrivateKey) -> Result<()> {
+    // Validate the private key by reconstructing the key pair for the location's key type.
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(location.key_type, private_key.as_ref())
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+    private_key.zeroize();
+
+    self.mutate_vault(did, true, |vault| {
+      vault.insert(SledKey::new(location.clone(), &keypair));
+      Ok(())
+    })
+  }
+
+  async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    Ok(
+      self
+        .read_vault(did)?
+        .map(|vault| vault.get(location).is_some())
+        .unwrap_or(false),
+    )
+  }
+
+  async fn key_list(&self, did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    let vault: SledVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    Ok(vault.0.iter().map(|key| key.location.clone()).collect())
+  }
+
+  async fn key_delete_all(&self, did: &CoreDID) -> Result<usize> {
+    self.mutate_vault(did, false, |vault| {
+      let removed: usize = vault.0.len();
+      vault.0.clear();
+      Ok(removed)
+    })
+  }
+
+  async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey> {
+    self.keypair(did, location).map(|keypair| keypair.public().clone())
+  }
+
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    let vault: SledVault = self.read_vault(did)?.ok_or(Error::KeyVaultNotFound)?;
+    let key: &SledKey = vault.get(location).ok_or(Error::KeyNotFound)?;
+    let keypair: KeyPair = key.keypair()?;
+
+    Ok(KeyMetadata::new(
+      keypair.public().clone(),
+      keypair.type_(),
+      key.created_at,
+    ))
+  }
+
+  async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    self.mutate_vault(did, false, |vault| Ok(vault.remove(location)))
+  }
+
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, false, |vault| {
+      if vault.get(old).is_none() {
+        return Err(Error::KeyNotFound);
+      }
+
+      let keypair: KeyPair = KeyPair::new(old.key_type)?;
+      let location: KeyLocation = KeyLocation::new(old.key_type, fragment.to_owned(), keypair.public().as_ref());
+
+      vault.remove(old);
+      vault.insert(SledKey::new(location.clone(), &keypair));
+
+      Ok(location)
+    })
+  }
+
+  async fn key_move(&self, did: &CoreDID, from: &KeyLocation, new_fragment: &str) -> Result<KeyLocation> {
+    self.mutate_vault(did, false, |vault| {
+      let key: &SledKey = vault.get(from).ok_or(Error::KeyNotFound)?;
+      let location: KeyLocation = from.with_fragment(new_fragment.to_owned());
+      let moved: SledKey = SledKey {
+        location: location.clone(),
+        private_key: key.private_key.clone(),
+        created_at: key.created_at,
+      };
+
+      vault.remove(from);
+      vault.insert(moved);
+
+      Ok(location)
+    })
+  }
+
+  async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature> {
+    let keypair: KeyPair = self.keypair(did, location)?;
+
+    match location.key_type {
+      KeyType::Ed25519 => {
+        let signature: [u8; 64] = Ed25519::sign(&data, keypair.private())?;
+        Signature::new_checked(KeyType::Ed25519, signature.to_vec())
+      }
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
+  async fn key_exchange(&self, did: &CoreDID, location: &KeyLocation, public_key: &PublicKey) -> Result<[u8; 32]> {
+    let keypair: KeyPair = self.keypair(did, location)?;
+
+    match keypair.type_() {
+      KeyType::Ed25519 => Err(Error::InvalidPrivateKey(
+        "Ed25519 keys are not supported for key exchange".to_owned(),
+      )),
+      KeyType::X25519 => {
+        let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key.as_ref().try_into().map_err(|_| {
+          Error::InvalidPublicKey(format!("expected public key of length {}", X25519::PUBLIC_KEY_LENGTH))
+        })?;
+        X25519::key_exchange(keypair.private(), &public_key).map_err(Into::into)
+      }
+    }
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt(
+    &self,
+    did: &CoreDID,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
+  ) -> Result<EncryptedData> {
+    let sender: KeyPair = match sender_key {
+      Some(location) => self.keypair(did, location)?,
+      None => {
+        return self
+          .data_encrypt_anonymous(
+            plaintext,
+            associated_data,
+            encryption_algorithm,
+            cek_algorithm,
+            public_key,
+          )
+          .await
+      }
+    };
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      Some(&sender),
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt_anonymous(
+    &self,
+    plaintext: Vec<u8>,
+    associated_data: Vec<u8>,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    memstore_encryption::encrypt(
+      plaintext,
+      associated_data,
+      encryption_algorithm,
+      cek_algorithm,
+      public_key,
+      None,
+      &mut memstore_encryption::os_random,
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+  ) -> Result<Vec<u8>> {
+    let keypair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+    )
+  }
+
+  #[cfg(feature = "encryption")]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    let keypair: KeyPair = self.keypair(did, private_key)?;
+    memstore_encryption::decrypt_into(
+      &keypair,
+      data,
+      encryption_algorithm,
+      cek_algorithm,
+      sender_public_key.as_ref(),
+      out,
+    )
+  }
+
+  async fn blob_set(&self, did: &CoreDID, blob: Vec<u8>) -> Result<()> {
+    self.blob_set_named(did, DEFAULT_BLOB_NAME, blob).await
+  }
+
+  async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>> {
+    self.blob_get_named(did, DEFAULT_BLOB_NAME).await
+  }
+
+  async fn blob_compare_and_swap(&self, did: &CoreDID, expected: Option<&[u8]>, new: Vec<u8>) -> Result<bool> {
+    self
+      .blobs
+      .transaction(|tree: &TransactionalTree| {
+        let mut blobs: SledBlobs = match tree.get(did.as_str())? {
+          Some(bytes) => SledBlobs::from_json_slice(&bytes)
+            .map_err(Error::from)
+            .map_err(ConflictableTransactionError::Abort)?,
+          None => SledBlobs::default(),
+        };
+
+        if !ct_eq_option(blobs.0.get(DEFAULT_BLOB_NAME).map(Vec::as_slice), expected) {
+          return Ok(false);
+        }
+
+        blobs.0.insert(DEFAULT_BLOB_NAME.to_owned(), new.clone());
+
+        let bytes: Vec<u8> = blobs
+          .to_json_vec()
+          .map_err(Error::from)
+          .map_err(ConflictableTransactionError::Abort)?;
+        tree.insert(did.as_str(), bytes)?;
+
+        Ok(true)
+      })
+      .map_err(transaction_error)
+  }
+
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, blob: Vec<u8>) -> Result<()> {
+    self
+      .blobs
+      .transaction(|tree: &TransactionalTree| {
+        let mut blobs: SledBlobs = match tree.get(did.as_str())? {
+          Some(bytes) => SledBlobs::from_json_slice(&bytes)
+            .map_err(Error::from)
+            .map_err(ConflictableTransactionError::Abort)?,
+          None => SledBlobs::default(),
+        };
+
+        blobs.0.insert(name.to_owned(), blob.clone());
+
+        let bytes: Vec<u8> = blobs
+          .to_json_vec()
+          .map_err(Error::from)
+          .map_err(ConflictableTransactionError::Abort)?;
+        tree.insert(did.as_str(), bytes)?;
+
+        Ok(())
+      })
+      .map_err(transaction_error)
+  }
+
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    Ok(self.read_blobs(did)?.and_then(|mut blobs| blobs.0.remove(name)))
+  }
+
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    Ok(
+      self
+        .read_blobs(did)?
+        .map(|blobs| blobs.0.into_keys().collect())
+        .unwrap_or_default(),
+    )
+  }
+
+  async fn stats(&self) -> Result<StorageStats> {
+    // The trees are read one after another, so concurrent writes may be partially reflected.
+    let mut key_count: usize = 0;
+    for entry in self.vaults.iter() {
+      let (key, bytes): (IVec, IVec) = entry?;
+      let did: CoreDID = CoreDID::parse(String::from_utf8_lossy(&key))?;
+      key_count += self.decrypt_vault(&did, &bytes)?.0.len();
+    }
+
+    let mut blob_bytes: usize = 0;
+    for bytes in self.blobs.iter().values() {
+      blob_bytes += SledBlobs::from_json_slice(&bytes?)?
+        .0
+        .values()
+        .map(|blob| blob.len())
+        .sum::<usize>();
+    }
+
+    Ok(StorageStats::new(self.vaults.len(), key_count, blob_bytes))
+  }
+
+  async fn flush_changes(&self) -> Result<()> {
+    self.db.flush_async().await?;
+    Ok(())
+  }
+}
+
+impl Debug for SledStore {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.write_str("SledStore")
+  }
+}
+
+/// The serialized representation of the keys of a DID.
+#[derive(Default, Deserialize, Serialize)]
+struct SledVault(Vec<SledKey>);
+
+impl SledVault {
+  fn get(&self, location: &KeyLocation) -> Option<&SledKey> {
+    self.0.iter().find(|key| &key.location == location)
+  }
+
+  /// Inserts the `key`, overwriting any key at the same location.
+  fn insert(&mut self, key: SledKey) {
+    self.remove(&key.location);
+    self.0.push(key);
+  }
+
+  /// Removes the key at `location` and returns whether it existed.
+  fn remove(&mut self, location: &KeyLocation) -> bool {
+    let len: usize = self.0.len();
+    self.0.retain(|key| &key.location != location);
+    self.0.len() != len
+  }
+}
+
+/// The serialized representation of the named blobs of a DID.
+#[derive(Default, Deserialize, Serialize)]
+struct SledBlobs(BTreeMap<String, Vec<u8>>);
+
+/// A private key together with its location. The private key is zeroized on drop.
+#[derive(Deserialize, Serialize)]
+struct SledKey {
+  location: KeyLocation,
+  private_key: Vec<u8>,
+  // Absent in vaults written before creation times were tracked.
+  #[serde(default)]
+  created_at: Option<Timestamp>,
+}
+
+impl SledKey {
+  fn new(location: KeyLocation, keypair: &KeyPair) -> Self {
+    Self {
+      location,
+      private_key: keypair.private().as_ref().to_vec(),
+      created_at: Some(Timestamp::now_utc()),
+    }
+  }
+
+  fn keypair(&self) -> Result<KeyPair> {
+    KeyPair::try_from_private_key_bytes(self.location.key_type, &self.private_key).map_err(Into::into)
+  }
+}
+
+impl Drop for SledKey {
+  fn drop(&mut self) {
+    self.private_key.zeroize();
+  }
+}
+
+fn transaction_error(error: TransactionError<Error>) -> Error {
+  match error {
+    TransactionError::Abort(error) => error,
+    TransactionError::Storage(error) => Error::SledError(error),
+  }
+}
+
+#[cfg(test)]
+#[cfg(feature = "storage-test-suite")]
+mod tests {
+  use identity_did::did::CoreDID;
+  use identity_iota_core::tangle::Network;
+
+  use crate::error::Error;
+  use crate::storage::Storage;
+  use crate::storage::StorageTestSuite;
+  use crate::types::DIDType;
+  use crate::types::KeyLocation;
+
+  use super::SledStore;
+
+  fn test_sledstore() -> impl Storage {
+    let db: ::sled::Db = ::sled::Config::new().temporary(true).open().unwrap();
+    SledStore::from_db(db, [0x42; 32]).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_sledstore_run_all() {
+    let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { test_sledstore() }).await.unwrap();
+
+    let mut expected: Vec<&'static str> = vec![
+      "key_expiry_test",
+      "did_metadata_test",
+      "did_timestamps_test",
+      "blob_delete_test",
+      "did_export_import_test",
+      "data_decrypt_expired_key_test",
+      "data_decrypt_with_conversion_test",
+    ];
+    #[cfg(feature = "encryption")]
+    expected.push("data_encrypt_stream_test");
+    assert_eq!(skipped, expected);
+  }
+
+  #[tokio::test]
+  async fn test_sledstore_encrypted_vaults() {
+    let db: ::sled::Db = ::sled::Config::new().temporary(true).open().unwrap();
+
+    let sledstore: SledStore = SledStore::from_db(db.clone(), [0x42; 32]).unwrap();
+    let (did, location): (CoreDID, KeyLocation) = sledstore
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), "key-1", None)
+      .await
+      .unwrap();
+    let private_key: Vec<u8> = sledstore.keypair(&did, &location).unwrap().private().as_ref().to_vec();
+    drop(sledstore);
+
+    // The private key is not stored in plaintext.
+    let vault: ::sled::IVec = db
+      .open_tree(super::VAULTS_TREE)
+      .unwrap()
+      .get(did.as_str())
+      .unwrap()
+      .unwrap();
+    assert!(!vault
+      .windows(private_key.len())
+      .any(|window| window == private_key.as_slice()));
+
+    // The vaults cannot be decrypted with a different master key.
+    let sledstore: SledStore = SledStore::from_db(db, [0; 32]).unwrap();
+    assert!(matches!(
+      sledstore.key_exists(&did, &location).await.unwrap_err(),
+      Error::DecryptionFailure(_)
+    ));
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_sledstore_concurrency() {
+    StorageTestSuite::concurrency_test(test_sledstore()).await.unwrap()
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[tokio::test(flavor = "multi_thread")]
+  async fn test_sledstore_blob_append() {
+    StorageTestSuite::blob_append_test(test_sledstore()).await.unwrap()
+  }
+}
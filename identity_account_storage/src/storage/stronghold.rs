@@ -5,16 +5,17 @@ use std::collections::BTreeSet;
 
 use async_trait::async_trait;
 use crypto::ciphers::aes_gcm::Aes256Gcm;
+use crypto::ciphers::chacha::XChaCha20Poly1305;
 use crypto::ciphers::traits::Aead;
 use futures::executor;
 use identity_core::convert::FromJson;
 use identity_core::convert::ToJson;
+use identity_core::crypto::KeyPair;
 use identity_core::crypto::KeyType;
 use identity_core::crypto::PrivateKey;
 use identity_core::crypto::PublicKey;
 use identity_core::crypto::X25519;
 use identity_did::did::CoreDID;
-use identity_iota_core::did::IotaDID;
 use identity_iota_core::tangle::NetworkName;
 use iota_stronghold::procedures;
 use iota_stronghold::procedures::ProcedureError;
@@ -44,6 +45,7 @@ use crate::types::CekAlgorithm;
 use crate::types::DIDType;
 use crate::types::EncryptedData;
 use crate::types::EncryptionAlgorithm;
+use crate::types::KeyDerivation;
 use crate::types::KeyLocation;
 use crate::types::Signature;
 
@@ -66,6 +68,9 @@ impl Storage for Stronghold {
     fragment: &str,
     private_key: Option<PrivateKey>,
   ) -> Result<(CoreDID, KeyLocation)> {
+    // Reject an invalid network before any key is written to the vault.
+    did_type.validate_network(&network)?;
+
     // =============================
     // KEY GENERATION/INSERTION
     // =============================
@@ -84,13 +89,7 @@ impl Storage for Stronghold {
 
     let public_key: PublicKey = retrieve_public_key(&tmp_client, &tmp_location)?;
 
-    let did: CoreDID = {
-      match did_type {
-        DIDType::IotaDID => IotaDID::new_with_network(public_key.as_ref(), network)
-          .map_err(|err| crate::Error::DIDCreationError(err.to_string()))?
-          .into(),
-      }
-    };
+    let did: CoreDID = did_type.create_did(public_key.as_ref(), network)?;
 
     // =============================
     // ADD DID TO INDEX
@@ -229,6 +228,11 @@ impl Storage for Stronghold {
   }
 
   async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, private_key: PrivateKey) -> Result<()> {
+    // Stronghold accepts arbitrary secrets, so validate the private key against the location's key type
+    // before writing it, rather than failing on the first use of the key.
+    KeyPair::try_from_private_key_bytes(location.key_type, private_key.as_ref())
+      .map_err(|err| Error::InvalidPrivateKey(err.to_string()))?;
+
     self.mutate_client(did, |client| insert_private_key(&client, private_key, location))
   }
 
@@ -290,7 +294,10 @@ impl Storage for Stronghold {
     encryption_algorithm: &EncryptionAlgorithm,
     cek_algorithm: &CekAlgorithm,
     public_key: PublicKey,
+    _sender_key: Option<&KeyLocation>,
   ) -> Result<EncryptedData> {
+    check_encryption_algorithm(encryption_algorithm)?;
+    check_key_derivation(cek_algorithm)?;
     // Changes won't be written to the snapshot state since the created keys are temporary
     let client: Client = self.client(&ClientPath::from(did))?;
     let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = public_key
@@ -332,6 +339,12 @@ impl Storage for Stronghold {
         .await?;
         Ok(encrypted_data)
       }
+      // Stronghold's key wrap procedure only supports AES256.
+      CekAlgorithm::ECDH_ES_A128KW(_) | CekAlgorithm::ECDH_ES_A192KW(_) => {
+        Err(Error::NotSupported(cek_algorithm.name()))
+      }
+      // Combining the ephemeral-static and static-static shared secrets is not possible with stronghold procedures.
+      CekAlgorithm::ECDH_1PU(_) | CekAlgorithm::ECDH_1PU_A256KW(_) => Err(Error::NotSupported(cek_algorithm.name())),
     }
   }
 
@@ -343,7 +356,11 @@ impl Storage for Stronghold {
     encryption_algorithm: &EncryptionAlgorithm,
     cek_algorithm: &CekAlgorithm,
     private_key: &KeyLocation,
+    _sender_public_key: Option<PublicKey>,
   ) -> Result<Vec<u8>> {
+    check_encryption_algorithm(encryption_algorithm)?;
+    check_key_derivation(cek_algorithm)?;
+    data.validate(encryption_algorithm)?;
     // Changes won't be written to the snapshot state since the created keys are temporary
     let client: Client = self.client(&ClientPath::from(did))?;
     let public_key: [u8; X25519::PUBLIC_KEY_LENGTH] = data
@@ -379,6 +396,10 @@ impl Storage for Stronghold {
 
         aead_decrypt(&client, encryption_algorithm, cek, data).await
       }
+      CekAlgorithm::ECDH_ES_A128KW(_)
+      | CekAlgorithm::ECDH_ES_A192KW(_)
+      | CekAlgorithm::ECDH_1PU(_)
+      | CekAlgorithm::ECDH_1PU_A256KW(_) => Err(Error::NotSupported(cek_algorithm.name())),
     }
   }
 
@@ -471,7 +492,7 @@ fn sign_ed25519(client: &Client, payload: Vec<u8>, location: &KeyLocation) -> Re
     .execute_procedure(procedure)
     .map_err(|err| procedure_error::<procedures::Ed25519Sign>(vec![location.clone()], err))?;
 
-  Ok(Signature::new(signature.into()))
+  Signature::new_checked(KeyType::Ed25519, signature.into())
 }
 
 pub(crate) async fn diffie_hellman(
@@ -501,20 +522,16 @@ pub(crate) async fn concat_kdf(
 ) -> Result<Location> {
   let location: [u8; 32] = rand::Rng::gen(&mut rand::thread_rng());
   let output: Location = Location::generic(VAULT_PATH.to_vec(), location.to_vec());
-  let derived_secret: procedures::ConcatKdf = {
-    match encryption_algorithm {
-      EncryptionAlgorithm::AES256GCM => procedures::ConcatKdf {
-        hash: Sha2Hash::Sha256,
-        algorithm_id,
-        shared_secret,
-        key_len: Aes256Gcm::KEY_LENGTH,
-        apu: agreement.apu.clone(),
-        apv: agreement.apv.clone(),
-        pub_info: agreement.pub_info.clone(),
-        priv_info: agreement.priv_info.clone(),
-        output: output.clone(),
-      },
-    }
+  let derived_secret: procedures::ConcatKdf = procedures::ConcatKdf {
+    hash: Sha2Hash::Sha256,
+    algorithm_id,
+    shared_secret,
+    key_len: encryption_algorithm.key_length(),
+    apu: agreement.apu.clone(),
+    apv: agreement.apv.clone(),
+    pub_info: agreement.pub_info.clone(),
+    priv_info: agreement.priv_info.clone(),
+    output: output.clone(),
   };
   client
     .execute_procedure(derived_secret)
@@ -522,6 +539,24 @@ pub(crate) async fn concat_kdf(
   Ok(output)
 }
 
+/// Returns [`Error::NotSupported`] for encryption algorithms without a corresponding stronghold procedure.
+///
+/// This is checked before any keys are derived, which would otherwise fail with less helpful errors.
+fn check_encryption_algorithm(algorithm: &EncryptionAlgorithm) -> Result<()> {
+  match algorithm {
+    EncryptionAlgorithm::AES128GCM | EncryptionAlgorithm::AES192GCM => Err(Error::NotSupported(algorithm.name())),
+    EncryptionAlgorithm::AES256GCM | EncryptionAlgorithm::XCHACHA20POLY1305 => Ok(()),
+  }
+}
+
+/// Returns [`Error::NotSupported`] for key derivations without a corresponding stronghold procedure.
+fn check_key_derivation(cek_algorithm: &CekAlgorithm) -> Result<()> {
+  match cek_algorithm.key_derivation() {
+    KeyDerivation::ConcatKdf => Ok(()),
+    key_derivation @ KeyDerivation::HkdfSha256 => Err(Error::NotSupported(key_derivation.name())),
+  }
+}
+
 pub(crate) async fn aead_encrypt(
   client: &Client,
   algorithm: &EncryptionAlgorithm,
@@ -531,29 +566,44 @@ pub(crate) async fn aead_encrypt(
   encrypted_cek: Vec<u8>,
   ephemeral_public_key: Vec<u8>,
 ) -> Result<EncryptedData> {
-  match algorithm {
-    EncryptionAlgorithm::AES256GCM => {
-      let nonce: &[u8] = &Aes256Gcm::random_nonce().map_err(Error::EncryptionFailure)?;
-      let aead_encrypt: procedures::AeadEncrypt = procedures::AeadEncrypt {
-        cipher: procedures::AeadCipher::Aes256Gcm,
-        associated_data: associated_data.clone(),
-        plaintext,
-        nonce: nonce.to_vec(),
-        key,
-      };
-      let mut data = client
-        .execute_procedure(aead_encrypt)
-        .map_err(|err| procedure_error::<procedures::AeadEncrypt>(vec![], err))?;
-      Ok(EncryptedData::new(
-        nonce.to_vec(),
-        associated_data,
-        data.drain(..Aes256Gcm::TAG_LENGTH).collect(),
-        data,
-        encrypted_cek,
-        ephemeral_public_key,
-      ))
+  let (cipher, nonce, tag_length): (procedures::AeadCipher, Vec<u8>, usize) = match algorithm {
+    EncryptionAlgorithm::AES128GCM | EncryptionAlgorithm::AES192GCM => {
+      return Err(Error::NotSupported(algorithm.name()))
     }
-  }
+    EncryptionAlgorithm::AES256GCM => (
+      procedures::AeadCipher::Aes256Gcm,
+      Aes256Gcm::random_nonce().map_err(Error::EncryptionFailure)?.to_vec(),
+      Aes256Gcm::TAG_LENGTH,
+    ),
+    EncryptionAlgorithm::XCHACHA20POLY1305 => (
+      procedures::AeadCipher::XChaCha20Poly1305,
+      XChaCha20Poly1305::random_nonce()
+        .map_err(Error::EncryptionFailure)?
+        .to_vec(),
+      XChaCha20Poly1305::TAG_LENGTH,
+    ),
+  };
+  let aead_encrypt: procedures::AeadEncrypt = procedures::AeadEncrypt {
+    cipher,
+    associated_data: associated_data.clone(),
+    plaintext,
+    nonce: nonce.clone(),
+    key,
+  };
+  let mut data = client
+    .execute_procedure(aead_encrypt)
+    .map_err(|err| procedure_error::<procedures::AeadEncrypt>(vec![], err))?;
+  Ok(
+    EncryptedData::new(
+      nonce,
+      associated_data,
+      data.drain(..tag_length).collect(),
+      data,
+      encrypted_cek,
+      ephemeral_public_key,
+    )
+    .with_algorithm(algorithm),
+  )
 }
 
 pub(crate) async fn aead_decrypt(
@@ -562,22 +612,25 @@ pub(crate) async fn aead_decrypt(
   key: Location,
   encrypted_data: EncryptedData,
 ) -> Result<Vec<u8>> {
-  match algorithm {
-    EncryptionAlgorithm::AES256GCM => {
-      let aead_decrypt: procedures::AeadDecrypt = procedures::AeadDecrypt {
-        cipher: procedures::AeadCipher::Aes256Gcm,
-        key,
-        ciphertext: encrypted_data.ciphertext,
-        associated_data: encrypted_data.associated_data,
-        tag: encrypted_data.tag,
-        nonce: encrypted_data.nonce,
-      };
-      let data = client
-        .execute_procedure(aead_decrypt)
-        .map_err(|err| procedure_error::<procedures::AeadDecrypt>(vec![], err))?;
-      Ok(data)
+  let cipher: procedures::AeadCipher = match algorithm {
+    EncryptionAlgorithm::AES128GCM | EncryptionAlgorithm::AES192GCM => {
+      return Err(Error::NotSupported(algorithm.name()))
     }
-  }
+    EncryptionAlgorithm::AES256GCM => procedures::AeadCipher::Aes256Gcm,
+    EncryptionAlgorithm::XCHACHA20POLY1305 => procedures::AeadCipher::XChaCha20Poly1305,
+  };
+  let aead_decrypt: procedures::AeadDecrypt = procedures::AeadDecrypt {
+    cipher,
+    key,
+    ciphertext: encrypted_data.ciphertext,
+    associated_data: encrypted_data.associated_data,
+    tag: encrypted_data.tag,
+    nonce: encrypted_data.nonce,
+  };
+  let data = client
+    .execute_procedure(aead_decrypt)
+    .map_err(|err| procedure_error::<procedures::AeadDecrypt>(vec![], err))?;
+  Ok(data)
 }
 
 /// Creates an ephemeral pair of X25519 keys, obtains the shared secret by runnning the Diffie-Hellman algorithm and
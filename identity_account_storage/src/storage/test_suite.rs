@@ -1,18 +1,45 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use std::collections::BTreeMap;
+#[cfg(feature = "send-sync-storage")]
+use std::collections::HashSet;
+use std::future::Future;
+#[cfg(feature = "encryption")]
+use std::pin::Pin;
+#[cfg(feature = "send-sync-storage")]
+use std::sync::Arc;
+#[cfg(feature = "encryption")]
+use std::task::Poll;
+
 use anyhow::Context;
+#[cfg(feature = "encryption")]
+use crypto::hashes::sha::Sha256;
+#[cfg(feature = "encryption")]
+use crypto::hashes::Digest;
 use function_name::named;
+#[cfg(feature = "encryption")]
+use futures::io::AsyncWrite;
 use identity_did::did::CoreDID;
+use identity_did::did::DID;
 use rand::distributions::DistString;
 use rand::rngs::OsRng;
+#[cfg(feature = "send-sync-storage")]
+use tokio::task::JoinHandle;
 
+use identity_core::common::Duration;
+use identity_core::common::Timestamp;
 use identity_core::convert::FromJson;
 use identity_core::convert::ToJson;
+use identity_core::crypto::Ed25519;
 use identity_core::crypto::KeyPair;
 use identity_core::crypto::KeyType;
 use identity_core::crypto::PrivateKey;
 use identity_core::crypto::PublicKey;
+use identity_core::crypto::Verify;
+use identity_core::crypto::X25519;
+use identity_core::utils::Base;
+use identity_core::utils::BaseEncoding;
 use identity_iota_core::did::IotaDID;
 use identity_iota_core::document::IotaDocument;
 use identity_iota_core::document::IotaVerificationMethod;
@@ -26,10 +53,15 @@ use crate::types::CekAlgorithm;
 use crate::types::DIDType;
 use crate::types::EncryptedData;
 use crate::types::EncryptionAlgorithm;
+use crate::types::JwsAlgorithm;
+use crate::types::KeyDerivation;
 use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
 use crate::types::Signature;
+use crate::types::StorageStats;
 
 use super::Storage;
+use super::MIN_SEED_LENGTH;
 
 macro_rules! ensure {
   ($cond:expr, $($msg:expr),*) => {{
@@ -51,6 +83,45 @@ fn random_string() -> String {
   rand::distributions::Alphanumeric.sample_string(&mut OsRng, 32)
 }
 
+fn decode_hex(hex: &str) -> Vec<u8> {
+  (0..hex.len())
+    .step_by(2)
+    .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+    .collect()
+}
+
+/// A sink which only hashes the written data, so that large streams need not be held in memory.
+#[cfg(feature = "encryption")]
+#[derive(Default)]
+struct DigestWriter(Sha256);
+
+#[cfg(feature = "encryption")]
+impl DigestWriter {
+  fn finalize(self) -> Vec<u8> {
+    self.0.finalize().to_vec()
+  }
+}
+
+#[cfg(feature = "encryption")]
+impl AsyncWrite for DigestWriter {
+  fn poll_write(
+    mut self: Pin<&mut Self>,
+    _cx: &mut std::task::Context<'_>,
+    buf: &[u8],
+  ) -> Poll<std::io::Result<usize>> {
+    self.0.update(buf);
+    Poll::Ready(Ok(buf.len()))
+  }
+
+  fn poll_flush(self: Pin<&mut Self>, _cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<()>> {
+    Poll::Ready(Ok(()))
+  }
+
+  fn poll_close(self: Pin<&mut Self>, _cx: &mut std::task::Context<'_>) -> Poll<std::io::Result<()>> {
+    Poll::Ready(Ok(()))
+  }
+}
+
 /// A test suite for the `Storage` interface.
 ///
 /// This contains a set of tests that a correct storage implementation
@@ -59,9 +130,141 @@ fn random_string() -> String {
 /// Tests usually rely on multiple interface methods being implemented, so they should only
 /// be run on a fully implemented version. That's why there is not a single test case for every
 /// interface method.
+///
+/// A storage implementation can run the entire suite from a single test with
+/// [`StorageTestSuite::run_all`], asserting which tests are skipped because it does not support them:
+///
+/// ```ignore
+/// #[tokio::test]
+/// async fn test_my_storage() {
+///   let skipped: Vec<&'static str> = StorageTestSuite::run_all(|| async { MyStorage::new() }).await.unwrap();
+///   assert_eq!(skipped, ["did_export_import_test"]);
+/// }
+/// ```
+///
+/// The individual tests remain available for granular runs.
 pub struct StorageTestSuite;
 
+/// Returns `true` if `error` was caused by the storage not supporting an optional operation.
+fn is_not_supported(error: &anyhow::Error) -> bool {
+  error.chain().any(|cause| {
+    matches!(
+      cause.downcast_ref::<crate::Error>(),
+      Some(crate::Error::NotSupported(_))
+    )
+  })
+}
+
 impl StorageTestSuite {
+  /// Runs every test of the suite in sequence, each against fresh storage instances created by
+  /// `make_storage`, and returns a single error listing all tests that failed.
+  ///
+  /// Tests which fail because the storage returns [`Error::NotSupported`](crate::Error::NotSupported) for an
+  /// optional operation are skipped. On success, the names of the skipped tests are returned in the order
+  /// they were run, so that callers can assert that only the operations they expect are unsupported.
+  ///
+  /// Tests with special requirements are not run and need to be called individually:
+  /// - [`StorageTestSuite::concurrency_test`], which needs a multi-threaded runtime.
+  /// - [`StorageTestSuite::blob_append_test`], which needs a multi-threaded runtime.
+  /// - [`StorageTestSuite::flush_failure_test`], which needs a storage that fails the next flush.
+  /// - [`StorageTestSuite::transaction_rollback_test`], which needs a storage that rolls back failed transactions.
+  /// - [`StorageTestSuite::encryption_vector_test`], which needs a storage with deterministic randomness.
+  /// - [`StorageTestSuite::close_test`] and [`StorageTestSuite::chain_state_persistence_test`], which need to reopen
+  ///   the storage.
+  pub async fn run_all<S, F, Fut>(make_storage: F) -> anyhow::Result<Vec<&'static str>>
+  where
+    S: Storage,
+    F: Fn() -> Fut,
+    Fut: Future<Output = S>,
+  {
+    let mut failures: Vec<String> = Vec::new();
+    let mut skipped: Vec<&'static str> = Vec::new();
+
+    macro_rules! run {
+      ($test:ident($($storage:expr),+)) => {
+        if let Err(error) = Self::$test($($storage.await),+).await {
+          if is_not_supported(&error) {
+            skipped.push(stringify!($test));
+          } else {
+            failures.push(format!("{}: {error:#}", stringify!($test)));
+          }
+        }
+      };
+    }
+
+    run!(did_create_private_key_test(make_storage()));
+    run!(did_create_generate_key_test(make_storage()));
+    run!(did_create_from_keypair_test(make_storage()));
+    run!(did_create_web_test(make_storage()));
+    run!(did_create_invalid_network_test(make_storage()));
+    run!(did_create_many_test(make_storage()));
+    run!(key_generate_test(make_storage()));
+    run!(key_generate_from_seed_test(make_storage()));
+    run!(key_delete_test(make_storage()));
+    run!(key_delete_checked_test(make_storage()));
+    run!(key_list_test(make_storage()));
+    run!(key_list_by_type_test(make_storage()));
+    run!(key_public_many_test(make_storage()));
+    run!(key_delete_all_test(make_storage()));
+    run!(key_metadata_test(make_storage()));
+    run!(key_rotate_test(make_storage()));
+    run!(key_move_test(make_storage()));
+    run!(key_expiry_test(make_storage()));
+    run!(key_exchange_test(make_storage(), make_storage()));
+    run!(did_list_test(make_storage()));
+    run!(did_list_paged_test(make_storage()));
+    run!(did_exists_many_test(make_storage()));
+    run!(did_metadata_test(make_storage()));
+    run!(did_timestamps_test(make_storage()));
+    run!(key_insert_test(make_storage()));
+    run!(key_insert_indexed_test(make_storage()));
+    run!(key_insert_type_mismatch_test(make_storage()));
+    run!(key_sign_ed25519_test(make_storage()));
+    run!(signing_determinism_test(make_storage()));
+    run!(key_verify_test(make_storage()));
+    run!(key_sign_jws_test(make_storage()));
+    run!(key_value_store_test(make_storage()));
+    run!(blob_delete_test(make_storage()));
+    run!(blob_compare_and_swap_test(make_storage()));
+    run!(named_blobs_test(make_storage()));
+    run!(stats_test(make_storage()));
+    run!(did_copy_test(make_storage()));
+    run!(did_export_import_test(make_storage()));
+    run!(did_purge_test(make_storage()));
+    run!(did_purge_missing_test(make_storage()));
+    run!(encryption_test(make_storage(), make_storage()));
+    run!(hkdf_encryption_test(make_storage(), make_storage()));
+    run!(encrypted_data_version_test(make_storage()));
+    run!(associated_data_tamper_test(make_storage()));
+    run!(data_decrypt_expired_key_test(make_storage()));
+    run!(data_encrypt_anonymous_test(make_storage(), make_storage()));
+    run!(data_decrypt_into_test(make_storage()));
+    run!(data_decrypt_with_conversion_test(make_storage()));
+    #[cfg(feature = "encryption")]
+    run!(data_encrypt_stream_test(make_storage(), make_storage()));
+    run!(ecdh_1pu_encryption_test(make_storage(), make_storage()));
+
+    if failures.is_empty() {
+      Ok(skipped)
+    } else {
+      Err(anyhow::Error::msg(format!(
+        "{} storage test(s) failed:\n{}",
+        failures.len(),
+        failures.join("\n")
+      )))
+    }
+  }
+
+  /// Returns a predictable source of randomness for [`StorageTestSuite::encryption_vector_test`],
+  /// which fills the n-th requested buffer entirely with the byte `n`, starting at `1`.
+  pub fn deterministic_fill() -> impl FnMut(&mut [u8]) + Send + 'static {
+    let mut counter: u8 = 0;
+    move |bytes: &mut [u8]| {
+      counter = counter.wrapping_add(1);
+      bytes.fill(counter);
+    }
+  }
+
   #[named]
   pub async fn did_create_private_key_test(storage: impl Storage) -> anyhow::Result<()> {
     let fragment: String = random_string();
@@ -166,6 +369,229 @@ impl StorageTestSuite {
     Ok(())
   }
 
+  #[named]
+  pub async fn did_create_from_keypair_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let x25519_keypair: KeyPair = KeyPair::new(KeyType::X25519).unwrap();
+    let result: Result<(CoreDID, KeyLocation), crate::Error> = storage
+      .did_create_from_keypair(DIDType::IotaDID, network.clone(), &fragment, x25519_keypair)
+      .await;
+
+    ensure!(
+      matches!(
+        result,
+        Err(crate::Error::DIDError(identity_did::Error::InvalidMethodType))
+      ),
+      "expected did_create_from_keypair to reject an X25519 key pair, got {:?}",
+      result.map(|(did, _)| did)
+    );
+
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create_from_keypair(DIDType::IotaDID, network.clone(), &fragment, keypair.clone())
+      .await
+      .context("did_create_from_keypair returned an error")?;
+
+    let expected_did: CoreDID = IotaDID::new_with_network(keypair.public().as_ref(), network)
+      .unwrap()
+      .into();
+    ensure_eq!(
+      did,
+      expected_did,
+      "expected did `{expected_did}` derived from the key pair, got `{did}`"
+    );
+
+    let expected_location: KeyLocation = KeyLocation::new(KeyType::Ed25519, fragment, keypair.public().as_ref());
+    ensure_eq!(
+      location,
+      expected_location,
+      "expected location `{expected_location}`, got `{location}`"
+    );
+
+    let public_key: PublicKey = storage
+      .key_public(&did, &location)
+      .await
+      .context("key_public returned an error")?;
+
+    ensure_eq!(
+      public_key.as_ref(),
+      keypair.public().as_ref(),
+      "expected the stored public key to be the one of the given key pair"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_create_web_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let did_type: DIDType = DIDType::WebDID {
+      domain: "example.com:3000".to_owned(),
+    };
+
+    let expected_did: String = format!(
+      "did:web:example.com%3A3000:{}",
+      IotaDID::encode_key(keypair.public().as_ref())
+    );
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(
+        did_type,
+        Network::Mainnet.name(),
+        &fragment,
+        Some(keypair.private().to_owned()),
+      )
+      .await
+      .context("did_create returned an error")?;
+
+    ensure_eq!(
+      did.as_str(),
+      expected_did,
+      "expected returned did to be `{expected_did}`, was `{did}`"
+    );
+
+    ensure_eq!(
+      did.method(),
+      "web",
+      "expected the did method to be `web`, was `{}`",
+      did.method()
+    );
+
+    let exists: bool = storage
+      .key_exists(&did, &location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(exists, "expected key at location `{location}` to exist");
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_create_invalid_network_test(storage: impl Storage) -> anyhow::Result<()> {
+    // Deserialization does not validate the name, which contains uppercase characters.
+    let network: NetworkName = NetworkName::from_json("\"Main\"").unwrap();
+
+    let result: Result<_, crate::Error> = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await;
+
+    ensure!(
+      matches!(&result, Err(crate::Error::InvalidNetwork(name)) if name == "Main"),
+      "expected did_create to return `InvalidNetwork` for an invalid network name, got {result:?}"
+    );
+
+    let dids: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+
+    ensure!(
+      dids.is_empty(),
+      "expected did_list to be empty after a failed did_create, got {dids:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_create_many_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_IDENTITIES: usize = 50;
+    let network: NetworkName = Network::Mainnet.name();
+
+    let keypairs: Vec<KeyPair> = (0..NUM_IDENTITIES)
+      .map(|_| KeyPair::new(KeyType::Ed25519).unwrap())
+      .collect();
+
+    let mut requests: Vec<(DIDType, NetworkName, String, Option<PrivateKey>)> = keypairs
+      .iter()
+      .map(|keypair| {
+        (
+          DIDType::IotaDID,
+          network.clone(),
+          random_string(),
+          Some(keypair.private().to_owned()),
+        )
+      })
+      .collect();
+
+    // Creating the same identity twice within a batch must fail the entire batch.
+    requests.push((
+      DIDType::IotaDID,
+      network.clone(),
+      random_string(),
+      Some(keypairs[0].private().to_owned()),
+    ));
+
+    let result: Result<_, crate::Error> = storage.did_create_many(requests).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::IdentityAlreadyExists)),
+      "expected did_create_many to return `IdentityAlreadyExists` for a batch containing a duplicate"
+    );
+
+    let list: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+
+    ensure!(
+      list.is_empty(),
+      "expected did_create_many to roll back the batch, but found {} element(s)",
+      list.len()
+    );
+
+    let requests: Vec<(DIDType, NetworkName, String, Option<PrivateKey>)> = keypairs
+      .iter()
+      .map(|keypair| {
+        (
+          DIDType::IotaDID,
+          network.clone(),
+          random_string(),
+          Some(keypair.private().to_owned()),
+        )
+      })
+      .collect();
+
+    let created: Vec<(CoreDID, KeyLocation)> = storage
+      .did_create_many(requests)
+      .await
+      .context("did_create_many returned an error")?;
+
+    ensure_eq!(
+      created.len(),
+      NUM_IDENTITIES,
+      "expected did_create_many to return {NUM_IDENTITIES} identities, got {}",
+      created.len()
+    );
+
+    for ((did, location), keypair) in created.iter().zip(keypairs.iter()) {
+      let expected_did: CoreDID = IotaDID::new_with_network(keypair.public().as_ref(), network.clone())
+        .unwrap()
+        .into();
+
+      ensure_eq!(
+        did,
+        &expected_did,
+        "expected returned did to be `{expected_did}`, was `{did}`"
+      );
+
+      let exists: bool = storage
+        .key_exists(did, location)
+        .await
+        .context("key_exists returned an error")?;
+
+      ensure!(exists, "expected key at location `{location}` to exist");
+    }
+
+    let list_len: usize = storage.did_list().await.context("did_list returned an error")?.len();
+
+    ensure_eq!(
+      list_len,
+      NUM_IDENTITIES,
+      "expected did_list to return a list of len {NUM_IDENTITIES}, got {list_len} elements instead"
+    );
+
+    Ok(())
+  }
+
   #[named]
   pub async fn key_generate_test(storage: impl Storage) -> anyhow::Result<()> {
     let fragment: String = random_string();
@@ -207,6 +633,77 @@ impl StorageTestSuite {
     Ok(())
   }
 
+  #[named]
+  pub async fn key_generate_from_seed_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+    let seed: Vec<u8> = random_string().into_bytes();
+
+    let (did_1, _): (CoreDID, _) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+    let (did_2, _): (CoreDID, _) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    for key_type in [KeyType::Ed25519, KeyType::X25519] {
+      let location_1: KeyLocation = storage
+        .key_generate_from_seed(&did_1, key_type, &fragment, &seed)
+        .await
+        .context("key_generate_from_seed returned an error")?;
+      let location_2: KeyLocation = storage
+        .key_generate_from_seed(&did_2, key_type, &fragment, &seed)
+        .await
+        .context("key_generate_from_seed returned an error")?;
+
+      ensure_eq!(
+        location_1,
+        location_2,
+        "expected the same seed to yield the same location, got `{location_1}` and `{location_2}`"
+      );
+
+      let public_key_1: PublicKey = storage
+        .key_public(&did_1, &location_1)
+        .await
+        .context("key_public returned an error")?;
+      let public_key_2: PublicKey = storage
+        .key_public(&did_2, &location_2)
+        .await
+        .context("key_public returned an error")?;
+
+      ensure_eq!(
+        public_key_1.as_ref(),
+        public_key_2.as_ref(),
+        "expected the same seed to yield the same public key"
+      );
+
+      let other_location: KeyLocation = storage
+        .key_generate_from_seed(&did_1, key_type, &fragment, random_string().as_bytes())
+        .await
+        .context("key_generate_from_seed returned an error")?;
+
+      ensure!(
+        other_location != location_1,
+        "expected a different seed to yield a different location than `{location_1}`"
+      );
+
+      for seed in [Vec::new(), vec![0x01; MIN_SEED_LENGTH - 1]] {
+        let result: crate::Result<KeyLocation> = storage
+          .key_generate_from_seed(&did_1, key_type, &random_string(), &seed)
+          .await;
+        ensure!(
+          matches!(result, Err(crate::Error::SeedTooShort(length)) if length == seed.len()),
+          "expected SeedTooShort for a seed of {} bytes, got {result:?}",
+          seed.len()
+        );
+      }
+    }
+
+    Ok(())
+  }
+
   #[named]
   pub async fn key_delete_test(storage: impl Storage) -> anyhow::Result<()> {
     const NUM_IDENTITIES: usize = 20;
@@ -256,260 +753,3177 @@ impl StorageTestSuite {
   }
 
   #[named]
-  pub async fn did_list_test(storage: impl Storage) -> anyhow::Result<()> {
-    const NUM_IDENTITIES: usize = 20;
-    let fragment: String = random_string();
-    let network: NetworkName = Network::Mainnet.name();
+  pub async fn key_delete_checked_test(storage: impl Storage) -> anyhow::Result<()> {
+    let (did, signing_location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+    let x25519_location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
 
-    let list: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+    // The X25519 key cannot sign, so it does not prevent the deletion of the only signing key.
+    let result: Result<bool, crate::Error> = storage.key_delete_checked(&did, &signing_location).await;
+    ensure!(
+      matches!(result, Err(crate::Error::CannotRemoveLastSigningKey)),
+      "expected key_delete_checked to return `CannotRemoveLastSigningKey` for the only signing key, got {result:?}"
+    );
+    let exists: bool = storage
+      .key_exists(&did, &signing_location)
+      .await
+      .context("key_exists returned an error")?;
+    ensure!(exists, "expected key at location `{signing_location}` to still exist");
+
+    let deleted: bool = storage
+      .key_delete_checked(&did, &x25519_location)
+      .await
+      .context("key_delete_checked returned an error")?;
+    ensure!(deleted, "expected key at location `{x25519_location}` to be deleted");
+
+    let other_signing_location: KeyLocation = storage
+      .key_generate(&did, KeyType::Ed25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    let deleted: bool = storage
+      .key_delete_checked(&did, &signing_location)
+      .await
+      .context("key_delete_checked returned an error")?;
+    ensure!(deleted, "expected key at location `{signing_location}` to be deleted");
 
+    let deleted: bool = storage
+      .key_delete_checked(&did, &signing_location)
+      .await
+      .context("key_delete_checked returned an error")?;
     ensure!(
-      list.is_empty(),
-      "expected list to be empty, but found {} element(s)",
-      list.len()
+      !deleted,
+      "expected key at location `{signing_location}` to already be deleted"
     );
 
-    for i in 0..NUM_IDENTITIES {
-      let (did, _): (CoreDID, _) = storage
-        .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+    let result: Result<bool, crate::Error> = storage.key_delete_checked(&did, &other_signing_location).await;
+    ensure!(
+      matches!(result, Err(crate::Error::CannotRemoveLastSigningKey)),
+      "expected key_delete_checked to return `CannotRemoveLastSigningKey` for the last signing key, got {result:?}"
+    );
+
+    // The unchecked variant remains permissive.
+    let deleted: bool = storage
+      .key_delete(&did, &other_signing_location)
+      .await
+      .context("key_delete returned an error")?;
+    ensure!(deleted, "expected key_delete to delete the last signing key");
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_public_many_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_KEYS: usize = 5;
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let mut locations: Vec<KeyLocation> = Vec::with_capacity(NUM_KEYS + 1);
+    for i in 0..NUM_KEYS {
+      let key_type: KeyType = if i % 2 == 0 { KeyType::Ed25519 } else { KeyType::X25519 };
+      let location: KeyLocation = storage
+        .key_generate(&did, key_type, &random_string())
         .await
-        .context("did_create returned an error")?;
+        .context("key_generate returned an error")?;
+      locations.push(location);
+    }
+    // The initial key goes last, so the result cannot match by accident of the creation order.
+    locations.push(location);
 
-      let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
-      ensure!(exists, "expected did `{did}` to exist");
+    let public_keys: Vec<PublicKey> = storage
+      .key_public_many(&did, &locations)
+      .await
+      .context("key_public_many returned an error")?;
 
-      let list_len: usize = storage.did_list().await.context("did_list returned an error")?.len();
-      let expected_len: usize = i + 1;
+    ensure_eq!(
+      public_keys.len(),
+      locations.len(),
+      "expected key_public_many to return {} keys, got {}",
+      locations.len(),
+      public_keys.len()
+    );
+
+    for (location, public_key) in locations.iter().zip(public_keys.iter()) {
+      let expected: PublicKey = storage
+        .key_public(&did, location)
+        .await
+        .context("key_public returned an error")?;
 
       ensure_eq!(
-        list_len,
-        expected_len,
-        "expected did_list to return a list of len {expected_len}, got {list_len} elements instead"
+        public_key.as_ref(),
+        expected.as_ref(),
+        "expected key_public_many to return the key of `{location}`"
       );
     }
 
+    let missing: KeyLocation = KeyLocation::new(KeyType::Ed25519, random_string(), &[0; 32]);
+    let mut with_missing: Vec<KeyLocation> = locations.clone();
+    with_missing.insert(1, missing.clone());
+    let result: Result<Vec<PublicKey>, _> = storage.key_public_many(&did, &with_missing).await;
+
+    ensure!(
+      matches!(&result, Err(crate::Error::KeyNotFoundAt(location)) if *location == missing),
+      "expected key_public_many to return `KeyNotFoundAt` naming `{missing}`, got {result:?}"
+    );
+
     Ok(())
   }
 
   #[named]
-  pub async fn key_insert_test(storage: impl Storage) -> anyhow::Result<()> {
+  pub async fn key_list_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_KEYS: usize = 10;
     let fragment: String = random_string();
     let network: NetworkName = Network::Mainnet.name();
 
-    let (did, _): (CoreDID, _) = storage
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let mut expected_locations: Vec<KeyLocation> = Vec::with_capacity(NUM_KEYS + 1);
+    expected_locations.push(location);
+
+    for i in 0..NUM_KEYS {
+      let key_type: KeyType = if i % 2 == 0 { KeyType::Ed25519 } else { KeyType::X25519 };
+      let location: KeyLocation = storage
+        .key_generate(&did, key_type, &random_string())
+        .await
+        .context("key_generate returned an error")?;
+      expected_locations.push(location);
+    }
+
+    let locations: Vec<KeyLocation> = storage.key_list(&did).await.context("key_list returned an error")?;
+
+    ensure_eq!(
+      locations.len(),
+      expected_locations.len(),
+      "expected key_list to return {} locations, got {}",
+      expected_locations.len(),
+      locations.len()
+    );
+
+    for expected_location in expected_locations.iter() {
+      let count: usize = locations
+        .iter()
+        .filter(|location| *location == expected_location)
+        .count();
+
+      ensure_eq!(
+        count,
+        1,
+        "expected location `{expected_location}` to be returned exactly once, was returned {count} times"
+      );
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_list_by_type_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_KEYS: usize = 10;
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let mut expected_ed25519: Vec<KeyLocation> = vec![location];
+    let mut expected_x25519: Vec<KeyLocation> = Vec::with_capacity(NUM_KEYS);
+
+    for i in 0..NUM_KEYS {
+      // Generate more X25519 than Ed25519 keys, so that both filters return a different number of keys.
+      let key_type: KeyType = if i % 3 == 0 { KeyType::Ed25519 } else { KeyType::X25519 };
+      let location: KeyLocation = storage
+        .key_generate(&did, key_type, &random_string())
+        .await
+        .context("key_generate returned an error")?;
+
+      match key_type {
+        KeyType::Ed25519 => expected_ed25519.push(location),
+        KeyType::X25519 => expected_x25519.push(location),
+      }
+    }
+
+    for (key_type, expected_locations) in [(KeyType::Ed25519, expected_ed25519), (KeyType::X25519, expected_x25519)] {
+      let locations: Vec<KeyLocation> = storage
+        .key_list_by_type(&did, key_type)
+        .await
+        .context("key_list_by_type returned an error")?;
+
+      ensure_eq!(
+        locations.len(),
+        expected_locations.len(),
+        "expected key_list_by_type to return {} locations of type {key_type:?}, got {}",
+        expected_locations.len(),
+        locations.len()
+      );
+
+      for location in locations.iter() {
+        ensure_eq!(
+          location.key_type,
+          key_type,
+          "expected key_list_by_type to only return locations of type {key_type:?}, got `{location}`"
+        );
+        ensure!(
+          expected_locations.contains(location),
+          "expected location `{location}` to be one of the generated {key_type:?} keys"
+        );
+      }
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_delete_all_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_KEYS: usize = 5;
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    for _ in 0..NUM_KEYS {
+      storage
+        .key_generate(&did, KeyType::Ed25519, &random_string())
+        .await
+        .context("key_generate returned an error")?;
+    }
+
+    let expected_blob: Vec<u8> = random_string().into_bytes();
+
+    storage
+      .blob_set(&did, expected_blob.clone())
+      .await
+      .context("blob_set returned an error")?;
+
+    let removed: usize = storage
+      .key_delete_all(&did)
+      .await
+      .context("key_delete_all returned an error")?;
+
+    // The key generated by did_create is removed as well.
+    ensure_eq!(
+      removed,
+      NUM_KEYS + 1,
+      "expected key_delete_all to remove {} keys, removed {removed}",
+      NUM_KEYS + 1
+    );
+
+    let locations: Vec<KeyLocation> = storage.key_list(&did).await.context("key_list returned an error")?;
+
+    ensure!(
+      locations.is_empty(),
+      "expected key_list to return an empty list after key_delete_all"
+    );
+
+    let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
+
+    ensure!(exists, "expected did `{did}` to still exist after key_delete_all");
+
+    let blob: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure_eq!(
+      blob,
+      Some(expected_blob),
+      "expected the blob of `{did}` to survive key_delete_all"
+    );
+
+    let removed: usize = storage
+      .key_delete_all(&did)
+      .await
+      .context("key_delete_all returned an error")?;
+
+    ensure_eq!(
+      removed,
+      0,
+      "expected key_delete_all to remove 0 keys, removed {removed}"
+    );
+
+    let unknown_did: CoreDID = IotaDID::new_with_network(KeyPair::new(KeyType::Ed25519)?.public().as_ref(), network)
+      .unwrap()
+      .into();
+    let result: Result<usize, _> = storage.key_delete_all(&unknown_did).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::KeyVaultNotFound)),
+      "expected key_delete_all to return `KeyVaultNotFound` for a DID that was never created"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_metadata_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    for key_type in [KeyType::Ed25519, KeyType::X25519] {
+      let location: KeyLocation = storage
+        .key_generate(&did, key_type, &random_string())
+        .await
+        .context("key_generate returned an error")?;
+
+      let metadata: KeyMetadata = storage
+        .key_metadata(&did, &location)
+        .await
+        .context("key_metadata returned an error")?;
+
+      ensure_eq!(
+        metadata.key_type,
+        key_type,
+        "expected key type `{key_type:?}`, got `{:?}`",
+        metadata.key_type
+      );
+
+      let public_key: PublicKey = storage
+        .key_public(&did, &location)
+        .await
+        .context("key_public returned an error")?;
+
+      ensure_eq!(
+        metadata.public_key.as_ref(),
+        public_key.as_ref(),
+        "expected key_metadata to return the same public key as key_public"
+      );
+    }
+
+    let missing_location: KeyLocation = KeyLocation::new(KeyType::Ed25519, random_string(), &[0; 32]);
+    let result: Result<KeyMetadata, crate::Error> = storage.key_metadata(&did, &missing_location).await;
+
+    ensure!(
+      result.is_err(),
+      "expected key_metadata to fail for a non-existent location, got {result:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_rotate_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let old_location: KeyLocation = storage
+      .key_generate(&did, KeyType::Ed25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    // Rotating a key that does not exist must fail without modifying the storage.
+    let missing_location: KeyLocation = KeyLocation::new(KeyType::Ed25519, random_string(), &[0; 32]);
+    let result: Result<KeyLocation, crate::Error> = storage.key_rotate(&did, &missing_location, &fragment).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::KeyNotFound)),
+      "expected key_rotate to return `KeyNotFound` for a non-existent location, got {result:?}"
+    );
+
+    let exists: bool = storage
+      .key_exists(&did, &old_location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      exists,
+      "expected key at location `{old_location}` to still exist after failed rotation"
+    );
+
+    let new_location: KeyLocation = storage
+      .key_rotate(&did, &old_location, &random_string())
+      .await
+      .context("key_rotate returned an error")?;
+
+    ensure_eq!(
+      new_location.key_type,
+      old_location.key_type,
+      "expected rotated key to have key type `{}`, was `{}`",
+      old_location.key_type,
+      new_location.key_type
+    );
+
+    let exists: bool = storage
+      .key_exists(&did, &old_location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      !exists,
+      "expected key at location `{old_location}` to no longer exist after rotation"
+    );
+
+    let message: &[u8] = b"rotated key message";
+
+    let signature: Signature = storage
+      .key_sign(&did, &new_location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    let public_key: PublicKey = storage
+      .key_public(&did, &new_location)
+      .await
+      .context("key_public returned an error")?;
+
+    ensure!(
+      Ed25519::verify(message, signature.as_bytes(), public_key.as_ref()).is_ok(),
+      "expected signature of the rotated key at location `{new_location}` to be valid"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_move_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let old_location: KeyLocation = storage
+      .key_generate(&did, KeyType::Ed25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    // Moving a key that does not exist must fail.
+    let missing_location: KeyLocation = KeyLocation::new(KeyType::Ed25519, random_string(), &[0; 32]);
+    let result: Result<KeyLocation, crate::Error> = storage.key_move(&did, &missing_location, &random_string()).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::KeyNotFound)),
+      "expected key_move to return `KeyNotFound` for a non-existent location, got {result:?}"
+    );
+
+    let message: &[u8] = b"moved key message";
+
+    let old_signature: Signature = storage
+      .key_sign(&did, &old_location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    let old_public_key: PublicKey = storage
+      .key_public(&did, &old_location)
+      .await
+      .context("key_public returned an error")?;
+
+    let new_fragment: String = random_string();
+    let new_location: KeyLocation = storage
+      .key_move(&did, &old_location, &new_fragment)
+      .await
+      .context("key_move returned an error")?;
+
+    let expected_location: KeyLocation = old_location.with_fragment(new_fragment);
+    ensure_eq!(
+      new_location,
+      expected_location,
+      "expected moved key to be at location `{expected_location}`, was `{new_location}`"
+    );
+
+    let exists: bool = storage
+      .key_exists(&did, &old_location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      !exists,
+      "expected key at location `{old_location}` to no longer exist after the move"
+    );
+
+    let new_public_key: PublicKey = storage
+      .key_public(&did, &new_location)
+      .await
+      .context("key_public returned an error")?;
+
+    ensure_eq!(
+      new_public_key.as_ref(),
+      old_public_key.as_ref(),
+      "expected the public key to be unchanged after the move"
+    );
+
+    // Ed25519 signatures are deterministic, so the same key produces the same signature.
+    let new_signature: Signature = storage
+      .key_sign(&did, &new_location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    ensure_eq!(
+      new_signature.as_bytes(),
+      old_signature.as_bytes(),
+      "expected the key at location `{new_location}` to sign identically to the key before the move"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_expiry_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let now: Timestamp = Timestamp::now_utc();
+    let past: Timestamp = now.checked_sub(Duration::minutes(1)).unwrap();
+    let future: Timestamp = now.checked_add(Duration::hours(1)).unwrap();
+
+    let expired_location: KeyLocation = storage
+      .key_generate_with_expiry(&did, KeyType::Ed25519, &random_string(), past)
+      .await
+      .context("key_generate_with_expiry returned an error")?;
+
+    let valid_location: KeyLocation = storage
+      .key_generate_with_expiry(&did, KeyType::Ed25519, &random_string(), future)
+      .await
+      .context("key_generate_with_expiry returned an error")?;
+
+    let message: &[u8] = b"expiring key message";
+
+    let result: Result<Signature, crate::Error> = storage.key_sign(&did, &expired_location, message.to_vec()).await;
+    ensure!(
+      matches!(result, Err(crate::Error::KeyExpired)),
+      "expected key_sign to return `KeyExpired` for an expired key, got {result:?}"
+    );
+
+    let result: Result<PublicKey, crate::Error> = storage.key_public(&did, &expired_location).await;
+    ensure!(
+      matches!(result, Err(crate::Error::KeyExpired)),
+      "expected key_public to return `KeyExpired` for an expired key, got {result:?}"
+    );
+
+    let signature: Signature = storage
+      .key_sign(&did, &valid_location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    let public_key: PublicKey = storage
+      .key_public(&did, &valid_location)
+      .await
+      .context("key_public returned an error")?;
+
+    ensure!(
+      Ed25519::verify(message, signature.as_bytes(), public_key.as_ref()).is_ok(),
+      "expected signature of the unexpired key at location `{valid_location}` to be valid"
+    );
+
+    let pruned: usize = storage
+      .key_prune_expired(&did)
+      .await
+      .context("key_prune_expired returned an error")?;
+
+    ensure_eq!(
+      pruned,
+      1,
+      "expected key_prune_expired to delete 1 key, deleted {pruned}"
+    );
+
+    let exists: bool = storage
+      .key_exists(&did, &expired_location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      !exists,
+      "expected expired key at location `{expired_location}` to no longer exist after pruning"
+    );
+
+    let exists: bool = storage
+      .key_exists(&did, &valid_location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      exists,
+      "expected unexpired key at location `{valid_location}` to still exist after pruning"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_list_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_IDENTITIES: usize = 20;
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let list: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+
+    ensure!(
+      list.is_empty(),
+      "expected list to be empty, but found {} element(s)",
+      list.len()
+    );
+
+    for i in 0..NUM_IDENTITIES {
+      let (did, _): (CoreDID, _) = storage
+        .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+        .await
+        .context("did_create returned an error")?;
+
+      let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
+      ensure!(exists, "expected did `{did}` to exist");
+
+      let list_len: usize = storage.did_list().await.context("did_list returned an error")?.len();
+      let expected_len: usize = i + 1;
+
+      ensure_eq!(
+        list_len,
+        expected_len,
+        "expected did_list to return a list of len {expected_len}, got {list_len} elements instead"
+      );
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_list_paged_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_IDENTITIES: usize = 50;
+    const PAGE_SIZE: usize = 10;
+    let network: NetworkName = Network::Mainnet.name();
+
+    for _ in 0..NUM_IDENTITIES {
+      storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+    }
+
+    let mut paged: Vec<CoreDID> = Vec::with_capacity(NUM_IDENTITIES);
+    loop {
+      let page: Vec<CoreDID> = storage
+        .did_list_paged(paged.len(), PAGE_SIZE)
+        .await
+        .context("did_list_paged returned an error")?;
+
+      ensure!(
+        page.len() <= PAGE_SIZE,
+        "expected a page of at most {PAGE_SIZE} DIDs, got {}",
+        page.len()
+      );
+
+      if page.is_empty() {
+        break;
+      }
+
+      paged.extend(page);
+    }
+
+    ensure!(
+      paged.windows(2).all(|pair| pair[0].as_str() < pair[1].as_str()),
+      "expected the pages to be sorted without overlapping"
+    );
+
+    let paged: HashSet<CoreDID> = paged.into_iter().collect();
+    let list: HashSet<CoreDID> = storage
+      .did_list()
+      .await
+      .context("did_list returned an error")?
+      .into_iter()
+      .collect();
+
+    ensure_eq!(
+      paged.len(),
+      NUM_IDENTITIES,
+      "expected {NUM_IDENTITIES} DIDs across all pages, got {}",
+      paged.len()
+    );
+    ensure_eq!(
+      paged,
+      list,
+      "expected the pages to contain the DIDs returned by did_list"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_exists_many_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let mut dids: Vec<CoreDID> = Vec::new();
+    let mut expected: Vec<bool> = Vec::new();
+    for index in 0..6 {
+      if index % 2 == 0 {
+        let (did, _): (CoreDID, KeyLocation) = storage
+          .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+          .await
+          .context("did_create returned an error")?;
+        dids.push(did);
+        expected.push(true);
+      } else {
+        let did: CoreDID =
+          IotaDID::new_with_network(KeyPair::new(KeyType::Ed25519)?.public().as_ref(), network.clone())
+            .unwrap()
+            .into();
+        dids.push(did);
+        expected.push(false);
+      }
+    }
+
+    let exists: Vec<bool> = storage
+      .did_exists_many(&dids)
+      .await
+      .context("did_exists_many returned an error")?;
+
+    ensure_eq!(
+      exists,
+      expected,
+      "expected did_exists_many to return {expected:?}, got {exists:?}"
+    );
+
+    let exists: Vec<bool> = storage
+      .did_exists_many(&[])
+      .await
+      .context("did_exists_many returned an error")?;
+
+    ensure!(
+      exists.is_empty(),
+      "expected did_exists_many to return an empty list for no DIDs"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_timestamps_test(storage: impl Storage) -> anyhow::Result<()> {
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, Network::Mainnet.name(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let (created, updated): (Timestamp, Timestamp) = storage
+      .did_timestamps(&did)
+      .await
+      .context("did_timestamps returned an error")?;
+    ensure_eq!(
+      created,
+      updated,
+      "expected a new DID to be updated when it was created at {created}, was updated at {updated}"
+    );
+
+    // Timestamps have a resolution of one second.
+    std::thread::sleep(std::time::Duration::from_millis(1100));
+
+    storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    let (created_after, updated_after): (Timestamp, Timestamp) = storage
+      .did_timestamps(&did)
+      .await
+      .context("did_timestamps returned an error")?;
+    ensure_eq!(
+      created_after,
+      created,
+      "expected the creation time to remain {created}, was {created_after}"
+    );
+    ensure!(
+      updated_after > created,
+      "expected the update time {updated_after} to be after the creation time {created}"
+    );
+
+    let unknown_did: CoreDID = IotaDID::new_with_network(
+      KeyPair::new(KeyType::Ed25519)?.public().as_ref(),
+      Network::Mainnet.name(),
+    )
+    .unwrap()
+    .into();
+    let result: Result<(Timestamp, Timestamp), crate::Error> = storage.did_timestamps(&unknown_did).await;
+    ensure!(
+      matches!(result, Err(crate::Error::KeyVaultNotFound)),
+      "expected did_timestamps to return `KeyVaultNotFound` for a DID that was never created, got {result:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_metadata_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let mut dids: Vec<CoreDID> = Vec::with_capacity(3);
+    for _ in 0..3 {
+      let (did, _): (CoreDID, KeyLocation) = storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+      dids.push(did);
+    }
+
+    let metadata: BTreeMap<String, String> = BTreeMap::from([
+      ("tenant".to_owned(), "tenant-1".to_owned()),
+      ("label".to_owned(), "issuer".to_owned()),
+    ]);
+    storage
+      .did_set_metadata(&dids[0], metadata.clone())
+      .await
+      .context("did_set_metadata returned an error")?;
+    storage
+      .did_set_metadata(&dids[1], BTreeMap::from([("tenant".to_owned(), "tenant-1".to_owned())]))
+      .await
+      .context("did_set_metadata returned an error")?;
+
+    let untagged: BTreeMap<String, String> = storage
+      .did_get_metadata(&dids[2])
+      .await
+      .context("did_get_metadata returned an error")?;
+    ensure!(
+      untagged.is_empty(),
+      "expected no metadata for an untagged did, got {untagged:?}"
+    );
+
+    // Blobs are stored independently of the metadata.
+    storage
+      .blob_set(&dids[0], b"blob".to_vec())
+      .await
+      .context("blob_set returned an error")?;
+
+    let stored: BTreeMap<String, String> = storage
+      .did_get_metadata(&dids[0])
+      .await
+      .context("did_get_metadata returned an error")?;
+    ensure_eq!(stored, metadata, "expected metadata to survive blob_set");
+
+    let found: Vec<CoreDID> = storage
+      .did_find_by_metadata("tenant", "tenant-1")
+      .await
+      .context("did_find_by_metadata returned an error")?;
+    ensure!(
+      found.len() == 2 && found.contains(&dids[0]) && found.contains(&dids[1]),
+      "expected to find `{}` and `{}`, got {found:?}",
+      dids[0],
+      dids[1]
+    );
+
+    let found: Vec<CoreDID> = storage
+      .did_find_by_metadata("label", "tenant-1")
+      .await
+      .context("did_find_by_metadata returned an error")?;
+    ensure!(
+      found.is_empty(),
+      "expected to find no did for a value of another key, got {found:?}"
+    );
+
+    // Setting the metadata replaces it entirely.
+    storage
+      .did_set_metadata(&dids[0], BTreeMap::new())
+      .await
+      .context("did_set_metadata returned an error")?;
+    let found: Vec<CoreDID> = storage
+      .did_find_by_metadata("tenant", "tenant-1")
+      .await
+      .context("did_find_by_metadata returned an error")?;
+    ensure_eq!(found, vec![dids[1].clone()], "expected metadata to be replaced");
+
+    storage
+      .did_purge(&dids[1])
+      .await
+      .context("did_purge returned an error")?;
+
+    let purged: BTreeMap<String, String> = storage
+      .did_get_metadata(&dids[1])
+      .await
+      .context("did_get_metadata returned an error")?;
+    ensure!(
+      purged.is_empty(),
+      "expected did_purge to remove the metadata, got {purged:?}"
+    );
+
+    let found: Vec<CoreDID> = storage
+      .did_find_by_metadata("tenant", "tenant-1")
+      .await
+      .context("did_find_by_metadata returned an error")?;
+    ensure!(found.is_empty(), "expected to find no did after purging, got {found:?}");
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_insert_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, _) = storage
       .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
       .await
       .context("did_create returned an error")?;
 
     let key_types: [KeyType; 2] = [KeyType::Ed25519, KeyType::X25519];
 
-    let mut locations: Vec<KeyLocation> = Vec::with_capacity(key_types.len());
-    let mut public_keys: Vec<PublicKey> = Vec::with_capacity(key_types.len());
+    let mut locations: Vec<KeyLocation> = Vec::with_capacity(key_types.len());
+    let mut public_keys: Vec<PublicKey> = Vec::with_capacity(key_types.len());
+
+    for key_type in key_types {
+      let key_fragment: String = random_string();
+      let keypair: KeyPair = KeyPair::new(key_type).unwrap();
+      let location: KeyLocation = KeyLocation::new(key_type, key_fragment, keypair.public().as_ref());
+
+      storage
+        .key_insert(&did, &location, keypair.private().to_owned())
+        .await
+        .context("key_insert returned an error")?;
+
+      public_keys.push(keypair.public().to_owned());
+      locations.push(location);
+    }
+
+    for (i, location) in locations.into_iter().enumerate() {
+      let exists: bool = storage
+        .key_exists(&did, &location)
+        .await
+        .context("key_exists returned an error")?;
+
+      ensure!(exists, "expected key at location `{location}` to exist");
+
+      let public_key: PublicKey = storage
+        .key_public(&did, &location)
+        .await
+        .context("key_public returned an error")?;
+
+      let expected_public_key: &PublicKey = &public_keys[i];
+
+      ensure_eq!(
+        public_key.as_ref(),
+        expected_public_key.as_ref(),
+        "expected public key at location `{location}` to be {expected_public_key:?}, was {public_key:?}"
+      );
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_insert_indexed_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    // Both keys share the fragment and are only distinguished by their index.
+    let fragment: String = random_string();
+    let mut keys: Vec<(KeyLocation, PublicKey)> = Vec::with_capacity(2);
+
+    for index in 0..2 {
+      let keypair: KeyPair = KeyPair::new(KeyType::Ed25519)?;
+      let location: KeyLocation = KeyLocation::with_index(KeyType::Ed25519, fragment.clone(), index);
+
+      storage
+        .key_insert(&did, &location, keypair.private().to_owned())
+        .await
+        .context("key_insert returned an error")?;
+
+      keys.push((location, keypair.public().to_owned()));
+    }
+
+    for (location, expected_public_key) in keys.iter() {
+      let public_key: PublicKey = storage
+        .key_public(&did, location)
+        .await
+        .context("key_public returned an error")?;
+
+      ensure_eq!(
+        public_key.as_ref(),
+        expected_public_key.as_ref(),
+        "expected public key at location `{location}` to be {expected_public_key:?}, was {public_key:?}"
+      );
+    }
+
+    let (location, _): &(KeyLocation, PublicKey) = &keys[0];
+    storage
+      .key_delete(&did, location)
+      .await
+      .context("key_delete returned an error")?;
+
+    let (location, _): &(KeyLocation, PublicKey) = &keys[1];
+    let exists: bool = storage
+      .key_exists(&did, location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      exists,
+      "expected key at location `{location}` to exist after deleting the key at another index"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_insert_type_mismatch_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    // Both key types use 32-byte private keys, so only keys of the wrong length can be detected.
+    let invalid_keys: [(KeyType, usize); 4] = [
+      (KeyType::Ed25519, Ed25519::PRIVATE_KEY_LENGTH - 1),
+      (KeyType::Ed25519, Ed25519::PRIVATE_KEY_LENGTH * 2),
+      (KeyType::X25519, X25519::PRIVATE_KEY_LENGTH - 1),
+      (KeyType::X25519, X25519::PRIVATE_KEY_LENGTH + 1),
+    ];
+
+    for (key_type, length) in invalid_keys {
+      let location: KeyLocation = KeyLocation::new(key_type, random_string(), &[0; 32]);
+      let private_key: PrivateKey = PrivateKey::from(vec![0x42; length]);
+
+      let result: Result<(), crate::Error> = storage.key_insert(&did, &location, private_key).await;
+
+      ensure!(
+        matches!(result, Err(crate::Error::InvalidPrivateKey(_))),
+        "expected key_insert to return `InvalidPrivateKey` for a {key_type:?} key of length {length}, got {result:?}"
+      );
+
+      let exists: bool = storage
+        .key_exists(&did, &location)
+        .await
+        .context("key_exists returned an error")?;
+
+      ensure!(!exists, "expected no key to be inserted at location `{location}`");
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_sign_ed25519_test(storage: impl Storage) -> anyhow::Result<()> {
+    // The following test vector is taken from Test 2 of RFC 8032
+    // https://datatracker.ietf.org/doc/html/rfc8032#section-7
+    const PRIVATE_KEY: [u8; 32] = [
+      76, 205, 8, 155, 40, 255, 150, 218, 157, 182, 195, 70, 236, 17, 78, 15, 91, 138, 49, 159, 53, 171, 166, 36, 218,
+      140, 246, 237, 79, 184, 166, 251,
+    ];
+    const MESSAGE: [u8; 1] = [114];
+    const SIGNATURE: [u8; 64] = [
+      146, 160, 9, 169, 240, 212, 202, 184, 114, 14, 130, 11, 95, 100, 37, 64, 162, 178, 123, 84, 22, 80, 63, 143, 179,
+      118, 34, 35, 235, 219, 105, 218, 8, 90, 193, 228, 62, 21, 153, 110, 69, 143, 54, 19, 208, 241, 29, 140, 56, 123,
+      46, 174, 180, 48, 42, 238, 176, 13, 41, 22, 18, 187, 12, 0,
+    ];
+
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(
+        DIDType::IotaDID,
+        network.clone(),
+        &fragment,
+        Some(PrivateKey::from(PRIVATE_KEY.to_vec())),
+      )
+      .await
+      .context("did_create returned an error")?;
+
+    let signature: Signature = storage
+      .key_sign(&did, &location, MESSAGE.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    ensure_eq!(
+      signature.as_bytes(),
+      &SIGNATURE,
+      "expected signature to be `{SIGNATURE:?}`, was `{:?}`",
+      signature.as_bytes()
+    );
+
+    ensure_eq!(
+      signature.algorithm(),
+      Some(JwsAlgorithm::EdDSA),
+      "expected signature algorithm to be EdDSA, was {:?}",
+      signature.algorithm()
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn signing_determinism_test(storage: impl Storage) -> anyhow::Result<()> {
+    const ITERATIONS: usize = 100;
+    const MESSAGE: &[u8] = b"signing determinism message";
+
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let expected: Signature = storage
+      .key_sign(&did, &location, MESSAGE.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    // Ed25519 signatures are deterministic, so any deviation points to a nondeterministic signer.
+    for iteration in 1..ITERATIONS {
+      let signature: Signature = storage
+        .key_sign(&did, &location, MESSAGE.to_vec())
+        .await
+        .context("key_sign returned an error")?;
+
+      ensure_eq!(
+        signature.as_bytes(),
+        expected.as_bytes(),
+        "expected signature {iteration} to be `{:?}`, was `{:?}`",
+        expected.as_bytes(),
+        signature.as_bytes()
+      );
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_sign_jws_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let signing_input: &[u8] = b"eyJhbGciOiJFZERTQSJ9.RXhhbXBsZSBvZiBFZDI1NTE5IHNpZ25pbmc";
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let jws_signature: String = storage
+      .key_sign_jws(&did, &location, signing_input, JwsAlgorithm::EdDSA)
+      .await
+      .context("key_sign_jws returned an error")?;
+
+    ensure!(
+      jws_signature
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_'),
+      "expected the signature to be base64url-encoded without padding, got `{jws_signature}`"
+    );
+
+    let decoded: Vec<u8> = BaseEncoding::decode(&jws_signature, Base::Base64Url)?;
+    // Ed25519 signatures are deterministic, so the raw signature must match.
+    let signature: Signature = storage
+      .key_sign(&did, &location, signing_input.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    ensure_eq!(
+      decoded.as_slice(),
+      signature.as_bytes(),
+      "expected the decoded JWS signature to match the raw signature"
+    );
+
+    let x25519_location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    let result: Result<String, _> = storage
+      .key_sign_jws(&did, &x25519_location, signing_input, JwsAlgorithm::EdDSA)
+      .await;
+
+    ensure!(
+      result.is_err(),
+      "expected key_sign_jws to fail for EdDSA with an X25519 key"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_verify_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let message: &[u8] = b"This msg will be signed and verified";
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let signature: Signature = storage
+      .key_sign(&did, &location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    let verified: bool = storage
+      .key_verify(&did, &location, message, &signature)
+      .await
+      .context("key_verify returned an error")?;
+
+    ensure!(verified, "expected signature to be valid");
+
+    let mut tampered: Vec<u8> = signature.as_bytes().to_vec();
+    tampered[0] ^= 0xff;
+    let tampered: Signature =
+      Signature::new_checked(KeyType::Ed25519, tampered).context("Signature::new_checked returned an error")?;
+
+    let verified: bool = storage
+      .key_verify(&did, &location, message, &tampered)
+      .await
+      .context("key_verify returned an error")?;
+
+    ensure!(!verified, "expected tampered signature to be invalid");
+
+    let x25519_location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    let result: Result<bool, _> = storage.key_verify(&did, &x25519_location, message, &signature).await;
+
+    ensure!(result.is_err(), "expected key_verify to fail for an X25519 key");
+
+    Ok(())
+  }
+
+  /// Runs many concurrent tasks performing interleaved `did_create`, `key_generate`, `key_sign`
+  /// and `did_purge` calls against the same `storage`.
+  ///
+  /// Must be run on a multi-threaded runtime to actually exercise contention.
+  #[cfg(feature = "send-sync-storage")]
+  #[named]
+  pub async fn concurrency_test<S>(storage: S) -> anyhow::Result<()>
+  where
+    S: Storage + 'static,
+  {
+    const NUM_TASKS: usize = 32;
+    const NUM_ROUNDS: usize = 4;
+
+    let storage: Arc<S> = Arc::new(storage);
+    let mut handles: Vec<JoinHandle<anyhow::Result<Vec<(CoreDID, bool)>>>> = Vec::with_capacity(NUM_TASKS);
+
+    for task in 0..NUM_TASKS {
+      let storage: Arc<S> = Arc::clone(&storage);
+
+      handles.push(tokio::spawn(async move {
+        let network: NetworkName = Network::Mainnet.name();
+        let mut dids: Vec<(CoreDID, bool)> = Vec::with_capacity(NUM_ROUNDS);
+
+        for round in 0..NUM_ROUNDS {
+          let (did, location): (CoreDID, KeyLocation) = storage
+            .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+            .await
+            .context("did_create returned an error")?;
+
+          let generated_location: KeyLocation = storage
+            .key_generate(&did, KeyType::Ed25519, &random_string())
+            .await
+            .context("key_generate returned an error")?;
+
+          for location in [&location, &generated_location] {
+            let message: Vec<u8> = random_string().into_bytes();
+            let signature: Signature = storage
+              .key_sign(&did, location, message.clone())
+              .await
+              .context("key_sign returned an error")?;
+            let public_key: PublicKey = storage
+              .key_public(&did, location)
+              .await
+              .context("key_public returned an error")?;
+
+            ensure!(
+              Ed25519::verify(&message, signature.as_bytes(), public_key.as_ref()).is_ok(),
+              "expected signature of the key at location `{location}` to be valid"
+            );
+          }
+
+          // Purge every other DID, so removals interleave with the operations of other tasks.
+          let purge: bool = (task + round) % 2 == 0;
+          if purge {
+            let purged: bool = storage.did_purge(&did).await.context("did_purge returned an error")?;
+
+            ensure!(purged, "expected did `{did}` to be purged");
+          }
+
+          dids.push((did, purge));
+        }
+
+        Ok(dids)
+      }));
+    }
+
+    let mut expected_dids: HashSet<CoreDID> = HashSet::new();
+    let mut purged_dids: Vec<CoreDID> = Vec::new();
+
+    for handle in handles {
+      // A panicking task surfaces as an error when joining it.
+      let dids: Vec<(CoreDID, bool)> = handle.await.context("task panicked")??;
+
+      for (did, purged) in dids {
+        if purged {
+          purged_dids.push(did);
+        } else {
+          expected_dids.insert(did);
+        }
+      }
+    }
+
+    let list: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+
+    ensure_eq!(
+      list.len(),
+      expected_dids.len(),
+      "expected did_list to return {} element(s), got {}",
+      expected_dids.len(),
+      list.len()
+    );
+
+    for did in list.iter() {
+      ensure!(
+        expected_dids.contains(did),
+        "expected did_list to only return dids that were not purged, found `{did}`"
+      );
+    }
+
+    for did in purged_dids.iter() {
+      let exists: bool = storage.did_exists(did).await.context("did_exists returned an error")?;
+
+      ensure!(!exists, "expected purged did `{did}` to not exist");
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn named_blobs_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let names: Vec<String> = storage
+      .blob_list_names(&did)
+      .await
+      .context("blob_list_names returned an error")?;
+
+    ensure!(names.is_empty(), "expected no blob names for a new DID, got {names:?}");
+
+    let blobs: [(&str, &[u8]); 2] = [("document", b"document blob"), ("profile", b"profile blob")];
+
+    for (name, blob) in blobs {
+      storage
+        .blob_set_named(&did, name, blob.to_vec())
+        .await
+        .context("blob_set_named returned an error")?;
+    }
+
+    for (name, expected_blob) in blobs {
+      let blob: Option<Vec<u8>> = storage
+        .blob_get_named(&did, name)
+        .await
+        .context("blob_get_named returned an error")?;
+
+      ensure_eq!(
+        blob.as_deref(),
+        Some(expected_blob),
+        "expected blob `{name}` to be `{expected_blob:?}`, got `{blob:?}`"
+      );
+    }
+
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure!(
+      value.is_none(),
+      "expected blob_get to return `None` when only named blobs were set"
+    );
+
+    let mut names: Vec<String> = storage
+      .blob_list_names(&did)
+      .await
+      .context("blob_list_names returned an error")?;
+    names.sort();
+
+    ensure_eq!(
+      names,
+      vec!["document".to_owned(), "profile".to_owned()],
+      "expected blob names `document` and `profile`, got {names:?}"
+    );
+
+    let purged: bool = storage.did_purge(&did).await.context("did_purge returned an error")?;
+
+    ensure!(purged, "expected did to be purged");
+
+    for (name, _) in blobs {
+      let blob: Option<Vec<u8>> = storage
+        .blob_get_named(&did, name)
+        .await
+        .context("blob_get_named returned an error")?;
+
+      ensure!(blob.is_none(), "expected blob `{name}` to be removed by did_purge");
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_value_store_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure!(value.is_none(), "expected blob_get to return `None` for a new DID");
+
+    let public_key: PublicKey = storage
+      .key_public(&did, &location)
+      .await
+      .context("key_public returned an error")?;
+
+    let method: IotaVerificationMethod = IotaVerificationMethod::new(
+      did.clone().try_into().unwrap(),
+      KeyType::Ed25519,
+      &public_key,
+      &fragment,
+    )
+    .unwrap();
+
+    let expected_document: IotaDocument = IotaDocument::from_verification_method(method).unwrap();
+    storage
+      .blob_set(&did, expected_document.to_json_vec().unwrap())
+      .await
+      .context("blob_set returned an error")?;
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+    let document: IotaDocument = IotaDocument::from_json_slice(&value.unwrap()).unwrap();
+    ensure_eq!(
+      expected_document,
+      document,
+      "expected `{expected_document}`, got `{document}`"
+    );
+
+    let mut expected_chain_state: ChainState = ChainState::new();
+    expected_chain_state.set_last_integration_message_id(MessageId::new([0xff; 32]));
+    storage
+      .blob_set(&did, expected_chain_state.to_json_vec().unwrap())
+      .await
+      .context("blob_set returned an error")?;
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+    let chain_state: ChainState = ChainState::from_json_slice(&value.unwrap()).unwrap();
+    ensure_eq!(
+      expected_chain_state,
+      chain_state,
+      "expected `{expected_chain_state:?}`, got `{chain_state:?}`"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn blob_delete_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    storage
+      .blob_set(&did, random_string().into_bytes())
+      .await
+      .context("blob_set returned an error")?;
+
+    let deleted: bool = storage
+      .blob_delete(&did)
+      .await
+      .context("blob_delete returned an error")?;
+
+    ensure!(deleted, "expected blob_delete to return `true` if a blob was stored");
+
+    let blob: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure!(blob.is_none(), "expected blob_get to return `None` after blob_delete");
+
+    // The operation is idempotent.
+    let deleted: bool = storage
+      .blob_delete(&did)
+      .await
+      .context("blob_delete returned an error")?;
+
+    ensure!(!deleted, "expected blob_delete to return `false` if no blob was stored");
+
+    let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
+
+    ensure!(exists, "expected did `{did}` to still exist after blob_delete");
+
+    let exists: bool = storage
+      .key_exists(&did, &location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      exists,
+      "expected key at location `{location}` to still exist after blob_delete"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn blob_compare_and_swap_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let swapped: bool = storage
+      .blob_compare_and_swap(&did, None, b"initial".to_vec())
+      .await
+      .context("blob_compare_and_swap returned an error")?;
+
+    ensure!(swapped, "expected the swap to succeed when no blob exists");
+
+    // Two writers read the same blob and both attempt to replace it.
+    let read: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+    let read: Vec<u8> = read.context("expected the blob to exist")?;
+
+    let first_swapped: bool = storage
+      .blob_compare_and_swap(&did, Some(&read), b"first writer".to_vec())
+      .await
+      .context("blob_compare_and_swap returned an error")?;
+
+    ensure!(first_swapped, "expected the swap of the first writer to succeed");
+
+    let second_swapped: bool = storage
+      .blob_compare_and_swap(&did, Some(&read), b"second writer".to_vec())
+      .await
+      .context("blob_compare_and_swap returned an error")?;
+
+    ensure!(
+      !second_swapped,
+      "expected the swap of the second writer to fail since the blob was changed"
+    );
+
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure_eq!(
+      value.as_deref(),
+      Some(b"first writer".as_slice()),
+      "expected the blob of the first writer to be retained, got {value:?}"
+    );
+
+    let swapped: bool = storage
+      .blob_compare_and_swap(&did, None, b"overwrite".to_vec())
+      .await
+      .context("blob_compare_and_swap returned an error")?;
+
+    ensure!(
+      !swapped,
+      "expected the swap to fail when a blob exists but none was expected"
+    );
+
+    Ok(())
+  }
+
+  #[cfg(feature = "send-sync-storage")]
+  #[named]
+  pub async fn blob_append_test<S>(storage: S) -> anyhow::Result<()>
+  where
+    S: Storage + 'static,
+  {
+    const NUM_TASKS: usize = 16;
+    const NUM_APPENDS: usize = 16;
+    // Every record has the same length, so the records can be split apart again.
+    const RECORD_LENGTH: usize = 10;
+
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let length: usize = storage
+      .blob_append(&did, b"0000:0000;")
+      .await
+      .context("blob_append returned an error")?;
+
+    ensure_eq!(
+      length,
+      RECORD_LENGTH,
+      "expected the first append to create a blob of length {RECORD_LENGTH}, got {length}"
+    );
+
+    let storage: Arc<S> = Arc::new(storage);
+    let mut handles: Vec<JoinHandle<anyhow::Result<Vec<usize>>>> = Vec::with_capacity(NUM_TASKS);
+
+    for task in 1..=NUM_TASKS {
+      let storage: Arc<S> = Arc::clone(&storage);
+      let did: CoreDID = did.clone();
+
+      handles.push(tokio::spawn(async move {
+        let mut lengths: Vec<usize> = Vec::with_capacity(NUM_APPENDS);
+
+        for append in 0..NUM_APPENDS {
+          let record: String = format!("{task:04}:{append:04};");
+          let length: usize = storage
+            .blob_append(&did, record.as_bytes())
+            .await
+            .context("blob_append returned an error")?;
+          lengths.push(length);
+        }
+
+        Ok(lengths)
+      }));
+    }
+
+    let mut lengths: HashSet<usize> = HashSet::new();
+    for handle in handles {
+      for length in handle.await.context("task panicked")?? {
+        ensure!(
+          lengths.insert(length),
+          "expected every append to return a distinct length, got {length} twice"
+        );
+      }
+    }
+
+    let expected_length: usize = (NUM_TASKS * NUM_APPENDS + 1) * RECORD_LENGTH;
+    let blob: Vec<u8> = storage
+      .blob_get(&did)
+      .await
+      .context("blob_get returned an error")?
+      .context("expected the blob to exist")?;
+
+    ensure_eq!(
+      blob.len(),
+      expected_length,
+      "expected the blob to have length {expected_length}, got {}",
+      blob.len()
+    );
+
+    let records: HashSet<&[u8]> = blob.chunks(RECORD_LENGTH).collect();
+    for task in 1..=NUM_TASKS {
+      for append in 0..NUM_APPENDS {
+        let record: String = format!("{task:04}:{append:04};");
+        ensure!(
+          records.contains(record.as_bytes()),
+          "expected the record `{record}` to be appended"
+        );
+      }
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn stats_test(storage: impl Storage) -> anyhow::Result<()> {
+    const NUM_DIDS: usize = 3;
+    const NUM_KEYS: usize = 2;
+    const BLOB_LEN: usize = 16;
+    let network: NetworkName = Network::Mainnet.name();
+
+    let stats: StorageStats = storage.stats().await.context("stats returned an error")?;
+
+    ensure_eq!(
+      stats,
+      StorageStats::default(),
+      "expected stats of an empty storage to be zero, was {stats:?}"
+    );
+
+    for _ in 0..NUM_DIDS {
+      let (did, _): (CoreDID, KeyLocation) = storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+
+      for _ in 0..NUM_KEYS {
+        storage
+          .key_generate(&did, KeyType::X25519, &random_string())
+          .await
+          .context("key_generate returned an error")?;
+      }
+
+      storage
+        .blob_set(&did, vec![0xff; BLOB_LEN])
+        .await
+        .context("blob_set returned an error")?;
+    }
+
+    let stats: StorageStats = storage.stats().await.context("stats returned an error")?;
+    // Every DID additionally holds the key generated by did_create.
+    let expected_stats: StorageStats = StorageStats::new(NUM_DIDS, NUM_DIDS * (NUM_KEYS + 1), NUM_DIDS * BLOB_LEN);
+
+    ensure_eq!(
+      stats,
+      expected_stats,
+      "expected stats to be {expected_stats:?}, was {stats:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_copy_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let message: &[u8] = b"This msg will be signed before and after copying";
+
+    let (from, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let x25519_location: KeyLocation = storage
+      .key_generate(&from, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    let expected_blob: Vec<u8> = random_string().into_bytes();
+    storage
+      .blob_set(&from, expected_blob.clone())
+      .await
+      .context("blob_set returned an error")?;
+
+    let to: CoreDID = IotaDID::new_with_network(KeyPair::new(KeyType::Ed25519)?.public().as_ref(), network.clone())
+      .unwrap()
+      .into();
+
+    storage
+      .did_copy(&from, to.clone())
+      .await
+      .context("did_copy returned an error")?;
+
+    for did in [&from, &to] {
+      let exists: bool = storage.did_exists(did).await.context("did_exists returned an error")?;
+
+      ensure!(exists, "expected did `{did}` to exist after copying");
+
+      let exists: bool = storage
+        .key_exists(did, &x25519_location)
+        .await
+        .context("key_exists returned an error")?;
+
+      ensure!(
+        exists,
+        "expected key at location `{x25519_location}` to exist for did `{did}` after copying"
+      );
+
+      let blob: Option<Vec<u8>> = storage.blob_get(did).await.context("blob_get returned an error")?;
+
+      ensure_eq!(
+        blob.as_ref(),
+        Some(&expected_blob),
+        "expected the blob of did `{did}` to match after copying"
+      );
+    }
+
+    // Ed25519 signatures are deterministic, so the copied key must produce the same signature.
+    let expected_signature: Signature = storage
+      .key_sign(&from, &location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+    let signature: Signature = storage
+      .key_sign(&to, &location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    ensure_eq!(
+      signature.as_bytes(),
+      expected_signature.as_bytes(),
+      "expected the copied key to produce the same signature"
+    );
+
+    let result: Result<(), _> = storage.did_copy(&from, to.clone()).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::IdentityAlreadyExists)),
+      "expected did_copy to return `IdentityAlreadyExists` if the target already exists"
+    );
+
+    let unknown_did: CoreDID = IotaDID::new_with_network(KeyPair::new(KeyType::Ed25519)?.public().as_ref(), network)
+      .unwrap()
+      .into();
+    let result: Result<(), _> = storage.did_copy(&unknown_did, to).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::KeyVaultNotFound)),
+      "expected did_copy to return `KeyVaultNotFound` if the source does not exist"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_export_import_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let message: &[u8] = b"This msg will be signed before and after the backup";
+    let password: String = random_string();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let x25519_location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+
+    let expected_blob: Vec<u8> = random_string().into_bytes();
+    storage
+      .blob_set(&did, expected_blob.clone())
+      .await
+      .context("blob_set returned an error")?;
+
+    let expected_signature: Signature = storage
+      .key_sign(&did, &location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    let backup: Vec<u8> = storage
+      .did_export(&did, &password)
+      .await
+      .context("did_export returned an error")?;
+
+    let result: Result<CoreDID, _> = storage.did_import(&backup, &password).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::IdentityAlreadyExists)),
+      "expected did_import to return `IdentityAlreadyExists` if the identity already exists"
+    );
+
+    storage.did_purge(&did).await.context("did_purge returned an error")?;
+
+    let result: Result<CoreDID, _> = storage.did_import(&backup, &random_string()).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::DecryptionFailure(_))),
+      "expected did_import to return `DecryptionFailure` for a wrong password"
+    );
+
+    let result: Result<CoreDID, _> = storage.did_import(&backup[..backup.len() - 1], &password).await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::DecryptionFailure(_))),
+      "expected did_import to return `DecryptionFailure` for corrupted data"
+    );
+
+    let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
+
+    ensure!(!exists, "expected did `{did}` to not exist after a failed import");
+
+    let imported_did: CoreDID = storage
+      .did_import(&backup, &password)
+      .await
+      .context("did_import returned an error")?;
+
+    ensure_eq!(imported_did, did, "expected did_import to return the exported did");
+
+    let exists: bool = storage
+      .key_exists(&did, &x25519_location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      exists,
+      "expected key at location `{x25519_location}` to exist after importing"
+    );
+
+    // Ed25519 signatures are deterministic, so the imported key must produce the same signature.
+    let signature: Signature = storage
+      .key_sign(&did, &location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    ensure_eq!(
+      signature.as_bytes(),
+      expected_signature.as_bytes(),
+      "expected the imported key to produce the same signature"
+    );
+
+    let blob: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure_eq!(blob, Some(expected_blob), "expected the blob to match after importing");
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_purge_test(storage: impl Storage) -> anyhow::Result<()> {
+    let fragment: String = random_string();
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+      .await
+      .context("did_create returned an error")?;
+
+    let list_len: usize = storage.did_list().await.context("did_list returned an error")?.len();
+
+    ensure_eq!(
+      list_len,
+      1,
+      "expected did_list to return a list of size 1 after creation"
+    );
+
+    let mut expected_chain_state: ChainState = ChainState::new();
+    expected_chain_state.set_last_integration_message_id(MessageId::new([0xff; 32]));
+
+    storage
+      .blob_set(&did, expected_chain_state.to_json_vec().unwrap())
+      .await
+      .context("chain_state_set returned an error")?;
+
+    let purged: bool = storage.did_purge(&did).await.context("did_purge returned an error")?;
+
+    ensure!(purged, "expected did `{did}` to have been purged");
+
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure!(value.is_none(), "expected blob_get to return `None` after purging");
+
+    let exists: bool = storage
+      .key_exists(&did, &location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      !exists,
+      "expected key at location `{location}` to no longer exist after purge"
+    );
+
+    let list: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+
+    ensure!(
+      list.is_empty(),
+      "expected did_list to return an empty list after purging"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn did_purge_missing_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let unknown_did: CoreDID =
+      IotaDID::new_with_network(KeyPair::new(KeyType::Ed25519)?.public().as_ref(), network.clone())
+        .unwrap()
+        .into();
+    let purged: bool = storage
+      .did_purge(&unknown_did)
+      .await
+      .context("did_purge returned an error for a DID that was never created")?;
+
+    ensure!(
+      !purged,
+      "expected did_purge to return `false` for a DID that was never created"
+    );
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let purged: bool = storage.did_purge(&did).await.context("did_purge returned an error")?;
+
+    ensure!(purged, "expected the first did_purge of `{did}` to return `true`");
+
+    let purged: bool = storage
+      .did_purge(&did)
+      .await
+      .context("did_purge returned an error for an already purged DID")?;
+
+    ensure!(!purged, "expected the second did_purge of `{did}` to return `false`");
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn key_exchange_test(alice_storage: impl Storage, bob_storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (alice_did, alice_signing_location): (CoreDID, KeyLocation) = alice_storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let (bob_did, _): (CoreDID, KeyLocation) = bob_storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let alice_location: KeyLocation = alice_storage
+      .key_generate(&alice_did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let alice_public_key: PublicKey = alice_storage
+      .key_public(&alice_did, &alice_location)
+      .await
+      .context("key_public returned an error")?;
+
+    let bob_location: KeyLocation = bob_storage
+      .key_generate(&bob_did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let bob_public_key: PublicKey = bob_storage
+      .key_public(&bob_did, &bob_location)
+      .await
+      .context("key_public returned an error")?;
+
+    let alice_shared_secret: [u8; 32] = alice_storage
+      .key_exchange(&alice_did, &alice_location, &bob_public_key)
+      .await
+      .context("key_exchange returned an error")?;
+
+    let bob_shared_secret: [u8; 32] = bob_storage
+      .key_exchange(&bob_did, &bob_location, &alice_public_key)
+      .await
+      .context("key_exchange returned an error")?;
+
+    ensure_eq!(
+      alice_shared_secret,
+      bob_shared_secret,
+      "expected both parties to derive the same shared secret"
+    );
+
+    let result: Result<[u8; 32], crate::Error> = alice_storage
+      .key_exchange(&alice_did, &alice_signing_location, &bob_public_key)
+      .await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::InvalidPrivateKey(_))),
+      "expected key_exchange to return `InvalidPrivateKey` for an Ed25519 key, got {result:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn encryption_test(alice_storage: impl Storage, bob_storage: impl Storage) -> anyhow::Result<()> {
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
+
+    let cek_algorithms: [CekAlgorithm; 4] = [
+      CekAlgorithm::ECDH_ES(agreement.clone()),
+      CekAlgorithm::ECDH_ES_A128KW(agreement.clone()),
+      CekAlgorithm::ECDH_ES_A192KW(agreement.clone()),
+      CekAlgorithm::ECDH_ES_A256KW(agreement),
+    ];
+    let encryption_algorithms: [EncryptionAlgorithm; 4] = [
+      EncryptionAlgorithm::AES128GCM,
+      EncryptionAlgorithm::AES192GCM,
+      EncryptionAlgorithm::AES256GCM,
+      EncryptionAlgorithm::XCHACHA20POLY1305,
+    ];
+
+    for (cek_algorithm, encryption_algorithm) in cek_algorithms
+      .iter()
+      .flat_map(|cek| encryption_algorithms.iter().map(move |enc| (cek, enc)))
+    {
+      let network: NetworkName = Network::Mainnet.name();
+
+      // Both Alice (Sender) and Bob (Receiver) must have a DID.
+      let (alice_did, _): (CoreDID, KeyLocation) = alice_storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+
+      let (bob_did, _): (CoreDID, KeyLocation) = bob_storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+
+      // The target of the message must share an X25519 public key.
+      let bob_fragment: String = random_string();
+      let bob_location: KeyLocation = bob_storage
+        .key_generate(&bob_did, KeyType::X25519, &bob_fragment)
+        .await
+        .context("key_generate returned an error")?;
+      let bob_public_key: PublicKey = bob_storage
+        .key_public(&bob_did, &bob_location)
+        .await
+        .context("key_public returned an error")?;
+
+      // Alice encrypts the message to be sent to Bob.
+      let plaintext: &[u8] = b"This msg will be encrypted and decrypted";
+
+      let result: Result<EncryptedData, _> = alice_storage
+        .data_encrypt(
+          &alice_did,
+          plaintext.to_vec(),
+          b"associated_data".to_vec(),
+          encryption_algorithm,
+          cek_algorithm,
+          bob_public_key,
+          None,
+        )
+        .await;
+
+      // Storages are only required to support AES256GCM and XCHACHA20POLY1305, and AES256 key wrapping.
+      let encrypted_data: EncryptedData = match result {
+        Err(crate::Error::NotSupported(_))
+          if matches!(
+            encryption_algorithm,
+            EncryptionAlgorithm::AES128GCM | EncryptionAlgorithm::AES192GCM
+          ) || matches!(
+            cek_algorithm,
+            CekAlgorithm::ECDH_ES_A128KW(_) | CekAlgorithm::ECDH_ES_A192KW(_)
+          ) =>
+        {
+          continue;
+        }
+        result => result.context("data_encrypt returned an error")?,
+      };
+
+      // Bob must be able to decrypt the message using the shared secret.
+      let decrypted_msg: Vec<u8> = bob_storage
+        .data_decrypt(
+          &bob_did,
+          encrypted_data,
+          encryption_algorithm,
+          cek_algorithm,
+          &bob_location,
+          None,
+        )
+        .await
+        .context("data_decrypt returned an error")?;
+
+      ensure_eq!(
+        plaintext,
+        &decrypted_msg,
+        "decrypted message does not match the original message for {encryption_algorithm:?} with {}",
+        cek_algorithm.name()
+      );
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn hkdf_encryption_test(alice_storage: impl Storage, bob_storage: impl Storage) -> anyhow::Result<()> {
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new())
+      .with_key_derivation(KeyDerivation::HkdfSha256);
+    let concat_kdf_agreement: AgreementInfo = agreement.clone().with_key_derivation(KeyDerivation::ConcatKdf);
+
+    let cek_algorithms: [(CekAlgorithm, CekAlgorithm); 2] = [
+      (
+        CekAlgorithm::ECDH_ES(agreement.clone()),
+        CekAlgorithm::ECDH_ES(concat_kdf_agreement.clone()),
+      ),
+      (
+        CekAlgorithm::ECDH_ES_A256KW(agreement),
+        CekAlgorithm::ECDH_ES_A256KW(concat_kdf_agreement),
+      ),
+    ];
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let network: NetworkName = Network::Mainnet.name();
+
+    for (cek_algorithm, concat_kdf_cek_algorithm) in cek_algorithms.iter() {
+      let (alice_did, _): (CoreDID, KeyLocation) = alice_storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+
+      let (bob_did, _): (CoreDID, KeyLocation) = bob_storage
+        .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+        .await
+        .context("did_create returned an error")?;
+
+      let bob_location: KeyLocation = bob_storage
+        .key_generate(&bob_did, KeyType::X25519, &random_string())
+        .await
+        .context("key_generate returned an error")?;
+      let bob_public_key: PublicKey = bob_storage
+        .key_public(&bob_did, &bob_location)
+        .await
+        .context("key_public returned an error")?;
+
+      let plaintext: &[u8] = b"This msg will be encrypted and decrypted using HKDF";
+
+      let encrypted_data: EncryptedData = alice_storage
+        .data_encrypt(
+          &alice_did,
+          plaintext.to_vec(),
+          b"associated_data".to_vec(),
+          &encryption_algorithm,
+          cek_algorithm,
+          bob_public_key,
+          None,
+        )
+        .await
+        .context("data_encrypt returned an error")?;
+
+      let decrypted_msg: Vec<u8> = bob_storage
+        .data_decrypt(
+          &bob_did,
+          encrypted_data.clone(),
+          &encryption_algorithm,
+          cek_algorithm,
+          &bob_location,
+          None,
+        )
+        .await
+        .context("data_decrypt returned an error")?;
+
+      ensure_eq!(
+        plaintext,
+        &decrypted_msg,
+        "decrypted message does not match the original message for {} with HKDF",
+        cek_algorithm.name()
+      );
+
+      // The key derivation must be the same on both ends.
+      let result: Result<Vec<u8>, _> = bob_storage
+        .data_decrypt(
+          &bob_did,
+          encrypted_data,
+          &encryption_algorithm,
+          concat_kdf_cek_algorithm,
+          &bob_location,
+          None,
+        )
+        .await;
+
+      ensure!(
+        result.is_err(),
+        "expected data_decrypt to fail with Concat KDF for data encrypted using HKDF with {}",
+        cek_algorithm.name()
+      );
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn data_encrypt_anonymous_test(
+    sender_storage: impl Storage,
+    recipient_storage: impl Storage,
+  ) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES_A256KW(agreement);
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let plaintext: &[u8] = b"This msg will be encrypted and decrypted";
+
+    // Only the recipient has a DID.
+    let (recipient_did, _): (CoreDID, KeyLocation) = recipient_storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+    let recipient_location: KeyLocation = recipient_storage
+      .key_generate(&recipient_did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let recipient_public_key: PublicKey = recipient_storage
+      .key_public(&recipient_did, &recipient_location)
+      .await
+      .context("key_public returned an error")?;
+
+    let encrypted_data: EncryptedData = sender_storage
+      .data_encrypt_anonymous(
+        plaintext.to_vec(),
+        b"associated_data".to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        recipient_public_key,
+      )
+      .await
+      .context("data_encrypt_anonymous returned an error")?;
+
+    let sender_dids: Vec<CoreDID> = sender_storage.did_list().await.context("did_list returned an error")?;
+    ensure!(
+      sender_dids.is_empty(),
+      "expected data_encrypt_anonymous to not create any DIDs, found {sender_dids:?}"
+    );
+
+    let decrypted: Vec<u8> = recipient_storage
+      .data_decrypt(
+        &recipient_did,
+        encrypted_data,
+        &encryption_algorithm,
+        &cek_algorithm,
+        &recipient_location,
+        None,
+      )
+      .await
+      .context("data_decrypt returned an error")?;
+
+    ensure_eq!(
+      plaintext,
+      decrypted.as_slice(),
+      "expected the recipient to decrypt the original plaintext"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn associated_data_tamper_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES(agreement);
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let message: &[u8] = b"This msg will be encrypted";
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let public_key: PublicKey = storage
+      .key_public(&did, &location)
+      .await
+      .context("key_public returned an error")?;
+
+    let encrypted_data: EncryptedData = storage
+      .data_encrypt(
+        &did,
+        message.to_vec(),
+        b"associated data".to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        public_key,
+        None,
+      )
+      .await
+      .context("data_encrypt returned an error")?;
+
+    let mut tampered: EncryptedData = encrypted_data.clone();
+    tampered.associated_data[0] ^= 0x01;
+    let result: Result<Vec<u8>, _> = storage
+      .data_decrypt(&did, tampered, &encryption_algorithm, &cek_algorithm, &location, None)
+      .await;
+
+    ensure!(
+      matches!(
+        &result,
+        Err(crate::Error::DecryptionFailure(
+          crypto::error::Error::InvalidArgumentError { expected, .. }
+        )) if expected.contains("associated data")
+      ),
+      "expected data_decrypt to describe the modified associated data, got {result:?}"
+    );
+
+    let decrypted: Vec<u8> = storage
+      .data_decrypt(
+        &did,
+        encrypted_data,
+        &encryption_algorithm,
+        &cek_algorithm,
+        &location,
+        None,
+      )
+      .await
+      .context("data_decrypt returned an error")?;
+    ensure_eq!(
+      decrypted.as_slice(),
+      message,
+      "expected the untampered data to decrypt to the original message"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn data_decrypt_expired_key_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES(agreement);
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let message: &[u8] = b"This msg will be encrypted";
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let expires_at: Timestamp = Timestamp::now_utc().checked_sub(Duration::minutes(1)).unwrap();
+    let location: KeyLocation = storage
+      .key_generate_with_expiry(&did, KeyType::X25519, &random_string(), expires_at)
+      .await
+      .context("key_generate_with_expiry returned an error")?;
+
+    // The metadata of an expired key is still available, and tells that it has expired.
+    let metadata: KeyMetadata = storage
+      .key_metadata(&did, &location)
+      .await
+      .context("key_metadata returned an error")?;
+    ensure_eq!(
+      metadata.expires_at,
+      Some(expires_at),
+      "expected key_metadata to return the expiry time of the key"
+    );
+
+    let encrypted_data: EncryptedData = storage
+      .data_encrypt(
+        &did,
+        message.to_vec(),
+        b"associated data".to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        metadata.public_key,
+        None,
+      )
+      .await
+      .context("data_encrypt returned an error")?;
+
+    let result: Result<Vec<u8>, _> = storage
+      .data_decrypt(
+        &did,
+        encrypted_data,
+        &encryption_algorithm,
+        &cek_algorithm,
+        &location,
+        None,
+      )
+      .await;
+    ensure!(
+      matches!(result, Err(crate::Error::KeyExpired)),
+      "expected data_decrypt to return `KeyExpired` for an expired key, got {result:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn encrypted_data_version_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES(agreement);
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let public_key: PublicKey = storage
+      .key_public(&did, &location)
+      .await
+      .context("key_public returned an error")?;
+
+    let encrypted_data: EncryptedData = storage
+      .data_encrypt(
+        &did,
+        b"This msg will be encrypted".to_vec(),
+        Vec::new(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        public_key,
+        None,
+      )
+      .await
+      .context("data_encrypt returned an error")?;
+
+    ensure_eq!(
+      encrypted_data.version,
+      EncryptedData::VERSION,
+      "expected encrypted data of version {}, got {}",
+      EncryptedData::VERSION,
+      encrypted_data.version
+    );
+    ensure_eq!(
+      encrypted_data.algorithm_id,
+      encryption_algorithm.name(),
+      "expected the algorithm id `{}`, got `{}`",
+      encryption_algorithm.name(),
+      encrypted_data.algorithm_id
+    );
+
+    let mut bumped: EncryptedData = encrypted_data.clone();
+    bumped.version += 1;
+    let result: Result<Vec<u8>, _> = storage
+      .data_decrypt(&did, bumped, &encryption_algorithm, &cek_algorithm, &location, None)
+      .await;
+
+    ensure!(
+      matches!(
+        result,
+        Err(crate::Error::DecryptionFailure(
+          crypto::error::Error::InvalidArgumentError { .. }
+        ))
+      ),
+      "expected data_decrypt to describe the version mismatch, got {result:?}"
+    );
+
+    let result: Result<Vec<u8>, _> = storage
+      .data_decrypt(
+        &did,
+        encrypted_data,
+        &EncryptionAlgorithm::XCHACHA20POLY1305,
+        &cek_algorithm,
+        &location,
+        None,
+      )
+      .await;
+
+    ensure!(
+      matches!(
+        result,
+        Err(crate::Error::DecryptionFailure(
+          crypto::error::Error::InvalidArgumentError { .. }
+        ))
+      ),
+      "expected data_decrypt to describe the algorithm mismatch, got {result:?}"
+    );
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn data_decrypt_into_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES(AgreementInfo::default());
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+    let location: KeyLocation = storage
+      .key_generate(&did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let public_key: PublicKey = storage
+      .key_public(&did, &location)
+      .await
+      .context("key_public returned an error")?;
+
+    // The largest message comes first, so the buffer never needs to grow afterwards.
+    let plaintexts: [Vec<u8>; 3] = [vec![0x01; 4096], vec![0x02; 16], vec![0x03; 1024]];
+    let mut out: Vec<u8> = Vec::new();
+    let mut buffer: Option<usize> = None;
+
+    for plaintext in plaintexts {
+      let encrypted_data: EncryptedData = storage
+        .data_encrypt(
+          &did,
+          plaintext.clone(),
+          b"associated_data".to_vec(),
+          &encryption_algorithm,
+          &cek_algorithm,
+          public_key.clone(),
+          None,
+        )
+        .await
+        .context("data_encrypt returned an error")?;
+
+      let len: usize = storage
+        .data_decrypt_into(
+          &did,
+          encrypted_data,
+          &encryption_algorithm,
+          &cek_algorithm,
+          &location,
+          None,
+          &mut out,
+        )
+        .await
+        .context("data_decrypt_into returned an error")?;
+
+      ensure_eq!(
+        len,
+        plaintext.len(),
+        "expected the length of the plaintext to be returned"
+      );
+      ensure_eq!(
+        out,
+        plaintext,
+        "expected the buffer to contain the decrypted message only"
+      );
+
+      let address: usize = *buffer.get_or_insert(out.as_ptr() as usize);
+      ensure_eq!(address, out.as_ptr() as usize, "expected the buffer to be reused");
+    }
+
+    Ok(())
+  }
+
+  #[named]
+  pub async fn data_decrypt_with_conversion_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES_A256KW(AgreementInfo::default());
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let plaintext: Vec<u8> = b"This msg will be encrypted to a converted key".to_vec();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+    let ed25519_public_key: PublicKey = storage
+      .key_public(&did, &location)
+      .await
+      .context("key_public returned an error")?;
+    let public_key: PublicKey =
+      X25519::ed25519_to_x25519_public(&ed25519_public_key).context("ed25519_to_x25519_public returned an error")?;
+
+    let encrypted_data: EncryptedData = storage
+      .data_encrypt(
+        &did,
+        plaintext.clone(),
+        b"associated_data".to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        public_key,
+        None,
+      )
+      .await
+      .context("data_encrypt returned an error")?;
+
+    ensure!(
+      storage
+        .data_decrypt(
+          &did,
+          encrypted_data.clone(),
+          &encryption_algorithm,
+          &cek_algorithm,
+          &location,
+          None
+        )
+        .await
+        .is_err(),
+      "expected data_decrypt to reject the Ed25519 key"
+    );
+
+    ensure!(
+      storage
+        .data_decrypt_with_conversion(
+          &did,
+          encrypted_data.clone(),
+          &encryption_algorithm,
+          &cek_algorithm,
+          &location,
+          None,
+          false
+        )
+        .await
+        .is_err(),
+      "expected the Ed25519 key to be rejected without conversion"
+    );
+
+    let decrypted: Vec<u8> = storage
+      .data_decrypt_with_conversion(
+        &did,
+        encrypted_data,
+        &encryption_algorithm,
+        &cek_algorithm,
+        &location,
+        None,
+        true,
+      )
+      .await
+      .context("data_decrypt_with_conversion returned an error")?;
+    ensure_eq!(decrypted, plaintext, "expected the decrypted message to match");
+
+    Ok(())
+  }
+
+  #[cfg(feature = "encryption")]
+  #[named]
+  pub async fn data_encrypt_stream_test(alice_storage: impl Storage, bob_storage: impl Storage) -> anyhow::Result<()> {
+    const PLAINTEXT_LENGTH: usize = 10 * 1024 * 1024;
+    let network: NetworkName = Network::Mainnet.name();
+    let associated_data: &[u8] = b"associated_data";
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let cek_algorithm: CekAlgorithm = CekAlgorithm::ECDH_ES_A256KW(AgreementInfo::new(
+      b"Alice".to_vec(),
+      b"Bob".to_vec(),
+      Vec::new(),
+      Vec::new(),
+    ));
+
+    let (alice_did, _): (CoreDID, KeyLocation) = alice_storage
+      .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let (bob_did, _): (CoreDID, KeyLocation) = bob_storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let bob_location: KeyLocation = bob_storage
+      .key_generate(&bob_did, KeyType::X25519, &random_string())
+      .await
+      .context("key_generate returned an error")?;
+    let bob_public_key: PublicKey = bob_storage
+      .key_public(&bob_did, &bob_location)
+      .await
+      .context("key_public returned an error")?;
+
+    // The length is not a multiple of the chunk length, so the last chunk is shorter.
+    let plaintext: Vec<u8> = (0..PLAINTEXT_LENGTH + 7).map(|index| index as u8).collect();
+    let plaintext_digest: Vec<u8> = Sha256::digest(&plaintext).to_vec();
+
+    let mut ciphertext: Vec<u8> = Vec::new();
+    alice_storage
+      .data_encrypt_stream(
+        &alice_did,
+        &mut plaintext.as_slice(),
+        &mut ciphertext,
+        associated_data.to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        bob_public_key,
+        None,
+      )
+      .await
+      .context("data_encrypt_stream returned an error")?;
+
+    // Only the digest of the plaintext is kept, so that at most two copies of the data are held at once.
+    drop(plaintext);
+
+    let mut decrypted: DigestWriter = DigestWriter::default();
+    bob_storage
+      .data_decrypt_stream(
+        &bob_did,
+        &mut ciphertext.as_slice(),
+        &mut decrypted,
+        associated_data.to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        &bob_location,
+        None,
+      )
+      .await
+      .context("data_decrypt_stream returned an error")?;
+
+    ensure_eq!(
+      plaintext_digest,
+      decrypted.finalize(),
+      "decrypted stream does not match the original data"
+    );
+
+    // Dropping the last chunk must be detected.
+    let truncated: &[u8] = &ciphertext[..ciphertext.len() - 1024];
+    let result: Result<(), crate::Error> = bob_storage
+      .data_decrypt_stream(
+        &bob_did,
+        &mut &*truncated,
+        &mut DigestWriter::default(),
+        associated_data.to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        &bob_location,
+        None,
+      )
+      .await;
+
+    ensure!(result.is_err(), "expected decryption of a truncated stream to fail");
+
+    // Modifying a chunk must be detected.
+    let last: usize = ciphertext.len() - 1;
+    ciphertext[last] ^= 0xff;
+    let result: Result<(), crate::Error> = bob_storage
+      .data_decrypt_stream(
+        &bob_did,
+        &mut ciphertext.as_slice(),
+        &mut DigestWriter::default(),
+        associated_data.to_vec(),
+        &encryption_algorithm,
+        &cek_algorithm,
+        &bob_location,
+        None,
+      )
+      .await;
+
+    ensure!(
+      matches!(result, Err(crate::Error::DecryptionFailure(_))),
+      "expected decryption of a modified stream to fail with `DecryptionFailure`, got {result:?}"
+    );
+
+    Ok(())
+  }
+
+  /// Checks that a failing `flush_changes` leaves the storage in a consistent state.
+  ///
+  /// The `storage` must fail the next call to `flush_changes`, and only that one.
+  #[named]
+  pub async fn flush_failure_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
+
+    let (did, location): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
+
+    let result: Result<(), _> = storage.flush_changes().await;
+
+    ensure!(result.is_err(), "expected flush_changes to fail");
+
+    storage
+      .flush_changes()
+      .await
+      .context("flush_changes returned an error after a failed flush")?;
+
+    let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
+
+    ensure!(exists, "expected did `{did}` to exist after a failed flush");
+
+    let exists: bool = storage
+      .key_exists(&did, &location)
+      .await
+      .context("key_exists returned an error")?;
 
-    for key_type in key_types {
-      let key_fragment: String = random_string();
-      let keypair: KeyPair = KeyPair::new(key_type).unwrap();
-      let location: KeyLocation = KeyLocation::new(key_type, key_fragment, keypair.public().as_ref());
+    ensure!(
+      exists,
+      "expected key at location `{location}` to exist after a failed flush"
+    );
 
-      storage
-        .key_insert(&did, &location, keypair.private().to_owned())
-        .await
-        .context("key_insert returned an error")?;
+    Ok(())
+  }
 
-      public_keys.push(keypair.public().to_owned());
-      locations.push(location);
-    }
+  /// Checks that the changes of a failed [`Storage::transaction`] are rolled back, while the changes of a
+  /// successful one are kept.
+  ///
+  /// The `storage` must support rolling back transactions.
+  #[named]
+  pub async fn transaction_rollback_test(storage: impl Storage) -> anyhow::Result<()> {
+    let network: NetworkName = Network::Mainnet.name();
 
-    for (i, location) in locations.into_iter().enumerate() {
-      let exists: bool = storage
-        .key_exists(&did, &location)
-        .await
-        .context("key_exists returned an error")?;
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
+      .await
+      .context("did_create returned an error")?;
 
-      ensure!(exists, "expected key at location `{location}` to exist");
+    let mut generated: Option<KeyLocation> = None;
+    let result: Result<(), crate::Error> = storage
+      .transaction(Box::pin(async {
+        generated = Some(storage.key_generate(&did, KeyType::Ed25519, &random_string()).await?);
 
-      let public_key: PublicKey = storage
-        .key_public(&did, &location)
-        .await
-        .context("key_public returned an error")?;
+        Err(crate::Error::IoError(std::io::Error::new(
+          std::io::ErrorKind::Other,
+          "injected transaction failure",
+        )))
+      }))
+      .await;
 
-      let expected_public_key: &PublicKey = &public_keys[i];
+    ensure!(
+      matches!(result, Err(crate::Error::IoError(_))),
+      "expected transaction to return the error of the failed future, got {result:?}"
+    );
 
-      ensure_eq!(
-        public_key.as_ref(),
-        expected_public_key.as_ref(),
-        "expected public key at location `{location}` to be {expected_public_key:?}, was {public_key:?}"
-      );
-    }
+    let location: KeyLocation = generated.context("expected key_generate to be called in the transaction")?;
+    let exists: bool = storage
+      .key_exists(&did, &location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      !exists,
+      "expected key at location `{location}` to be rolled back after a failed transaction"
+    );
+
+    let mut generated: Option<KeyLocation> = None;
+    storage
+      .transaction(Box::pin(async {
+        generated = Some(storage.key_generate(&did, KeyType::Ed25519, &random_string()).await?);
+        Ok(())
+      }))
+      .await
+      .context("transaction returned an error")?;
+
+    let location: KeyLocation = generated.context("expected key_generate to be called in the transaction")?;
+    let exists: bool = storage
+      .key_exists(&did, &location)
+      .await
+      .context("key_exists returned an error")?;
+
+    ensure!(
+      exists,
+      "expected key at location `{location}` to exist after a successful transaction"
+    );
 
     Ok(())
   }
 
+  /// Closes the `storage` after mutating it and checks that the changes are visible in the storage
+  /// returned by `reopen`, which must access the same underlying data.
   #[named]
-  pub async fn key_sign_ed25519_test(storage: impl Storage) -> anyhow::Result<()> {
-    // The following test vector is taken from Test 2 of RFC 8032
-    // https://datatracker.ietf.org/doc/html/rfc8032#section-7
-    const PRIVATE_KEY: [u8; 32] = [
-      76, 205, 8, 155, 40, 255, 150, 218, 157, 182, 195, 70, 236, 17, 78, 15, 91, 138, 49, 159, 53, 171, 166, 36, 218,
-      140, 246, 237, 79, 184, 166, 251,
-    ];
-    const MESSAGE: [u8; 1] = [114];
-    const SIGNATURE: [u8; 64] = [
-      146, 160, 9, 169, 240, 212, 202, 184, 114, 14, 130, 11, 95, 100, 37, 64, 162, 178, 123, 84, 22, 80, 63, 143, 179,
-      118, 34, 35, 235, 219, 105, 218, 8, 90, 193, 228, 62, 21, 153, 110, 69, 143, 54, 19, 208, 241, 29, 140, 56, 123,
-      46, 174, 180, 48, 42, 238, 176, 13, 41, 22, 18, 187, 12, 0,
-    ];
-
-    let fragment: String = random_string();
+  pub async fn close_test<S, T, F>(storage: S, reopen: F) -> anyhow::Result<()>
+  where
+    S: Storage,
+    T: Storage,
+    F: FnOnce() -> T,
+  {
     let network: NetworkName = Network::Mainnet.name();
+    let message: &[u8] = b"This msg will be signed before and after closing";
 
     let (did, location): (CoreDID, KeyLocation) = storage
-      .did_create(
-        DIDType::IotaDID,
-        network.clone(),
-        &fragment,
-        Some(PrivateKey::from(PRIVATE_KEY.to_vec())),
-      )
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
       .await
       .context("did_create returned an error")?;
 
+    let expected_blob: Vec<u8> = random_string().into_bytes();
+    storage
+      .blob_set(&did, expected_blob.clone())
+      .await
+      .context("blob_set returned an error")?;
+
+    let expected_signature: Signature = storage
+      .key_sign(&did, &location, message.to_vec())
+      .await
+      .context("key_sign returned an error")?;
+
+    storage.close().await.context("close returned an error")?;
+
+    let storage: T = reopen();
+
+    let exists: bool = storage.did_exists(&did).await.context("did_exists returned an error")?;
+
+    ensure!(exists, "expected did `{did}` to exist after closing");
+
+    let blob: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+
+    ensure_eq!(blob, Some(expected_blob), "expected the blob to match after closing");
+
     let signature: Signature = storage
-      .key_sign(&did, &location, MESSAGE.to_vec())
+      .key_sign(&did, &location, message.to_vec())
       .await
       .context("key_sign returned an error")?;
 
     ensure_eq!(
       signature.as_bytes(),
-      &SIGNATURE,
-      "expected signature to be `{SIGNATURE:?}`, was `{:?}`",
-      signature.as_bytes()
+      expected_signature.as_bytes(),
+      "expected the key to produce the same signature after closing"
     );
 
     Ok(())
   }
 
+  /// Checks that a [`ChainState`] stored in the blob survives reopening the storage, as well as purging
+  /// and recreating the identity.
+  ///
+  /// The `storage` is closed and `reopen` must return a storage with the same contents. Storages without
+  /// persistence may return another handle to the same state.
   #[named]
-  pub async fn key_value_store_test(storage: impl Storage) -> anyhow::Result<()> {
-    let fragment: String = random_string();
+  pub async fn chain_state_persistence_test<S, T, F>(storage: S, reopen: F) -> anyhow::Result<()>
+  where
+    S: Storage,
+    T: Storage,
+    F: FnOnce() -> T,
+  {
     let network: NetworkName = Network::Mainnet.name();
+    let fragment: String = random_string();
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
 
-    let (did, location): (CoreDID, KeyLocation) = storage
-      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(
+        DIDType::IotaDID,
+        network.clone(),
+        &fragment,
+        Some(keypair.private().clone()),
+      )
       .await
       .context("did_create returned an error")?;
 
+    let mut expected_chain_state: ChainState = ChainState::new();
+    expected_chain_state.set_last_integration_message_id(MessageId::new([0xaa; 32]));
+    expected_chain_state.set_last_diff_message_id(MessageId::new([0xbb; 32]));
+
+    storage
+      .blob_set(&did, expected_chain_state.to_json_vec().unwrap())
+      .await
+      .context("blob_set returned an error")?;
+    storage
+      .flush_changes()
+      .await
+      .context("flush_changes returned an error")?;
+    storage.close().await.context("close returned an error")?;
+
+    let storage: T = reopen();
+
     let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+    let value: Vec<u8> = value.context("expected the blob to exist after reopening")?;
+    let chain_state: ChainState =
+      ChainState::from_json_slice(&value).context("the stored chain state could not be deserialized")?;
 
-    ensure!(value.is_none(), "expected blob_get to return `None` for a new DID");
+    ensure_eq!(
+      expected_chain_state,
+      chain_state,
+      "expected `{expected_chain_state:?}` after reopening, got `{chain_state:?}`"
+    );
 
-    let public_key: PublicKey = storage
-      .key_public(&did, &location)
-      .await
-      .context("key_public returned an error")?;
+    let purged: bool = storage.did_purge(&did).await.context("did_purge returned an error")?;
+    ensure!(purged, "expected did `{did}` to be purged");
 
-    let method: IotaVerificationMethod = IotaVerificationMethod::new(
-      did.clone().try_into().unwrap(),
-      KeyType::Ed25519,
-      &public_key,
-      &fragment,
-    )
-    .unwrap();
+    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+    ensure!(value.is_none(), "expected the blob to be removed by did_purge");
 
-    let expected_document: IotaDocument = IotaDocument::from_verification_method(method).unwrap();
-    storage
-      .blob_set(&did, expected_document.to_json_vec().unwrap())
+    // Recreating the identity from the same key yields the same DID, under which the chain state is restored.
+    let (recreated_did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &fragment, Some(keypair.private().clone()))
       .await
-      .context("blob_set returned an error")?;
-    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
-    let document: IotaDocument = IotaDocument::from_json_slice(&value.unwrap()).unwrap();
+      .context("did_create returned an error")?;
+
     ensure_eq!(
-      expected_document,
-      document,
-      "expected `{expected_document}`, got `{document}`"
+      did,
+      recreated_did,
+      "expected the recreated did to be `{did}`, got `{recreated_did}`"
     );
 
-    let mut expected_chain_state: ChainState = ChainState::new();
-    expected_chain_state.set_last_integration_message_id(MessageId::new([0xff; 32]));
     storage
-      .blob_set(&did, expected_chain_state.to_json_vec().unwrap())
+      .blob_set(&did, chain_state.to_json_vec().unwrap())
       .await
       .context("blob_set returned an error")?;
+    storage
+      .flush_changes()
+      .await
+      .context("flush_changes returned an error")?;
+
     let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
-    let chain_state: ChainState = ChainState::from_json_slice(&value.unwrap()).unwrap();
+    let value: Vec<u8> = value.context("expected the restored blob to exist")?;
+    let chain_state: ChainState =
+      ChainState::from_json_slice(&value).context("the restored chain state could not be deserialized")?;
+
     ensure_eq!(
       expected_chain_state,
       chain_state,
-      "expected `{expected_chain_state:?}`, got `{chain_state:?}`"
+      "expected `{expected_chain_state:?}` after restoring, got `{chain_state:?}`"
     );
 
     Ok(())
   }
 
+  /// Checks the output of `data_encrypt` against known vectors.
+  ///
+  /// The `storage` must draw its randomness from a fresh [`StorageTestSuite::deterministic_fill`].
   #[named]
-  pub async fn did_purge_test(storage: impl Storage) -> anyhow::Result<()> {
-    let fragment: String = random_string();
+  pub async fn encryption_vector_test(storage: impl Storage) -> anyhow::Result<()> {
     let network: NetworkName = Network::Mainnet.name();
 
-    let (did, location): (CoreDID, KeyLocation) = storage
-      .did_create(DIDType::IotaDID, network.clone(), &fragment, None)
+    let (did, _): (CoreDID, KeyLocation) = storage
+      .did_create(DIDType::IotaDID, network, &random_string(), None)
       .await
       .context("did_create returned an error")?;
 
-    let list_len: usize = storage.did_list().await.context("did_list returned an error")?.len();
-
-    ensure_eq!(
-      list_len,
-      1,
-      "expected did_list to return a list of size 1 after creation"
-    );
-
-    let mut expected_chain_state: ChainState = ChainState::new();
-    expected_chain_state.set_last_integration_message_id(MessageId::new([0xff; 32]));
+    // Bob's X25519 key pair from RFC 7748, section 6.1.
+    let private_key: PrivateKey = decode_hex("5dab087e624a8a4b79e17f8b83800ee66f3bb1292618b6fd1c2f8b27ff88e0eb").into();
+    let public_key: Vec<u8> = decode_hex("de9edb7d7b7dc1b4d35b61c2ece435373f8343c85b78674dadfc7e146f882b4f");
+    let location: KeyLocation = KeyLocation::new(KeyType::X25519, random_string(), &public_key);
 
     storage
-      .blob_set(&did, expected_chain_state.to_json_vec().unwrap())
+      .key_insert(&did, &location, private_key)
       .await
-      .context("chain_state_set returned an error")?;
-
-    let purged: bool = storage.did_purge(&did).await.context("did_purge returned an error")?;
-
-    ensure!(purged, "expected did `{did}` to have been purged");
-
-    let value: Option<Vec<u8>> = storage.blob_get(&did).await.context("blob_get returned an error")?;
+      .context("key_insert returned an error")?;
 
-    ensure!(value.is_none(), "expected blob_get to return `None` after purging");
+    let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
+    let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let plaintext: &[u8] = b"HelloWorld!";
+    let associated_data: &[u8] = b"AssociatedData";
+
+    // The randomness is consumed in the order: ephemeral key, content encryption key (if any), nonce.
+    let vectors: [(CekAlgorithm, EncryptedData); 2] = [
+      (
+        CekAlgorithm::ECDH_ES(agreement.clone()),
+        EncryptedData::new(
+          vec![0x02; 12],
+          associated_data.to_vec(),
+          decode_hex("4b893acdb0c3fd46f0a1db927c25307d"),
+          decode_hex("eadcd1192ac44d00c12d62"),
+          Vec::new(),
+          decode_hex("a4e09292b651c278b9772c569f5fa9bb13d906b46ab68c9df9dc2b4409f8a209"),
+        )
+        .with_algorithm(&encryption_algorithm),
+      ),
+      (
+        CekAlgorithm::ECDH_ES_A256KW(agreement),
+        EncryptedData::new(
+          vec![0x05; 12],
+          associated_data.to_vec(),
+          decode_hex("a9f6b079766eb78d2548bb4b655a4822"),
+          decode_hex("8d5c1d85c6b0c506b8b328"),
+          decode_hex("5f5a4f3a3bc86eb7b077080eab3c55df1a2b44016d0c096de5a2dbc7c603b2f82b3a28e682bd3b1c"),
+          decode_hex("5dfedd3b6bd47f6fa28ee15d969d5bb0ea53774d488bdaf9df1c6e0124b3ef22"),
+        )
+        .with_algorithm(&encryption_algorithm),
+      ),
+    ];
 
-    let exists: bool = storage
-      .key_exists(&did, &location)
-      .await
-      .context("key_exists returned an error")?;
+    for (cek_algorithm, expected) in vectors {
+      let encrypted_data: EncryptedData = storage
+        .data_encrypt(
+          &did,
+          plaintext.to_vec(),
+          associated_data.to_vec(),
+          &encryption_algorithm,
+          &cek_algorithm,
+          public_key.clone().into(),
+          None,
+        )
+        .await
+        .context("data_encrypt returned an error")?;
 
-    ensure!(
-      !exists,
-      "expected key at location `{location}` to no longer exist after purge"
-    );
+      ensure_eq!(
+        encrypted_data,
+        expected,
+        "expected encrypted data for {} to be {expected:?}, was {encrypted_data:?}",
+        cek_algorithm.name()
+      );
 
-    let list: Vec<CoreDID> = storage.did_list().await.context("did_list returned an error")?;
+      let decrypted_msg: Vec<u8> = storage
+        .data_decrypt(
+          &did,
+          encrypted_data,
+          &encryption_algorithm,
+          &cek_algorithm,
+          &location,
+          None,
+        )
+        .await
+        .context("data_decrypt returned an error")?;
 
-    ensure!(
-      list.is_empty(),
-      "expected did_list to return an empty list after purging"
-    );
+      ensure_eq!(
+        plaintext,
+        &decrypted_msg,
+        "decrypted message does not match the original message for {}",
+        cek_algorithm.name()
+      );
+    }
 
     Ok(())
   }
 
   #[named]
-  pub async fn encryption_test(alice_storage: impl Storage, bob_storage: impl Storage) -> anyhow::Result<()> {
+  pub async fn ecdh_1pu_encryption_test(alice_storage: impl Storage, bob_storage: impl Storage) -> anyhow::Result<()> {
     let agreement: AgreementInfo = AgreementInfo::new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new());
 
     for cek_algorithm in [
-      CekAlgorithm::ECDH_ES(agreement.clone()),
-      CekAlgorithm::ECDH_ES_A256KW(agreement),
+      CekAlgorithm::ECDH_1PU(agreement.clone()),
+      CekAlgorithm::ECDH_1PU_A256KW(agreement),
     ] {
       let network: NetworkName = Network::Mainnet.name();
 
-      // Both Alice (Sender) and Bob (Receiver) must have a DID.
       let (alice_did, _): (CoreDID, KeyLocation) = alice_storage
         .did_create(DIDType::IotaDID, network.clone(), &random_string(), None)
         .await
@@ -520,10 +3934,18 @@ impl StorageTestSuite {
         .await
         .context("did_create returned an error")?;
 
-      // The target of the message must share an X25519 public key.
-      let bob_fragment: String = random_string();
+      // Both the sender and the receiver must have an X25519 key, since the sender is authenticated.
+      let alice_location: KeyLocation = alice_storage
+        .key_generate(&alice_did, KeyType::X25519, &random_string())
+        .await
+        .context("key_generate returned an error")?;
+      let alice_public_key: PublicKey = alice_storage
+        .key_public(&alice_did, &alice_location)
+        .await
+        .context("key_public returned an error")?;
+
       let bob_location: KeyLocation = bob_storage
-        .key_generate(&bob_did, KeyType::X25519, &bob_fragment)
+        .key_generate(&bob_did, KeyType::X25519, &random_string())
         .await
         .context("key_generate returned an error")?;
       let bob_public_key: PublicKey = bob_storage
@@ -531,10 +3953,29 @@ impl StorageTestSuite {
         .await
         .context("key_public returned an error")?;
 
-      // Alice encrypts the message to be sent to Bob.
       let encryption_algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
       let plaintext: &[u8] = b"This msg will be encrypted and decrypted";
 
+      // Encryption must fail without the sender's key.
+      let result: Result<EncryptedData, _> = alice_storage
+        .data_encrypt(
+          &alice_did,
+          plaintext.to_vec(),
+          b"associated_data".to_vec(),
+          &encryption_algorithm,
+          &cek_algorithm,
+          bob_public_key.clone(),
+          None,
+        )
+        .await;
+
+      ensure!(
+        result.is_err(),
+        "expected data_encrypt to fail without a sender key for {}",
+        cek_algorithm.name()
+      );
+
+      // Alice encrypts the message to be sent to Bob with her own key.
       let encrypted_data: EncryptedData = alice_storage
         .data_encrypt(
           &alice_did,
@@ -543,11 +3984,30 @@ impl StorageTestSuite {
           &encryption_algorithm,
           &cek_algorithm,
           bob_public_key,
+          Some(&alice_location),
         )
         .await
         .context("data_encrypt returned an error")?;
 
-      // Bob must be able to decrypt the message using the shared secret.
+      // Bob cannot decrypt the message without authenticating Alice.
+      let result: Result<Vec<u8>, _> = bob_storage
+        .data_decrypt(
+          &bob_did,
+          encrypted_data.clone(),
+          &encryption_algorithm,
+          &cek_algorithm,
+          &bob_location,
+          Some(KeyPair::new(KeyType::X25519)?.public().clone()),
+        )
+        .await;
+
+      ensure!(
+        result.is_err(),
+        "expected data_decrypt to fail with the wrong sender public key for {}",
+        cek_algorithm.name()
+      );
+
+      // Bob decrypts the message using Alice's public key.
       let decrypted_msg: Vec<u8> = bob_storage
         .data_decrypt(
           &bob_did,
@@ -555,6 +4015,7 @@ impl StorageTestSuite {
           &encryption_algorithm,
           &cek_algorithm,
           &bob_location,
+          Some(alice_public_key),
         )
         .await
         .context("data_decrypt returned an error")?;
@@ -562,7 +4023,8 @@ impl StorageTestSuite {
       ensure_eq!(
         plaintext,
         &decrypted_msg,
-        "decrypted message does not match the original message"
+        "decrypted message does not match the original message for {}",
+        cek_algorithm.name()
       );
     }
 
@@ -2,15 +2,27 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use core::fmt::Debug;
+use std::collections::BTreeMap;
 
 use async_trait::async_trait;
+use crypto::hashes::sha::SHA256;
+use crypto::hashes::sha::SHA256_LEN;
 
+use identity_core::common::Timestamp;
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyPair;
 use identity_core::crypto::KeyType;
 use identity_core::crypto::PrivateKey;
 use identity_core::crypto::PublicKey;
+use identity_core::crypto::Verify;
+use identity_core::utils::Base;
+use identity_core::utils::BaseEncoding;
 use identity_did::did::CoreDID;
+use identity_did::did::DID;
 use identity_iota_core::tangle::NetworkName;
+use zeroize::Zeroizing;
 
+use crate::error::Error;
 use crate::error::Result;
 #[cfg(feature = "encryption")]
 use crate::types::CekAlgorithm;
@@ -19,21 +31,87 @@ use crate::types::DIDType;
 use crate::types::EncryptedData;
 #[cfg(feature = "encryption")]
 use crate::types::EncryptionAlgorithm;
+use crate::types::JwsAlgorithm;
 use crate::types::KeyLocation;
+use crate::types::KeyMetadata;
 use crate::types::Signature;
+use crate::types::StorageStats;
+#[cfg(feature = "tracing")]
+use crate::utils::did_hash;
 
 #[cfg(not(feature = "send-sync-storage"))]
 mod storage_sub_trait {
   pub trait StorageSendSyncMaybe {}
   impl<S: super::Storage> StorageSendSyncMaybe for S {}
+
+  /// The future passed to [`Storage::transaction`](super::Storage::transaction).
+  pub type TransactionFuture<'s> =
+    core::pin::Pin<Box<dyn core::future::Future<Output = crate::error::Result<()>> + 's>>;
+
+  /// A source of the data streamed through [`Storage::data_encrypt_stream`](super::Storage::data_encrypt_stream)
+  /// and [`Storage::data_decrypt_stream`](super::Storage::data_decrypt_stream).
+  #[cfg(feature = "encryption")]
+  pub trait DataStreamReader: futures::io::AsyncRead + Unpin {}
+  #[cfg(feature = "encryption")]
+  impl<R: futures::io::AsyncRead + Unpin + ?Sized> DataStreamReader for R {}
+
+  /// A sink of the data streamed through [`Storage::data_encrypt_stream`](super::Storage::data_encrypt_stream)
+  /// and [`Storage::data_decrypt_stream`](super::Storage::data_decrypt_stream).
+  #[cfg(feature = "encryption")]
+  pub trait DataStreamWriter: futures::io::AsyncWrite + Unpin {}
+  #[cfg(feature = "encryption")]
+  impl<W: futures::io::AsyncWrite + Unpin + ?Sized> DataStreamWriter for W {}
 }
 
 #[cfg(feature = "send-sync-storage")]
 mod storage_sub_trait {
   pub trait StorageSendSyncMaybe: Send + Sync {}
   impl<S: Send + Sync + super::Storage> StorageSendSyncMaybe for S {}
+
+  /// The future passed to [`Storage::transaction`](super::Storage::transaction).
+  pub type TransactionFuture<'s> =
+    core::pin::Pin<Box<dyn core::future::Future<Output = crate::error::Result<()>> + Send + 's>>;
+
+  /// A source of the data streamed through [`Storage::data_encrypt_stream`](super::Storage::data_encrypt_stream)
+  /// and [`Storage::data_decrypt_stream`](super::Storage::data_decrypt_stream).
+  #[cfg(feature = "encryption")]
+  pub trait DataStreamReader: futures::io::AsyncRead + Unpin + Send {}
+  #[cfg(feature = "encryption")]
+  impl<R: futures::io::AsyncRead + Unpin + Send + ?Sized> DataStreamReader for R {}
+
+  /// A sink of the data streamed through [`Storage::data_encrypt_stream`](super::Storage::data_encrypt_stream)
+  /// and [`Storage::data_decrypt_stream`](super::Storage::data_decrypt_stream).
+  #[cfg(feature = "encryption")]
+  pub trait DataStreamWriter: futures::io::AsyncWrite + Unpin + Send {}
+  #[cfg(feature = "encryption")]
+  impl<W: futures::io::AsyncWrite + Unpin + Send + ?Sized> DataStreamWriter for W {}
 }
 
+#[cfg(feature = "encryption")]
+pub use self::storage_sub_trait::DataStreamReader;
+#[cfg(feature = "encryption")]
+pub use self::storage_sub_trait::DataStreamWriter;
+pub use self::storage_sub_trait::TransactionFuture;
+
+/// The name of the blob accessed by [`Storage::blob_set`] and [`Storage::blob_get`].
+pub const DEFAULT_BLOB_NAME: &str = "__default__";
+
+/// The minimum length in bytes of the seed passed to [`Storage::key_generate_from_seed`].
+pub const MIN_SEED_LENGTH: usize = 32;
+
+/// A [`Storage`] trait object, e.g. for selecting the storage implementation at runtime.
+///
+/// This is an `Arc` if the `send-sync-storage` feature is enabled, so it can be shared across threads,
+/// and a `Box` otherwise.
+#[cfg(feature = "send-sync-storage")]
+pub type DynStorage = std::sync::Arc<dyn Storage>;
+/// A [`Storage`] trait object, e.g. for selecting the storage implementation at runtime.
+///
+/// This is an `Arc` if the `send-sync-storage` feature is enabled, so it can be shared across threads,
+/// and a `Box` otherwise.
+#[cfg(not(feature = "send-sync-storage"))]
+pub type DynStorage = Box<dyn Storage>;
+
 /// An interface for Account storage implementations.
 ///
 /// The [`Storage`] interface is used for secure key operations, such as key generation and signing,
@@ -68,6 +146,11 @@ mod storage_sub_trait {
 /// # Implementation example
 ///
 /// See [`MemStore`][crate::storage::MemStore] for a test/example implementation.
+///
+/// # Testing
+///
+/// With the `storage-test-suite` feature enabled, an implementation can be checked against the shared
+/// test suite with a single call to `StorageTestSuite::run_all`.
 #[cfg_attr(not(feature = "send-sync-storage"), async_trait(?Send))]
 #[cfg_attr(feature = "send-sync-storage", async_trait)]
 pub trait Storage: storage_sub_trait::StorageSendSyncMaybe + Debug {
@@ -86,6 +169,101 @@ pub trait Storage: storage_sub_trait::StorageSendSyncMaybe + Debug {
     private_key: Option<PrivateKey>,
   ) -> Result<(CoreDID, KeyLocation)>;
 
+  /// Creates a new identity of the type declared in `did_type` for the given `network` from an existing
+  /// `keypair`, which is stored under the given `fragment`.
+  ///
+  /// - Returns [`identity_did::Error::InvalidMethodType`] if the key type of `keypair` cannot be used for signing.
+  /// - Returns an error if the DID already exists.
+  ///
+  /// Returns the generated DID represented as a [`CoreDID`] and the location at which the key was stored.
+  ///
+  /// The default implementation passes the private key of `keypair` to [`Storage::did_create`], which derives
+  /// the public key again. Implementations should override it if they can store the key pair directly.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_create_from_keypair(
+    &self,
+    did_type: DIDType,
+    network: NetworkName,
+    fragment: &str,
+    keypair: KeyPair,
+  ) -> Result<(CoreDID, KeyLocation)> {
+    match keypair.type_() {
+      KeyType::Ed25519 => {
+        self
+          .did_create(did_type, network, fragment, Some(keypair.private().clone()))
+          .await
+      }
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
+  /// Creates multiple identities at once, where each request consists of the parameters of
+  /// [`Storage::did_create`].
+  ///
+  /// - Returns [`Error::IdentityAlreadyExists`] if any of the DIDs already exists or would be created more than once.
+  ///   In that case none of the identities are created.
+  ///
+  /// Returns the generated DIDs and key locations in the order of the given `requests`.
+  ///
+  /// The default implementation calls [`Storage::did_create`] for each request and purges the identities
+  /// created so far if one of the calls fails, returning the error of the failed call. Implementations should override it if they can create
+  /// the identities more efficiently or atomically.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_create_many(
+    &self,
+    requests: Vec<(DIDType, NetworkName, String, Option<PrivateKey>)>,
+  ) -> Result<Vec<(CoreDID, KeyLocation)>> {
+    let mut created: Vec<(CoreDID, KeyLocation)> = Vec::with_capacity(requests.len());
+
+    for (did_type, network, fragment, private_key) in requests {
+      match self.did_create(did_type, network, &fragment, private_key).await {
+        Ok(output) => created.push(output),
+        Err(error) => {
+          // Roll back the identities created as part of this batch. A failed purge must neither stop the
+          // rollback of the remaining identities nor replace the original error.
+          for (did, _) in created {
+            let _ = self.did_purge(&did).await;
+          }
+
+          return Err(error);
+        }
+      }
+    }
+
+    Ok(created)
+  }
+
+  /// Copies all keys and blobs of the identity `from` to the new identity `to`, e.g. to migrate an
+  /// identity to another DID method. The identity `from` is left intact.
+  ///
+  /// - Returns [`Error::KeyVaultNotFound`] if `from` does not exist.
+  /// - Returns [`Error::IdentityAlreadyExists`] if `to` already exists.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_copy(&self, _from: &CoreDID, _to: CoreDID) -> Result<()> {
+    Err(Error::NotSupported("did_copy"))
+  }
+
+  /// Exports all keys and blobs of the given `did` as a single backup, encrypted with AES-256-GCM
+  /// under a key derived from `password`. The backup can be restored with [`Storage::did_import`].
+  ///
+  /// Returns [`Error::KeyVaultNotFound`] if `did` does not exist.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_export(&self, _did: &CoreDID, _password: &str) -> Result<Vec<u8>> {
+    Err(Error::NotSupported("did_export"))
+  }
+
+  /// Restores an identity from a backup created by [`Storage::did_export`] and returns its DID.
+  ///
+  /// - Returns [`Error::DecryptionFailure`] if `password` is wrong or `data` is corrupted.
+  /// - Returns [`Error::IdentityAlreadyExists`] if the identity already exists in this storage.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_import(&self, _data: &[u8], _password: &str) -> Result<CoreDID> {
+    Err(Error::NotSupported("did_import"))
+  }
+
   /// Removes the keys and any other state for the given `did`.
   ///
   /// This operation is idempotent: it does not fail if the given `did` does not (or no longer) exist.
@@ -96,21 +274,182 @@ pub trait Storage: storage_sub_trait::StorageSendSyncMaybe + Debug {
   /// Returns `true` if `did` exists in the list of stored DIDs.
   async fn did_exists(&self, did: &CoreDID) -> Result<bool>;
 
+  /// Returns for each of the given `dids` whether it exists in the list of stored DIDs, in the order of `dids`.
+  ///
+  /// The default implementation calls [`Storage::did_exists`] for each DID. Implementations should override
+  /// it if they can check the DIDs more efficiently.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_exists_many(&self, dids: &[CoreDID]) -> Result<Vec<bool>> {
+    let mut exists: Vec<bool> = Vec::with_capacity(dids.len());
+    for did in dids {
+      exists.push(self.did_exists(did).await?);
+    }
+    Ok(exists)
+  }
+
   /// Returns the list of stored DIDs.
   async fn did_list(&self) -> Result<Vec<CoreDID>>;
 
+  /// Returns at most `limit` DIDs of the list returned by [`Storage::did_list`], starting at `offset`.
+  ///
+  /// The DIDs are ordered by their string representation, so consecutive pages neither overlap nor skip
+  /// DIDs as long as no identities are created or purged in between. Returns an empty list once `offset`
+  /// is past the last DID.
+  ///
+  /// The default implementation sorts the full list returned by [`Storage::did_list`]. Implementations
+  /// should override it if they can avoid loading every DID.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn did_list_paged(&self, offset: usize, limit: usize) -> Result<Vec<CoreDID>> {
+    let mut dids: Vec<CoreDID> = self.did_list().await?;
+    dids.sort_unstable_by(|a, b| a.as_str().cmp(b.as_str()));
+
+    Ok(dids.into_iter().skip(offset).take(limit).collect())
+  }
+
+  /// Sets the metadata of the identity specified by `did`, e.g. to tag it with a tenant or labels,
+  /// replacing any previously set metadata.
+  ///
+  /// The metadata is stored independently of the blobs and removed by [`Storage::did_purge`].
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_set_metadata(&self, _did: &CoreDID, _metadata: BTreeMap<String, String>) -> Result<()> {
+    Err(Error::NotSupported("did_set_metadata"))
+  }
+
+  /// Returns the metadata of the identity specified by `did`, which is empty if none was set.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_get_metadata(&self, _did: &CoreDID) -> Result<BTreeMap<String, String>> {
+    Err(Error::NotSupported("did_get_metadata"))
+  }
+
+  /// Returns the DIDs whose metadata maps `key` to `value`.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_find_by_metadata(&self, _key: &str, _value: &str) -> Result<Vec<CoreDID>> {
+    Err(Error::NotSupported("did_find_by_metadata"))
+  }
+
+  /// Returns the times at which the given `did` was created and last updated in this storage.
+  ///
+  /// Every operation that modifies the keys, blobs or metadata of the `did`, such as [`Storage::key_generate`],
+  /// [`Storage::key_delete`] or [`Storage::blob_set`], updates the latter. Returns [`Error::KeyVaultNotFound`]
+  /// if the `did` does not exist.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn did_timestamps(&self, _did: &CoreDID) -> Result<(Timestamp, Timestamp)> {
+    Err(Error::NotSupported("did_timestamps"))
+  }
+
   /// Generates a new key for the given `did` with the given `key_type` and `fragment` identifier
   /// and returns the location of the newly generated key.
   async fn key_generate(&self, did: &CoreDID, key_type: KeyType, fragment: &str) -> Result<KeyLocation>;
 
+  /// Generates a new key like [`Storage::key_generate`], which becomes unusable at `expires_at`.
+  ///
+  /// Once `expires_at` has passed, operations that use the key, such as [`Storage::key_sign`] and
+  /// [`Storage::key_public`], return [`Error::KeyExpired`]. The expired key is still stored until it is
+  /// deleted or removed with [`Storage::key_prune_expired`].
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn key_generate_with_expiry(
+    &self,
+    _did: &CoreDID,
+    _key_type: KeyType,
+    _fragment: &str,
+    _expires_at: Timestamp,
+  ) -> Result<KeyLocation> {
+    Err(Error::NotSupported("key_generate_with_expiry"))
+  }
+
+  /// Deletes all keys of the given `did` whose expiry time has passed, see [`Storage::key_generate_with_expiry`].
+  ///
+  /// Returns the number of deleted keys.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn key_prune_expired(&self, _did: &CoreDID) -> Result<usize> {
+    Err(Error::NotSupported("key_prune_expired"))
+  }
+
+  /// Deterministically derives a key of the given `key_type` from `seed` for the given `did` with the
+  /// given `fragment` identifier and returns the location of the derived key.
+  ///
+  /// The private key is the SHA-256 digest of the name of `key_type` followed by `seed`, so the same
+  /// `seed` and `fragment` always yield the same key and location, while different key types never
+  /// share a private key. The `seed` should contain at least 32 bytes of entropy.
+  ///
+  /// Returns [`Error::SeedTooShort`] if the `seed` is shorter than [`MIN_SEED_LENGTH`], since a short seed
+  /// yields a key that can be guessed.
+  ///
+  /// The default implementation derives the key in memory and stores it with [`Storage::key_insert`].
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_generate_from_seed(
+    &self,
+    did: &CoreDID,
+    key_type: KeyType,
+    fragment: &str,
+    seed: &[u8],
+  ) -> Result<KeyLocation> {
+    if seed.len() < MIN_SEED_LENGTH {
+      return Err(Error::SeedTooShort(seed.len()));
+    }
+
+    let mut input: Zeroizing<Vec<u8>> = Zeroizing::new(Vec::with_capacity(key_type.as_str().len() + seed.len()));
+    input.extend_from_slice(key_type.as_str().as_bytes());
+    input.extend_from_slice(seed);
+
+    let mut private_key: Zeroizing<[u8; SHA256_LEN]> = Zeroizing::new([0; SHA256_LEN]);
+    SHA256(&input, &mut private_key);
+
+    let keypair: KeyPair = KeyPair::try_from_private_key_bytes(key_type, private_key.as_ref())?;
+    let location: KeyLocation = KeyLocation::new(key_type, fragment.to_owned(), keypair.public().as_ref());
+    self.key_insert(did, &location, keypair.private().clone()).await?;
+
+    Ok(location)
+  }
+
   /// Inserts a private key at the specified `location`.
   ///
   /// If a key at `location` exists, it is overwritten.
+  ///
+  /// Returns [`Error::InvalidPrivateKey`] if `private_key` is not a valid key for the key type of `location`.
+  /// Note that Ed25519 and X25519 private keys have the same length, so they cannot be told apart.
   async fn key_insert(&self, did: &CoreDID, location: &KeyLocation, private_key: PrivateKey) -> Result<()>;
 
   /// Retrieves the public key from `location`.
   async fn key_public(&self, did: &CoreDID, location: &KeyLocation) -> Result<PublicKey>;
 
+  /// Retrieves the public keys from `locations`, in the order of `locations`.
+  ///
+  /// Returns [`Error::KeyNotFoundAt`] with the first location that does not hold a key.
+  ///
+  /// The default implementation calls [`Storage::key_exists`] and [`Storage::key_public`] for each
+  /// location. Implementations should override it if they can retrieve the keys more efficiently.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_public_many(&self, did: &CoreDID, locations: &[KeyLocation]) -> Result<Vec<PublicKey>> {
+    let mut public_keys: Vec<PublicKey> = Vec::with_capacity(locations.len());
+    for location in locations {
+      if !self.key_exists(did, location).await? {
+        return Err(Error::KeyNotFoundAt(location.clone()));
+      }
+      public_keys.push(self.key_public(did, location).await?);
+    }
+    Ok(public_keys)
+  }
+
+  /// Retrieves the [`KeyMetadata`] of the key at `location`.
+  ///
+  /// Storages that support [`Storage::key_generate_with_expiry`] also return the metadata of expired keys,
+  /// with [`KeyMetadata::expires_at`] set.
+  ///
+  /// The default implementation derives the metadata from [`Storage::key_public`] and `location`,
+  /// without a creation or expiry time.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_metadata(&self, did: &CoreDID, location: &KeyLocation) -> Result<KeyMetadata> {
+    let public_key: PublicKey = self.key_public(did, location).await?;
+    Ok(KeyMetadata::new(public_key, location.key_type, None))
+  }
+
   /// Deletes the key at `location`.
   ///
   /// This operation is idempotent: it does not fail if the key does not exist.
@@ -118,16 +457,167 @@ pub trait Storage: storage_sub_trait::StorageSendSyncMaybe + Debug {
   /// Returns `true` if it removed the key, `false` if nothing was done.
   async fn key_delete(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool>;
 
+  /// Deletes the key at `location` like [`Storage::key_delete`], unless it is the last key of the `did`
+  /// that can create signatures, which would leave the identity unable to sign updates.
+  ///
+  /// Returns [`Error::CannotRemoveLastSigningKey`] and leaves the storage untouched in that case.
+  ///
+  /// The default implementation is composed of [`Storage::key_list`] and [`Storage::key_delete`] and is
+  /// therefore not atomic. Implementations should override it if they can perform the check and deletion
+  /// in a single operation.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_delete_checked(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool> {
+    if location.can_sign() {
+      let locations: Vec<KeyLocation> = self.key_list(did).await?;
+      // Key locations compare equal regardless of their key type, so the type is checked separately.
+      let is_stored: bool = locations.iter().any(|other| other == location && other.can_sign());
+      let remaining: usize = locations
+        .iter()
+        .filter(|other| *other != location && other.can_sign())
+        .count();
+
+      if is_stored && remaining == 0 {
+        return Err(Error::CannotRemoveLastSigningKey);
+      }
+    }
+
+    self.key_delete(did, location).await
+  }
+
+  /// Replaces the key at `old` with a newly generated key of the same [`KeyType`] under the given `fragment`.
+  ///
+  /// - Returns [`Error::KeyNotFound`] and leaves the storage untouched if no key exists at `old`.
+  /// - Removes the key at `old` once the new key was stored.
+  ///
+  /// Returns the location of the newly generated key.
+  ///
+  /// The default implementation is composed of [`Storage::key_exists`], [`Storage::key_generate`] and
+  /// [`Storage::key_delete`] and is therefore not atomic. Implementations should override it if they
+  /// can perform the rotation in a single operation.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_rotate(&self, did: &CoreDID, old: &KeyLocation, fragment: &str) -> Result<KeyLocation> {
+    if !self.key_exists(did, old).await? {
+      return Err(Error::KeyNotFound);
+    }
+
+    let location: KeyLocation = self.key_generate(did, old.key_type, fragment).await?;
+    self.key_delete(did, old).await?;
+
+    Ok(location)
+  }
+
+  /// Moves the key at `from` to the location of the same key under `new_fragment`,
+  /// as computed by [`KeyLocation::with_fragment`].
+  ///
+  /// - Returns [`Error::KeyNotFound`] and leaves the storage untouched if no key exists at `from`.
+  /// - Overwrites a key that already exists at the new location.
+  ///
+  /// Returns the new location of the key.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn key_move(&self, _did: &CoreDID, _from: &KeyLocation, _new_fragment: &str) -> Result<KeyLocation> {
+    Err(Error::NotSupported("key_move"))
+  }
+
   /// Signs `data` with the private key at the specified `location`.
   async fn key_sign(&self, did: &CoreDID, location: &KeyLocation, data: Vec<u8>) -> Result<Signature>;
 
+  /// Signs the JWS `signing_input` with the private key at the specified `location` and returns the
+  /// base64url-encoded signature, as used in the compact serialization of a JWS.
+  ///
+  /// Returns an error if the key type of `location` does not match `alg`.
+  ///
+  /// The default implementation signs with [`Storage::key_sign`].
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_sign_jws(
+    &self,
+    did: &CoreDID,
+    location: &KeyLocation,
+    signing_input: &[u8],
+    alg: JwsAlgorithm,
+  ) -> Result<String> {
+    if location.key_type != alg.key_type() {
+      return Err(identity_did::Error::InvalidMethodType.into());
+    }
+
+    let signature: Signature = self.key_sign(did, location, signing_input.to_vec()).await?;
+
+    Ok(BaseEncoding::encode(signature.as_bytes(), Base::Base64Url))
+  }
+
+  /// Verifies the `signature` of `data` with the public key at the specified `location`.
+  ///
+  /// Returns `false` if the signature is invalid. Returns an error if the key at `location`
+  /// cannot be used for signatures.
+  ///
+  /// The default implementation verifies the signature with the key returned by [`Storage::key_public`].
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn key_verify(
+    &self,
+    did: &CoreDID,
+    location: &KeyLocation,
+    data: &[u8],
+    signature: &Signature,
+  ) -> Result<bool> {
+    let public_key: PublicKey = self.key_public(did, location).await?;
+
+    match location.key_type {
+      KeyType::Ed25519 => Ok(Ed25519::verify(data, signature.as_bytes(), public_key.as_ref()).is_ok()),
+      KeyType::X25519 => Err(identity_did::Error::InvalidMethodType.into()),
+    }
+  }
+
   /// Returns `true` if a key exists at the specified `location`.
   async fn key_exists(&self, did: &CoreDID, location: &KeyLocation) -> Result<bool>;
 
+  /// Returns the locations of all keys stored for the given `did`.
+  ///
+  /// The order of the returned locations is unspecified, but no location is returned more than once.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn key_list(&self, _did: &CoreDID) -> Result<Vec<KeyLocation>> {
+    Err(Error::NotSupported("key_list"))
+  }
+
+  /// Returns the locations of all keys of the given `key_type` stored for the given `did`, e.g. to collect
+  /// the [`X25519`](KeyType::X25519) keys for the `keyAgreement` section of a document.
+  ///
+  /// The default implementation filters the locations returned by [`Storage::key_list`].
+  async fn key_list_by_type(&self, did: &CoreDID, key_type: KeyType) -> Result<Vec<KeyLocation>> {
+    let mut locations: Vec<KeyLocation> = self.key_list(did).await?;
+    locations.retain(|location| location.key_type == key_type);
+    Ok(locations)
+  }
+
+  /// Deletes all keys stored for the given `did`, while the `did` itself and its blobs are retained.
+  ///
+  /// Returns the number of removed keys, which is `0` if the `did` has no keys.
+  /// Returns [`Error::KeyVaultNotFound`] if the `did` does not exist.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn key_delete_all(&self, _did: &CoreDID) -> Result<usize> {
+    Err(Error::NotSupported("key_delete_all"))
+  }
+
+  /// Performs an X25519 Diffie-Hellman key exchange between the private key at `location` and the
+  /// given `public_key` and returns the resulting shared secret.
+  ///
+  /// The private key never leaves the storage. Returns [`Error::InvalidPrivateKey`] if the key at
+  /// `location` is not an [`X25519`](KeyType::X25519) key.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn key_exchange(&self, _did: &CoreDID, _location: &KeyLocation, _public_key: &PublicKey) -> Result<[u8; 32]> {
+    Err(Error::NotSupported("key_exchange"))
+  }
+
   /// Encrypts the given `plaintext` with the specified `encryption_algorithm` and `cek_algorithm`.
   ///
+  /// The sender-authenticated [`CekAlgorithm::ECDH_1PU`] and [`CekAlgorithm::ECDH_1PU_A256KW`] additionally
+  /// require the location of the sender's X25519 key in `sender_key`. It is ignored by the other algorithms.
+  ///
   /// Returns an [`EncryptedData`] instance.
   #[cfg(feature = "encryption")]
+  #[allow(clippy::too_many_arguments)]
   async fn data_encrypt(
     &self,
     did: &CoreDID,
@@ -136,10 +626,34 @@ pub trait Storage: storage_sub_trait::StorageSendSyncMaybe + Debug {
     encryption_algorithm: &EncryptionAlgorithm,
     cek_algorithm: &CekAlgorithm,
     public_key: PublicKey,
+    sender_key: Option<&KeyLocation>,
   ) -> Result<EncryptedData>;
 
+  /// Encrypts the given `plaintext` for the holder of `public_key` with the specified `encryption_algorithm`
+  /// and `cek_algorithm`, without involving any DID in this storage.
+  ///
+  /// This allows one-off encryption to an external recipient. Since the sender is anonymous, the
+  /// sender-authenticated [`CekAlgorithm::ECDH_1PU`] and [`CekAlgorithm::ECDH_1PU_A256KW`] cannot be used;
+  /// use [`Storage::data_encrypt`] with a sender key for those.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  #[cfg(feature = "encryption")]
+  async fn data_encrypt_anonymous(
+    &self,
+    _plaintext: Vec<u8>,
+    _associated_data: Vec<u8>,
+    _encryption_algorithm: &EncryptionAlgorithm,
+    _cek_algorithm: &CekAlgorithm,
+    _public_key: PublicKey,
+  ) -> Result<EncryptedData> {
+    Err(Error::NotSupported("data_encrypt_anonymous"))
+  }
+
   /// Decrypts the given `data` with the specified `encryption_algorithm` and `cek_algorithm`.
   ///
+  /// The sender-authenticated [`CekAlgorithm::ECDH_1PU`] and [`CekAlgorithm::ECDH_1PU_A256KW`] additionally
+  /// require the sender's X25519 public key in `sender_public_key`. It is ignored by the other algorithms.
+  ///
   /// Returns the decrypted text.
   #[cfg(feature = "encryption")]
   async fn data_decrypt(
@@ -149,14 +663,293 @@ pub trait Storage: storage_sub_trait::StorageSendSyncMaybe + Debug {
     encryption_algorithm: &EncryptionAlgorithm,
     cek_algorithm: &CekAlgorithm,
     private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
   ) -> Result<Vec<u8>>;
 
+  /// Decrypts the given `data` like [`Storage::data_decrypt`], but writes the decrypted text into `out`,
+  /// so the same buffer can be reused for many decryptions.
+  ///
+  /// On success, the previous content of `out` is replaced by the decrypted text.
+  ///
+  /// Returns the length of the decrypted text.
+  ///
+  /// The default implementation copies the output of [`Storage::data_decrypt`] into `out`.
+  #[cfg(feature = "encryption")]
+  #[allow(clippy::too_many_arguments)]
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn data_decrypt_into(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    out: &mut Vec<u8>,
+  ) -> Result<usize> {
+    let plaintext: Vec<u8> = self
+      .data_decrypt(
+        did,
+        data,
+        encryption_algorithm,
+        cek_algorithm,
+        private_key,
+        sender_public_key,
+      )
+      .await?;
+    out.clear();
+    out.extend_from_slice(&plaintext);
+    Ok(plaintext.len())
+  }
+
+  /// Decrypts the given `data` like [`Storage::data_decrypt`], but if `convert_ed25519` is `true`, also
+  /// accepts an Ed25519 key at `private_key`, from which the X25519 private key is derived.
+  ///
+  /// The corresponding public key to encrypt to is the Ed25519 public key converted with
+  /// [`X25519::ed25519_to_x25519_public`](identity_core::crypto::X25519::ed25519_to_x25519_public).
+  /// Since this reuses a signing key for key agreement, the conversion has to be enabled explicitly.
+  ///
+  /// The default implementation calls [`Storage::data_decrypt`] if `convert_ed25519` is `false` and returns
+  /// [`Error::NotSupported`] otherwise.
+  #[cfg(feature = "encryption")]
+  #[allow(clippy::too_many_arguments)]
+  async fn data_decrypt_with_conversion(
+    &self,
+    did: &CoreDID,
+    data: EncryptedData,
+    encryption_algorithm: &EncryptionAlgorithm,
+    cek_algorithm: &CekAlgorithm,
+    private_key: &KeyLocation,
+    sender_public_key: Option<PublicKey>,
+    convert_ed25519: bool,
+  ) -> Result<Vec<u8>> {
+    if convert_ed25519 {
+      return Err(Error::NotSupported("data_decrypt_with_conversion"));
+    }
+    self
+      .data_decrypt(
+        did,
+        data,
+        encryption_algorithm,
+        cek_algorithm,
+        private_key,
+        sender_public_key,
+      )
+      .await
+  }
+
+  /// Encrypts the data read from `reader` and writes the encrypted stream to `writer`, without holding
+  /// the entire data in memory.
+  ///
+  /// The content encryption key is established as in [`Storage::data_encrypt`]. The `associated_data`
+  /// is authenticated but not written to the stream, so it must be passed to [`Storage::data_decrypt_stream`]
+  /// as well. Since the stream has no single tag to derive the key-wrapping key from,
+  /// [`CekAlgorithm::ECDH_1PU_A256KW`] is not supported.
+  ///
+  /// # Stream Format
+  ///
+  /// The stream starts with a header, followed by one or more chunks:
+  ///
+  /// ```text
+  /// header = ephemeral_public_key (32 bytes)
+  ///          || encrypted_cek_length (u16, big-endian) || encrypted_cek
+  ///          || base_nonce (nonce length of the encryption algorithm)
+  /// chunk  = flag (1 byte: 0x00, or 0x01 for the last chunk)
+  ///          || plaintext_length (u32, big-endian) || ciphertext || tag
+  /// ```
+  ///
+  /// The `encrypted_cek` is empty unless the `cek_algorithm` uses key wrapping. Every chunk holds at most
+  /// 64 KiB of plaintext. The nonce of the `i`-th chunk, starting at zero, is the `base_nonce` with its last
+  /// eight bytes XOR-ed with `i` as a big-endian `u64`. The associated data of every chunk is the
+  /// `associated_data` followed by the chunk's flag, which prevents undetected truncation of the stream.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  #[cfg(feature = "encryption")]
+  #[allow(clippy::too_many_arguments)]
+  async fn data_encrypt_stream(
+    &self,
+    _did: &CoreDID,
+    _reader: &mut dyn DataStreamReader,
+    _writer: &mut dyn DataStreamWriter,
+    _associated_data: Vec<u8>,
+    _encryption_algorithm: &EncryptionAlgorithm,
+    _cek_algorithm: &CekAlgorithm,
+    _public_key: PublicKey,
+    _sender_key: Option<&KeyLocation>,
+  ) -> Result<()> {
+    Err(Error::NotSupported("data_encrypt_stream"))
+  }
+
+  /// Decrypts the stream created by [`Storage::data_encrypt_stream`] read from `reader` and writes the
+  /// decrypted data to `writer`.
+  ///
+  /// Every chunk is authenticated before it is written to `writer`. If an error is returned, chunks
+  /// preceding the invalid chunk may already have been written and must be discarded.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  #[cfg(feature = "encryption")]
+  #[allow(clippy::too_many_arguments)]
+  async fn data_decrypt_stream(
+    &self,
+    _did: &CoreDID,
+    _reader: &mut dyn DataStreamReader,
+    _writer: &mut dyn DataStreamWriter,
+    _associated_data: Vec<u8>,
+    _encryption_algorithm: &EncryptionAlgorithm,
+    _cek_algorithm: &CekAlgorithm,
+    _private_key: &KeyLocation,
+    _sender_public_key: Option<PublicKey>,
+  ) -> Result<()> {
+    Err(Error::NotSupported("data_decrypt_stream"))
+  }
+
   /// Stores an arbitrary blob for the identity specified by `did`.
+  ///
+  /// This is the blob named [`DEFAULT_BLOB_NAME`].
   async fn blob_set(&self, did: &CoreDID, blob: Vec<u8>) -> Result<()>;
 
   /// Returns the blob stored by the identity specified by `did`.
+  ///
+  /// This is the blob named [`DEFAULT_BLOB_NAME`].
   async fn blob_get(&self, did: &CoreDID) -> Result<Option<Vec<u8>>>;
 
+  /// Removes the blob stored by the identity specified by `did`, leaving its keys intact.
+  ///
+  /// This operation is idempotent: it does not fail if no blob is stored.
+  ///
+  /// Returns `true` if a blob was removed, `false` if nothing was done.
+  ///
+  /// This is the blob named [`DEFAULT_BLOB_NAME`].
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn blob_delete(&self, _did: &CoreDID) -> Result<bool> {
+    Err(Error::NotSupported("blob_delete"))
+  }
+
+  /// Stores `new` as the blob of the identity specified by `did`, but only if the currently stored blob
+  /// equals `expected`, where `None` expects that no blob is stored.
+  ///
+  /// Returns `true` if the blob was written and `false` if the stored blob did not match. This allows
+  /// concurrent writers to detect and retry lost updates.
+  ///
+  /// This is the blob named [`DEFAULT_BLOB_NAME`].
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn blob_compare_and_swap(&self, _did: &CoreDID, _expected: Option<&[u8]>, _new: Vec<u8>) -> Result<bool> {
+    Err(Error::NotSupported("blob_compare_and_swap"))
+  }
+
+  /// Atomically appends `data` to the blob of the identity specified by `did`, creating the blob if none
+  /// is stored, and returns the length of the blob after appending.
+  ///
+  /// Concurrent appends never overwrite each other, which makes the blob suitable for append-only logs.
+  ///
+  /// This is the blob named [`DEFAULT_BLOB_NAME`].
+  ///
+  /// The default implementation retries [`Storage::blob_compare_and_swap`] until no concurrent write
+  /// happened in between, and therefore returns [`Error::NotSupported`] if that method is not supported.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_append(&self, did: &CoreDID, data: &[u8]) -> Result<usize> {
+    loop {
+      let current: Option<Vec<u8>> = self.blob_get(did).await?;
+
+      let mut blob: Vec<u8> = current.clone().unwrap_or_default();
+      blob.extend_from_slice(data);
+      let length: usize = blob.len();
+
+      if self.blob_compare_and_swap(did, current.as_deref(), blob).await? {
+        return Ok(length);
+      }
+    }
+  }
+
+  /// Stores an arbitrary blob under `name` for the identity specified by `did`.
+  ///
+  /// The default implementation only supports the [`DEFAULT_BLOB_NAME`], which it delegates to
+  /// [`Storage::blob_set`], and returns [`Error::NotSupported`] for any other name.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_set_named(&self, did: &CoreDID, name: &str, blob: Vec<u8>) -> Result<()> {
+    if name == DEFAULT_BLOB_NAME {
+      self.blob_set(did, blob).await
+    } else {
+      Err(Error::NotSupported("blob_set_named"))
+    }
+  }
+
+  /// Returns the blob stored under `name` by the identity specified by `did`.
+  ///
+  /// The default implementation only supports the [`DEFAULT_BLOB_NAME`], which it delegates to
+  /// [`Storage::blob_get`], and returns [`Error::NotSupported`] for any other name.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_get_named(&self, did: &CoreDID, name: &str) -> Result<Option<Vec<u8>>> {
+    if name == DEFAULT_BLOB_NAME {
+      self.blob_get(did).await
+    } else {
+      Err(Error::NotSupported("blob_get_named"))
+    }
+  }
+
+  /// Returns the names of all blobs stored by the identity specified by `did`.
+  ///
+  /// The default implementation only knows about the [`DEFAULT_BLOB_NAME`].
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(did = %did_hash(did))))]
+  async fn blob_list_names(&self, did: &CoreDID) -> Result<Vec<String>> {
+    match self.blob_get(did).await? {
+      Some(_) => Ok(vec![DEFAULT_BLOB_NAME.to_owned()]),
+      None => Ok(Vec::new()),
+    }
+  }
+
+  /// Returns the number of stored DIDs and keys and the total size of all stored blobs.
+  ///
+  /// The default implementation returns [`Error::NotSupported`].
+  async fn stats(&self) -> Result<StorageStats> {
+    Err(Error::NotSupported("stats"))
+  }
+
   /// Persists any unsaved changes.
+  ///
+  /// If this fails, implementations must leave their state consistent: all changes made before the call
+  /// remain visible to subsequent operations and are persisted by the next successful call.
   async fn flush_changes(&self) -> Result<()>;
+
+  /// Runs the mutations performed by the `transaction` future as a single unit and persists them with a
+  /// single call to [`Storage::flush_changes`] once it succeeds.
+  ///
+  /// If the `transaction` fails, its error is returned without flushing. Implementations that support it roll
+  /// back the changes made by the `transaction`, others keep them, so that they are persisted by the next
+  /// successful flush. Transactions are not isolated: operations running concurrently may observe the changes
+  /// made by the `transaction`, and their own changes may be discarded by a rollback.
+  ///
+  /// The `transaction` usually borrows the storage it runs on, and can pass its results out through
+  /// borrowed variables:
+  ///
+  /// ```ignore
+  /// let mut location: Option<KeyLocation> = None;
+  /// storage
+  ///   .transaction(Box::pin(async {
+  ///     location = Some(storage.key_generate(&did, KeyType::Ed25519, "key-2").await?);
+  ///     storage.blob_set(&did, blob).await
+  ///   }))
+  ///   .await?;
+  /// ```
+  ///
+  /// The default implementation does not roll back. Implementations should override it if they can.
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn transaction<'s>(&'s self, transaction: TransactionFuture<'s>) -> Result<()> {
+    transaction.await?;
+    self.flush_changes().await
+  }
+
+  /// Persists any unsaved changes and releases the resources held by the storage, such as connections.
+  ///
+  /// Callers should prefer `close` over relying on `Drop`, since `Drop` cannot be async and therefore
+  /// cannot flush changes or shut down connections gracefully. The storage must not be used after it
+  /// was closed; implementations that release resources fail subsequent operations.
+  ///
+  /// The default implementation calls [`Storage::flush_changes`].
+  #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all))]
+  async fn close(&self) -> Result<()> {
+    self.flush_changes().await
+  }
 }
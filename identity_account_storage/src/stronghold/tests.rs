@@ -216,6 +216,27 @@ mod stronghold_storage_test_suite {
       .unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_did_create_from_keypair() {
+    StorageTestSuite::did_create_from_keypair_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_did_create_web() {
+    StorageTestSuite::did_create_web_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_did_create_invalid_network() {
+    StorageTestSuite::did_create_invalid_network_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_key_generate() {
     StorageTestSuite::key_generate_test(test_stronghold().await)
@@ -223,6 +244,13 @@ mod stronghold_storage_test_suite {
       .unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_key_generate_from_seed() {
+    StorageTestSuite::key_generate_from_seed_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_key_delete() {
     StorageTestSuite::key_delete_test(test_stronghold().await)
@@ -230,11 +258,32 @@ mod stronghold_storage_test_suite {
       .unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_key_public_many() {
+    StorageTestSuite::key_public_many_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_key_metadata() {
+    StorageTestSuite::key_metadata_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_did_list() {
     StorageTestSuite::did_list_test(test_stronghold().await).await.unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_did_list_paged() {
+    StorageTestSuite::did_list_paged_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_key_insert() {
     StorageTestSuite::key_insert_test(test_stronghold().await)
@@ -242,6 +291,20 @@ mod stronghold_storage_test_suite {
       .unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_key_insert_indexed() {
+    StorageTestSuite::key_insert_indexed_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_key_insert_type_mismatch() {
+    StorageTestSuite::key_insert_type_mismatch_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_key_sign_ed25519() {
     StorageTestSuite::key_sign_ed25519_test(test_stronghold().await)
@@ -249,6 +312,27 @@ mod stronghold_storage_test_suite {
       .unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_signing_determinism() {
+    StorageTestSuite::signing_determinism_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_key_verify() {
+    StorageTestSuite::key_verify_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_key_sign_jws() {
+    StorageTestSuite::key_sign_jws_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_key_value_store() {
     StorageTestSuite::key_value_store_test(test_stronghold().await)
@@ -261,10 +345,31 @@ mod stronghold_storage_test_suite {
     StorageTestSuite::did_purge_test(test_stronghold().await).await.unwrap()
   }
 
+  #[tokio::test]
+  async fn test_stronghold_did_purge_missing() {
+    StorageTestSuite::did_purge_missing_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
   #[tokio::test]
   async fn test_stronghold_encryption() {
     StorageTestSuite::encryption_test(test_stronghold().await, test_stronghold().await)
       .await
       .unwrap()
   }
+
+  #[tokio::test]
+  async fn test_stronghold_encrypted_data_version() {
+    StorageTestSuite::encrypted_data_version_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_stronghold_data_decrypt_into() {
+    StorageTestSuite::data_decrypt_into_test(test_stronghold().await)
+      .await
+      .unwrap()
+  }
 }
@@ -0,0 +1,14 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+/// Decides which data is kept for a DID that exists in both stores passed to
+/// [`MemStore::merge`](crate::storage::MemStore::merge).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictPolicy {
+  /// Keeps the keys and blobs of the store that is merged into.
+  KeepSelf,
+  /// Replaces the keys and blobs of the store that is merged into with those of the other store.
+  KeepOther,
+  /// Fails without merging anything if any DID exists in both stores.
+  Error,
+}
@@ -1,9 +1,119 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use identity_did::did::CoreDID;
+use identity_iota_core::did::IotaDID;
+use identity_iota_core::tangle::NetworkName;
+
+use crate::error::Error;
+use crate::error::Result;
+
 /// Supported types representing a DID that can be generated by the [`Storage`](crate::storage::Storage) interface.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum DIDType {
   /// Corresponds to [`IotaDID`](identity_iota_core::did::IotaDID).
   IotaDID,
+  /// Corresponds to a `did:web` DID hosted on `domain`.
+  ///
+  /// The generated DID has the form `did:web:<domain>:<tag>`, where the tag is derived from the
+  /// public key in the same way as for an [`IotaDID`](identity_iota_core::did::IotaDID).
+  WebDID {
+    /// The domain hosting the DID document, optionally including a port.
+    domain: String,
+  },
+}
+
+impl DIDType {
+  /// The method name of `did:web` DIDs.
+  const WEB_METHOD: &'static str = "web";
+
+  /// Derives the DID of this type from the given `public_key`.
+  ///
+  /// The `network` is ignored by DID types that are not bound to a network. Callers check it with
+  /// [`DIDType::validate_network`] first, otherwise an invalid name fails with [`Error::DIDCreationError`].
+  pub(crate) fn create_did(&self, public_key: &[u8], network: NetworkName) -> Result<CoreDID> {
+    match self {
+      DIDType::IotaDID => IotaDID::new_with_network(public_key, network)
+        .map(Into::into)
+        .map_err(Error::DIDCreationError),
+      DIDType::WebDID { domain } => {
+        // A port must be percent-encoded, since colons separate the path segments of a did:web.
+        let domain: String = domain.replace(':', "%3A");
+        let tag: String = IotaDID::encode_key(public_key);
+
+        CoreDID::parse(format!("did:{}:{}:{}", Self::WEB_METHOD, domain, tag)).map_err(Error::WebDIDCreationError)
+      }
+    }
+  }
+
+  /// Returns [`Error::InvalidNetwork`] if the `network` name is not spec-compliant and used by this DID type.
+  ///
+  /// A [`NetworkName`] is only validated when it is constructed from a string, not when it is deserialized,
+  /// so storages check it before creating any state for a new identity.
+  pub(crate) fn validate_network(&self, network: &NetworkName) -> Result<()> {
+    match self {
+      DIDType::IotaDID => NetworkName::validate_network_name(network.as_ref())
+        .map_err(|_| Error::InvalidNetwork(network.as_ref().to_owned())),
+      DIDType::WebDID { .. } => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use identity_core::convert::FromJson;
+  use identity_core::crypto::KeyPair;
+  use identity_core::crypto::KeyType;
+  use identity_iota_core::tangle::NetworkName;
+
+  use super::DIDType;
+  use crate::Error;
+
+  #[test]
+  fn test_validate_network() {
+    // Deserialization does not validate the name, which exceeds the maximum length.
+    let network: NetworkName = NetworkName::from_json("\"toolongnet\"").unwrap();
+
+    let error: Error = DIDType::IotaDID.validate_network(&network).unwrap_err();
+
+    assert!(matches!(error, Error::InvalidNetwork(ref name) if name == "toolongnet"));
+    assert_eq!(error.to_string(), "invalid network name: toolongnet");
+
+    // did:web is not bound to a network.
+    let did_type: DIDType = DIDType::WebDID {
+      domain: "example.com".to_owned(),
+    };
+    assert!(did_type.validate_network(&network).is_ok());
+  }
+
+  #[test]
+  fn test_create_did_invalid_network() {
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let network: NetworkName = NetworkName::from_json("\"toolongnet\"").unwrap();
+
+    // Without prior validation, the name is rejected by `IotaDID::new_with_network`.
+    let error: Error = DIDType::IotaDID
+      .create_did(keypair.public().as_ref(), network)
+      .unwrap_err();
+
+    assert!(matches!(
+      error,
+      Error::DIDCreationError(identity_iota_core::Error::InvalidNetworkName)
+    ));
+    assert_eq!(error.to_string(), "DID creation failed: Invalid Network Name");
+  }
+
+  #[test]
+  fn test_create_did_invalid_web_domain() {
+    let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+    let did_type: DIDType = DIDType::WebDID {
+      domain: "example com".to_owned(),
+    };
+
+    let error: Error = did_type
+      .create_did(keypair.public().as_ref(), NetworkName::try_from("main").unwrap())
+      .unwrap_err();
+
+    assert!(matches!(error, Error::WebDIDCreationError(_)));
+  }
 }
@@ -4,6 +4,9 @@
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::error::Error;
+use crate::error::Result;
+
 /// Supported algorithms used to determine and potentially encrypt the content encryption key (CEK).
 #[allow(non_camel_case_types)]
 #[non_exhaustive]
@@ -11,8 +14,19 @@ use serde::Serialize;
 pub enum CekAlgorithm {
   /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF.
   ECDH_ES(AgreementInfo),
+  /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF with AES128 key wrapping.
+  ECDH_ES_A128KW(AgreementInfo),
+  /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF with AES192 key wrapping.
+  ECDH_ES_A192KW(AgreementInfo),
   /// Elliptic Curve Diffie-Hellman Ephemeral Static key agreement using Concat KDF with AES256 key wrapping.
   ECDH_ES_A256KW(AgreementInfo),
+  /// Elliptic Curve Diffie-Hellman One-Pass Unified Model key agreement using Concat KDF.
+  ECDH_1PU(AgreementInfo),
+  /// Elliptic Curve Diffie-Hellman One-Pass Unified Model key agreement using Concat KDF with AES256 key wrapping.
+  ///
+  /// The tag of the content encryption is appended to the `SuppPubInfo` of the key derivation, as required by
+  /// draft-madden-jose-ecdh-1pu-04.
+  ECDH_1PU_A256KW(AgreementInfo),
 }
 
 impl CekAlgorithm {
@@ -20,12 +34,58 @@ impl CekAlgorithm {
   pub const fn name(&self) -> &'static str {
     match self {
       CekAlgorithm::ECDH_ES(_) => "ECDH-ES",
+      CekAlgorithm::ECDH_ES_A128KW(_) => "ECDH-ES+A128KW",
+      CekAlgorithm::ECDH_ES_A192KW(_) => "ECDH-ES+A192KW",
       CekAlgorithm::ECDH_ES_A256KW(_) => "ECDH-ES+A256KW",
+      CekAlgorithm::ECDH_1PU(_) => "ECDH-1PU",
+      CekAlgorithm::ECDH_1PU_A256KW(_) => "ECDH-1PU+A256KW",
+    }
+  }
+
+  /// Returns the [`KeyDerivation`] selected in the [`AgreementInfo`] of the algorithm.
+  pub const fn key_derivation(&self) -> KeyDerivation {
+    match self {
+      CekAlgorithm::ECDH_ES(agreement)
+      | CekAlgorithm::ECDH_ES_A128KW(agreement)
+      | CekAlgorithm::ECDH_ES_A192KW(agreement)
+      | CekAlgorithm::ECDH_ES_A256KW(agreement)
+      | CekAlgorithm::ECDH_1PU(agreement)
+      | CekAlgorithm::ECDH_1PU_A256KW(agreement) => agreement.key_derivation,
+    }
+  }
+}
+
+/// Supported functions to derive a key from the shared secret of a key agreement.
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum KeyDerivation {
+  /// The Concat KDF (using SHA-256) as defined in Section 5.8.1 of NIST.800-56A.
+  ConcatKdf,
+  /// HKDF (using HMAC-SHA256) as defined in RFC 5869, with an empty salt.
+  ///
+  /// The `info` parameter has the layout of the Concat KDF's `OtherInfo`:
+  /// `AlgorithmID || PartyUInfo || PartyVInfo || SuppPubInfo || SuppPrivInfo`, where `AlgorithmID`,
+  /// `PartyUInfo` and `PartyVInfo` are each prefixed with their length as a 32-bit big-endian integer.
+  HkdfSha256,
+}
+
+impl KeyDerivation {
+  /// Returns the name of the key derivation function.
+  pub const fn name(&self) -> &'static str {
+    match self {
+      KeyDerivation::ConcatKdf => "Concat KDF",
+      KeyDerivation::HkdfSha256 => "HKDF-SHA256",
     }
   }
 }
 
-/// Agreement information used as the input for the Concat KDF.
+impl Default for KeyDerivation {
+  fn default() -> Self {
+    KeyDerivation::ConcatKdf
+  }
+}
+
+/// Agreement information used as the input for the key derivation.
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct AgreementInfo {
   /// Agreement PartyUInfo.
@@ -36,16 +96,80 @@ pub struct AgreementInfo {
   pub pub_info: Vec<u8>,
   /// SuppPrivInfo.
   pub priv_info: Vec<u8>,
+  /// The function deriving the key from the shared secret, [`KeyDerivation::ConcatKdf`] by default.
+  #[serde(default)]
+  pub key_derivation: KeyDerivation,
 }
 
 impl AgreementInfo {
   /// Creates a new [`AgreementInfo`] instance.
+  ///
+  /// See [`AgreementInfo::try_new`] for a version that validates the lengths of the inputs.
   pub fn new(apu: Vec<u8>, apv: Vec<u8>, pub_info: Vec<u8>, priv_info: Vec<u8>) -> Self {
     Self {
       apu,
       apv,
       pub_info,
       priv_info,
+      key_derivation: KeyDerivation::ConcatKdf,
     }
   }
+
+  /// Selects the function deriving the key from the shared secret.
+  pub fn with_key_derivation(mut self, key_derivation: KeyDerivation) -> Self {
+    self.key_derivation = key_derivation;
+    self
+  }
+
+  /// Creates a new [`AgreementInfo`] instance.
+  ///
+  /// Returns [`Error::InvalidAgreementInfo`] if any of the inputs is invalid, see [`AgreementInfo::validate`].
+  pub fn try_new(apu: Vec<u8>, apv: Vec<u8>, pub_info: Vec<u8>, priv_info: Vec<u8>) -> Result<Self> {
+    let agreement: Self = Self::new(apu, apv, pub_info, priv_info);
+    agreement.validate()?;
+    Ok(agreement)
+  }
+
+  /// Checks that every input is at most `u32::MAX` bytes long, since the Concat KDF prefixes
+  /// the inputs with their length as a 32-bit integer.
+  ///
+  /// Returns [`Error::InvalidAgreementInfo`] otherwise.
+  pub fn validate(&self) -> Result<()> {
+    check_length("apu", self.apu.len())?;
+    check_length("apv", self.apv.len())?;
+    check_length("pub_info", self.pub_info.len())?;
+    check_length("priv_info", self.priv_info.len())?;
+    Ok(())
+  }
+}
+
+fn check_length(name: &'static str, length: usize) -> Result<()> {
+  u32::try_from(length)
+    .map(|_| ())
+    .map_err(|_| Error::InvalidAgreementInfo(format!("{name} exceeds the maximum length of {} bytes", u32::MAX)))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::check_length;
+  use super::AgreementInfo;
+
+  #[test]
+  fn test_agreement_info_try_new() {
+    let agreement: AgreementInfo =
+      AgreementInfo::try_new(b"Alice".to_vec(), b"Bob".to_vec(), Vec::new(), Vec::new()).unwrap();
+    assert!(agreement.validate().is_ok());
+  }
+
+  // Allocating inputs of this size is not feasible in a test, so only the length check is tested.
+  #[cfg(target_pointer_width = "64")]
+  #[test]
+  fn test_agreement_info_length_boundary() {
+    assert!(check_length("apu", 0).is_ok());
+    assert!(check_length("apu", u32::MAX as usize).is_ok());
+    assert!(matches!(
+      check_length("apu", u32::MAX as usize + 1),
+      Err(crate::Error::InvalidAgreementInfo(_))
+    ));
+  }
 }
@@ -1,12 +1,28 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use identity_core::crypto::X25519;
 use serde::Deserialize;
 use serde::Serialize;
+use zeroize::Zeroize;
+
+use crate::error::Error;
+use crate::error::Result;
+use crate::types::EncryptionAlgorithm;
 
 /// The ciphertext together with supplementary data.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct EncryptedData {
+  /// The version of the layout, see [`EncryptedData::VERSION`].
+  ///
+  /// Data serialized before the version was recorded has the layout of version 1.
+  #[serde(default = "EncryptedData::legacy_version")]
+  pub version: u8,
+  /// The name of the content encryption algorithm, see [`EncryptionAlgorithm::name`].
+  ///
+  /// Empty if the algorithm was not recorded, in which case it is not checked on decryption.
+  #[serde(default)]
+  pub algorithm_id: String,
   pub associated_data: Vec<u8>,
   pub nonce: Vec<u8>,
   pub tag: Vec<u8>,
@@ -16,7 +32,12 @@ pub struct EncryptedData {
 }
 
 impl EncryptedData {
-  /// Creates a new `EncryptedData` instance.
+  /// The current version of the layout.
+  pub const VERSION: u8 = 1;
+
+  /// Creates a new `EncryptedData` instance of the current [`EncryptedData::VERSION`].
+  ///
+  /// The content encryption algorithm is not recorded, see [`EncryptedData::with_algorithm`].
   pub fn new(
     nonce: Vec<u8>,
     associated_data: Vec<u8>,
@@ -26,6 +47,8 @@ impl EncryptedData {
     ephemeral_public_key: Vec<u8>,
   ) -> Self {
     Self {
+      version: Self::VERSION,
+      algorithm_id: String::new(),
       associated_data,
       nonce,
       tag,
@@ -34,4 +57,128 @@ impl EncryptedData {
       ephemeral_public_key,
     }
   }
+
+  /// Records the content encryption `algorithm` the data was encrypted with.
+  pub fn with_algorithm(mut self, algorithm: &EncryptionAlgorithm) -> Self {
+    self.algorithm_id = algorithm.name().to_owned();
+    self
+  }
+
+  /// Checks that the data has the current [`EncryptedData::VERSION`], was encrypted with the given content
+  /// encryption `algorithm` if it is recorded, and that the nonce, tag and ephemeral public key have the
+  /// lengths expected by the `algorithm`.
+  ///
+  /// Returns [`Error::DecryptionFailure`] describing the mismatch otherwise, so that incompatible or
+  /// truncated data is not reported as a failed authentication.
+  pub fn validate(&self, algorithm: &EncryptionAlgorithm) -> Result<()> {
+    if self.version != Self::VERSION {
+      return Err(invalid_data(algorithm, "encrypted data of version 1"));
+    }
+
+    if !self.algorithm_id.is_empty() && self.algorithm_id != algorithm.name() {
+      return Err(invalid_data(
+        algorithm,
+        "encrypted data of the same content encryption algorithm",
+      ));
+    }
+
+    if self.nonce.len() != algorithm.nonce_length() {
+      return Err(invalid_data(algorithm, "a nonce of the algorithm's nonce length"));
+    }
+
+    if self.tag.len() != algorithm.tag_length() {
+      return Err(invalid_data(algorithm, "a tag of the algorithm's tag length"));
+    }
+
+    if self.ephemeral_public_key.len() != X25519::PUBLIC_KEY_LENGTH {
+      return Err(invalid_data(algorithm, "an ephemeral X25519 public key of 32 bytes"));
+    }
+
+    Ok(())
+  }
+
+  fn legacy_version() -> u8 {
+    1
+  }
+}
+
+fn invalid_data(algorithm: &EncryptionAlgorithm, expected: &'static str) -> Error {
+  Error::DecryptionFailure(crypto::error::Error::InvalidArgumentError {
+    alg: algorithm.name(),
+    expected,
+  })
+}
+
+impl Zeroize for EncryptedData {
+  /// Zeroizes the ciphertext and the supplementary data.
+  ///
+  /// The data does not contain key material, but callers may want to wipe it once it was decrypted.
+  fn zeroize(&mut self) {
+    self.associated_data.zeroize();
+    self.nonce.zeroize();
+    self.tag.zeroize();
+    self.ciphertext.zeroize();
+    self.encrypted_cek.zeroize();
+    self.ephemeral_public_key.zeroize();
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use identity_core::crypto::X25519;
+
+  use super::EncryptedData;
+  use crate::types::EncryptionAlgorithm;
+
+  fn encrypted_data(algorithm: &EncryptionAlgorithm) -> EncryptedData {
+    EncryptedData::new(
+      vec![0x01; algorithm.nonce_length()],
+      b"associated_data".to_vec(),
+      vec![0x02; algorithm.tag_length()],
+      b"ciphertext".to_vec(),
+      Vec::new(),
+      vec![0x03; X25519::PUBLIC_KEY_LENGTH],
+    )
+    .with_algorithm(algorithm)
+  }
+
+  fn assert_invalid(data: &EncryptedData, algorithm: &EncryptionAlgorithm, expected: &str) {
+    match data.validate(algorithm) {
+      Err(crate::Error::DecryptionFailure(crypto::error::Error::InvalidArgumentError { expected: actual, .. })) => {
+        assert_eq!(actual, expected)
+      }
+      result => panic!("expected DecryptionFailure, got {result:?}"),
+    }
+  }
+
+  #[test]
+  fn test_encrypted_data_validate() {
+    for algorithm in [EncryptionAlgorithm::AES256GCM, EncryptionAlgorithm::XCHACHA20POLY1305] {
+      assert!(encrypted_data(&algorithm).validate(&algorithm).is_ok());
+    }
+  }
+
+  #[test]
+  fn test_encrypted_data_validate_truncated_nonce() {
+    let algorithm: EncryptionAlgorithm = EncryptionAlgorithm::XCHACHA20POLY1305;
+    let mut data: EncryptedData = encrypted_data(&algorithm);
+    data.nonce.truncate(EncryptionAlgorithm::AES256GCM.nonce_length());
+    assert_invalid(&data, &algorithm, "a nonce of the algorithm's nonce length");
+  }
+
+  #[test]
+  fn test_encrypted_data_validate_truncated_tag() {
+    let algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let mut data: EncryptedData = encrypted_data(&algorithm);
+    data.tag.truncate(algorithm.tag_length() - 1);
+    assert_invalid(&data, &algorithm, "a tag of the algorithm's tag length");
+  }
+
+  #[test]
+  fn test_encrypted_data_validate_truncated_ephemeral_public_key() {
+    let algorithm: EncryptionAlgorithm = EncryptionAlgorithm::AES256GCM;
+    let mut data: EncryptedData = encrypted_data(&algorithm);
+    data.ephemeral_public_key.truncate(X25519::PUBLIC_KEY_LENGTH - 1);
+    assert_invalid(&data, &algorithm, "an ephemeral X25519 public key of 32 bytes");
+  }
 }
@@ -1,7 +1,10 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use crypto::ciphers::aes_gcm::Aes128Gcm;
+use crypto::ciphers::aes_gcm::Aes192Gcm;
 use crypto::ciphers::aes_gcm::Aes256Gcm;
+use crypto::ciphers::chacha::XChaCha20Poly1305;
 use crypto::ciphers::traits::Aead;
 use serde::Deserialize;
 use serde::Serialize;
@@ -10,15 +13,54 @@ use serde::Serialize;
 #[non_exhaustive]
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub enum EncryptionAlgorithm {
+  /// AES GCM using 128-bit key.
+  AES128GCM,
+  /// AES GCM using 192-bit key.
+  AES192GCM,
   /// AES GCM using 256-bit key.
   AES256GCM,
+  /// XChaCha20-Poly1305 using 256-bit key and 192-bit nonce.
+  XCHACHA20POLY1305,
 }
 
 impl EncryptionAlgorithm {
+  /// Returns the JSON Web Algorithms name of the encryption algorithm.
+  pub const fn name(&self) -> &'static str {
+    match self {
+      EncryptionAlgorithm::AES128GCM => "A128GCM",
+      EncryptionAlgorithm::AES192GCM => "A192GCM",
+      EncryptionAlgorithm::AES256GCM => "A256GCM",
+      EncryptionAlgorithm::XCHACHA20POLY1305 => "XC20P",
+    }
+  }
+
   /// Returns the length of the cipher's key.
   pub const fn key_length(&self) -> usize {
     match self {
+      EncryptionAlgorithm::AES128GCM => Aes128Gcm::KEY_LENGTH,
+      EncryptionAlgorithm::AES192GCM => Aes192Gcm::KEY_LENGTH,
       EncryptionAlgorithm::AES256GCM => Aes256Gcm::KEY_LENGTH,
+      EncryptionAlgorithm::XCHACHA20POLY1305 => XChaCha20Poly1305::KEY_LENGTH,
+    }
+  }
+
+  /// Returns the length of the cipher's nonce.
+  pub const fn nonce_length(&self) -> usize {
+    match self {
+      EncryptionAlgorithm::AES128GCM => Aes128Gcm::NONCE_LENGTH,
+      EncryptionAlgorithm::AES192GCM => Aes192Gcm::NONCE_LENGTH,
+      EncryptionAlgorithm::AES256GCM => Aes256Gcm::NONCE_LENGTH,
+      EncryptionAlgorithm::XCHACHA20POLY1305 => XChaCha20Poly1305::NONCE_LENGTH,
+    }
+  }
+
+  /// Returns the length of the cipher's authentication tag.
+  pub const fn tag_length(&self) -> usize {
+    match self {
+      EncryptionAlgorithm::AES128GCM => Aes128Gcm::TAG_LENGTH,
+      EncryptionAlgorithm::AES192GCM => Aes192Gcm::TAG_LENGTH,
+      EncryptionAlgorithm::AES256GCM => Aes256Gcm::TAG_LENGTH,
+      EncryptionAlgorithm::XCHACHA20POLY1305 => XChaCha20Poly1305::TAG_LENGTH,
     }
   }
 }
@@ -0,0 +1,44 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use identity_core::crypto::KeyType;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Asymmetric signature algorithms for the JSON Web Signatures `alg` header parameter,
+/// see [`Storage::key_sign_jws`](crate::storage::Storage::key_sign_jws).
+///
+/// This is also the algorithm a [`Signature`](crate::types::Signature) was created with.
+///
+/// [More Info](https://www.iana.org/assignments/jose/jose.xhtml#web-signature-encryption-algorithms)
+#[non_exhaustive]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum JwsAlgorithm {
+  /// EdDSA signature algorithms.
+  EdDSA,
+}
+
+impl JwsAlgorithm {
+  /// Returns the JWS algorithm as a `str` slice.
+  pub const fn name(&self) -> &'static str {
+    match self {
+      JwsAlgorithm::EdDSA => "EdDSA",
+    }
+  }
+
+  /// Returns the algorithm keys of the given [`KeyType`] sign with, or `None` if the key type cannot be
+  /// used for signing.
+  pub const fn from_key_type(key_type: KeyType) -> Option<Self> {
+    match key_type {
+      KeyType::Ed25519 => Some(JwsAlgorithm::EdDSA),
+      KeyType::X25519 => None,
+    }
+  }
+
+  /// Returns the [`KeyType`] signing with this algorithm.
+  pub const fn key_type(&self) -> KeyType {
+    match self {
+      JwsAlgorithm::EdDSA => KeyType::Ed25519,
+    }
+  }
+}
@@ -21,6 +21,9 @@ use std::hash::Hasher;
 /// same identity with the same fragment. The `key_hash` disambiguates the keys in
 /// situations like these.
 ///
+/// Alternatively, keys derived from a deterministic scheme can be identified by the fragment and
+/// their derivation index, see [`KeyLocation::with_index`].
+///
 /// The string representation of that location can be obtained via `canonical_repr`.
 #[derive(Debug, Clone, serde::Deserialize, serde::Serialize)]
 pub struct KeyLocation {
@@ -30,11 +33,19 @@ pub struct KeyLocation {
   fragment: String,
   /// The hash of the public key.
   pub(in crate::types::key_location) key_hash: String,
+  /// The derivation index of the key, if it was created with [`KeyLocation::with_index`].
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  index: Option<u32>,
 }
 
 impl KeyLocation {
   /// Create a location from a [`KeyType`], the fragment of a verification method
   /// and the bytes of a public key.
+  ///
+  /// The key hash is the full 64-bit [SeaHash](https://docs.rs/seahash) of the public key bytes,
+  /// formatted as a decimal integer. The hash is not truncated, so two distinct public keys under the
+  /// same fragment only map to the same location with a probability of about 2^-64. The derivation
+  /// must stay stable, since existing storages address their keys by it.
   pub fn new(key_type: KeyType, fragment: String, public_key: &[u8]) -> Self {
     let mut hasher = SeaHasher::new();
     hasher.write(public_key);
@@ -44,9 +55,79 @@ impl KeyLocation {
       key_type,
       fragment,
       key_hash: key_hash.to_string(),
+      index: None,
+    }
+  }
+
+  /// Create a location from a [`KeyType`], the fragment of a verification method
+  /// and the `index` of a key in a deterministic derivation scheme.
+  ///
+  /// Locations with the same fragment but different indices identify different keys.
+  pub fn with_index(key_type: KeyType, fragment: String, index: u32) -> Self {
+    Self {
+      key_type,
+      fragment,
+      key_hash: String::new(),
+      index: Some(index),
+    }
+  }
+
+  /// Returns the fragment of the verification method the key belongs to.
+  pub fn fragment(&self) -> &str {
+    &self.fragment
+  }
+
+  /// Returns a copy of this location with its fragment replaced by `fragment`.
+  ///
+  /// The key hash or derivation index is retained, so both locations refer to the same key material.
+  pub fn with_fragment(&self, fragment: String) -> Self {
+    Self {
+      fragment,
+      ..self.clone()
+    }
+  }
+
+  /// Returns the hash of the public key, which disambiguates keys with the same fragment,
+  /// see [`KeyLocation::new`].
+  ///
+  /// Returns `None` if the location was created with [`KeyLocation::with_index`].
+  pub fn key_hash(&self) -> Option<&str> {
+    match self.index {
+      Some(_) => None,
+      None => Some(&self.key_hash),
+    }
+  }
+
+  /// Returns the derivation index of the key, if the location was created with [`KeyLocation::with_index`].
+  pub fn index(&self) -> Option<u32> {
+    self.index
+  }
+
+  /// Returns whether the key at this location can create signatures with [`Storage::key_sign`].
+  ///
+  /// [`Storage::key_sign`]: crate::storage::Storage::key_sign
+  pub fn can_sign(&self) -> bool {
+    match self.key_type {
+      KeyType::Ed25519 => true,
+      KeyType::X25519 => false,
+    }
+  }
+
+  /// Returns whether the key at this location can take part in the key agreement of
+  /// `Storage::data_encrypt` and `Storage::data_decrypt`.
+  pub fn can_encrypt(&self) -> bool {
+    match self.key_type {
+      KeyType::Ed25519 => false,
+      KeyType::X25519 => true,
     }
   }
 
+  /// Returns the unsigned-varint encoded multicodec prefix of the public key at this location,
+  /// see [`multicodec_prefix`](crate::utils::multicodec_prefix).
+  pub fn multicodec(&self) -> &'static [u8] {
+    crate::utils::multicodec_prefix(self.key_type)
+  }
+
   /// Obtain the location of a verification method's key in storage.
   pub fn from_verification_method(method: &IotaVerificationMethod) -> crate::Result<Self> {
     let fragment: &str = method
@@ -69,7 +150,10 @@ impl KeyLocation {
   ///
   /// This should be used as the representation for storage keys.
   pub fn canonical(&self) -> String {
-    format!("{}:{}", self.fragment, self.key_hash)
+    match self.index {
+      Some(index) => format!("{}/{}", self.fragment, index),
+      None => format!("{}:{}", self.fragment, self.key_hash),
+    }
   }
 }
 
@@ -89,7 +173,7 @@ impl Hash for KeyLocation {
 
 impl PartialEq for KeyLocation {
   fn eq(&self, other: &Self) -> bool {
-    self.fragment == other.fragment && self.key_hash == other.key_hash
+    self.fragment == other.fragment && self.key_hash == other.key_hash && self.index == other.index
   }
 }
 
@@ -97,6 +181,11 @@ impl Eq for KeyLocation {}
 
 #[cfg(test)]
 mod tests {
+  use std::collections::HashSet;
+
+  use identity_core::convert::FromJson;
+  use identity_core::convert::ToJson;
+  use identity_core::crypto::KeyPair;
   use identity_core::crypto::KeyType;
   use rand::distributions::DistString;
   use rand::rngs::OsRng;
@@ -138,6 +227,80 @@ mod tests {
 
       assert_eq!(fragment_str, &fragment);
       assert_eq!(key_hash_str, expected_hash);
+      assert_eq!(location.key_hash(), Some(expected_hash));
     }
   }
+
+  #[test]
+  fn test_key_location_distinct_public_keys() {
+    let fragment: String = rand::distributions::Alphanumeric.sample_string(&mut OsRng, 32);
+
+    let location_1: KeyLocation = KeyLocation::new(KeyType::Ed25519, fragment.clone(), &TEST_VECTOR_1.0);
+    let location_2: KeyLocation = KeyLocation::new(KeyType::Ed25519, fragment, &TEST_VECTOR_2.0);
+
+    assert_ne!(location_1.key_hash(), location_2.key_hash());
+    assert_ne!(location_1, location_2);
+    assert_ne!(location_1.canonical(), location_2.canonical());
+  }
+
+  #[test]
+  fn test_key_location_unique_for_many_keypairs() {
+    let fragment: String = rand::distributions::Alphanumeric.sample_string(&mut OsRng, 32);
+
+    let mut canonicals: HashSet<String> = HashSet::new();
+    for _ in 0..1000 {
+      let keypair: KeyPair = KeyPair::new(KeyType::Ed25519).unwrap();
+      let location: KeyLocation = KeyLocation::new(KeyType::Ed25519, fragment.clone(), keypair.public().as_ref());
+      assert!(canonicals.insert(location.canonical()), "duplicate location {location}");
+    }
+  }
+
+  #[test]
+  fn test_key_location_with_index() {
+    let fragment: String = rand::distributions::Alphanumeric.sample_string(&mut OsRng, 32);
+
+    let location_0: KeyLocation = KeyLocation::with_index(KeyType::Ed25519, fragment.clone(), 0);
+    let location_1: KeyLocation = KeyLocation::with_index(KeyType::Ed25519, fragment.clone(), 1);
+
+    assert_eq!(location_0.index(), Some(0));
+    assert_eq!(location_1.index(), Some(1));
+    assert_eq!(location_1.key_hash(), None);
+    assert_ne!(location_0, location_1);
+    assert_eq!(location_1.to_string(), format!("{fragment}/1"));
+
+    // The index survives a serialization roundtrip.
+    let json: String = location_1.to_json().unwrap();
+    assert_eq!(KeyLocation::from_json(&json).unwrap(), location_1);
+    assert_eq!(KeyLocation::from_json(&json).unwrap().index(), Some(1));
+
+    // Locations created from a public key are not indexed.
+    let location: KeyLocation = KeyLocation::new(KeyType::Ed25519, fragment, &TEST_VECTOR_1.0);
+    assert_eq!(location.index(), None);
+    assert_ne!(location, location_0);
+  }
+
+  #[test]
+  fn test_key_location_can_sign_can_encrypt() {
+    let ed25519: KeyLocation = KeyLocation::new(KeyType::Ed25519, "sign-0".to_owned(), &TEST_VECTOR_1.0);
+    assert!(ed25519.can_sign());
+    assert!(!ed25519.can_encrypt());
+
+    let x25519: KeyLocation = KeyLocation::new(KeyType::X25519, "kex-0".to_owned(), &TEST_VECTOR_2.0);
+    assert!(!x25519.can_sign());
+    assert!(x25519.can_encrypt());
+
+    // The capabilities only depend on the key type.
+    let indexed: KeyLocation = KeyLocation::with_index(KeyType::X25519, "kex-0".to_owned(), 0);
+    assert!(!indexed.can_sign());
+    assert!(indexed.can_encrypt());
+  }
+
+  #[test]
+  fn test_key_location_multicodec() {
+    let ed25519: KeyLocation = KeyLocation::new(KeyType::Ed25519, "sign-0".to_owned(), &TEST_VECTOR_1.0);
+    assert_eq!(ed25519.multicodec(), &[0xed, 0x01]);
+
+    let x25519: KeyLocation = KeyLocation::with_index(KeyType::X25519, "kex-0".to_owned(), 0);
+    assert_eq!(x25519.multicodec(), &[0xec, 0x01]);
+  }
 }
@@ -0,0 +1,39 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use identity_core::common::Timestamp;
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PublicKey;
+
+/// Metadata of a key held by a [`Storage`](crate::storage::Storage).
+#[derive(Clone, Debug)]
+pub struct KeyMetadata {
+  /// The public key.
+  pub public_key: PublicKey,
+  /// The type of the key.
+  pub key_type: KeyType,
+  /// The time at which the key was generated or inserted, if known by the storage.
+  pub created_at: Option<Timestamp>,
+  /// The time at which the key expires, if it was generated with an expiry.
+  ///
+  /// Metadata is returned for expired keys too, so callers should compare this against the current time.
+  pub expires_at: Option<Timestamp>,
+}
+
+impl KeyMetadata {
+  /// Creates a new [`KeyMetadata`] instance.
+  pub fn new(public_key: PublicKey, key_type: KeyType, created_at: Option<Timestamp>) -> Self {
+    Self {
+      public_key,
+      key_type,
+      created_at,
+      expires_at: None,
+    }
+  }
+
+  /// Sets the expiry time of the key.
+  pub fn with_expires_at(mut self, expires_at: Option<Timestamp>) -> Self {
+    self.expires_at = expires_at;
+    self
+  }
+}
@@ -0,0 +1,13 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+/// The outcome of a [`MemStore::merge`](crate::storage::MemStore::merge).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MergeReport {
+  /// The number of DIDs that only existed in the other store and were added.
+  pub added: usize,
+  /// The number of DIDs that existed in both stores and whose data was kept.
+  pub skipped: usize,
+  /// The number of DIDs that existed in both stores and whose data was replaced by the other store's.
+  pub overwritten: usize,
+}
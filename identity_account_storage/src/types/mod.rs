@@ -1,14 +1,24 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+mod conflict_policy;
 mod did_type;
 #[cfg(feature = "encryption")]
 mod encryption;
+mod jws_algorithm;
 mod key_location;
+mod key_metadata;
+mod merge_report;
 mod signature;
+mod storage_stats;
 
+pub use self::conflict_policy::*;
 pub use self::did_type::*;
 #[cfg(feature = "encryption")]
 pub use self::encryption::*;
+pub use self::jws_algorithm::*;
 pub use self::key_location::*;
+pub use self::key_metadata::*;
+pub use self::merge_report::*;
 pub use self::signature::*;
+pub use self::storage_stats::*;
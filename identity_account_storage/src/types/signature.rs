@@ -1,27 +1,175 @@
 // Copyright 2020-2022 IOTA Stiftung
 // SPDX-License-Identifier: Apache-2.0
 
+use identity_core::crypto::Ed25519;
+use identity_core::crypto::KeyType;
 use serde::Deserialize;
 use serde::Serialize;
 
+use crate::error::Error;
+use crate::error::Result;
+use crate::types::JwsAlgorithm;
+
 /// A digital signature.
+///
+/// The [`JwsAlgorithm`] is serialized together with the signature, if known.
 #[derive(Clone, Deserialize, Serialize)]
-pub struct Signature(pub(crate) Vec<u8>);
+#[serde(from = "SignatureRepr", into = "SignatureRepr")]
+pub struct Signature {
+  data: Vec<u8>,
+  // The key type the signature was created with, if known.
+  key_type: Option<KeyType>,
+}
 
 impl Signature {
-  /// Creates a `Signature`.
+  /// Creates a `Signature` without validating its length.
+  #[deprecated(note = "use `Signature::new_checked` to validate the length for the key type")]
   pub fn new(data: Vec<u8>) -> Self {
-    Signature(data)
+    Self { data, key_type: None }
+  }
+
+  /// Creates a `Signature` created with a key of the given `key_type`.
+  ///
+  /// Returns [`Error::InvalidSignature`] if `data` does not have the signature length of `key_type`,
+  /// or if `key_type` cannot be used for signing.
+  pub fn new_checked(key_type: KeyType, data: Vec<u8>) -> Result<Self> {
+    let expected: usize = match key_type {
+      KeyType::Ed25519 => Ed25519::SIGNATURE_LENGTH,
+      KeyType::X25519 => {
+        return Err(Error::InvalidSignature(format!(
+          "{} keys cannot be used for signing",
+          key_type.as_str()
+        )))
+      }
+    };
+
+    if data.len() != expected {
+      return Err(Error::InvalidSignature(format!(
+        "expected {} signature of length {expected}, got {}",
+        key_type.as_str(),
+        data.len()
+      )));
+    }
+
+    Ok(Self {
+      data,
+      key_type: Some(key_type),
+    })
+  }
+
+  /// Returns the key type the signature was created with, if it was created with
+  /// [`Signature::new_checked`].
+  pub fn key_type(&self) -> Option<KeyType> {
+    self.key_type
+  }
+
+  /// Returns the [`JwsAlgorithm`] the signature was created with, if the key type is known.
+  pub fn algorithm(&self) -> Option<JwsAlgorithm> {
+    self.key_type.and_then(JwsAlgorithm::from_key_type)
   }
 
   /// Returns the signature as a slice of bytes.
   pub fn as_bytes(&self) -> &[u8] {
-    &self.0
+    &self.data
   }
 }
 
 impl From<Signature> for Vec<u8> {
   fn from(signature: Signature) -> Self {
-    signature.0
+    signature.data
+  }
+}
+
+/// The serialized form of a [`Signature`].
+///
+/// Signatures serialized before the algorithm was recorded are plain byte arrays.
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum SignatureRepr {
+  Legacy(Vec<u8>),
+  Tagged {
+    data: Vec<u8>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    algorithm: Option<JwsAlgorithm>,
+  },
+}
+
+impl From<SignatureRepr> for Signature {
+  fn from(repr: SignatureRepr) -> Self {
+    match repr {
+      SignatureRepr::Legacy(data) => Self { data, key_type: None },
+      SignatureRepr::Tagged { data, algorithm } => Self {
+        data,
+        key_type: algorithm.map(|algorithm| algorithm.key_type()),
+      },
+    }
+  }
+}
+
+impl From<Signature> for SignatureRepr {
+  fn from(signature: Signature) -> Self {
+    SignatureRepr::Tagged {
+      algorithm: signature.algorithm(),
+      data: signature.data,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use identity_core::convert::FromJson;
+  use identity_core::convert::ToJson;
+  use identity_core::crypto::Ed25519;
+  use identity_core::crypto::KeyType;
+
+  use super::Signature;
+  use crate::types::JwsAlgorithm;
+  use crate::Error;
+
+  #[test]
+  fn test_signature_new_checked() {
+    let signature: Signature = Signature::new_checked(KeyType::Ed25519, vec![0; Ed25519::SIGNATURE_LENGTH]).unwrap();
+    assert_eq!(signature.as_bytes(), &[0; Ed25519::SIGNATURE_LENGTH]);
+    assert_eq!(signature.key_type(), Some(KeyType::Ed25519));
+    assert_eq!(signature.algorithm(), Some(JwsAlgorithm::EdDSA));
+  }
+
+  #[test]
+  fn test_signature_json_roundtrip() {
+    let signature: Signature = Signature::new_checked(KeyType::Ed25519, vec![7; Ed25519::SIGNATURE_LENGTH]).unwrap();
+
+    let json: String = signature.to_json().unwrap();
+    let decoded: Signature = Signature::from_json(&json).unwrap();
+
+    assert_eq!(decoded.as_bytes(), signature.as_bytes());
+    assert_eq!(decoded.algorithm(), Some(JwsAlgorithm::EdDSA));
+    assert_eq!(decoded.key_type(), Some(KeyType::Ed25519));
+  }
+
+  #[test]
+  fn test_signature_deserialize_legacy() {
+    // Signatures were previously serialized as plain byte arrays, without the algorithm.
+    let signature: Signature = Signature::from_json("[1,2,3]").unwrap();
+
+    assert_eq!(signature.as_bytes(), &[1, 2, 3]);
+    assert_eq!(signature.algorithm(), None);
+  }
+
+  #[test]
+  fn test_signature_new_checked_invalid_length() {
+    for length in [0, Ed25519::SIGNATURE_LENGTH - 1, Ed25519::SIGNATURE_LENGTH + 1] {
+      assert!(matches!(
+        Signature::new_checked(KeyType::Ed25519, vec![0; length]),
+        Err(Error::InvalidSignature(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn test_signature_new_checked_x25519() {
+    assert!(matches!(
+      Signature::new_checked(KeyType::X25519, vec![0; Ed25519::SIGNATURE_LENGTH]),
+      Err(Error::InvalidSignature(_))
+    ));
   }
 }
@@ -0,0 +1,24 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+/// Usage statistics of a [`Storage`](crate::storage::Storage).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct StorageStats {
+  /// The number of stored DIDs.
+  pub did_count: usize,
+  /// The number of keys stored across all DIDs.
+  pub key_count: usize,
+  /// The total size in bytes of the blobs stored across all DIDs.
+  pub blob_bytes: usize,
+}
+
+impl StorageStats {
+  /// Creates a new [`StorageStats`] instance.
+  pub fn new(did_count: usize, key_count: usize, blob_bytes: usize) -> Self {
+    Self {
+      did_count,
+      key_count,
+      blob_bytes,
+    }
+  }
+}
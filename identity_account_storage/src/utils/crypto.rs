@@ -2,6 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crypto::keys::pbkdf::PBKDF2_HMAC_SHA512;
+use subtle::ConstantTimeEq;
 
 const PBKDF_ITER: usize = 100;
 const PBKDF_SALT: &[u8] = b"identity.rs";
@@ -16,3 +17,42 @@ pub fn derive_encryption_key(password: &str) -> EncryptionKey {
 
   output
 }
+
+/// Compares `a` and `b` in constant time with respect to their contents, so that comparing secret-derived
+/// bytes does not leak how many leading bytes match. Only the lengths are compared in variable time.
+pub(crate) fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+  a.ct_eq(b).into()
+}
+
+/// Compares optional byte slices with [`ct_eq`]. Whether a value is present is not considered secret.
+pub(crate) fn ct_eq_option(a: Option<&[u8]>, b: Option<&[u8]>) -> bool {
+  match (a, b) {
+    (Some(a), Some(b)) => ct_eq(a, b),
+    (None, None) => true,
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::ct_eq;
+  use super::ct_eq_option;
+
+  #[test]
+  fn test_ct_eq() {
+    assert!(ct_eq(b"", b""));
+    assert!(ct_eq(b"secret", b"secret"));
+    assert!(!ct_eq(b"secret", b"secreT"));
+    assert!(!ct_eq(b"secret", b"secret!"));
+    assert!(!ct_eq(b"", b"secret"));
+  }
+
+  #[test]
+  fn test_ct_eq_option() {
+    assert!(ct_eq_option(None, None));
+    assert!(ct_eq_option(Some(b"secret"), Some(b"secret")));
+    assert!(!ct_eq_option(Some(b"secret"), Some(b"Secret")));
+    assert!(!ct_eq_option(Some(b""), None));
+    assert!(!ct_eq_option(None, Some(b"secret")));
+  }
+}
@@ -0,0 +1,87 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crypto::ciphers::aes_gcm::Aes256Gcm;
+use crypto::ciphers::traits::Aead;
+use zeroize::Zeroizing;
+
+use crate::error::Error;
+use crate::error::Result;
+
+/// Encrypts `plaintext` with AES-256-GCM under `master_key` into `nonce || tag || ciphertext`.
+///
+/// A random nonce is generated for every call. The `associated_data` is authenticated but not encrypted,
+/// and binds the ciphertext to the place it is stored at, so that it cannot be moved without being noticed.
+pub(crate) fn seal(
+  master_key: &[u8; Aes256Gcm::KEY_LENGTH],
+  associated_data: &[u8],
+  plaintext: &[u8],
+) -> Result<Vec<u8>> {
+  let mut nonce: [u8; Aes256Gcm::NONCE_LENGTH] = [0; Aes256Gcm::NONCE_LENGTH];
+  crypto::utils::rand::fill(&mut nonce).map_err(Error::EncryptionFailure)?;
+
+  let mut tag: [u8; Aes256Gcm::TAG_LENGTH] = [0; Aes256Gcm::TAG_LENGTH];
+  let mut ciphertext: Vec<u8> = vec![0; plaintext.len()];
+  Aes256Gcm::try_encrypt(
+    master_key,
+    &nonce,
+    associated_data,
+    plaintext,
+    &mut ciphertext,
+    &mut tag,
+  )
+  .map_err(Error::EncryptionFailure)?;
+
+  let mut bytes: Vec<u8> = Vec::with_capacity(nonce.len() + tag.len() + ciphertext.len());
+  bytes.extend_from_slice(&nonce);
+  bytes.extend_from_slice(&tag);
+  bytes.extend_from_slice(&ciphertext);
+  Ok(bytes)
+}
+
+/// Decrypts the `bytes` created by [`seal`] with the same `master_key` and `associated_data`.
+pub(crate) fn open(
+  master_key: &[u8; Aes256Gcm::KEY_LENGTH],
+  associated_data: &[u8],
+  bytes: &[u8],
+) -> Result<Zeroizing<Vec<u8>>> {
+  if bytes.len() < Aes256Gcm::NONCE_LENGTH + Aes256Gcm::TAG_LENGTH {
+    return Err(Error::DecryptionFailure(crypto::error::Error::BufferSize {
+      name: "sealed data",
+      needs: Aes256Gcm::NONCE_LENGTH + Aes256Gcm::TAG_LENGTH,
+      has: bytes.len(),
+    }));
+  }
+
+  let (nonce, bytes): (&[u8], &[u8]) = bytes.split_at(Aes256Gcm::NONCE_LENGTH);
+  let (tag, ciphertext): (&[u8], &[u8]) = bytes.split_at(Aes256Gcm::TAG_LENGTH);
+
+  let mut plaintext: Zeroizing<Vec<u8>> = Zeroizing::new(vec![0; ciphertext.len()]);
+  let len: usize = Aes256Gcm::try_decrypt(master_key, nonce, associated_data, &mut plaintext, ciphertext, tag)
+    .map_err(Error::DecryptionFailure)?;
+  plaintext.truncate(len);
+
+  Ok(plaintext)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::open;
+  use super::seal;
+
+  #[test]
+  fn test_seal_open() {
+    let master_key: [u8; 32] = [7; 32];
+    let sealed: Vec<u8> = seal(&master_key, b"did:iota:abc", b"secret").unwrap();
+
+    assert_eq!(
+      open(&master_key, b"did:iota:abc", &sealed).unwrap().as_slice(),
+      b"secret"
+    );
+    assert_ne!(seal(&master_key, b"did:iota:abc", b"secret").unwrap(), sealed);
+    assert!(open(&master_key, b"did:iota:xyz", &sealed).is_err());
+    assert!(open(&[8; 32], b"did:iota:abc", &sealed).is_err());
+    assert!(open(&master_key, b"did:iota:abc", &sealed[..sealed.len() - 1]).is_err());
+    assert!(open(&master_key, b"did:iota:abc", &sealed[..8]).is_err());
+  }
+}
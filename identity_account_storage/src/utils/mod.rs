@@ -2,9 +2,31 @@
 // SPDX-License-Identifier: Apache-2.0
 
 mod crypto;
+#[cfg(any(
+  feature = "fs-storage",
+  feature = "postgres-storage",
+  feature = "redis-storage",
+  feature = "rocksdb-storage",
+  feature = "sled-storage"
+))]
+mod envelope;
+mod multibase;
 mod shared;
+#[cfg(feature = "tracing")]
+mod trace;
 
 pub mod fs;
 
 pub use self::crypto::*;
+#[cfg(any(
+  feature = "fs-storage",
+  feature = "postgres-storage",
+  feature = "redis-storage",
+  feature = "rocksdb-storage",
+  feature = "sled-storage"
+))]
+pub(crate) use self::envelope::*;
+pub use self::multibase::*;
 pub use self::shared::*;
+#[cfg(feature = "tracing")]
+pub use self::trace::*;
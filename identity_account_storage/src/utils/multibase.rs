@@ -0,0 +1,79 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use identity_core::crypto::KeyType;
+use identity_core::crypto::PublicKey;
+use identity_core::utils::Base;
+use identity_core::utils::BaseEncoding;
+
+/// The unsigned-varint encoding of the `ed25519-pub` multicodec `0xed`.
+const ED25519_PUB_MULTICODEC: [u8; 2] = [0xed, 0x01];
+/// The unsigned-varint encoding of the `x25519-pub` multicodec `0xec`.
+const X25519_PUB_MULTICODEC: [u8; 2] = [0xec, 0x01];
+
+/// Returns the unsigned-varint encoded [multicodec](https://github.com/multiformats/multicodec) prefix
+/// of public keys of the given `key_type`, i.e. `0xed01` for Ed25519 and `0xec01` for X25519.
+pub fn multicodec_prefix(key_type: KeyType) -> &'static [u8] {
+  match key_type {
+    KeyType::Ed25519 => &ED25519_PUB_MULTICODEC,
+    KeyType::X25519 => &X25519_PUB_MULTICODEC,
+  }
+}
+
+/// Encodes the `public_key` of the given `key_type` as a base58btc multibase string of its
+/// [`multicodec_prefix`] followed by the key bytes, as used by `did:key` identifiers.
+///
+/// Unlike `MethodData::new_multibase`, which encodes the raw key bytes, the result identifies the key type.
+pub fn encode_public_key_multibase(key_type: KeyType, public_key: &PublicKey) -> String {
+  let prefix: &[u8] = multicodec_prefix(key_type);
+  let mut bytes: Vec<u8> = Vec::with_capacity(prefix.len() + public_key.as_ref().len());
+  bytes.extend_from_slice(prefix);
+  bytes.extend_from_slice(public_key.as_ref());
+  BaseEncoding::encode_multibase(&bytes, Some(Base::Base58Btc))
+}
+
+#[cfg(test)]
+mod tests {
+  use identity_core::crypto::KeyType;
+  use identity_core::crypto::PublicKey;
+
+  use super::encode_public_key_multibase;
+  use super::multicodec_prefix;
+
+  fn decode_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+      .step_by(2)
+      .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_multicodec_prefix() {
+    assert_eq!(multicodec_prefix(KeyType::Ed25519), &[0xed, 0x01]);
+    assert_eq!(multicodec_prefix(KeyType::X25519), &[0xec, 0x01]);
+  }
+
+  #[test]
+  fn test_encode_public_key_multibase_ed25519() {
+    // https://w3c-ccg.github.io/did-method-key/#ed25519-x25519
+    let public_key: PublicKey = PublicKey::from(decode_hex(
+      "2e6fcce36701dc791488e0d0b1745cc1e33a4c1c9fcc41c63bd343dbbe0970e6",
+    ));
+    assert_eq!(
+      encode_public_key_multibase(KeyType::Ed25519, &public_key),
+      "z6MkhaXgBZDvotDkL5257faiztiGiC2QtKLGpbnnEGta2doK"
+    );
+  }
+
+  #[test]
+  fn test_encode_public_key_multibase_x25519() {
+    // https://w3c-ccg.github.io/did-method-key/#x25519
+    let public_key: PublicKey = PublicKey::from(decode_hex(
+      "2fe57da347cd62431528daac5fbb290730fff684afc4cfc2ed90995f58cb3b74",
+    ));
+    assert_eq!(
+      encode_public_key_multibase(KeyType::X25519, &public_key),
+      "z6LSeu9HkTHSfLLeUs2nnzUSNedgDUevfNQgQjQC23ZCit6F"
+    );
+  }
+}
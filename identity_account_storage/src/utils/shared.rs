@@ -13,6 +13,10 @@ use serde::Serialize;
 use crate::error::Error;
 use crate::error::Result;
 
+/// A shared value behind a [`RwLock`].
+///
+/// With the `tracing` feature, acquiring the lock happens in a `lock` span, so contention shows up as a
+/// child span of the calling method.
 #[derive(Default, Deserialize, Serialize)]
 pub struct Shared<T>(RwLock<T>);
 
@@ -22,11 +26,21 @@ impl<T> Shared<T> {
   }
 
   pub fn read(&self) -> Result<RwLockReadGuard<'_, T>> {
-    self.0.read().map_err(|_| Error::SharedReadPoisoned)
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("lock", data = std::any::type_name::<T>(), access = "read").entered();
+    self.0.read().map_err(|_| Error::LockPoisoned("read"))
   }
 
   pub fn write(&self) -> Result<RwLockWriteGuard<'_, T>> {
-    self.0.write().map_err(|_| Error::SharedWritePoisoned)
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("lock", data = std::any::type_name::<T>(), access = "write").entered();
+    self.0.write().map_err(|_| Error::LockPoisoned("write"))
+  }
+
+  /// Clears the poisoned state of the lock, so subsequent calls to [`Shared::read`] and [`Shared::write`]
+  /// succeed again.
+  pub fn clear_poison(&self) {
+    self.0.clear_poison();
   }
 }
 
@@ -0,0 +1,40 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use crypto::hashes::sha::SHA256;
+use crypto::hashes::sha::SHA256_LEN;
+use identity_did::did::CoreDID;
+use identity_did::did::DID;
+
+/// The number of digest bytes of a DID that identify it in spans.
+const DID_HASH_LENGTH: usize = 8;
+
+/// Returns a truncated, hex-encoded SHA-256 digest of `did`, which identifies the DID in spans
+/// without logging the DID itself.
+pub(crate) fn did_hash(did: &CoreDID) -> String {
+  let mut digest: [u8; SHA256_LEN] = [0; SHA256_LEN];
+  SHA256(did.as_str().as_bytes(), &mut digest);
+
+  digest[..DID_HASH_LENGTH]
+    .iter()
+    .map(|byte| format!("{byte:02x}"))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use identity_did::did::CoreDID;
+  use identity_did::did::DID;
+
+  use super::did_hash;
+
+  #[test]
+  fn test_did_hash() {
+    let did: CoreDID = CoreDID::parse("did:iota:H3C2AVvLMv6gmMNam3uVAjZpfkcJCwDwnZn6z3wXmqPV").unwrap();
+    let hash: String = did_hash(&did);
+
+    assert_eq!(hash.len(), 16);
+    assert_eq!(hash, did_hash(&did));
+    assert!(!hash.contains(did.method_id()));
+  }
+}
@@ -117,9 +117,16 @@ impl CoreDID {
   }
 
   /// Validates whether a string is a valid [`DID`] method-id.
+  ///
+  /// Percent-encoded octets, such as the `%3A` encoding a port in a `did:web`, are accepted.
   pub fn valid_method_id(value: &str) -> Result<(), DIDError> {
-    if !value.chars().all(is_char_method_id) {
-      return Err(DIDError::InvalidMethodId);
+    let mut bytes: &[u8] = value.as_bytes();
+    while let Some((&byte, rest)) = bytes.split_first() {
+      bytes = match (byte, rest) {
+        (b'%', [high, low, rest @ ..]) if high.is_ascii_hexdigit() && low.is_ascii_hexdigit() => rest,
+        _ if is_char_method_id(byte as char) => rest,
+        _ => return Err(DIDError::InvalidMethodId),
+      };
     }
     Ok(())
   }
@@ -314,6 +321,10 @@ mod tests {
       CoreDID::parse("did:iota:main:123456890").unwrap(),
       "did:iota:main:123456890"
     );
+    assert_eq!(
+      CoreDID::parse("did:web:example.com%3A3000:123").unwrap(),
+      "did:web:example.com%3A3000:123"
+    );
   }
 
   #[test]
@@ -321,6 +332,9 @@ mod tests {
     assert!(CoreDID::parse("").is_err());
     assert!(CoreDID::parse("did:").is_err());
     assert!(CoreDID::parse("dad:example:123456890").is_err());
+    assert!(CoreDID::parse("did:web:example com").is_err());
+    assert!(CoreDID::parse("did:web:example.com%3").is_err());
+    assert!(CoreDID::parse("did:web:example.com%zz3000").is_err());
   }
 
   proptest::proptest! {
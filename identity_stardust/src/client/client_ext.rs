@@ -4,9 +4,12 @@
 use std::borrow::Cow;
 use std::ops::Deref;
 
+use identity_core::common::Timestamp;
 use identity_did::did::DIDError;
+use iota_client::api_types::responses::OutputMetadataResponse;
 use iota_client::api_types::responses::OutputResponse;
 use iota_client::block::address::Address;
+use iota_client::block::input::Input;
 use iota_client::block::output::feature::SenderFeature;
 use iota_client::block::output::unlock_condition::AddressUnlockCondition;
 use iota_client::block::output::unlock_condition::GovernorAddressUnlockCondition;
@@ -23,6 +26,7 @@ use iota_client::block::output::UnlockCondition;
 use iota_client::block::payload::transaction::TransactionEssence;
 use iota_client::block::payload::Payload;
 use iota_client::block::Block;
+use iota_client::node_api::indexer::query_parameters::QueryParameter;
 use iota_client::secret::SecretManager;
 use iota_client::Client;
 
@@ -31,6 +35,7 @@ use crate::Error;
 use crate::NetworkName;
 use crate::StardustDID;
 use crate::StardustDocument;
+use crate::StardustDocumentMetadata;
 
 /// An extension trait for a [`Client`] that provides helper functions for publication
 /// and resolution of DID documents in Alias Outputs.
@@ -60,34 +65,54 @@ pub trait StardustClientExt: Sync {
     document: StardustDocument,
     rent_structure: Option<RentStructure>,
   ) -> Result<AliasOutput> {
-    let rent_structure: RentStructure = if let Some(inner) = rent_structure {
-      inner
-    } else {
-      self
-        .client()
-        .get_rent_structure()
-        .await
-        .map_err(Error::DIDUpdateError)?
-    };
+    self
+      .new_did_output_with_governor(address, address, document, rent_structure)
+      .await
+  }
+
+  /// Create a DID with a new Alias Output containing the given `document`, which can be governed by a
+  /// different address than the one controlling its state.
+  ///
+  /// The `state_controller` will be set as the state controller unlock condition and the sender feature,
+  /// the `governor` as the governor unlock condition. This allows keeping the governor key, which can
+  /// change the controllers or destroy the output, separate from the key used for document updates.
+  ///
+  /// See [`new_did_output`](StardustClientExt::new_did_output) for the remaining parameters.
+  ///
+  /// NOTE: this does *not* publish the Alias Output. See [`publish_did_output`](StardustClientExt::publish_did_output).
+  ///
+  /// # Errors
+  ///
+  /// - Returns an [`Error::DIDUpdateError`] when retrieving the `RentStructure` fails.
+  /// - Returns an [`Error::AliasOutputBuildError`] when building the Alias Output fails.
+  async fn new_did_output_with_governor(
+    &self,
+    state_controller: Address,
+    governor: Address,
+    document: StardustDocument,
+    rent_structure: Option<RentStructure>,
+  ) -> Result<AliasOutput> {
+    let rent_structure: RentStructure = get_rent_structure(self.client(), rent_structure).await?;
 
     AliasOutputBuilder::new_with_minimum_storage_deposit(rent_structure, AliasId::null())
       .map_err(Error::AliasOutputBuildError)?
       .with_state_index(0)
       .with_foundry_counter(0)
       .with_state_metadata(document.pack()?)
-      .add_feature(Feature::Sender(SenderFeature::new(address)))
+      .add_feature(Feature::Sender(SenderFeature::new(state_controller)))
       .add_unlock_condition(UnlockCondition::StateControllerAddress(
-        StateControllerAddressUnlockCondition::new(address),
+        StateControllerAddressUnlockCondition::new(state_controller),
       ))
       .add_unlock_condition(UnlockCondition::GovernorAddress(GovernorAddressUnlockCondition::new(
-        address,
+        governor,
       )))
       .finish()
       .map_err(Error::AliasOutputBuildError)
   }
 
   /// Returns the updated Alias Output for further customization and publication. The storage deposit
-  /// on the output is unchanged. If the size of the document increased, the amount must be increased manually.
+  /// on the output is unchanged. If the size of the document increased, the amount must be increased manually,
+  /// see [`update_did_output_auto_deposit`](StardustClientExt::update_did_output_auto_deposit).
   ///
   /// NOTE: this does *not* publish the updated Alias Output. See
   /// [`publish_did_output`](StardustClientExt::publish_did_output).
@@ -109,6 +134,53 @@ pub trait StardustClientExt: Sync {
     alias_output_builder.finish().map_err(Error::AliasOutputBuildError)
   }
 
+  /// Returns the updated Alias Output like [`update_did_output`](StardustClientExt::update_did_output),
+  /// but raises the storage deposit to the minimum required for the new output size, if the current
+  /// deposit does not cover it. A sufficient deposit is left unchanged.
+  ///
+  /// The minimum deposit is computed according to the given `rent_structure`, which will be fetched from
+  /// the node if not provided.
+  ///
+  /// The additional tokens are taken from the funding `address`, which is checked to hold enough tokens in
+  /// Basic Outputs without expiration, timelock or storage deposit return conditions. They are transferred
+  /// when the output is published with [`publish_did_output`](StardustClientExt::publish_did_output), whose
+  /// `SecretManager` must control the funding `address`.
+  ///
+  /// NOTE: this does *not* publish the updated Alias Output.
+  ///
+  /// # Errors
+  ///
+  /// - Returns `Err` when failing to resolve the DID contained in `document`.
+  /// - Returns an [`Error::DIDUpdateError`] when retrieving the `RentStructure` or the funds of `address` fails.
+  /// - Returns an [`Error::AliasOutputBuildError`] when building the Alias Output fails.
+  /// - Returns an [`Error::InsufficientFunds`] when `address` cannot cover the additional deposit.
+  async fn update_did_output_auto_deposit(
+    &self,
+    document: StardustDocument,
+    address: Address,
+    rent_structure: Option<RentStructure>,
+  ) -> Result<AliasOutput> {
+    let rent_structure: RentStructure = get_rent_structure(self.client(), rent_structure).await?;
+    let alias_output: AliasOutput = self.update_did_output(document).await?;
+
+    let minimum_deposit_output: AliasOutput = AliasOutputBuilder::from(&alias_output)
+      .with_minimum_storage_deposit(rent_structure)
+      .finish()
+      .map_err(Error::AliasOutputBuildError)?;
+
+    if minimum_deposit_output.amount() <= alias_output.amount() {
+      return Ok(alias_output);
+    }
+
+    let required: u64 = minimum_deposit_output.amount() - alias_output.amount();
+    let available: u64 = get_address_balance(self.client(), &address).await?;
+    if available < required {
+      return Err(Error::InsufficientFunds { required, available });
+    }
+
+    Ok(minimum_deposit_output)
+  }
+
   /// Resolves the Alias Output associated to the `did`, removes the DID document,
   /// and publishes the output. This effectively deactivates the DID.
   /// Deactivating does not destroy the output. Hence, a deactivated DID can be
@@ -138,7 +210,13 @@ pub trait StardustClientExt: Sync {
   }
 
   /// Publish the given `alias_output` with the provided `secret_manager`
-  /// and returns the block they were published in.
+  /// and returns the DID document it contains.
+  ///
+  /// The output is wrapped in a transaction signed with `secret_manager` and submitted in a block. This
+  /// method then waits until the block is included (referenced by a milestone), promoting or reattaching
+  /// it if necessary. Inclusion is checked every [`INCLUSION_CHECK_INTERVAL_SECS`] seconds for at most
+  /// [`INCLUSION_CHECK_MAX_ATTEMPTS`] attempts, so it times out after about 200 seconds with an
+  /// [`Error::DIDUpdateError`].
   ///
   /// Needs to be called by the state controller of the Alias Output.
   ///
@@ -190,7 +268,11 @@ pub trait StardustClientExt: Sync {
       .map_err(Error::DIDUpdateError)?;
 
     let _ = client
-      .retry_until_included(&block.id(), None, None)
+      .retry_until_included(
+        &block.id(),
+        Some(INCLUSION_CHECK_INTERVAL_SECS),
+        Some(INCLUSION_CHECK_MAX_ATTEMPTS),
+      )
       .await
       .map_err(Error::DIDUpdateError)?;
 
@@ -215,15 +297,54 @@ pub trait StardustClientExt: Sync {
 
     let (_, _, alias_output) = resolve_alias_output(self.client(), did).await?;
 
-    if alias_output.state_metadata().is_empty() {
-      let mut empty_document: StardustDocument = StardustDocument::new_with_id(did.to_owned());
-      empty_document.metadata.deactivated = Some(true);
+    document_from_alias_output(did, &alias_output)
+  }
+
+  /// Resolve a [`StardustDID`] to the [`StardustDocument`] it had at the milestone with the given
+  /// `milestone_index`, i.e. the document contained in the state of the Alias Output that was booked at or
+  /// before that milestone.
+  ///
+  /// Starting from the current Alias Output, this walks back through the transactions that created each
+  /// state, so it makes two requests per state transition since the milestone.
+  ///
+  /// # Node requirements
+  ///
+  /// Earlier states are spent outputs, which the node only returns as long as it has not pruned them.
+  /// Resolving states older than the pruning window of the node requires a node that keeps the full
+  /// history, e.g. a permanode.
+  ///
+  /// # Errors
+  ///
+  /// - Returns a [`NetworkMismatch`](Error::NetworkMismatch) error if the DID's and the client's network do not match.
+  /// - Returns a [`NotFound`](iota_client::Error::NotFound) error if the associated Alias Output wasn't found.
+  /// - Returns a [`DIDNotFoundAtMilestone`](Error::DIDNotFoundAtMilestone) error if the Alias Output was created after
+  ///   the milestone.
+  /// - Returns a [`HistoricalStateUnavailable`](Error::HistoricalStateUnavailable) error if the node no longer provides
+  ///   an earlier state.
+  async fn resolve_did_at(&self, did: &StardustDID, milestone_index: u32) -> Result<StardustDocument> {
+    let client: &Client = self.client();
+    let network_hrp: String = get_network_hrp(client).await?;
 
-      Ok(empty_document)
-    } else {
-      let document: &[u8] = alias_output.state_metadata();
-      StardustDocument::unpack(did, document)
+    if did.network_str() != network_hrp.as_str() {
+      return Err(Error::NetworkMismatch {
+        expected: did.network_str().to_owned(),
+        actual: network_hrp,
+      });
     }
+
+    let (alias_id, mut output_id, mut alias_output, mut output_metadata) =
+      resolve_alias_output_with_metadata(client, did).await?;
+
+    while output_metadata.milestone_index_booked > milestone_index {
+      // Only the initial state of an Alias Output has a null id.
+      if alias_output.alias_id().is_null() {
+        return Err(Error::DIDNotFoundAtMilestone(milestone_index));
+      }
+      (output_id, alias_output, output_metadata) =
+        resolve_previous_alias_output(client, alias_id, &output_id, milestone_index).await?;
+    }
+
+    document_from_alias_output(did, &alias_output)
   }
 
   /// Resolve a [`StardustDID`] to an [`AliasOutput`].
@@ -233,6 +354,31 @@ pub trait StardustClientExt: Sync {
   /// - Returns a [`NetworkMismatch`](Error::NetworkMismatch) error if the DID's and the client's network do not match.
   /// - Returns a [`NotFound`](iota_client::Error::NotFound) error if the associated Alias Output wasn't found.
   async fn resolve_did_output(&self, did: &StardustDID) -> Result<AliasOutput> {
+    self
+      .resolve_did_output_with_metadata(did)
+      .await
+      .map(|(alias_output, _)| alias_output)
+  }
+
+  /// Resolve a [`StardustDID`] to an [`AliasOutput`] together with metadata derived from the ledger.
+  ///
+  /// The returned [`StardustDocumentMetadata`] does not contain any of the metadata stored in the document itself:
+  /// - `updated` is the timestamp of the milestone that booked the current Alias Output.
+  /// - `created` is the same timestamp if the output is still the one created with the DID, since earlier states of
+  ///   the output cannot be queried from the node. It is `None` after the first state or governance transition.
+  /// - `deactivated` is `Some(true)` if the output contains no document, see
+  ///   [`deactivate_did_output`](StardustClientExt::deactivate_did_output).
+  ///
+  /// Both timestamps are `None` if the node does not provide the milestone timestamp.
+  ///
+  /// # Errors
+  ///
+  /// - Returns a [`NetworkMismatch`](Error::NetworkMismatch) error if the DID's and the client's network do not match.
+  /// - Returns a [`NotFound`](iota_client::Error::NotFound) error if the associated Alias Output wasn't found.
+  async fn resolve_did_output_with_metadata(
+    &self,
+    did: &StardustDID,
+  ) -> Result<(AliasOutput, StardustDocumentMetadata)> {
     let network_hrp: String = get_network_hrp(self.client()).await?;
 
     if did.network_str() != network_hrp.as_str() {
@@ -242,9 +388,23 @@ pub trait StardustClientExt: Sync {
       });
     }
 
-    resolve_alias_output(self.client(), did)
-      .await
-      .map(|(_, _, alias_output)| alias_output)
+    let (_, _, alias_output, output_metadata) = resolve_alias_output_with_metadata(self.client(), did).await?;
+
+    let booked: Option<Timestamp> = match output_metadata.milestone_timestamp_booked {
+      0 => None,
+      timestamp => Timestamp::from_unix(i64::from(timestamp)).ok(),
+    };
+
+    let metadata: StardustDocumentMetadata = StardustDocumentMetadata {
+      // Only the initial state of an Alias Output has a null id, unlike the state index, which is not
+      // incremented by governance transitions.
+      created: booked.filter(|_| alias_output.alias_id().is_null()),
+      updated: booked,
+      deactivated: alias_output.state_metadata().is_empty().then_some(true),
+      properties: Default::default(),
+    };
+
+    Ok((alias_output, metadata))
   }
 
   /// Returns the network name of the connected node, which is the
@@ -268,7 +428,12 @@ impl StardustClientExt for &Client {
   }
 }
 
-/// Publishes an `alias_output`.
+/// The number of seconds between checks whether a published block was included.
+pub const INCLUSION_CHECK_INTERVAL_SECS: u64 = 5;
+/// The maximum number of checks whether a published block was included, before giving up.
+pub const INCLUSION_CHECK_MAX_ATTEMPTS: u64 = 40;
+
+/// Publishes an `alias_output` and waits until it is included.
 /// Returns the block that the output was included in.
 async fn publish_output(client: &Client, secret_manager: &SecretManager, alias_output: AliasOutput) -> Result<Block> {
   let block: Block = client
@@ -281,13 +446,47 @@ async fn publish_output(client: &Client, secret_manager: &SecretManager, alias_o
     .map_err(Error::DIDUpdateError)?;
 
   let _ = client
-    .retry_until_included(&block.id(), None, None)
+    .retry_until_included(
+      &block.id(),
+      Some(INCLUSION_CHECK_INTERVAL_SECS),
+      Some(INCLUSION_CHECK_MAX_ATTEMPTS),
+    )
     .await
     .map_err(Error::DIDUpdateError)?;
 
   Ok(block)
 }
 
+/// Returns the given `rent_structure` or fetches it from the node, if not provided.
+async fn get_rent_structure(client: &Client, rent_structure: Option<RentStructure>) -> Result<RentStructure> {
+  match rent_structure {
+    Some(rent_structure) => Ok(rent_structure),
+    None => client.get_rent_structure().await.map_err(Error::DIDUpdateError),
+  }
+}
+
+/// Returns the amount of tokens held by the `address` in Basic Outputs it can unlock without further conditions.
+async fn get_address_balance(client: &Client, address: &Address) -> Result<u64> {
+  let network_hrp: String = get_network_hrp(client).await?;
+  let output_ids: Vec<OutputId> = client
+    .basic_output_ids(vec![
+      QueryParameter::Address(address.to_bech32(&network_hrp)),
+      QueryParameter::HasExpirationCondition(false),
+      QueryParameter::HasTimelockCondition(false),
+      QueryParameter::HasStorageReturnCondition(false),
+    ])
+    .await
+    .map_err(Error::DIDUpdateError)?;
+
+  let mut balance: u64 = 0;
+  for output_response in client.get_outputs(output_ids).await.map_err(Error::DIDUpdateError)? {
+    let output: Output = Output::try_from(&output_response.output).map_err(Error::OutputConversionError)?;
+    balance += output.amount();
+  }
+
+  Ok(balance)
+}
+
 /// Get the BECH32 HRP from the client's network.
 async fn get_network_hrp(client: &Client) -> Result<String> {
   client
@@ -334,8 +533,80 @@ async fn documents_from_block(client: &Client, block: &Block) -> Result<Vec<Star
   Ok(documents)
 }
 
+/// Returns the document contained in the `alias_output`, or an empty, deactivated document if the output
+/// contains none.
+fn document_from_alias_output(did: &StardustDID, alias_output: &AliasOutput) -> Result<StardustDocument> {
+  if alias_output.state_metadata().is_empty() {
+    let mut empty_document: StardustDocument = StardustDocument::new_with_id(did.to_owned());
+    empty_document.metadata.deactivated = Some(true);
+
+    Ok(empty_document)
+  } else {
+    let document: &[u8] = alias_output.state_metadata();
+    StardustDocument::unpack(did, document)
+  }
+}
+
+/// Resolve the state of the Alias Output with `alias_id` that was consumed by the transaction creating
+/// `output_id`, together with its id and metadata.
+async fn resolve_previous_alias_output(
+  client: &Client,
+  alias_id: AliasId,
+  output_id: &OutputId,
+  milestone_index: u32,
+) -> Result<(OutputId, AliasOutput, OutputMetadataResponse)> {
+  let unavailable = |source: iota_client::Error| Error::HistoricalStateUnavailable {
+    milestone_index,
+    source: Some(source),
+  };
+
+  let block: Block = client
+    .get_included_block(output_id.transaction_id())
+    .await
+    .map_err(unavailable)?;
+
+  if let Some(Payload::Transaction(tx_payload)) = block.payload() {
+    let TransactionEssence::Regular(regular) = tx_payload.essence();
+
+    for input in regular.inputs().iter() {
+      if let Input::Utxo(utxo_input) = input {
+        let input_id: &OutputId = utxo_input.output_id();
+        let output_response: OutputResponse = client.get_output(input_id).await.map_err(unavailable)?;
+        let output: Output = Output::try_from(&output_response.output).map_err(Error::OutputConversionError)?;
+
+        if let Output::Alias(alias_output) = output {
+          let input_alias_id: AliasId = if alias_output.alias_id().is_null() {
+            AliasId::from(*input_id)
+          } else {
+            alias_output.alias_id().to_owned()
+          };
+
+          if input_alias_id == alias_id {
+            return Ok((*input_id, alias_output, output_response.metadata));
+          }
+        }
+      }
+    }
+  }
+
+  Err(Error::HistoricalStateUnavailable {
+    milestone_index,
+    source: None,
+  })
+}
+
 /// Resolve a did into an Alias Output and the associated identifiers.
 async fn resolve_alias_output(client: &Client, did: &StardustDID) -> Result<(AliasId, OutputId, AliasOutput)> {
+  resolve_alias_output_with_metadata(client, did)
+    .await
+    .map(|(alias_id, output_id, alias_output, _)| (alias_id, output_id, alias_output))
+}
+
+/// Resolve a did into an Alias Output, the associated identifiers and the output's metadata.
+async fn resolve_alias_output_with_metadata(
+  client: &Client,
+  did: &StardustDID,
+) -> Result<(AliasId, OutputId, AliasOutput, OutputMetadataResponse)> {
   let tag_bytes: [u8; StardustDID::TAG_BYTES_LEN] =
     prefix_hex::decode(did.tag()).map_err(|_| DIDError::InvalidMethodId)?;
   let alias_id: AliasId = AliasId::new(tag_bytes);
@@ -347,7 +618,7 @@ async fn resolve_alias_output(client: &Client, did: &StardustDID) -> Result<(Ali
   let output: Output = Output::try_from(&output_response.output).map_err(Error::OutputConversionError)?;
 
   if let Output::Alias(alias_output) = output {
-    Ok((alias_id, output_id, alias_output))
+    Ok((alias_id, output_id, alias_output, output_response.metadata))
   } else {
     Err(Error::NotAnAliasOutput(output_id))
   }
@@ -2,5 +2,10 @@
 // SPDX-License-Identifier: Apache-2.0
 
 pub use client_ext::StardustClientExt;
+pub use client_ext::INCLUSION_CHECK_INTERVAL_SECS;
+pub use client_ext::INCLUSION_CHECK_MAX_ATTEMPTS;
+pub use resolution_cache::CachingClient;
+pub use resolution_cache::ResolutionCache;
 
 mod client_ext;
+mod resolution_cache;
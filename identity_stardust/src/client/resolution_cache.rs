@@ -0,0 +1,300 @@
+// Copyright 2020-2022 IOTA Stiftung
+// SPDX-License-Identifier: Apache-2.0
+
+use core::fmt::Debug;
+use core::fmt::Formatter;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::time::Duration;
+use std::time::Instant;
+
+use iota_client::block::address::Address;
+use iota_client::block::output::AliasOutput;
+use iota_client::secret::SecretManager;
+use iota_client::Client;
+
+use crate::error::Result;
+use crate::StardustClientExt;
+use crate::StardustDID;
+use crate::StardustDocument;
+
+/// A cache of resolved [`StardustDocument`]s, keyed by their [`StardustDID`].
+///
+/// Documents are returned from the cache until their time-to-live has elapsed since they were resolved.
+/// Once the cache holds more than its maximum number of entries, the least-recently-used entry is evicted.
+#[derive(Debug)]
+pub struct ResolutionCache {
+  ttl: Duration,
+  max_entries: usize,
+  state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+  // A logical clock that advances on every access.
+  clock: u64,
+  entries: HashMap<StardustDID, CacheEntry>,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+  document: StardustDocument,
+  resolved_at: Instant,
+  last_used: u64,
+}
+
+impl ResolutionCache {
+  /// Creates an empty cache which keeps documents for `ttl` and holds at most `max_entries` documents.
+  pub fn new(ttl: Duration, max_entries: usize) -> Self {
+    Self {
+      ttl,
+      max_entries,
+      state: Mutex::new(CacheState::default()),
+    }
+  }
+
+  /// Returns the cached document of `did`, if it was resolved less than the time-to-live ago.
+  pub fn get(&self, did: &StardustDID) -> Option<StardustDocument> {
+    let mut state: MutexGuard<'_, CacheState> = self.lock();
+    state.clock += 1;
+    let clock: u64 = state.clock;
+
+    let expired: bool = match state.entries.get_mut(did) {
+      Some(entry) if entry.resolved_at.elapsed() < self.ttl => {
+        entry.last_used = clock;
+        return Some(entry.document.clone());
+      }
+      Some(_) => true,
+      None => false,
+    };
+    if expired {
+      state.entries.remove(did);
+    }
+
+    None
+  }
+
+  /// Caches the `document` of `did`, evicting the least-recently-used entry if the cache is full.
+  pub fn insert(&self, did: StardustDID, document: StardustDocument) {
+    let mut state: MutexGuard<'_, CacheState> = self.lock();
+    state.clock += 1;
+    let entry: CacheEntry = CacheEntry {
+      document,
+      resolved_at: Instant::now(),
+      last_used: state.clock,
+    };
+    state.entries.insert(did, entry);
+
+    while state.entries.len() > self.max_entries {
+      let least_recently_used: StardustDID = match state
+        .entries
+        .iter()
+        .min_by_key(|(_, entry)| entry.last_used)
+        .map(|(did, _)| did.clone())
+      {
+        Some(did) => did,
+        None => break,
+      };
+      state.entries.remove(&least_recently_used);
+    }
+  }
+
+  /// Returns the cached document of `did`, or awaits `resolve` and caches its output otherwise.
+  ///
+  /// `resolve` is not polled if the document is cached. Errors are not cached.
+  pub async fn get_or_resolve<F>(&self, did: &StardustDID, resolve: F) -> Result<StardustDocument>
+  where
+    F: Future<Output = Result<StardustDocument>>,
+  {
+    if let Some(document) = self.get(did) {
+      return Ok(document);
+    }
+
+    let document: StardustDocument = resolve.await?;
+    self.insert(did.clone(), document.clone());
+
+    Ok(document)
+  }
+
+  /// Removes the cached document of `did`, e.g. after the DID was updated.
+  ///
+  /// Returns `true` if a document was cached.
+  pub fn invalidate(&self, did: &StardustDID) -> bool {
+    self.lock().entries.remove(did).is_some()
+  }
+
+  /// Removes all cached documents.
+  pub fn clear(&self) {
+    self.lock().entries.clear();
+  }
+
+  /// Returns the number of cached documents, including those whose time-to-live has elapsed.
+  pub fn len(&self) -> usize {
+    self.lock().entries.len()
+  }
+
+  /// Returns `true` if no documents are cached.
+  pub fn is_empty(&self) -> bool {
+    self.len() == 0
+  }
+
+  fn lock(&self) -> MutexGuard<'_, CacheState> {
+    // The state is consistent after every operation, so it can still be used if another thread panicked.
+    self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+  }
+}
+
+/// A [`Client`] which caches the documents resolved with
+/// [`resolve_did`](StardustClientExt::resolve_did) in a [`ResolutionCache`].
+///
+/// All other methods of [`StardustClientExt`] bypass the cache. The cached document of a DID is invalidated
+/// when an update is published through this client, but not when the DID is updated in any other way, in which
+/// case [`CachingClient::invalidate_cache`] should be called.
+pub struct CachingClient {
+  client: Client,
+  cache: ResolutionCache,
+}
+
+impl CachingClient {
+  /// Wraps the `client`, caching resolved documents in `cache`.
+  pub fn new(client: Client, cache: ResolutionCache) -> Self {
+    Self { client, cache }
+  }
+
+  /// Returns the cache of resolved documents.
+  pub fn cache(&self) -> &ResolutionCache {
+    &self.cache
+  }
+
+  /// Removes the cached document of `did`, so that the next resolution queries the node.
+  ///
+  /// Returns `true` if a document was cached.
+  pub fn invalidate_cache(&self, did: &StardustDID) -> bool {
+    self.cache.invalidate(did)
+  }
+
+  /// Consumes the `CachingClient` and returns the wrapped client.
+  pub fn into_inner(self) -> Client {
+    self.client
+  }
+}
+
+impl Debug for CachingClient {
+  fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+    f.debug_struct("CachingClient").field("cache", &self.cache).finish()
+  }
+}
+
+#[async_trait::async_trait]
+impl StardustClientExt for CachingClient {
+  fn client(&self) -> &Client {
+    &self.client
+  }
+
+  async fn resolve_did(&self, did: &StardustDID) -> Result<StardustDocument> {
+    self.cache.get_or_resolve(did, self.client.resolve_did(did)).await
+  }
+
+  async fn publish_did_output(
+    &self,
+    secret_manager: &SecretManager,
+    alias_output: AliasOutput,
+  ) -> Result<StardustDocument> {
+    let document: StardustDocument = self.client.publish_did_output(secret_manager, alias_output).await?;
+    self.cache.invalidate(document.id());
+    Ok(document)
+  }
+
+  async fn deactivate_did_output(&self, secret_manager: &SecretManager, did: &StardustDID) -> Result<()> {
+    self.client.deactivate_did_output(secret_manager, did).await?;
+    self.cache.invalidate(did);
+    Ok(())
+  }
+
+  async fn delete_did_output(&self, secret_manager: &SecretManager, address: Address, did: &StardustDID) -> Result<()> {
+    self.client.delete_did_output(secret_manager, address, did).await?;
+    self.cache.invalidate(did);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::sync::atomic::AtomicUsize;
+  use std::sync::atomic::Ordering;
+  use std::time::Duration;
+
+  use crate::error::Result;
+  use crate::NetworkName;
+  use crate::StardustDID;
+  use crate::StardustDocument;
+
+  use super::ResolutionCache;
+
+  fn did(byte: u8) -> StardustDID {
+    StardustDID::new(&[byte; 32], &NetworkName::try_from("smr").unwrap())
+  }
+
+  async fn resolve(did: &StardustDID, calls: &AtomicUsize) -> Result<StardustDocument> {
+    calls.fetch_add(1, Ordering::SeqCst);
+    Ok(StardustDocument::new_with_id(did.clone()))
+  }
+
+  #[tokio::test]
+  async fn test_resolution_cache_hit_within_ttl() {
+    let cache: ResolutionCache = ResolutionCache::new(Duration::from_secs(60), 10);
+    let calls: AtomicUsize = AtomicUsize::new(0);
+    let did: StardustDID = did(1);
+
+    let first: StardustDocument = cache.get_or_resolve(&did, resolve(&did, &calls)).await.unwrap();
+    let second: StardustDocument = cache.get_or_resolve(&did, resolve(&did, &calls)).await.unwrap();
+
+    assert_eq!(first, second);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+  }
+
+  #[tokio::test]
+  async fn test_resolution_cache_expired_ttl() {
+    let cache: ResolutionCache = ResolutionCache::new(Duration::ZERO, 10);
+    let calls: AtomicUsize = AtomicUsize::new(0);
+    let did: StardustDID = did(1);
+
+    cache.get_or_resolve(&did, resolve(&did, &calls)).await.unwrap();
+    cache.get_or_resolve(&did, resolve(&did, &calls)).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[tokio::test]
+  async fn test_resolution_cache_invalidate() {
+    let cache: ResolutionCache = ResolutionCache::new(Duration::from_secs(60), 10);
+    let calls: AtomicUsize = AtomicUsize::new(0);
+    let did: StardustDID = did(1);
+
+    cache.get_or_resolve(&did, resolve(&did, &calls)).await.unwrap();
+    assert!(cache.invalidate(&did));
+    assert!(!cache.invalidate(&did));
+    cache.get_or_resolve(&did, resolve(&did, &calls)).await.unwrap();
+
+    assert_eq!(calls.load(Ordering::SeqCst), 2);
+  }
+
+  #[test]
+  fn test_resolution_cache_evicts_least_recently_used() {
+    let cache: ResolutionCache = ResolutionCache::new(Duration::from_secs(60), 2);
+    let (did_1, did_2, did_3): (StardustDID, StardustDID, StardustDID) = (did(1), did(2), did(3));
+
+    cache.insert(did_1.clone(), StardustDocument::new_with_id(did_1.clone()));
+    cache.insert(did_2.clone(), StardustDocument::new_with_id(did_2.clone()));
+    // Using the first DID makes the second one the least recently used.
+    assert!(cache.get(&did_1).is_some());
+    cache.insert(did_3.clone(), StardustDocument::new_with_id(did_3.clone()));
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.get(&did_1).is_some());
+    assert!(cache.get(&did_2).is_none());
+    assert!(cache.get(&did_3).is_some());
+  }
+}
@@ -40,4 +40,17 @@ pub enum Error {
   OutputConversionError(#[source] iota_client::block::DtoError),
   #[error("conversion to an OutputId failed: {0}")]
   OutputIdConversionError(String),
+  #[cfg(feature = "iota-client")]
+  #[error("the funding address holds {available} tokens, but {required} are needed to cover the storage deposit")]
+  InsufficientFunds { required: u64, available: u64 },
+  #[cfg(feature = "iota-client")]
+  #[error("the DID did not exist yet at milestone {0}")]
+  DIDNotFoundAtMilestone(u32),
+  #[cfg(feature = "iota-client")]
+  #[error("the state of the DID at milestone {milestone_index} is not available from the node")]
+  HistoricalStateUnavailable {
+    milestone_index: u32,
+    #[source]
+    source: Option<iota_client::error::Error>,
+  },
 }